@@ -9,12 +9,79 @@ cli-unrecognized-games = No info for these games:
 cli-confirm-restoration = Do you want to restore from {$path}?
 cli-unable-to-request-confirmation = Unable to request confirmation.
     .winpty-workaround = If you are using a Bash emulator (like Git Bash), try running winpty.
+cli-passphrase-prompt = Passphrase
+tui-games = {$operation ->
+    [backup] Games to back up
+    *[restore] Games to restore
+}
+tui-filter = Filter: {$filter}
+tui-selection = Selection
+tui-selected-games = {$total-games ->
+    [0] No games selected, so all enabled games will be processed.
+    [one] 1 game selected:
+    *[other] {$total-games} games selected:
+}
+tui-keys = {$operation ->
+    [backup] ↑/↓: move · space: toggle · a: select shown · c: clear · /: filter · tab: restore list · p: preview · enter: back up · q: quit
+    *[restore] ↑/↓: move · space: toggle · a: select shown · c: clear · /: filter · tab: backup list · p: preview · enter: restore · q: quit
+}
+tui-keys-filter = Type to filter games by name · enter/esc: done
+tui-confirm = {$prompt} (y/n)
+tui-press-any-key = Press any key to return.
+cli-game-running = Warning: {$game} seems to be running, so its saves might be backed up halfway through being written.
 
+label-save-slots = Save slots:
+save-slot = Slot {$slot}
 badge-failed = FAILED
 badge-duplicates = DUPLICATES
 badge-duplicated = DUPLICATED
 badge-ignored = IGNORED
 badge-redirected-from = FROM: {$path}
+badge-too-large = TOO LARGE, SELECT TO CONFIRM
+badge-unresolved = UNRESOLVED: {$reason ->
+    [empty] EMPTY TARGET
+    *[not-absolute] TARGET NOT ABSOLUTE
+}
+badge-last-backup = {$stale ->
+    [yes] STALE, LAST BACKUP: {$updated}
+    *[no] LAST BACKUP: {$updated}
+}
+badge-unresolved-count = {$total-files} UNRESOLVED
+badge-root-not-found = NOT FOUND
+badge-never-backed-up = NEVER BACKED UP
+badge-never-backed-up-count = {$total-games} {$total-games ->
+    [one] GAME
+    *[other] GAMES
+} NEVER BACKED UP
+badge-over-budget = OVER BUDGET
+badge-running = RUNNING
+badge-destination = DESTINATION: {$path}
+badge-backup-budget = BACKUP SIZE: {$processed-size} OF {$total-size}
+badge-changed = CHANGED
+badge-changed-count = {$total-games} {$total-games ->
+    [one] GAME
+    *[other] GAMES
+} CHANGED SINCE LAST BACKUP
+badge-since-days = ONLY FILES MODIFIED IN THE LAST {$days} {$days ->
+    [one] DAY
+    *[other] DAYS
+}
+
+operation-summary = {$operation ->
+    [backup] Backup
+    *[restore] Restore
+} finished: {$succeeded} succeeded, {$skipped} skipped, {$failed} failed.
+stats-manifest-games = In manifest: {$total-games}
+stats-roots = Roots: {$total-roots}
+stats-found-games = Found in last scan: {$total-games}
+stats-backed-up-games = With backups: {$total-games}
+history-entry = {$updated} - {$operation ->
+    [backup] Backup
+    *[restore] Restore
+}: {$succeeded} succeeded, {$skipped} skipped, {$failed} failed
+operation-health-succeeded = ALL SUCCEEDED
+operation-health-skipped = SOME SKIPPED
+operation-health-failed = SOME FAILED
 
 some-entries-failed = Some entries failed to process; look for {badge-failed} in the output for details. Double check whether you can access those files or whether their paths are very long.
 
@@ -37,32 +104,137 @@ button-restore = Restore
 button-nav-backup = BACKUP MODE
 button-nav-restore = RESTORE MODE
 button-nav-custom-games = CUSTOM GAMES
+button-nav-roots = ROOTS
 button-nav-other = OTHER
 button-add-root = Add root
 button-find-roots = Find roots
+button-edit-roots = Edit roots
 button-add-redirect = Add redirect
 button-add-game = Add game
 button-continue = Continue
 button-cancel = Cancel
 button-cancelling = Cancelling...
 button-okay = Okay
+button-details = Details
+button-copy = Copy
+button-apply-staged = Apply staged
+button-retry-failed = Retry failed
+button-reload-config = Reload config
+button-save-config = Save config
+button-export-config = Export config
+button-import-config = Import config
+button-merge = Merge
+button-replace = Replace
+button-keep-all-newer = Keep all newer
+button-overwrite-all = Overwrite all
+button-check-manifest = Check manifest
+button-test-restore = Test restore
+button-prune-orphans = Prune orphans
+button-migrate = Migrate
 button-select-all = Select all
 button-deselect-all = Deselect all
+button-invert-selection = Invert selection
+button-select-changed = Select changed
 button-enable-all = Enable all
 button-disable-all = Disable all
 
+scheduled-backup-started = Starting a scheduled backup.
+scheduled-backup-skipped-busy = Skipped a scheduled backup because Ludusavi was busy.
+scheduled-backup-skipped-passphrase = Skipped a scheduled backup because encryption is enabled, but LUDUSAVI_PASSPHRASE is not set.
+scheduled-backup-finished = Finished a scheduled backup.
+scheduled-backup-finished-with-failures = Finished a scheduled backup, but some entries failed.
+
+quick-backup-no-matches = No matching games.
+
+tray-back-up = Back up now
+tray-show-window = Show window
+tray-hide-window = Hide window
+tray-quit = Quit
+
+mode-backup-disabled =
+    Backups are disabled because this installation is set to restore only.
+    You can change this with the `mode` setting in your config file.
+mode-restore-disabled =
+    Restoring is disabled because this installation is set to back up only.
+    You can change this with the `mode` setting in your config file.
+
 no-roots-are-configured = Add some roots to back up even more data.
+roots-summary = {$total-roots} {$total-roots ->
+    [one] root
+    *[other] roots
+}, {$enabled} enabled
 
 config-is-invalid = Error: The config file is invalid.
+config-save-failed = Error: Unable to save the config, so your latest changes may be lost. Ludusavi will keep trying as you make more changes: {$path}
+unsaved-config-changes = Your config has changes that have not been saved yet.
+config-export-failed = Error: Unable to export the config to this file: {$path}
 manifest-is-invalid = Error: The manifest file is invalid.
+manifest-summary = Manifest: {$total-games} {$total-games ->
+    [one] game
+    *[other] games
+}, last updated {$updated ->
+    [unknown] at an unknown time
+    *[other] {$updated}
+}
+manifest-has-problems = Some manifest entries had problems and were skipped:
+manifest-problem-duplicate-game = [{$game}] Listed more than once; only the last entry was used.
+manifest-problem-malformed-game = [{$game}] Invalid entry: {$reason}
+manifest-problem-empty-path = [{$game}] Empty path.
+manifest-problem-unknown-placeholder = [{$game}] Unknown placeholder {$placeholder} in path: {$path}
+manifest-problem-overridden-game = [{$game}] Replaced by the entry from a later manifest: {$path}
+manifest-problem-unavailable-source = Unable to read a secondary manifest, so it was left out: {$path}
 manifest-cannot-be-updated = Error: Unable to check for an update to the manifest file. Is your Internet connection down?
+manifest-download-is-invalid = Error: The downloaded manifest file is invalid, possibly because the download was cut off. The previous manifest will be kept.
+manifest-checksum-mismatch = Error: The downloaded manifest file does not match its published checksum. The previous manifest will be kept.
 cannot-prepare-backup-target = Error: Unable to prepare backup target (either creating or emptying the folder). If you have the folder open in your file browser, try closing it: {$path}
+backup-target-not-writable = Error: Ludusavi does not have permission to write to the backup target. Please check the folder's permissions or choose a different location: {$path}
+unknown-backup = Error: There is no backup named "{$backup}" for {$game}. The name should match a folder in the game's backup, like "full-20220101T000000Z".
+cannot-prune-orphan = Error: Unable to delete an orphaned backup. Please check the folder's permissions: {$path}
+staged-restore-save-failed = Error: Unable to record which files are staged, so they may not be applied later. Please check the folder's permissions: {$path}
+backup-target-has-locked-backups = Error: The backup target has locked backups, which would be deleted by replacing its contents. Please use merge mode or unlock them first: {$path}
+backup-target-invalid = Error: The backup target is blank or the top of a drive, which could scatter files somewhere unexpected. Please choose a specific folder: {$path}
+backup-target-inside-root = Error: The backup target is inside of another folder that Ludusavi scans or restores from, so the backup could end up copying itself. Please choose a different location.
+    Target: {$path}
+    Conflicts with: {$root}
 restoration-source-is-invalid = Error: The restoration source is invalid (either doesn't exist or isn't a directory). Please double check the location: {$path}
+restoration-target-not-writable = Error: Ludusavi does not have permission to write to a folder that would be restored into. Please check the folder's permissions or add a redirect to a different location: {$path}
+backup-migration-failed = Error: Unable to copy the backup or verify the copy. Please double check the location: {$path}
 registry-issue = Error: Some registry entries were skipped.
+command-failed = Error: This command failed: {$command}
+operation-disabled = Error: {$operation ->
+    [backup] Backing up
+    *[restore] Restoring
+} is disabled by the `mode` setting in your config.
+read-only-mode = Error: Ludusavi is in read-only mode because of the `mode` setting in your config, so it will not write or delete any files. Previews are still allowed.
+sftp-transfer-failed = Error: Unable to transfer files over SFTP. Make sure that your SSH agent or key file can log in without a password: {$path}
+gui-unavailable =
+    Error: Unable to start the GUI: {$reason}
+
+    You can still use the CLI instead, including `ludusavi tui` for a full-screen
+    terminal interface. Run `ludusavi --help` for details.
+
+    If this system has no graphics acceleration (e.g., a virtual machine or remote desktop),
+    setting the environment variable `LIBGL_ALWAYS_SOFTWARE=1` may let the GUI use software rendering.
 unable-to-browse-file-system = Error: Unable to browse on your system.
 unable-to-open-directory = Error: Unable to open directory:
+unable-to-open-file = Error: Unable to open file:
 unable-to-open-url = Error: Unable to open URL:
 
+date = {$month ->
+    [1] Jan
+    [2] Feb
+    [3] Mar
+    [4] Apr
+    [5] May
+    [6] Jun
+    [7] Jul
+    [8] Aug
+    [9] Sep
+    [10] Oct
+    [11] Nov
+    *[12] Dec
+} {$day}, {$year}
+
 processed-games = {$total-games} {$total-games ->
     [one] game
     *[other] games
@@ -73,13 +245,56 @@ processed-games-subset = {$processed-games} of {$total-games} {$total-games ->
 }
 processed-size-subset = {$processed-size} of {$total-size}
 
+status-bar = {$operation ->
+    [backup] Backing up
+    [preview] Scanning
+    [restore] Restoring
+    *[cancel] Cancelling
+}: {$processed-games} of {$total-games} {$total-games ->
+    [one] game
+    *[other] games
+} ({$elapsed})
+
+window-title-progress = {$operation ->
+    [backup] Backing up
+    [preview] Scanning
+    [restore] Restoring
+    *[cancel] Cancelling
+} {$processed-games}/{$total-games}
+
+activity-scanning = Scanning: {$game} ({$total-files} {$total-files ->
+    [one] file
+    *[other] files
+} so far)
+activity-scanned = Found: {$game} ({$total-files} {$total-files ->
+    [one] file
+    *[other] files
+})
+activity-backed-up = Backed up: {$game} ({$total-files} {$total-files ->
+    [one] file
+    *[other] files
+})
+
 field-backup-target = Back up to:
 toggle-backup-merge = Merge
+toggle-backup-encrypt = Encrypt
 field-restore-source = Restore from:
+restore-source-backup = Latest backup: {$when} on {$host}
+unknown = unknown
+toggle-restore-only-changed = Only changed
+toggle-restore-stage = Stage first
+toggle-restore-mirror = Delete extra files
+toggle-link-paths = Same as backup
+toggle-files = Files
+toggle-registry = Registry
 field-custom-files = Paths:
 field-custom-registry = Registry:
+registry-section = Registry keys
 field-search = Search:
 field-sort = Sort:
+field-store = Store:
+field-stale-only = Only stale
+field-never-backed-up-only = Only never backed up
 field-redirect-source =
     .placeholder = Source (original location)
 field-redirect-target =
@@ -88,10 +303,24 @@ field-custom-game-name =
     .placeholder = Name
 field-search-game-name =
     .placeholder = Name
+field-quick-backup =
+    .placeholder = Type a game to back up, then press Enter
+field-passphrase =
+    .placeholder = Passphrase
 field-backup-excluded-items = Backup exclusions:
+field-manifest-categories = Back up games in these categories:
+field-config-file = Config file:
+field-history = Recent operations:
+field-stats = Overview:
+field-migrate-backup = Migrate backup:
+field-migration-source =
+    .placeholder = From
+field-migration-target =
+    .placeholder = To
 field-retention-full = Full:
 field-retention-differential = Differential:
 
+store-all = All stores
 store-epic = Epic
 store-gog = GOG
 store-gog-galaxy = GOG Galaxy
@@ -122,6 +351,15 @@ explanation-for-exclude-store-screenshots =
     screenshot functionality, this setting will not affect whether those
     screenshots are backed up.
 
+explanation-for-skip-hidden =
+    In backups, skip hidden files and clutter that operating systems leave
+    behind in folders, like desktop.ini, Thumbs.db, and .DS_Store.
+
+explanation-for-steam-account-settings =
+    In backups, include {store-steam}'s own settings for your games, like launch
+    options and controller configurations, as a separate entry called
+    "Steam account settings". This requires a {store-steam} root.
+
 consider-doing-a-preview =
     If you haven't already, consider doing a preview first so that there
     are no surprises.
@@ -137,6 +375,8 @@ confirm-backup =
 
     {consider-doing-a-preview}
 
+confirm-backup-commands = These commands from your config will also be run:
+
 confirm-restore =
     Are you sure you want to proceed with the restoration?
     This will overwrite any current files with the backups from here:
@@ -145,5 +385,79 @@ confirm-restore =
 
     {consider-doing-a-preview}
 
+confirm-registry-changes = These registry values will be affected:
+registry-change = {$change ->
+    [added] ADDED
+    [changed] CHANGED
+    *[removed] KEPT, NOT IN BACKUP
+}: {$path}
+
+confirm-deletions = These will be deleted, for a total of {$total-size}:
+cli-confirm-deletions = Delete these?
+
+confirm-restore-mirror =
+    Files from earlier backups of these games that aren't in the backup being restored will be deleted.
+
+confirm-restore-conflicts =
+    {$total-files} {$total-files ->
+        [one] file is
+        *[other] files are
+    } newer than the backup and would be overwritten.
+    Check the ones that you want to keep as they are:
+
+confirm-apply-staged-restore =
+    Are you sure you want to apply the staged restoration?
+    This will overwrite any current files with the staged copies from here:
+
+    {$path}
+
+backup-migrated = The backup was copied and verified here: {$path}
+
+confirm-test-restore =
+    Test restoring every game from here? Each backup will be restored into a
+    temporary folder and checked, then the temporary folder will be deleted.
+    Your current files will not be touched:
+
+    {$path}
+test-restore-passed = All {$total-games} {$total-games ->
+    [one] game
+    *[other] games
+} restored correctly.
+test-restore-failed = Only {$processed-games} of {$total-games} {$total-games ->
+    [one] game
+    *[other] games
+} restored correctly. These had problems:
+test-restore-failed-game = {$game} ({$total-files} {$total-files ->
+    [one] file
+    *[other] files
+})
+
+manifest-lint = These manifest paths have placeholders that can't be expanded on this system with your current roots, so they won't find anything ({$total-games} {$total-games ->
+    [one] game
+    *[other] games
+}, {$total-files} {$total-files ->
+    [one] path
+    *[other] paths
+}):
+manifest-lint-clean = All placeholders in the manifest can be expanded on this system.
+
+confirm-import-config =
+    How do you want to use the imported config?
+    Merging keeps your current paths and options, but adds the imported roots,
+    redirects, custom games, and exclusions. Custom games with the same name
+    will be replaced. Replacing discards your current config entirely.
+
 confirm-add-missing-roots = Add these roots?
 no-missing-roots = No additional roots found.
+no-orphaned-backups = There are no backups of games that Ludusavi no longer knows about.
+confirm-prune-orphans =
+    These games have backups, but were removed from the manifest or your custom games.
+    Games with locked backups are not included.
+no-backup-changes = The files are the same in both backups.
+failure-category = {$reason ->
+    [permission] Permission denied
+    [missing] Source missing
+    [in-use] File in use
+    [disk-full] Disk full
+    *[other] Failed
+}
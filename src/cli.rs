@@ -1,17 +1,22 @@
 use crate::{
-    config::{Config, RedirectConfig, Sort, SortKey},
+    config::{Config, RedirectConfig, RunningGamePolicy, Sort, SortKey},
+    history::{History, HistoryEntry},
     lang::Translator,
-    layout::BackupLayout,
+    layout::{restoration_source, BackupLayout},
     manifest::{Manifest, SteamMetadata},
     prelude::{
-        app_dir, back_up_game, game_file_restoration_target, prepare_backup_target, restore_game, scan_game_for_backup,
-        scan_game_for_restoration, BackupInfo, DuplicateDetector, Error, InstallDirRanking, OperationStatus,
-        OperationStepDecision, ScanInfo, StrictPath,
+        app_dir, back_up_game, check_restoration_targets_writable, configure_throttles, find_running_games,
+        game_file_restoration_target, game_store, need_restoration, prepare_backup_target, preview_restore_deletions,
+        relevant_backup_games, restore_game, scan_game_for_backup, scan_game_for_restoration, set_read_only,
+        BackupBudget, BackupInfo, CopyOptions, DeletionPreview, DuplicateDetector, Error, InstallDirRanking,
+        OperationStatus, OperationStepDecision, OperationSummary, RunningProcesses, ScanInfo, StrictPath,
+        PASSPHRASE_ENV_VAR, SCAN_THROTTLE,
     },
+    sftp::SftpTarget,
 };
 use clap::{CommandFactory, Parser};
 use indicatif::ParallelProgressIterator;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 fn parse_strict_path(path: &str) -> StrictPath {
     StrictPath::new(path.to_owned())
@@ -187,6 +192,67 @@ pub enum Subcommand {
         #[clap()]
         games: Vec<String>,
     },
+    #[clap(about = "Delete backups of games that are no longer in the manifest or custom games")]
+    PruneOrphans {
+        /// List out what would be deleted, but don't actually delete anything.
+        #[clap(long)]
+        preview: bool,
+
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[clap(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Don't ask for confirmation.
+        #[clap(long)]
+        force: bool,
+    },
+    #[clap(about = "Protect a backup from retention, or allow it to be pruned again")]
+    Lock {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[clap(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Unlock the backup instead.
+        #[clap(long)]
+        unlock: bool,
+
+        /// Name of the game.
+        #[clap()]
+        game: String,
+
+        /// Name of the backup folder, like `full-20220101T000000Z`.
+        /// Naming a differential backup locks the full backup that it belongs to.
+        #[clap()]
+        backup: String,
+    },
+    #[clap(about = "Show which files changed between two backups of a game")]
+    Compare {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[clap(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Print information to stdout in machine-readable JSON.
+        /// This replaces the default, human-readable output.
+        #[clap(long)]
+        api: bool,
+
+        /// Name of the game.
+        #[clap()]
+        game: String,
+
+        /// Name of the older backup folder, like `full-20220101T000000Z`.
+        #[clap()]
+        older: String,
+
+        /// Name of the newer backup folder, like `diff-20220201T000000Z`.
+        #[clap()]
+        newer: String,
+    },
+    #[clap(about = "Select, back up, and restore games in a full-screen terminal interface")]
+    Tui,
     #[clap(about = "Generate shell completion scripts")]
     Complete {
         #[clap(subcommand)]
@@ -263,6 +329,9 @@ struct JsonOutput {
     overall: OperationStatus,
     #[serde(serialize_with = "crate::serialization::ordered_map")]
     games: std::collections::HashMap<String, ApiGame>,
+    /// Paths that the operation would delete, with their sizes in bytes. Only set for previews.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletions: Option<std::collections::BTreeMap<String, u64>>,
 }
 
 #[derive(Debug)]
@@ -318,6 +387,27 @@ impl Reporter {
         }
     }
 
+    fn add_deletions(&mut self, deletions: &DeletionPreview) {
+        if deletions.is_empty() {
+            return;
+        }
+        match self {
+            Self::Standard { parts, translator, .. } => {
+                parts.push(translator.deletion_preview(deletions));
+                parts.push("".to_string());
+            }
+            Self::Json { output } => {
+                output.deletions = Some(
+                    deletions
+                        .entries
+                        .iter()
+                        .map(|(path, bytes)| (path.render(), *bytes))
+                        .collect(),
+                );
+            }
+        }
+    }
+
     fn add_game(
         &mut self,
         name: &str,
@@ -348,9 +438,13 @@ impl Reporter {
                 for entry in itertools::sorted(&scan_info.found_files) {
                     let mut redirected_from = None;
                     let readable = if let Some(original_path) = &entry.original_path {
-                        let (target, original_target) = game_file_restoration_target(original_path, redirects);
-                        redirected_from = original_target;
-                        target
+                        match game_file_restoration_target(original_path, redirects) {
+                            Ok((target, original_target)) => {
+                                redirected_from = original_target;
+                                target
+                            }
+                            Err(_) => original_path.to_owned(),
+                        }
                     } else {
                         entry.path.to_owned()
                     };
@@ -416,9 +510,13 @@ impl Reporter {
                     }
 
                     let readable = if let Some(original_path) = &entry.original_path {
-                        let (target, original_target) = game_file_restoration_target(original_path, redirects);
-                        api_file.original_path = original_target.map(|x| x.render());
-                        target
+                        match game_file_restoration_target(original_path, redirects) {
+                            Ok((target, original_target)) => {
+                                api_file.original_path = original_target.map(|x| x.render());
+                                target
+                            }
+                            Err(_) => original_path.to_owned(),
+                        }
                     } else {
                         entry.path.to_owned()
                     };
@@ -485,9 +583,34 @@ impl Reporter {
     }
 }
 
+fn request_passphrase(translator: &Translator) -> Result<String, Error> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+    dialoguer::Password::new()
+        .with_prompt(translator.cli_passphrase_prompt())
+        .interact()
+        .map_err(|_| Error::CliUnableToRequestConfirmation)
+}
+
+/// Show what would be deleted and, unless forced, ask whether to go ahead.
+fn confirm_deletions(translator: &Translator, deletions: &DeletionPreview, force: bool) -> Result<bool, Error> {
+    if force || deletions.is_empty() {
+        return Ok(true);
+    }
+    eprintln!("{}", translator.deletion_preview(deletions));
+    dialoguer::Confirm::new()
+        .with_prompt(translator.cli_confirm_deletions())
+        .interact()
+        .map_err(|_| Error::CliUnableToRequestConfirmation)
+}
+
 pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
     let translator = Translator::default();
     let mut config = Config::load()?;
+    set_read_only(config.mode.is_read_only());
     let mut failed = false;
     let mut duplicate_detector = DuplicateDetector::default();
 
@@ -506,6 +629,10 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             sort,
             games,
         } => {
+            if !preview && !config.mode.allows_backup() {
+                return Err(Error::OperationDisabled { restoring: false });
+            }
+
             let mut reporter = if api {
                 Reporter::json()
             } else {
@@ -527,27 +654,32 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 Manifest::load(&mut config, update)?
             };
 
-            let backup_dir = match path {
+            let backup_target = match path {
                 None => config.backup.path.clone(),
                 Some(p) => p,
             };
+            let remote = SftpTarget::parse(&backup_target.raw());
+            let backup_dir = match &remote {
+                Some(remote) => {
+                    remote.download()?;
+                    remote.mirror()
+                }
+                None => backup_target.clone(),
+            };
             let roots = &config.roots;
 
+            let keep_existing = if merge {
+                true
+            } else if no_merge {
+                false
+            } else {
+                config.backup.merge
+            };
             if !preview {
-                if !force && !merge && backup_dir.exists() {
+                if !force && !merge && remote.is_none() && backup_dir.exists() {
                     return Err(crate::prelude::Error::CliBackupTargetExists { path: backup_dir });
-                } else if let Err(e) = prepare_backup_target(
-                    &backup_dir,
-                    if merge {
-                        true
-                    } else if no_merge {
-                        false
-                    } else {
-                        config.backup.merge
-                    },
-                ) {
-                    return Err(e);
                 }
+                prepare_backup_target(&backup_dir, keep_existing, &config.roots, &config.restore.path)?;
             }
 
             let steam_ids_to_names = &manifest.map_steam_ids_to_names();
@@ -558,6 +690,9 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 }
                 all_games.add_custom_game(custom_game.clone());
             }
+            if config.backup.steam_account_settings {
+                all_games.add_steam_account_settings(&config.roots);
+            }
 
             let games_specified = !games.is_empty();
             let mut invalid_games: Vec<_> = games
@@ -599,23 +734,45 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                     games
                 }
             } else {
-                all_games.0.keys().cloned().collect()
+                all_games
+                    .0
+                    .iter()
+                    .filter(|(_, game)| !game.in_any_category(&config.manifest.excluded_categories))
+                    .map(|(name, _)| name.clone())
+                    .collect()
             };
             subjects.sort();
 
-            let layout = BackupLayout::new(backup_dir.clone(), config.backup.retention.clone());
+            let passphrase = if config.backup_encrypts() && !preview {
+                Some(request_passphrase(&translator)?)
+            } else {
+                None
+            };
+
+            configure_throttles(&config.backup);
+            let layout = BackupLayout::new(backup_dir.clone(), config.backup.retention.clone())
+                .with_folder_layout(config.backup.layout)
+                .with_format(config.backup.format)
+                .with_dedupe(config.backup.dedupe)
+                .with_passphrase(passphrase, config.backup.encrypt)
+                .with_destinations(&config.backup.destinations, &config.backup.encrypted_destinations);
             let filter = config.backup.filter.clone();
             let ranking = InstallDirRanking::scan(roots, &all_games, &subjects);
+            let running = match config.backup.running_games {
+                RunningGamePolicy::Ignore => Default::default(),
+                _ => find_running_games(&RunningProcesses::detect(), &all_games, &ranking),
+            };
             let toggled_paths = config.backup.toggled_paths.clone();
             let toggled_registry = config.backup.toggled_registry.clone();
 
-            let mut info: Vec<_> = subjects
+            let scanned: Vec<_> = subjects
                 .par_iter()
                 .progress_count(subjects.len() as u64)
                 .map(|name| {
-                    let game = &all_games.0[name];
+                    let game = all_games.get(name).unwrap();
                     let steam_id = &game.steam.clone().unwrap_or(SteamMetadata { id: None }).id;
 
+                    let scan_slot = SCAN_THROTTLE.acquire();
                     let scan_info = scan_game_for_backup(
                         game,
                         name,
@@ -627,24 +784,98 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                         &ranking,
                         &toggled_paths,
                         &toggled_registry,
+                        &config.backup.since_days,
                     );
-                    let ignored = !&config.is_game_enabled_for_backup(name) && !games_specified;
+                    drop(scan_slot);
+                    let ignored = (!&config.is_game_enabled_for_backup(name)
+                        || config.exceeds_size_guard(name, scan_info.sum_bytes(&None)))
+                        && !games_specified;
+                    (name, scan_info, ignored)
+                })
+                .collect();
+
+            // With a size budget, the most recently played games get the first claim.
+            let mut over_budget = std::collections::HashSet::new();
+            if config.backup.max_total_bytes.is_some() {
+                // A preview doesn't clear out the target, so only count what would be kept.
+                let counts_existing = keep_existing || !preview;
+                let used = if counts_existing { layout.total_size() } else { 0 };
+                let budget = BackupBudget::new(config.backup.max_total_bytes, used);
+                let mut candidates: Vec<_> = scanned
+                    .iter()
+                    .filter(|(_, scan_info, ignored)| !ignored && scan_info.found_anything())
+                    .map(|(name, scan_info, _)| (scan_info.latest_modified(), *name, scan_info.sum_bytes(&None)))
+                    .collect();
+                candidates.sort_by(|(a, _, _), (b, _, _)| b.cmp(a));
+                for (_, name, bytes) in candidates {
+                    let existing = if counts_existing { layout.game_size(name) } else { 0 };
+                    if !budget.reserve_replacing(bytes, existing) {
+                        over_budget.insert(name.clone());
+                    }
+                }
+            }
+
+            // Without merging, the target was already cleared, so there is nothing old to prune.
+            let deletions = if keep_existing {
+                let to_back_up: Vec<_> = scanned
+                    .iter()
+                    .filter(|(name, scan_info, ignored)| {
+                        !ignored
+                            && scan_info.found_anything()
+                            && !over_budget.contains(*name)
+                            && (config.backup.running_games != RunningGamePolicy::Skip || !running.contains(*name))
+                    })
+                    .map(|(name, _, _)| name.to_string())
+                    .collect();
+                layout.backups_to_prune(&to_back_up)
+            } else {
+                DeletionPreview::default()
+            };
+            if preview {
+                reporter.add_deletions(&deletions);
+            } else if !confirm_deletions(&translator, &deletions, force)? {
+                return Ok(());
+            }
+
+            let mut info: Vec<_> = scanned
+                .into_par_iter()
+                .map(|(name, scan_info, ignored)| {
                     let decision = if ignored {
                         OperationStepDecision::Ignored
+                    } else if config.backup.running_games == RunningGamePolicy::Skip && running.contains(name) {
+                        OperationStepDecision::Running
+                    } else if over_budget.contains(name) {
+                        OperationStepDecision::OverBudget
                     } else {
                         OperationStepDecision::Processed
                     };
-                    let backup_info = if preview || ignored {
+                    let backup_info = if preview || decision != OperationStepDecision::Processed {
                         crate::prelude::BackupInfo::default()
                     } else {
-                        back_up_game(&scan_info, name, &layout, config.backup.merge, &chrono::Utc::now())
+                        back_up_game(
+                            &scan_info,
+                            name,
+                            game_store(&scan_info, roots),
+                            &layout,
+                            config.backup.merge,
+                            config.backup.files,
+                            config.backup.registry,
+                            &chrono::Utc::now(),
+                            &CopyOptions::from(&config.backup),
+                        )
                     };
                     (name, scan_info, backup_info, decision)
                 })
                 .collect();
 
-            for (_, scan_info, _, _) in info.iter() {
+            for (name, scan_info, _, decision) in info.iter() {
                 duplicate_detector.add_game(scan_info);
+                if *decision == OperationStepDecision::Processed
+                    && running.contains(*name)
+                    && scan_info.found_anything()
+                {
+                    eprintln!("{}", translator.cli_game_running(name));
+                }
             }
 
             let sort = sort.map(From::from).unwrap_or_else(|| config.backup.sort.clone());
@@ -658,12 +889,31 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 info.reverse();
             }
 
+            let mut summary = OperationSummary::default();
             for (name, scan_info, backup_info, decision) in info {
+                if scan_info.found_anything() {
+                    summary.add(Some(&backup_info).filter(|_| decision == OperationStepDecision::Processed));
+                }
                 if !reporter.add_game(name, &scan_info, &backup_info, &decision, &[], &duplicate_detector) {
                     failed = true;
                 }
             }
-            reporter.print(&backup_dir);
+
+            if !preview {
+                if config.backup.dedupe {
+                    layout.prune_dedupe_store();
+                }
+                History::record(HistoryEntry {
+                    when: chrono::Utc::now(),
+                    restoring: false,
+                    path: backup_target.clone(),
+                    summary,
+                })?;
+                if let Some(remote) = &remote {
+                    remote.upload()?;
+                }
+            }
+            reporter.print(&backup_target);
         }
         Subcommand::Restore {
             preview,
@@ -674,6 +924,10 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             sort,
             games,
         } => {
+            if !preview && !config.mode.allows_restore() {
+                return Err(Error::OperationDisabled { restoring: true });
+            }
+
             let mut reporter = if api {
                 Reporter::json()
             } else {
@@ -698,7 +952,16 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 }
             }
 
-            let layout = BackupLayout::new(restore_dir.clone(), config.backup.retention.clone());
+            let mut layout = match SftpTarget::parse(&restore_dir.raw()) {
+                Some(remote) => {
+                    remote.download()?;
+                    BackupLayout::new(remote.mirror(), config.backup.retention.clone())
+                }
+                None => BackupLayout::new(restoration_source(&restore_dir)?, config.backup.retention.clone()),
+            };
+            if !preview && layout.any_encrypted() {
+                layout = layout.with_passphrase(Some(request_passphrase(&translator)?), false);
+            }
 
             let steam_ids_to_names = &manifest.map_steam_ids_to_names();
             let restorable_names = layout.restorable_games();
@@ -751,12 +1014,48 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             };
             subjects.sort();
 
-            let mut info: Vec<_> = subjects
+            let scanned: Vec<_> = subjects
                 .par_iter()
                 .progress_count(subjects.len() as u64)
                 .map(|name| {
                     let scan_info = scan_game_for_restoration(name, &layout);
-                    let ignored = !&config.is_game_enabled_for_restore(name) && !games_specified;
+                    let enabled = config.is_game_enabled_for_restore(name)
+                        && (!config.restore.only_changed
+                            || need_restoration(&scan_info, &layout, &config.get_redirects()));
+                    let ignored = !enabled && !games_specified;
+                    (name, scan_info, ignored)
+                })
+                .collect();
+
+            let mut deletions = DeletionPreview::default();
+            for (_, scan_info, ignored) in &scanned {
+                if !ignored {
+                    deletions.extend(preview_restore_deletions(
+                        scan_info,
+                        &layout,
+                        &config.get_redirects(),
+                        config.restore.files,
+                        config.restore.strategy,
+                    ));
+                }
+            }
+            if preview {
+                reporter.add_deletions(&deletions);
+            } else if !confirm_deletions(&translator, &deletions, force)? {
+                return Ok(());
+            }
+
+            if !preview && config.restore.files {
+                for (_, scan_info, ignored) in &scanned {
+                    if !ignored {
+                        check_restoration_targets_writable(scan_info, &config.get_redirects())?;
+                    }
+                }
+            }
+
+            let mut info: Vec<_> = scanned
+                .into_par_iter()
+                .map(|(name, scan_info, ignored)| {
                     let decision = if ignored {
                         OperationStepDecision::Ignored
                     } else {
@@ -765,7 +1064,17 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                     let restore_info = if preview || ignored {
                         crate::prelude::BackupInfo::default()
                     } else {
-                        restore_game(&scan_info, &config.get_redirects())
+                        restore_game(
+                            &scan_info,
+                            &layout,
+                            &config.get_redirects(),
+                            config.restore.files,
+                            config.restore.registry,
+                            config.restore.strategy,
+                            config.restore.overwrite_newer_by,
+                            config.restore.line_ending_patterns(name),
+                            &config.restore.save_slot_filter(name),
+                        )
                     };
                     (name, scan_info, restore_info, decision)
                 })
@@ -786,7 +1095,11 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 info.reverse();
             }
 
+            let mut summary = OperationSummary::default();
             for (name, scan_info, backup_info, decision) in info {
+                if scan_info.found_anything() {
+                    summary.add(Some(&backup_info).filter(|_| decision == OperationStepDecision::Processed));
+                }
                 if !reporter.add_game(
                     name,
                     &scan_info,
@@ -798,8 +1111,71 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                     failed = true;
                 }
             }
+            if !preview {
+                History::record(HistoryEntry {
+                    when: chrono::Utc::now(),
+                    restoring: true,
+                    path: restore_dir.clone(),
+                    summary,
+                })?;
+            }
             reporter.print(&restore_dir);
         }
+        Subcommand::PruneOrphans { preview, path, force } => {
+            if !preview && !config.mode.allows_backup() {
+                return Err(Error::OperationDisabled { restoring: false });
+            }
+
+            let manifest = Manifest::load(&mut config, false)?;
+            let backup_dir = path.unwrap_or_else(|| config.backup.path.clone());
+            let mut layout = BackupLayout::new(backup_dir, config.backup.retention.clone())
+                .with_destinations(&config.backup.destinations, &config.backup.encrypted_destinations);
+            let orphans = layout.orphaned_games(&relevant_backup_games(&config, &manifest));
+
+            if orphans.is_empty() {
+                println!("{}", translator.no_orphaned_backups());
+            } else if preview {
+                println!("{}", translator.deletion_preview(&orphans));
+            } else if confirm_deletions(&translator, &orphans, force)? {
+                layout.prune_orphans(&orphans)?;
+            }
+        }
+        Subcommand::Lock {
+            path,
+            unlock,
+            game,
+            backup,
+        } => {
+            if !config.mode.allows_backup() {
+                return Err(Error::OperationDisabled { restoring: false });
+            }
+
+            let backup_dir = path.unwrap_or_else(|| config.backup.path.clone());
+            let layout = BackupLayout::new(backup_dir, config.backup.retention.clone())
+                .with_destinations(&config.backup.destinations, &config.backup.encrypted_destinations);
+            layout.set_backup_locked(&game, &backup, !unlock)?;
+        }
+        Subcommand::Compare {
+            path,
+            api,
+            game,
+            older,
+            newer,
+        } => {
+            let backup_dir = path.unwrap_or_else(|| config.backup.path.clone());
+            let layout = BackupLayout::new(backup_dir, config.backup.retention.clone())
+                .with_destinations(&config.backup.destinations, &config.backup.encrypted_destinations);
+            let comparison = layout.compare_backups(&game, &older, &newer)?;
+
+            if api {
+                println!("{}", serde_json::to_string_pretty(&comparison).unwrap());
+            } else {
+                println!("{}", translator.backup_comparison(&comparison));
+            }
+        }
+        Subcommand::Tui => {
+            crate::tui::run_tui()?;
+        }
         Subcommand::Complete { shell } => {
             let clap_shell = match shell {
                 CompletionShell::Bash => clap_complete::Shell::Bash,
@@ -1106,6 +1482,126 @@ mod tests {
             }
         }
 
+        #[test]
+        fn accepts_cli_prune_orphans_with_minimal_arguments() {
+            check_args(
+                &["ludusavi", "prune-orphans"],
+                Cli {
+                    sub: Some(Subcommand::PruneOrphans {
+                        preview: false,
+                        path: None,
+                        force: false,
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_prune_orphans_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "prune-orphans",
+                    "--preview",
+                    "--path",
+                    "tests/backup",
+                    "--force",
+                ],
+                Cli {
+                    sub: Some(Subcommand::PruneOrphans {
+                        preview: true,
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        force: true,
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_lock() {
+            check_args(
+                &["ludusavi", "lock", "game1", "full-1"],
+                Cli {
+                    sub: Some(Subcommand::Lock {
+                        path: None,
+                        unlock: false,
+                        game: s("game1"),
+                        backup: s("full-1"),
+                    }),
+                },
+            );
+            check_args(
+                &[
+                    "ludusavi",
+                    "lock",
+                    "--path",
+                    "tests/backup",
+                    "--unlock",
+                    "game1",
+                    "full-1",
+                ],
+                Cli {
+                    sub: Some(Subcommand::Lock {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        unlock: true,
+                        game: s("game1"),
+                        backup: s("full-1"),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_compare_with_minimal_arguments() {
+            check_args(
+                &["ludusavi", "compare", "game1", "full-1", "diff-2"],
+                Cli {
+                    sub: Some(Subcommand::Compare {
+                        path: None,
+                        api: false,
+                        game: s("game1"),
+                        older: s("full-1"),
+                        newer: s("diff-2"),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_compare_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "compare",
+                    "--path",
+                    "tests/backup",
+                    "--api",
+                    "game1",
+                    "full-1",
+                    "diff-2",
+                ],
+                Cli {
+                    sub: Some(Subcommand::Compare {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        api: true,
+                        game: s("game1"),
+                        older: s("full-1"),
+                        newer: s("diff-2"),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_tui() {
+            check_args(
+                &["ludusavi", "tui"],
+                Cli {
+                    sub: Some(Subcommand::Tui),
+                },
+            );
+        }
+
         #[test]
         fn accepts_cli_complete_for_bash() {
             check_args(
@@ -1242,6 +1738,7 @@ Overall:
                     failed_registry: hashset! {
                         RegistryItem::new(s("HKEY_CURRENT_USER/Key1"))
                     },
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
@@ -1288,6 +1785,7 @@ Overall:
                 &BackupInfo {
                     failed_files: hashset! {},
                     failed_registry: hashset! {},
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
@@ -1311,6 +1809,7 @@ Overall:
                 &BackupInfo {
                     failed_files: hashset! {},
                     failed_registry: hashset! {},
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
@@ -1488,6 +1987,7 @@ Overall:
                     failed_registry: hashset! {
                         RegistryItem::new(s("HKEY_CURRENT_USER/Key1"))
                     },
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
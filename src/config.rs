@@ -1,6 +1,6 @@
 use crate::{
     manifest::Store,
-    prelude::{app_dir, Error, RegistryItem, StrictPath},
+    prelude::{app_dir, Error, RegistryItem, SaveSlotFilter, StrictPath, DEFAULT_SAVE_SLOT_PATTERN},
 };
 
 const MANIFEST_URL: &str = "https://raw.githubusercontent.com/mtkennerly/ludusavi-manifest/master/data/manifest.yaml";
@@ -17,20 +17,77 @@ pub struct Config {
     pub roots: Vec<RootsConfig>,
     pub backup: BackupConfig,
     pub restore: RestoreConfig,
+    #[serde(default, skip_serializing_if = "TrayConfig::is_default")]
+    pub tray: TrayConfig,
     #[serde(default, rename = "customGames")]
     pub custom_games: Vec<CustomGame>,
+    #[serde(default, skip_serializing_if = "OperationMode::is_default")]
+    pub mode: OperationMode,
+    /// Games to always list first, regardless of the sort.
+    #[serde(default, rename = "pinnedGames", skip_serializing_if = "Vec::is_empty")]
+    pub pinned_games: Vec<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ManifestConfig {
     pub url: String,
     pub etag: Option<String>,
+    /// When the manifest content was last downloaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// Games in any of these manifest categories are never backed up.
+    #[serde(
+        default,
+        rename = "excludedCategories",
+        serialize_with = "crate::serialization::ordered_set",
+        skip_serializing_if = "crate::serialization::is_empty_set"
+    )]
+    pub excluded_categories: std::collections::HashSet<String>,
+    /// More manifests to layer on top of the primary one. Later entries take
+    /// priority over earlier ones for games that appear in more than one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secondary: Vec<SecondaryManifestConfig>,
+    /// Where to find the published SHA-256 checksum of the primary manifest.
+    /// When set, a download that doesn't match is discarded.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "checksumUrl")]
+    pub checksum_url: Option<String>,
+}
+
+/// A manifest from somewhere other than the primary URL.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum SecondaryManifestConfig {
+    Local {
+        path: StrictPath,
+    },
+    Remote {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        etag: Option<String>,
+    },
+}
+
+impl SecondaryManifestConfig {
+    /// Where the manifest comes from, for display.
+    pub fn source(&self) -> String {
+        match self {
+            Self::Local { path } => path.render(),
+            Self::Remote { url, .. } => url.clone(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct RootsConfig {
     pub path: StrictPath,
     pub store: Store,
+    /// Keep the root in the list, but don't scan it.
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub ignore: bool,
+    /// Where games are installed under this root, instead of the store's
+    /// usual layout. This may use `<root>` and `<game>`.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "baseTemplate")]
+    pub base_template: Option<String>,
 }
 
 impl RootsConfig {
@@ -42,9 +99,40 @@ impl RootsConfig {
             .map(|path| RootsConfig {
                 path,
                 store: self.store,
+                ignore: self.ignore,
+                base_template: self.base_template.clone(),
             })
             .collect()
     }
+
+    /// Expand `<base>` for a game installed in `install_dir` under this root.
+    pub fn game_base(&self, install_dir: &str) -> String {
+        match &self.base_template {
+            Some(template) => template
+                .replace("<root>", &self.path.interpret())
+                .replace("<game>", install_dir),
+            None => match self.store {
+                Store::Steam => format!("{}/steamapps/common/{}", self.path.interpret(), install_dir),
+                _ => format!("{}/{}", self.path.interpret(), install_dir),
+            },
+        }
+    }
+
+    /// The folder whose subfolders may be game install folders.
+    pub fn install_parent(&self) -> StrictPath {
+        match self.base_template.as_ref().and_then(|x| x.split_once("<game>")) {
+            Some((parent, _)) => StrictPath::new(
+                parent
+                    .replace("<root>", &self.path.interpret())
+                    .trim_end_matches(['/', '\\'])
+                    .to_string(),
+            ),
+            None => match self.store {
+                Store::Steam => self.path.joined("steamapps/common"),
+                _ => self.path.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -71,8 +159,40 @@ pub struct BackupFilter {
     pub ignored_paths: Vec<StrictPath>,
     #[serde(default, rename = "ignoredRegistry")]
     pub ignored_registry: Vec<RegistryItem>,
+    /// Per game, file name patterns to include. Other files are skipped
+    /// for games listed here.
+    #[serde(
+        default,
+        rename = "allowedFiles",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty"
+    )]
+    pub allowed_files: std::collections::BTreeMap<String, Vec<String>>,
+    /// Skip hidden files and common OS clutter like `Thumbs.db`.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "skipHidden"
+    )]
+    pub skip_hidden: bool,
+    /// File name patterns to skip along with `JUNK_FILES` when `skip_hidden` is enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "junkFiles")]
+    pub junk_files: Vec<String>,
+    /// Games whose matched save folders are backed up in full,
+    /// rather than only the files that the manifest rules match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "wholeDirectories")]
+    pub whole_directories: Vec<String>,
 }
 
+/// File names that operating systems and file browsers leave behind in folders.
+pub const JUNK_FILES: &[&str] = &[
+    "desktop.ini",
+    "Thumbs.db",
+    "ehthumbs.db",
+    ".DS_Store",
+    "._*",
+    ".directory",
+];
+
 impl BackupFilter {
     pub fn is_path_ignored(&self, item: &StrictPath) -> bool {
         let interpreted = item.interpret();
@@ -81,6 +201,43 @@ impl BackupFilter {
             .any(|x| x.is_prefix_of(item) || x.interpret() == interpreted)
     }
 
+    pub fn is_whole_directory(&self, game: &str) -> bool {
+        self.whole_directories.iter().any(|x| x == game)
+    }
+
+    pub fn is_file_allowed(&self, game: &str, file: &StrictPath) -> bool {
+        match self.allowed_files.get(game) {
+            Some(patterns) if !patterns.is_empty() => file_name_matches(file, patterns),
+            _ => true,
+        }
+    }
+
+    pub fn is_junk(&self, file: &StrictPath) -> bool {
+        if !self.skip_hidden {
+            return false;
+        }
+        let file_name = match file.as_std_path_buf().file_name() {
+            Some(x) => x.to_string_lossy().to_string(),
+            None => return false,
+        };
+        if file_name.starts_with('.') || is_hidden_by_attribute(file) {
+            return true;
+        }
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        JUNK_FILES
+            .iter()
+            .copied()
+            .chain(self.junk_files.iter().map(|x| x.as_str()))
+            .any(|pattern| {
+                glob::Pattern::new(pattern)
+                    .map(|x| x.matches_with(&file_name, options))
+                    .unwrap_or(false)
+            })
+    }
+
     #[allow(dead_code)]
     pub fn is_registry_ignored(&self, item: &RegistryItem) -> bool {
         let interpreted = item.interpret();
@@ -90,6 +247,34 @@ impl BackupFilter {
     }
 }
 
+/// Whether a file's name matches any of the patterns (e.g., `*.sav`).
+pub fn file_name_matches(file: &StrictPath, patterns: &[String]) -> bool {
+    let file_name = match file.as_std_path_buf().file_name() {
+        Some(x) => x.to_string_lossy().to_string(),
+        None => return false,
+    };
+    patterns.iter().any(|pattern| {
+        glob::Pattern::new(pattern)
+            .map(|x| x.matches(&file_name))
+            .unwrap_or(false)
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn is_hidden_by_attribute(file: &StrictPath) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+    file.metadata()
+        .map(|x| x.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_hidden_by_attribute(_file: &StrictPath) -> bool {
+    false
+}
+
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ToggledPaths(std::collections::BTreeMap<String, std::collections::BTreeMap<StrictPath, bool>>);
 
@@ -159,6 +344,272 @@ pub struct BackupConfig {
     pub sort: Sort,
     #[serde(default)]
     pub retention: Retention,
+    /// Try a copy-on-write clone before falling back to a normal copy.
+    #[serde(default = "crate::serialization::default_true")]
+    pub reflink: bool,
+    /// Buffer size in bytes for normal copies. When unset, the OS default copy is used.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "copyBufferSize")]
+    pub copy_buffer_size: Option<usize>,
+    #[serde(default)]
+    pub layout: BackupFolderLayout,
+    #[serde(default)]
+    pub format: BackupFormat,
+    /// Games larger than this many bytes are deselected until confirmed.
+    #[serde(default, rename = "sizeGuard", skip_serializing_if = "Option::is_none")]
+    pub size_guard: Option<u64>,
+    /// Games that have been confirmed to back up despite the size guard.
+    #[serde(
+        default,
+        rename = "sizeGuardConfirmed",
+        serialize_with = "crate::serialization::ordered_set",
+        skip_serializing_if = "crate::serialization::is_empty_set"
+    )]
+    pub size_guard_confirmed: std::collections::HashSet<String>,
+    /// Include save files.
+    #[serde(default = "crate::serialization::default_true")]
+    pub files: bool,
+    /// Include registry data.
+    #[serde(default = "crate::serialization::default_true")]
+    pub registry: bool,
+    /// How many files may be copied at the same time. Zero means no limit.
+    #[serde(default = "default_io_concurrency", rename = "ioConcurrency")]
+    pub io_concurrency: usize,
+    /// How many games may be scanned at the same time. When unset, there is no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "scanConcurrency")]
+    pub scan_concurrency: Option<usize>,
+    /// Encrypt the files backed up to `path` with a passphrase, which is requested for each backup.
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub encrypt: bool,
+    /// Games whose last backup is older than this are considered stale.
+    #[serde(default = "default_stale_after_days", rename = "staleAfterDays")]
+    pub stale_after_days: u32,
+    /// Shell command to run before a backup in the GUI.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "preBackupCommand")]
+    pub pre_backup_command: Option<String>,
+    /// Shell command to run after a backup in the GUI.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "postBackupCommand")]
+    pub post_backup_command: Option<String>,
+    /// How often the GUI should back up on its own while running.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::serialization::optional_hours"
+    )]
+    pub schedule: Option<std::time::Duration>,
+    /// Only back up files that were modified within this many days.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "sinceDays")]
+    pub since_days: Option<u32>,
+    /// Stop backing up more games once the backup folder would grow past this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "maxTotalBytes")]
+    pub max_total_bytes: Option<u64>,
+    /// Check each file after writing it to the backup and mark the game as failed if it doesn't match.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "verifyAfterWrite"
+    )]
+    pub verify_after_write: bool,
+    /// Pace file copies so that backups write no more than this many bytes per second overall.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "maxBytesPerSecond")]
+    pub max_bytes_per_second: Option<u64>,
+    /// How many times to try copying each file before marking it as failed.
+    #[serde(default = "default_copy_attempts", rename = "copyAttempts")]
+    pub copy_attempts: u32,
+    #[serde(
+        default,
+        rename = "runningGames",
+        skip_serializing_if = "RunningGamePolicy::is_default"
+    )]
+    pub running_games: RunningGamePolicy,
+    /// Also back up Steam's per-game settings as a pseudo-game.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "steamAccountSettings"
+    )]
+    pub steam_account_settings: bool,
+    /// Per-game backup folders to use instead of `path`.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub destinations: std::collections::BTreeMap<String, StrictPath>,
+    /// Values from `destinations` whose backups should be encrypted, like `encrypt` does for `path`.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::BTreeSet::is_empty",
+        rename = "encryptedDestinations"
+    )]
+    pub encrypted_destinations: std::collections::BTreeSet<StrictPath>,
+    /// Which games to select when a preview fills the list.
+    /// When unset, games keep whatever selection they had before.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "defaultSelection")]
+    pub default_selection: Option<DefaultSelection>,
+    /// Store identical files only once across all game folders.
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub dedupe: bool,
+}
+
+fn default_io_concurrency() -> usize {
+    4
+}
+
+fn default_copy_attempts() -> u32 {
+    3
+}
+
+fn default_stale_after_days() -> u32 {
+    30
+}
+
+/// How game folders are arranged inside of the backup target.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum BackupFolderLayout {
+    /// `<target>/<game>`
+    #[default]
+    #[serde(rename = "byGame")]
+    ByGame,
+    /// `<target>/<store>/<game>`
+    #[serde(rename = "byStore")]
+    ByStore,
+}
+
+impl BackupFolderLayout {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How each game's backup data is stored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum BackupFormat {
+    /// Plain files in the game folder.
+    #[default]
+    #[serde(rename = "simple")]
+    Simple,
+    /// A single `backup.tar.gz` in the game folder.
+    #[serde(rename = "tarGz")]
+    TarGz,
+    /// Plain files in a `files` subfolder, with the mapping in a `meta` subfolder.
+    #[serde(rename = "split")]
+    Split,
+}
+
+impl BackupFormat {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do with a game that seems to be running when it would be backed up,
+/// since its saves could be caught halfway through being written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum RunningGamePolicy {
+    /// Back it up anyway, but point it out.
+    #[default]
+    #[serde(rename = "warn")]
+    Warn,
+    /// Leave it out of the backup.
+    #[serde(rename = "skip")]
+    Skip,
+    /// Don't check for running games.
+    #[serde(rename = "ignore")]
+    Ignore,
+}
+
+impl RunningGamePolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do with files in a game's save folders that aren't in the backup.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum RestoreStrategy {
+    /// Leave them alone, so that the backup is merged into the folders.
+    #[default]
+    #[serde(rename = "merge")]
+    Merge,
+    /// Delete them, so that the folders match the backup exactly.
+    #[serde(rename = "mirror")]
+    Mirror,
+}
+
+impl RestoreStrategy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Which games to select when a backup preview fills the list.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum DefaultSelection {
+    #[serde(rename = "all")]
+    All,
+    #[serde(rename = "none")]
+    None,
+    /// Games whose saves changed since their last backup, or that were never backed up.
+    #[serde(rename = "changed")]
+    Changed,
+}
+
+/// Which operations this installation may perform,
+/// as a safeguard for machines that only ever back up or restore.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum OperationMode {
+    #[default]
+    #[serde(rename = "normal")]
+    Normal,
+    #[serde(rename = "backupOnly")]
+    BackupOnly,
+    #[serde(rename = "restoreOnly")]
+    RestoreOnly,
+    #[serde(rename = "readOnly")]
+    ReadOnly,
+}
+
+impl OperationMode {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn allows_backup(&self) -> bool {
+        !matches!(self, Self::RestoreOnly | Self::ReadOnly)
+    }
+
+    pub fn allows_restore(&self) -> bool {
+        !matches!(self, Self::BackupOnly | Self::ReadOnly)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        *self == Self::ReadOnly
+    }
+
+    /// Previews never write anything, so read-only mode still allows them.
+    pub fn allows(&self, restoring: bool, preview: bool) -> bool {
+        match (restoring, preview) {
+            (_, true) if self.is_read_only() => true,
+            (false, _) => self.allows_backup(),
+            (true, _) => self.allows_restore(),
+        }
+    }
+}
+
+/// Options for the system tray icon in the GUI.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrayConfig {
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub enabled: bool,
+    /// Only applies when the tray is available; otherwise the window could not be reopened.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "startMinimized"
+    )]
+    pub start_minimized: bool,
+}
+
+impl TrayConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -174,6 +625,56 @@ pub struct RestoreConfig {
     pub redirects: Vec<RedirectConfig>,
     #[serde(default)]
     pub sort: Sort,
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "onlyChanged"
+    )]
+    pub only_changed: bool,
+    /// Restore into a staging area first, then move the files into place separately.
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub stage: bool,
+    #[serde(default, skip_serializing_if = "RestoreStrategy::is_default")]
+    pub strategy: RestoreStrategy,
+    /// Keep `path` the same as the backup path.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serialization::is_false",
+        rename = "linkPaths"
+    )]
+    pub link_paths: bool,
+    /// Include save files.
+    #[serde(default = "crate::serialization::default_true")]
+    pub files: bool,
+    /// Include registry data.
+    #[serde(default = "crate::serialization::default_true")]
+    pub registry: bool,
+    /// Only overwrite an existing file if the backup is newer by at least this many seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "overwriteNewerBy")]
+    pub overwrite_newer_by: Option<u64>,
+    /// Per game, file name patterns for text files whose line endings
+    /// should be converted to the current OS's style when restored.
+    #[serde(
+        default,
+        rename = "normalizeLineEndings",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty"
+    )]
+    pub normalize_line_endings: std::collections::BTreeMap<String, Vec<String>>,
+    /// Per game, a regular expression whose first capture group names the save slot
+    /// of a file. Games not listed here use `DEFAULT_SAVE_SLOT_PATTERN`.
+    #[serde(
+        default,
+        rename = "saveSlotPatterns",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty"
+    )]
+    pub save_slot_patterns: std::collections::BTreeMap<String, String>,
+    /// Per game, save slots to leave alone when restoring.
+    #[serde(
+        default,
+        rename = "skippedSaveSlots",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty"
+    )]
+    pub skipped_save_slots: std::collections::BTreeMap<String, std::collections::BTreeSet<String>>,
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -187,11 +688,55 @@ pub struct CustomGame {
     pub registry: Vec<String>,
 }
 
+impl RestoreConfig {
+    pub fn save_slot_filter(&self, game: &str) -> SaveSlotFilter {
+        SaveSlotFilter::new(
+            self.save_slot_patterns
+                .get(game)
+                .map(|x| x.as_str())
+                .unwrap_or(DEFAULT_SAVE_SLOT_PATTERN),
+            self.skipped_save_slots.get(game).cloned().unwrap_or_default(),
+        )
+    }
+
+    pub fn is_save_slot_skipped(&self, game: &str, slot: &str) -> bool {
+        self.skipped_save_slots
+            .get(game)
+            .map(|x| x.contains(slot))
+            .unwrap_or(false)
+    }
+
+    pub fn set_save_slot_skipped(&mut self, game: &str, slot: &str, skipped: bool) {
+        if skipped {
+            self.skipped_save_slots
+                .entry(game.to_string())
+                .or_default()
+                .insert(slot.to_string());
+        } else if let Some(slots) = self.skipped_save_slots.get_mut(game) {
+            slots.remove(slot);
+            if slots.is_empty() {
+                self.skipped_save_slots.remove(game);
+            }
+        }
+    }
+
+    pub fn line_ending_patterns(&self, game: &str) -> &[String] {
+        self.normalize_line_endings
+            .get(game)
+            .map(|x| x.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
 impl Default for ManifestConfig {
     fn default() -> Self {
         Self {
             url: MANIFEST_URL.to_string(),
             etag: None,
+            updated: None,
+            excluded_categories: Default::default(),
+            secondary: vec![],
+            checksum_url: None,
         }
     }
 }
@@ -207,6 +752,32 @@ impl Default for BackupConfig {
             toggled_registry: Default::default(),
             sort: Default::default(),
             retention: Retention::default(),
+            reflink: true,
+            copy_buffer_size: None,
+            layout: Default::default(),
+            format: Default::default(),
+            size_guard: None,
+            size_guard_confirmed: Default::default(),
+            files: true,
+            registry: true,
+            io_concurrency: default_io_concurrency(),
+            scan_concurrency: None,
+            encrypt: false,
+            stale_after_days: default_stale_after_days(),
+            pre_backup_command: None,
+            post_backup_command: None,
+            schedule: None,
+            since_days: None,
+            max_total_bytes: None,
+            verify_after_write: false,
+            max_bytes_per_second: None,
+            copy_attempts: 3,
+            running_games: Default::default(),
+            steam_account_settings: false,
+            destinations: Default::default(),
+            encrypted_destinations: Default::default(),
+            default_selection: None,
+            dedupe: false,
         }
     }
 }
@@ -218,6 +789,16 @@ impl Default for RestoreConfig {
             ignored_games: std::collections::HashSet::new(),
             redirects: vec![],
             sort: Default::default(),
+            only_changed: false,
+            stage: false,
+            strategy: Default::default(),
+            link_paths: false,
+            files: true,
+            registry: true,
+            overwrite_newer_by: None,
+            normalize_line_endings: Default::default(),
+            save_slot_patterns: Default::default(),
+            skipped_save_slots: Default::default(),
         }
     }
 }
@@ -229,35 +810,143 @@ impl Config {
         path
     }
 
-    pub fn save(&self) {
-        let new_content = serde_yaml::to_string(&self).unwrap();
+    pub fn path() -> StrictPath {
+        StrictPath::from_std_path_buf(&Self::file())
+    }
+
+    /// Where we keep the last good config before replacing it.
+    fn backup_file(file: &std::path::Path) -> std::path::PathBuf {
+        file.with_extension("yaml.bak")
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        self.save_to(&Self::file())
+    }
 
-        if let Ok(old) = Self::load() {
-            let old_content = serde_yaml::to_string(&old).unwrap();
-            if old_content == new_content {
-                return;
+    /// The new content is written to a temporary file and then renamed over the old one,
+    /// so that the config is never left half-written if Ludusavi stops in the middle.
+    fn save_to(&self, file: &std::path::Path) -> Result<(), Error> {
+        let failed = || Error::ConfigSaveFailed {
+            path: StrictPath::from(file.to_path_buf()),
+        };
+        let new_content = serde_yaml::to_string(&self).map_err(|_| failed())?;
+
+        let old_content = std::fs::read_to_string(file)
+            .ok()
+            .filter(|x| Self::load_from_string(x).is_ok());
+        if let Some(old) = old_content.as_ref().and_then(|x| Self::load_from_string(x).ok()) {
+            if serde_yaml::to_string(&old).ok().as_ref() == Some(&new_content) {
+                return Ok(());
             }
         }
 
-        if std::fs::create_dir_all(app_dir()).is_ok() {
-            std::fs::write(Self::file(), new_content.as_bytes()).unwrap();
+        // Never replace a config with something we couldn't read back.
+        if Self::load_from_string(&new_content).is_err() {
+            return Err(failed());
+        }
+
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).map_err(|_| failed())?;
         }
+
+        let temp = file.with_extension("yaml.tmp");
+        let written = std::fs::File::create(&temp).and_then(|mut handle| {
+            use std::io::Write;
+            handle.write_all(new_content.as_bytes())?;
+            handle.sync_all()
+        });
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+            return Err(failed());
+        }
+        if let Some(old) = old_content {
+            let _ = std::fs::write(Self::backup_file(file), old.as_bytes());
+        }
+        std::fs::rename(&temp, file).map_err(|_| failed())
     }
 
     pub fn load() -> Result<Self, Error> {
-        if !std::path::Path::new(&Self::file()).exists() {
+        Self::load_from(&Self::file())
+    }
+
+    /// If the config can't be read, then we fall back to the last good one.
+    fn load_from(file: &std::path::Path) -> Result<Self, Error> {
+        let backup = Self::backup_file(file);
+        if !file.exists() && !backup.exists() {
             let mut starter = Self::default();
             starter.add_common_roots();
             return Ok(starter);
         }
-        let content = std::fs::read_to_string(Self::file()).unwrap();
-        Self::load_from_string(&content)
+
+        let loaded = std::fs::read_to_string(file)
+            .map_err(|e| Error::ConfigInvalid { why: e.to_string() })
+            .and_then(|content| Self::load_from_string(&content));
+        match loaded {
+            Ok(config) => Ok(config),
+            Err(e) => match std::fs::read_to_string(&backup) {
+                Ok(content) => Self::load_from_string(&content).map_err(|_| e),
+                Err(_) => Err(e),
+            },
+        }
     }
 
     pub fn load_from_string(content: &str) -> Result<Self, Error> {
         serde_yaml::from_str(content).map_err(|e| Error::ConfigInvalid { why: format!("{}", e) })
     }
 
+    /// Write the whole config to a file of the user's choosing,
+    /// so that it can be imported on another machine.
+    pub fn export(&self, file: &StrictPath) -> Result<(), Error> {
+        let failed = || Error::ConfigExportFailed { path: file.clone() };
+        let content = serde_yaml::to_string(&self).map_err(|_| failed())?;
+        file.create_parent_dir()
+            .and_then(|_| std::fs::write(file.interpret(), content.as_bytes()))
+            .map_err(|_| failed())
+    }
+
+    pub fn import(file: &StrictPath) -> Result<Self, Error> {
+        let content =
+            std::fs::read_to_string(file.interpret()).map_err(|e| Error::ConfigInvalid { why: e.to_string() })?;
+        Self::load_from_string(&content)
+    }
+
+    /// Bring in the roots, redirects, custom games, and game selections from
+    /// an imported config, while keeping the current paths and options.
+    /// Custom games with the same name are replaced by the imported ones.
+    pub fn merge(&mut self, other: Self) {
+        for root in other.roots {
+            if !self.roots.iter().any(|x| x.path == root.path && x.store == root.store) {
+                self.roots.push(root);
+            }
+        }
+        for redirect in other.restore.redirects {
+            if !self.restore.redirects.contains(&redirect) {
+                self.restore.redirects.push(redirect);
+            }
+        }
+        for game in other.custom_games {
+            match self.custom_games.iter_mut().find(|x| x.name == game.name) {
+                Some(existing) => *existing = game,
+                None => self.custom_games.push(game),
+            }
+        }
+        for game in other.pinned_games {
+            self.pin_game(&game);
+        }
+        self.backup.ignored_games.extend(other.backup.ignored_games);
+        self.restore.ignored_games.extend(other.restore.ignored_games);
+        for path in other.backup.filter.ignored_paths {
+            if !self.backup.filter.ignored_paths.contains(&path) {
+                self.backup.filter.ignored_paths.push(path);
+            }
+        }
+        for item in other.backup.filter.ignored_registry {
+            if !self.backup.filter.ignored_registry.contains(&item) {
+                self.backup.filter.ignored_registry.push(item);
+            }
+        }
+    }
+
     pub fn find_missing_roots(&self) -> Vec<RootsConfig> {
         let mut pf32 = "C:/Program Files (x86)".to_string();
         let mut pf64 = "C:/Program Files".to_string();
@@ -360,6 +1049,8 @@ impl Config {
                 roots.push(RootsConfig {
                     path: sp.rendered(),
                     store,
+                    ignore: false,
+                    base_template: None,
                 });
             }
             checked.insert(sp.interpreted());
@@ -384,6 +1075,85 @@ impl Config {
         self.backup.ignored_games.insert(name.to_owned());
     }
 
+    /// Select or deselect a game according to `backup.defaultSelection`.
+    /// Returns whether its selection changed.
+    pub fn apply_default_selection(&mut self, name: &str, changed: bool) -> bool {
+        let enabled = match self.backup.default_selection {
+            None => return false,
+            Some(DefaultSelection::All) => true,
+            Some(DefaultSelection::None) => false,
+            Some(DefaultSelection::Changed) => changed,
+        };
+        if enabled == self.is_game_enabled_for_backup(name) {
+            return false;
+        }
+        if enabled {
+            self.enable_game_for_backup(name);
+        } else {
+            self.disable_game_for_backup(name);
+        }
+        true
+    }
+
+    /// Whether a game's data is too large to back up without confirmation.
+    pub fn exceeds_size_guard(&self, name: &str, bytes: u64) -> bool {
+        match self.backup.size_guard {
+            Some(limit) => bytes > limit && !self.backup.size_guard_confirmed.contains(name),
+            None => false,
+        }
+    }
+
+    /// The commands that a backup would run, for review before confirming it.
+    pub fn backup_commands(&self) -> Vec<&str> {
+        [&self.backup.pre_backup_command, &self.backup.post_backup_command]
+            .into_iter()
+            .filter_map(|x| x.as_deref())
+            .filter(|x| !x.trim().is_empty())
+            .collect()
+    }
+
+    /// Whether a game has gone too long without a backup.
+    pub fn is_backup_stale(
+        &self,
+        last_backup: &chrono::DateTime<chrono::Utc>,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        *now - *last_backup > chrono::Duration::days(self.backup.stale_after_days as i64)
+    }
+
+    pub fn confirm_size_guard(&mut self, name: &str) {
+        self.backup.size_guard_confirmed.insert(name.to_owned());
+    }
+
+    pub fn is_game_pinned(&self, name: &str) -> bool {
+        self.pinned_games.iter().any(|x| x == name)
+    }
+
+    pub fn pin_game(&mut self, name: &str) {
+        if !self.is_game_pinned(name) {
+            self.pinned_games.push(name.to_owned());
+        }
+    }
+
+    pub fn unpin_game(&mut self, name: &str) {
+        self.pinned_games.retain(|x| x != name);
+    }
+
+    pub fn backup_destination(&self, name: &str) -> Option<&StrictPath> {
+        self.backup.destinations.get(name)
+    }
+
+    /// Whether a backup may encrypt anything, either in the backup path
+    /// or in one of the destinations.
+    pub fn backup_encrypts(&self) -> bool {
+        self.backup.encrypt
+            || self
+                .backup
+                .destinations
+                .values()
+                .any(|x| self.backup.encrypted_destinations.iter().any(|y| y.same_path(x)))
+    }
+
     pub fn is_game_enabled_for_restore(&self, name: &str) -> bool {
         !self.restore.ignored_games.contains(name)
     }
@@ -640,6 +1410,10 @@ mod tests {
                 manifest: ManifestConfig {
                     url: s("example.com"),
                     etag: None,
+                    updated: None,
+                    excluded_categories: Default::default(),
+                    secondary: vec![],
+                    checksum_url: None,
                 },
                 roots: vec![],
                 backup: BackupConfig {
@@ -655,13 +1429,52 @@ mod tests {
                     toggled_registry: Default::default(),
                     sort: Default::default(),
                     retention: Retention::default(),
+                    reflink: true,
+                    copy_buffer_size: None,
+                    layout: Default::default(),
+                    format: Default::default(),
+                    size_guard: None,
+                    size_guard_confirmed: Default::default(),
+                    files: true,
+                    registry: true,
+                    io_concurrency: default_io_concurrency(),
+                    scan_concurrency: None,
+                    encrypt: false,
+                    stale_after_days: default_stale_after_days(),
+                    pre_backup_command: None,
+                    post_backup_command: None,
+                    schedule: None,
+                    since_days: None,
+                    max_total_bytes: None,
+                    verify_after_write: false,
+                    max_bytes_per_second: None,
+                    copy_attempts: 3,
+                    running_games: Default::default(),
+                    steam_account_settings: false,
+                    destinations: Default::default(),
+                    encrypted_destinations: Default::default(),
+                    default_selection: None,
+                    dedupe: false,
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
                     ignored_games: std::collections::HashSet::new(),
                     redirects: vec![],
                     sort: Default::default(),
+                    only_changed: false,
+                    stage: false,
+                    strategy: Default::default(),
+                    link_paths: false,
+                    files: true,
+                    registry: true,
+                    overwrite_newer_by: None,
+                    normalize_line_endings: Default::default(),
+                    save_slot_patterns: Default::default(),
+                    skipped_save_slots: Default::default(),
                 },
+                tray: Default::default(),
+                mode: Default::default(),
+                pinned_games: vec![],
                 custom_games: vec![],
             },
             config,
@@ -675,11 +1488,19 @@ mod tests {
             manifest:
               url: example.com
               etag: "foo"
+              excludedCategories:
+                - multiplayer
+              secondary:
+                - path: ~/manifest.yaml
+                - url: example.com/extra.yaml
+                  etag: bar
+              checksumUrl: example.com/manifest.yaml.sha256
             roots:
               - path: ~/steam
                 store: steam
               - path: ~/other
                 store: other
+                baseTemplate: <root>/Games/<game>
             backup:
               path: ~/backup
               ignoredGames:
@@ -690,6 +1511,42 @@ mod tests {
               filter:
                 excludeOtherOsData: true
                 excludeStoreScreenshots: true
+                allowedFiles:
+                  Backup Game 1:
+                    - "*.sav"
+                skipHidden: true
+                junkFiles:
+                  - "*.tmp"
+                wholeDirectories:
+                  - Backup Game 1
+              reflink: false
+              copyBufferSize: 1048576
+              layout: byStore
+              format: tarGz
+              sizeGuard: 1000
+              sizeGuardConfirmed:
+                - Big Game
+              registry: false
+              ioConcurrency: 1
+              scanConcurrency: 2
+              encrypt: true
+              staleAfterDays: 7
+              preBackupCommand: echo before
+              postBackupCommand: echo after
+              schedule: 1.5
+              sinceDays: 7
+              maxTotalBytes: 50000000000
+              verifyAfterWrite: true
+              maxBytesPerSecond: 10000000
+              copyAttempts: 5
+              runningGames: skip
+              steamAccountSettings: true
+              destinations:
+                Game 1: ~/cloud
+              encryptedDestinations:
+                - ~/cloud
+              defaultSelection: changed
+              dedupe: true
             restore:
               path: ~/restore
               ignoredGames:
@@ -699,6 +1556,26 @@ mod tests {
               redirects:
                 - source: ~/old
                   target: ~/new
+              onlyChanged: true
+              stage: true
+              strategy: mirror
+              linkPaths: true
+              files: false
+              overwriteNewerBy: 3600
+              normalizeLineEndings:
+                Restore Game 1:
+                  - "*.ini"
+              saveSlotPatterns:
+                Restore Game 1: "world-(\\w+)"
+              skippedSaveSlots:
+                Restore Game 1:
+                  - alpha
+            tray:
+              enabled: true
+              startMinimized: true
+            mode: backupOnly
+            pinnedGames:
+              - Game 1
             customGames:
               - name: Custom Game 1
               - name: Custom Game 2
@@ -719,15 +1596,31 @@ mod tests {
                 manifest: ManifestConfig {
                     url: s("example.com"),
                     etag: Some(s("foo")),
+                    updated: None,
+                    excluded_categories: hashset! { s("multiplayer") },
+                    secondary: vec![
+                        SecondaryManifestConfig::Local {
+                            path: StrictPath::new(s("~/manifest.yaml")),
+                        },
+                        SecondaryManifestConfig::Remote {
+                            url: s("example.com/extra.yaml"),
+                            etag: Some(s("bar")),
+                        },
+                    ],
+                    checksum_url: Some(s("example.com/manifest.yaml.sha256")),
                 },
                 roots: vec![
                     RootsConfig {
                         path: StrictPath::new(s("~/steam")),
                         store: Store::Steam,
+                        ignore: false,
+                        base_template: None,
                     },
                     RootsConfig {
                         path: StrictPath::new(s("~/other")),
                         store: Store::Other,
+                        ignore: false,
+                        base_template: Some(s("<root>/Games/<game>")),
                     },
                 ],
                 backup: BackupConfig {
@@ -740,12 +1633,46 @@ mod tests {
                     filter: BackupFilter {
                         exclude_other_os_data: true,
                         exclude_store_screenshots: true,
+                        allowed_files: maplit::btreemap! {
+                            s("Backup Game 1") => vec![s("*.sav")],
+                        },
+                        skip_hidden: true,
+                        junk_files: vec![s("*.tmp")],
+                        whole_directories: vec![s("Backup Game 1")],
                         ..Default::default()
                     },
                     toggled_paths: Default::default(),
                     toggled_registry: Default::default(),
                     sort: Default::default(),
                     retention: Retention::default(),
+                    reflink: false,
+                    copy_buffer_size: Some(1048576),
+                    layout: BackupFolderLayout::ByStore,
+                    format: BackupFormat::TarGz,
+                    size_guard: Some(1000),
+                    size_guard_confirmed: hashset! { s("Big Game") },
+                    files: true,
+                    registry: false,
+                    io_concurrency: 1,
+                    scan_concurrency: Some(2),
+                    encrypt: true,
+                    stale_after_days: 7,
+                    pre_backup_command: Some(s("echo before")),
+                    post_backup_command: Some(s("echo after")),
+                    schedule: Some(std::time::Duration::from_secs(5400)),
+                    since_days: Some(7),
+                    max_total_bytes: Some(50_000_000_000),
+                    verify_after_write: true,
+                    max_bytes_per_second: Some(10_000_000),
+                    copy_attempts: 5,
+                    running_games: RunningGamePolicy::Skip,
+                    steam_account_settings: true,
+                    destinations: maplit::btreemap! {
+                        s("Game 1") => StrictPath::new(s("~/cloud")),
+                    },
+                    encrypted_destinations: maplit::btreeset! { StrictPath::new(s("~/cloud")) },
+                    default_selection: Some(DefaultSelection::Changed),
+                    dedupe: true,
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
@@ -758,7 +1685,29 @@ mod tests {
                         target: StrictPath::new(s("~/new")),
                     }],
                     sort: Default::default(),
+                    only_changed: true,
+                    stage: true,
+                    strategy: RestoreStrategy::Mirror,
+                    link_paths: true,
+                    files: false,
+                    registry: true,
+                    overwrite_newer_by: Some(3600),
+                    normalize_line_endings: maplit::btreemap! {
+                        s("Restore Game 1") => vec![s("*.ini")],
+                    },
+                    save_slot_patterns: maplit::btreemap! {
+                        s("Restore Game 1") => s("world-(\\w+)"),
+                    },
+                    skipped_save_slots: maplit::btreemap! {
+                        s("Restore Game 1") => maplit::btreeset! { s("alpha") },
+                    },
+                },
+                tray: TrayConfig {
+                    enabled: true,
+                    start_minimized: true,
                 },
+                mode: OperationMode::BackupOnly,
+                pinned_games: vec![s("Game 1")],
                 custom_games: vec![
                     CustomGame {
                         name: s("Custom Game 1"),
@@ -804,10 +1753,16 @@ mod tests {
                 manifest: ManifestConfig {
                     url: s("example.com"),
                     etag: None,
+                    updated: None,
+                    excluded_categories: Default::default(),
+                    secondary: vec![],
+                    checksum_url: None,
                 },
                 roots: vec![RootsConfig {
                     path: StrictPath::new(s("~/other")),
                     store: Store::Other,
+                    ignore: false,
+                    base_template: None,
                 }],
                 backup: BackupConfig {
                     path: StrictPath::new(s("~/backup")),
@@ -822,19 +1777,66 @@ mod tests {
                     toggled_registry: Default::default(),
                     sort: Default::default(),
                     retention: Retention::default(),
+                    reflink: true,
+                    copy_buffer_size: None,
+                    layout: Default::default(),
+                    format: Default::default(),
+                    size_guard: None,
+                    size_guard_confirmed: Default::default(),
+                    files: true,
+                    registry: true,
+                    io_concurrency: default_io_concurrency(),
+                    scan_concurrency: None,
+                    encrypt: false,
+                    stale_after_days: default_stale_after_days(),
+                    pre_backup_command: None,
+                    post_backup_command: None,
+                    schedule: None,
+                    since_days: None,
+                    max_total_bytes: None,
+                    verify_after_write: false,
+                    max_bytes_per_second: None,
+                    copy_attempts: 3,
+                    running_games: Default::default(),
+                    steam_account_settings: false,
+                    destinations: Default::default(),
+                    encrypted_destinations: Default::default(),
+                    default_selection: None,
+                    dedupe: false,
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
                     ignored_games: std::collections::HashSet::new(),
                     redirects: vec![],
                     sort: Default::default(),
+                    only_changed: false,
+                    stage: false,
+                    strategy: Default::default(),
+                    link_paths: false,
+                    files: true,
+                    registry: true,
+                    overwrite_newer_by: None,
+                    normalize_line_endings: Default::default(),
+                    save_slot_patterns: Default::default(),
+                    skipped_save_slots: Default::default(),
                 },
+                tray: Default::default(),
+                mode: Default::default(),
+                pinned_games: vec![],
                 custom_games: vec![],
             },
             config,
         );
     }
 
+    #[test]
+    fn can_tell_if_backup_is_stale() {
+        let config = Config::default();
+        let now = chrono::Utc::now();
+        assert!(!config.is_backup_stale(&(now - chrono::Duration::days(30)), &now));
+        assert!(config.is_backup_stale(&(now - chrono::Duration::days(31)), &now));
+    }
+
     #[test]
     fn can_be_serialized() {
         assert_eq!(
@@ -848,6 +1850,7 @@ roots:
     store: steam
   - path: ~/other
     store: other
+    baseTemplate: "<root>/Games/<game>"
 backup:
   path: ~/backup
   ignoredGames:
@@ -868,6 +1871,14 @@ backup:
   retention:
     full: 1
     differential: 0
+  reflink: true
+  layout: byGame
+  format: simple
+  files: true
+  registry: true
+  ioConcurrency: 4
+  staleAfterDays: 30
+  copyAttempts: 3
 restore:
   path: ~/restore
   ignoredGames:
@@ -880,6 +1891,8 @@ restore:
   sort:
     key: name
     reversed: false
+  files: true
+  registry: true
 customGames:
   - name: Custom Game 1
     files: []
@@ -899,15 +1912,23 @@ customGames:
                 manifest: ManifestConfig {
                     url: s("example.com"),
                     etag: Some(s("foo")),
+                    updated: None,
+                    excluded_categories: Default::default(),
+                    secondary: vec![],
+                    checksum_url: None,
                 },
                 roots: vec![
                     RootsConfig {
                         path: StrictPath::new(s("~/steam")),
                         store: Store::Steam,
+                        ignore: false,
+                        base_template: None,
                     },
                     RootsConfig {
                         path: StrictPath::new(s("~/other")),
                         store: Store::Other,
+                        ignore: false,
+                        base_template: Some(s("<root>/Games/<game>")),
                     },
                 ],
                 backup: BackupConfig {
@@ -927,6 +1948,32 @@ customGames:
                     toggled_registry: Default::default(),
                     sort: Default::default(),
                     retention: Retention::default(),
+                    reflink: true,
+                    copy_buffer_size: None,
+                    layout: Default::default(),
+                    format: Default::default(),
+                    size_guard: None,
+                    size_guard_confirmed: Default::default(),
+                    files: true,
+                    registry: true,
+                    io_concurrency: default_io_concurrency(),
+                    scan_concurrency: None,
+                    encrypt: false,
+                    stale_after_days: default_stale_after_days(),
+                    pre_backup_command: None,
+                    post_backup_command: None,
+                    schedule: None,
+                    since_days: None,
+                    max_total_bytes: None,
+                    verify_after_write: false,
+                    max_bytes_per_second: None,
+                    copy_attempts: 3,
+                    running_games: Default::default(),
+                    steam_account_settings: false,
+                    destinations: Default::default(),
+                    encrypted_destinations: Default::default(),
+                    default_selection: None,
+                    dedupe: false,
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
@@ -940,7 +1987,20 @@ customGames:
                         target: StrictPath::new(s("~/new")),
                     }],
                     sort: Default::default(),
+                    only_changed: false,
+                    stage: false,
+                    strategy: Default::default(),
+                    link_paths: false,
+                    files: true,
+                    registry: true,
+                    overwrite_newer_by: None,
+                    normalize_line_endings: Default::default(),
+                    save_slot_patterns: Default::default(),
+                    skipped_save_slots: Default::default(),
                 },
+                tray: Default::default(),
+                mode: Default::default(),
+                pinned_games: vec![],
                 custom_games: vec![
                     CustomGame {
                         name: s("Custom Game 1"),
@@ -961,6 +2021,209 @@ customGames:
         );
     }
 
+    #[test]
+    fn falls_back_to_last_good_config_when_file_is_corrupt() {
+        let dir = std::env::temp_dir().join("ludusavi-test-config-save");
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = dir.join("config.yaml");
+
+        let mut first = Config::default();
+        first.backup.path = StrictPath::new(s("~/first"));
+        first.save_to(&file).unwrap();
+        let mut second = Config::default();
+        second.backup.path = StrictPath::new(s("~/second"));
+        second.save_to(&file).unwrap();
+
+        assert_eq!(second, Config::load_from(&file).unwrap());
+        assert!(!file.with_extension("yaml.tmp").exists());
+
+        std::fs::write(&file, "backup: [").unwrap();
+        assert_eq!(first, Config::load_from(&file).unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_failure_when_config_cannot_be_saved() {
+        let dir = std::env::temp_dir().join("ludusavi-test-config-save-failure");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let blocker = dir.join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let file = blocker.join("config.yaml");
+
+        assert_eq!(
+            Err(Error::ConfigSaveFailed {
+                path: StrictPath::from(file.clone())
+            }),
+            Config::default().save_to(&file)
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn can_export_and_import_config() {
+        let dir = std::env::temp_dir().join("ludusavi-test-config-export");
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = StrictPath::from(dir.join("exported.yaml"));
+
+        let mut config = Config::default();
+        config.backup.path = StrictPath::new(s("~/exported"));
+        config.export(&file).unwrap();
+        assert_eq!(config, Config::import(&file).unwrap());
+
+        std::fs::write(file.interpret(), "backup: [").unwrap();
+        assert!(Config::import(&file).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn can_merge_imported_config() {
+        let mut config = Config {
+            roots: vec![RootsConfig {
+                path: StrictPath::new(s("~/steam")),
+                store: Store::Steam,
+                ignore: false,
+                base_template: None,
+            }],
+            custom_games: vec![CustomGame {
+                name: s("foo"),
+                ignore: false,
+                files: vec![s("old")],
+                registry: vec![],
+            }],
+            ..Default::default()
+        };
+        config.backup.path = StrictPath::new(s("~/current"));
+
+        let mut imported = Config {
+            roots: vec![
+                RootsConfig {
+                    path: StrictPath::new(s("~/steam")),
+                    store: Store::Steam,
+                    ignore: true,
+                    base_template: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("~/gog")),
+                    store: Store::Gog,
+                    ignore: false,
+                    base_template: None,
+                },
+            ],
+            custom_games: vec![
+                CustomGame {
+                    name: s("foo"),
+                    ignore: false,
+                    files: vec![s("new")],
+                    registry: vec![],
+                },
+                CustomGame {
+                    name: s("bar"),
+                    ignore: false,
+                    files: vec![],
+                    registry: vec![],
+                },
+            ],
+            ..Default::default()
+        };
+        imported.backup.path = StrictPath::new(s("~/imported"));
+        imported.backup.ignored_games.insert(s("baz"));
+
+        config.merge(imported);
+
+        assert_eq!(StrictPath::new(s("~/current")), config.backup.path);
+        assert_eq!(
+            vec![
+                RootsConfig {
+                    path: StrictPath::new(s("~/steam")),
+                    store: Store::Steam,
+                    ignore: false,
+                    base_template: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("~/gog")),
+                    store: Store::Gog,
+                    ignore: false,
+                    base_template: None,
+                },
+            ],
+            config.roots
+        );
+        assert_eq!(
+            vec![
+                CustomGame {
+                    name: s("foo"),
+                    ignore: false,
+                    files: vec![s("new")],
+                    registry: vec![],
+                },
+                CustomGame {
+                    name: s("bar"),
+                    ignore: false,
+                    files: vec![],
+                    registry: vec![],
+                },
+            ],
+            config.custom_games
+        );
+        assert_eq!(hashset! {s("baz")}, config.backup.ignored_games);
+    }
+
+    #[test]
+    fn read_only_mode_only_allows_previews() {
+        let mode = OperationMode::ReadOnly;
+        assert!(mode.allows(false, true));
+        assert!(mode.allows(true, true));
+        assert!(!mode.allows(false, false));
+        assert!(!mode.allows(true, false));
+
+        let mode = OperationMode::BackupOnly;
+        assert!(mode.allows(false, false));
+        assert!(!mode.allows(true, true));
+    }
+
+    #[test]
+    fn can_detect_junk_files() {
+        let mut filter = BackupFilter {
+            junk_files: vec![s("*.tmp")],
+            ..Default::default()
+        };
+        assert!(!filter.is_junk(&StrictPath::new(s("/saves/Thumbs.db"))));
+
+        filter.skip_hidden = true;
+        assert!(filter.is_junk(&StrictPath::new(s("/saves/Thumbs.db"))));
+        assert!(filter.is_junk(&StrictPath::new(s("/saves/DESKTOP.INI"))));
+        assert!(filter.is_junk(&StrictPath::new(s("/saves/.hidden"))));
+        assert!(filter.is_junk(&StrictPath::new(s("/saves/save.tmp"))));
+        assert!(!filter.is_junk(&StrictPath::new(s("/saves/save.dat"))));
+        assert!(!filter.is_junk(&StrictPath::new(s("/saves/.config/save.dat"))));
+    }
+
+    #[test]
+    fn can_apply_default_selection() {
+        let mut config = Config::default();
+        assert!(!config.apply_default_selection("foo", false));
+        assert!(config.is_game_enabled_for_backup("foo"));
+
+        config.backup.default_selection = Some(DefaultSelection::None);
+        assert!(config.apply_default_selection("foo", true));
+        assert!(!config.is_game_enabled_for_backup("foo"));
+        assert!(!config.apply_default_selection("foo", true));
+
+        config.backup.default_selection = Some(DefaultSelection::Changed);
+        config.apply_default_selection("foo", true);
+        assert!(config.is_game_enabled_for_backup("foo"));
+        config.apply_default_selection("foo", false);
+        assert!(!config.is_game_enabled_for_backup("foo"));
+
+        config.backup.default_selection = Some(DefaultSelection::All);
+        config.apply_default_selection("foo", false);
+        assert!(config.is_game_enabled_for_backup("foo"));
+    }
+
     mod ignored_paths {
         use super::*;
         use maplit::*;
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+
+#[cfg(not(test))]
+const KEY_ROUNDS: u32 = 100_000;
+#[cfg(test)]
+const KEY_ROUNDS: u32 = 1;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+/// Encrypted alongside the salt so that a wrong passphrase can be detected
+/// before touching any of the backed up files.
+const VERIFIER: &[u8] = b"ludusavi";
+
+/// What is needed to decrypt a game's backup, other than the passphrase.
+/// The key itself is never stored.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EncryptionInfo {
+    /// Base64-encoded salt for deriving the key from the passphrase.
+    pub salt: String,
+    /// Base64-encoded nonce and cipher text of a known value.
+    pub verifier: String,
+    /// Base64-encoded nonce for each encrypted file.
+    /// Keys are paths relative to the game's backup folder.
+    #[serde(default, serialize_with = "crate::serialization::ordered_map")]
+    pub nonces: HashMap<String, String>,
+}
+
+impl EncryptionInfo {
+    /// Set up encryption with a fresh salt.
+    pub fn new(passphrase: &str) -> (Self, Cipher) {
+        let mut salt = [0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = Cipher::derive(passphrase, &salt);
+        let info = Self {
+            salt: base64::encode(salt),
+            verifier: base64::encode(cipher.seal(VERIFIER)),
+            nonces: Default::default(),
+        };
+        (info, cipher)
+    }
+
+    /// Derive the key, as long as the passphrase is the one that was used for the backup.
+    pub fn unlock(&self, passphrase: &str) -> Option<Cipher> {
+        let salt = base64::decode(&self.salt).ok()?;
+        let cipher = Cipher::derive(passphrase, &salt);
+        let verifier = base64::decode(&self.verifier).ok()?;
+        (cipher.open(&verifier)? == VERIFIER).then_some(cipher)
+    }
+
+    pub fn nonce(&self, relative: &str) -> Option<Vec<u8>> {
+        self.nonces.get(relative).and_then(|x| base64::decode(x).ok())
+    }
+}
+
+#[derive(Clone)]
+pub struct Cipher(Aes256Gcm);
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cipher")
+    }
+}
+
+impl Cipher {
+    fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0; 32];
+        pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(passphrase.as_bytes(), salt, KEY_ROUNDS, &mut key);
+        Self(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    /// Returns the new nonce and the cipher text.
+    pub fn encrypt(&self, data: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let encrypted = self.0.encrypt(&nonce, data).ok()?;
+        Some((nonce.to_vec(), encrypted))
+    }
+
+    pub fn decrypt(&self, nonce: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+        if nonce.len() != NONCE_SIZE {
+            return None;
+        }
+        self.0.decrypt(Nonce::from_slice(nonce), data).ok()
+    }
+
+    /// Encrypt with the nonce prepended to the output.
+    fn seal(&self, data: &[u8]) -> Vec<u8> {
+        let (mut nonce, encrypted) = self.encrypt(data).unwrap_or_default();
+        nonce.extend(encrypted);
+        nonce
+    }
+
+    fn open(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < NONCE_SIZE {
+            return None;
+        }
+        let (nonce, encrypted) = data.split_at(NONCE_SIZE);
+        self.decrypt(nonce, encrypted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_round_trip_with_the_right_passphrase_only() {
+        let (info, cipher) = EncryptionInfo::new("secret");
+        let (nonce, encrypted) = cipher.encrypt(b"save data").unwrap();
+        assert_ne!(b"save data".to_vec(), encrypted);
+
+        let unlocked = info.unlock("secret").unwrap();
+        assert_eq!(Some(b"save data".to_vec()), unlocked.decrypt(&nonce, &encrypted));
+        assert!(info.unlock("wrong").is_none());
+    }
+}
@@ -6,20 +6,49 @@ pub mod custom_games_editor;
 pub mod custom_games_screen;
 pub mod disappearing_progress;
 pub mod file_tree;
+pub mod focus;
 pub mod game_list;
 pub mod icon;
 pub mod ignored_items_editor;
 pub mod modal;
 pub mod number_input;
 pub mod other_screen;
+pub mod palette;
 pub mod redirect_editor;
 pub mod restore_screen;
 pub mod root_editor;
+pub mod roots_screen;
+pub mod scan_progress;
+pub mod schedule;
 pub mod search;
+pub mod status_bar;
 pub mod style;
+pub mod tray;
+
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use iced::Application;
 
+use crate::prelude::Error;
+
+/// Set once the GUI has rendered for the first time. Before that, a panic most likely
+/// means that no window or renderer could be created, rather than a bug in the app.
+static RENDERER_READY: AtomicBool = AtomicBool::new(false);
+
+pub fn mark_renderer_ready() {
+    RENDERER_READY.store(true, Ordering::Relaxed);
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
 pub fn set_app_icon<T>(settings: &mut iced::Settings<T>) {
     settings.window.icon = match image::load_from_memory(include_bytes!("../assets/icon.png")) {
         Ok(buffer) => {
@@ -40,9 +69,34 @@ pub fn set_app_min_size<T>(settings: &mut iced::Settings<T>) {
     settings.window.min_size = Some((800, 600));
 }
 
-pub fn run_gui() {
+pub fn run_gui() -> Result<(), Error> {
     let mut settings = iced::Settings::default();
     set_app_icon(&mut settings);
     set_app_min_size(&mut settings);
-    let _ = app::App::run(settings);
+
+    // Without a display server, the windowing library can fail in ways that can't be caught.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err(Error::GuiUnavailable {
+            why: "no display is available".to_string(),
+        });
+    }
+
+    // Windowing and graphics libraries may panic when there is no display or GPU.
+    // Keep that stack trace out of the way, since the error is reported instead.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if RENDERER_READY.load(Ordering::Relaxed) {
+            default_hook(info);
+        }
+    }));
+
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| app::App::run(settings))) {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(Error::GuiUnavailable { why: e.to_string() }),
+        Err(payload) if !RENDERER_READY.load(Ordering::Relaxed) => Err(Error::GuiUnavailable {
+            why: panic_message(payload.as_ref()),
+        }),
+        Err(payload) => std::panic::resume_unwind(payload),
+    }
 }
@@ -1,36 +1,50 @@
 use crate::{
-    config::{Config, CustomGame, RootsConfig},
+    config::{Config, CustomGame, RestoreStrategy, RootsConfig, RunningGamePolicy},
     gui::{
         backup_screen::BackupScreenComponent,
         common::*,
         custom_games_editor::{CustomGamesEditorEntry, CustomGamesEditorEntryRow},
         custom_games_screen::CustomGamesScreenComponent,
         disappearing_progress::DisappearingProgress,
+        focus::FocusTarget,
         game_list::GameListEntry,
         modal::ModalComponent,
         modal::ModalTheme,
-        other_screen::OtherScreenComponent,
+        other_screen::{OtherScreenComponent, Stats},
+        palette::PaletteComponent,
         redirect_editor::RedirectEditorRow,
         restore_screen::RestoreScreenComponent,
         root_editor::RootEditorRow,
+        roots_screen::RootsScreenComponent,
+        scan_progress, schedule,
+        search::StoreFilter,
+        status_bar::StatusBar,
         style,
+        tray::{self, TrayAction},
     },
+    history::{History, HistoryEntry},
     lang::Translator,
-    layout::BackupLayout,
+    layout::{restoration_source, BackupLayout},
     manifest::{Manifest, Store},
     prelude::{
-        app_dir, back_up_game, prepare_backup_target, restore_game, scan_game_for_backup, scan_game_for_restoration,
-        Error, InstallDirRanking, OperationStepDecision, StrictPath,
+        app_dir, back_up_game, check_restoration_targets_writable, configure_throttles, find_running_games, game_store,
+        lint_manifest_placeholders, migrate_backup, need_restoration, prepare_backup_target, preview_restore_deletions,
+        registry_changes_for_restoration, relevant_backup_games, restore_conflicts, restore_game, run_command,
+        scan_game_for_backup_cancellable, scan_game_for_restoration, set_read_only, test_restore_game, BackupBudget,
+        CopyOptions, DeletionPreview, Error, InstallDirRanking, OperationStepDecision, RestoreConflict,
+        RunningProcesses, StrictPath, PASSPHRASE_ENV_VAR, SCAN_THROTTLE,
     },
     registry_compat::RegistryItem,
+    sftp::{self, SftpTarget},
     shortcuts::Shortcut,
+    staging::StagedRestoration,
 };
 
 use iced::{
     alignment::Horizontal as HorizontalAlignment,
     button, executor,
     keyboard::{KeyCode, Modifiers},
-    Alignment, Application, Button, Column, Command, Element, Length, Row, Subscription, Text,
+    Alignment, Application, Button, Column, Command, Container, Element, Length, Row, Subscription, Text,
 };
 
 pub fn get_key_pressed(event: iced::keyboard::Event) -> Option<(KeyCode, Modifiers)> {
@@ -40,6 +54,27 @@ pub fn get_key_pressed(event: iced::keyboard::Event) -> Option<(KeyCode, Modifie
     }
 }
 
+/// With a size budget, games are scanned first and only backed up once all scans are done,
+/// so that the most recently played games can get the first claim on the budget.
+/// What a backup scan needs while waiting for running games to be detected.
+struct PendingBackupScan {
+    preview: bool,
+    config: std::sync::Arc<Config>,
+    layout: std::sync::Arc<BackupLayout>,
+    filter: std::sync::Arc<crate::config::BackupFilter>,
+    ranking: std::sync::Arc<InstallDirRanking>,
+    all_games: std::sync::Arc<Manifest>,
+    subjects: Vec<String>,
+}
+
+struct BudgetedBackup {
+    config: std::sync::Arc<Config>,
+    layout: std::sync::Arc<BackupLayout>,
+    /// Whether the target's existing backups count toward the budget.
+    counts_existing: bool,
+    scanned: Vec<(Option<crate::prelude::ScanInfo>, OperationStepDecision)>,
+}
+
 #[derive(Default)]
 pub struct App {
     config: Config,
@@ -49,25 +84,126 @@ pub struct App {
     screen: Screen,
     modal_theme: Option<ModalTheme>,
     modal: ModalComponent,
+    palette: PaletteComponent,
     nav_to_backup_button: button::State,
     nav_to_restore_button: button::State,
+    nav_to_roots_button: button::State,
     nav_to_custom_games_button: button::State,
     nav_to_other_button: button::State,
     backup_screen: BackupScreenComponent,
     restore_screen: RestoreScreenComponent,
+    roots_screen: RootsScreenComponent,
     custom_games_screen: CustomGamesScreenComponent,
     other_screen: OtherScreenComponent,
     operation_should_cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
     progress: DisappearingProgress,
+    status_bar: StatusBar,
+    staged: StagedRestoration,
+    focus: Option<FocusTarget>,
+    modifiers: Modifiers,
+    last_backups: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>,
+    budgeted_backup: Option<BudgetedBackup>,
+    pending_backup_scan: Option<PendingBackupScan>,
+    tray: bool,
+    hidden: bool,
+    exiting: bool,
+    next_scheduled_backup: Option<std::time::Instant>,
+    scheduled_backup_running: bool,
+    config_save_error: Option<Error>,
+    /// The config as of the last successful load or save, to tell whether there are unsaved changes.
+    saved_config: Config,
+    save_config_button: button::State,
 }
 
 impl App {
+    /// Save the config, keeping any failure on screen until a later save succeeds.
+    /// A banner is used rather than a modal so that it can't interrupt an operation.
+    fn save_config(&mut self) {
+        self.config_save_error = self.config.save().err();
+        if self.config_save_error.is_none() {
+            self.saved_config = self.config.clone();
+        }
+    }
+
+    fn move_focus(&mut self, backward: bool) {
+        self.focus = FocusTarget::next(self.focus, self.screen, backward);
+
+        let path_input_focused = self.focus == Some(FocusTarget::PathInput);
+        for (screen, input) in [
+            (Screen::Backup, &mut self.backup_screen.backup_target_input),
+            (Screen::Restore, &mut self.restore_screen.restore_source_input),
+        ] {
+            if path_input_focused && self.screen == screen {
+                input.focus();
+                input.move_cursor_to_end();
+            } else {
+                input.unfocus();
+            }
+        }
+    }
+
+    fn activate_focus(&mut self) -> Command<Message> {
+        let focus = match self.focus {
+            Some(x) if !x.is_text_input() => x,
+            _ => return Command::none(),
+        };
+        let all_selected = match self.screen {
+            Screen::Backup => self.backup_screen.log.all_entries_selected(&self.config, false),
+            Screen::Restore => self.restore_screen.log.all_entries_selected(&self.config, true),
+            Screen::CustomGames => self.config.are_all_custom_games_enabled(),
+            Screen::Roots | Screen::Other => false,
+        };
+        let message = focus.activate(self.screen, all_selected);
+        self.update(message)
+    }
+
     fn start_backup(&mut self, preview: bool, games: Option<Vec<String>>) -> Command<Message> {
         if self.operation.is_some() {
             return Command::none();
         }
+        if !self.config.mode.allows(false, preview) {
+            self.modal_theme = Some(ModalTheme::Error {
+                variant: Error::OperationDisabled { restoring: false },
+            });
+            return Command::none();
+        }
+
+        configure_throttles(&self.config.backup);
+        if let Some(remote) = SftpTarget::parse(&self.config.backup.path.raw()) {
+            self.operation = Some(if preview {
+                OngoingOperation::PreviewBackup
+            } else {
+                OngoingOperation::Backup
+            });
+            return Command::perform(async move { (games, remote.download()) }, move |(games, result)| {
+                Message::BackupPrepared { preview, games, result }
+            });
+        }
+        self.begin_backup(preview, games)
+    }
 
-        let backup_path = &self.config.backup.path;
+    /// Continue a backup once any remote target is in sync.
+    fn begin_backup(&mut self, preview: bool, games: Option<Vec<String>>) -> Command<Message> {
+        if !preview {
+            if let Some(command) = self
+                .config
+                .backup
+                .pre_backup_command
+                .clone()
+                .filter(|x| !x.trim().is_empty())
+            {
+                self.operation = Some(OngoingOperation::Backup);
+                return Command::perform(async move { (games, run_command(&command)) }, move |(games, result)| {
+                    Message::PreBackupCommandFinished { games, result }
+                });
+            }
+        }
+        self.run_backup(preview, games)
+    }
+
+    /// Continue a backup once any pre-backup command has finished.
+    fn run_backup(&mut self, preview: bool, games: Option<Vec<String>>) -> Command<Message> {
+        let backup_path = &sftp::local_path(&self.config.backup.path);
         if !preview {
             if let Err(e) = prepare_backup_target(
                 backup_path,
@@ -76,6 +212,8 @@ impl App {
                 } else {
                     self.config.backup.merge
                 },
+                &self.config.roots,
+                &self.config.restore.path,
             ) {
                 self.modal_theme = Some(ModalTheme::Error { variant: e });
                 return Command::none();
@@ -83,12 +221,16 @@ impl App {
         }
 
         let mut all_games = self.manifest.clone();
+        all_games.exclude_categories(&self.config.manifest.excluded_categories);
         for custom_game in &self.config.custom_games {
             if custom_game.ignore {
                 continue;
             }
             all_games.add_custom_game(custom_game.clone());
         }
+        if self.config.backup.steam_account_settings {
+            all_games.add_steam_account_settings(&self.config.roots);
+        }
 
         if preview {
             self.backup_screen.recent_found_games.clear();
@@ -112,6 +254,11 @@ impl App {
         } else {
             self.backup_screen.log.entries.clear();
             self.backup_screen.duplicate_detector.clear();
+            self.backup_screen.clear_activity();
+        }
+        if self.scheduled_backup_running {
+            self.backup_screen
+                .log_activity(self.translator.scheduled_backup_started());
         }
         self.modal_theme = None;
         self.progress.current = 0.0;
@@ -122,20 +269,80 @@ impl App {
         } else {
             OngoingOperation::Backup
         });
+        self.status_bar.start();
 
+        let passphrase = Some(std::mem::take(&mut self.modal.passphrase)).filter(|x| !preview && !x.is_empty());
         let config = std::sync::Arc::new(self.config.clone());
-        let layout = std::sync::Arc::new(BackupLayout::new(backup_path.clone(), config.backup.retention.clone()));
+        let layout = std::sync::Arc::new(
+            BackupLayout::new(backup_path.clone(), config.backup.retention.clone())
+                .with_folder_layout(config.backup.layout)
+                .with_format(config.backup.format)
+                .with_dedupe(config.backup.dedupe)
+                .with_passphrase(passphrase, config.backup.encrypt)
+                .with_destinations(&config.backup.destinations, &config.backup.encrypted_destinations),
+        );
+        self.last_backups = layout.last_backup_times();
+        let budgeted = config.backup.max_total_bytes.is_some();
+        self.budgeted_backup = budgeted.then(|| BudgetedBackup {
+            config: config.clone(),
+            layout: layout.clone(),
+            // A preview doesn't clear out the target, so only count what would be kept.
+            counts_existing: !preview || config.backup.merge || games.is_some(),
+            scanned: vec![],
+        });
         let filter = std::sync::Arc::new(self.config.backup.filter.clone());
         let ranking = std::sync::Arc::new(InstallDirRanking::scan(&self.config.roots, &all_games, &subjects));
+        let all_games = std::sync::Arc::new(all_games);
+        if games.is_none() {
+            self.backup_screen.running_games.clear();
+        }
+
+        let check_running = config.backup.running_games != RunningGamePolicy::Ignore;
+        self.pending_backup_scan = Some(PendingBackupScan {
+            preview,
+            config,
+            layout,
+            filter,
+            ranking: ranking.clone(),
+            all_games: all_games.clone(),
+            subjects,
+        });
+        if !check_running {
+            return self.scan_for_backup(Default::default());
+        }
+        // Listing processes can take a while, so it shouldn't block the window.
+        Command::perform(
+            async move { find_running_games(&RunningProcesses::detect(), &all_games, &ranking) },
+            Message::RunningGamesDetected,
+        )
+    }
+
+    /// Scan each game for a backup once any running games are known.
+    fn scan_for_backup(&mut self, running: std::collections::HashSet<String>) -> Command<Message> {
+        let PendingBackupScan {
+            preview,
+            config,
+            layout,
+            filter,
+            ranking,
+            all_games,
+            subjects,
+        } = match self.pending_backup_scan.take() {
+            Some(x) => x,
+            None => return Command::none(),
+        };
+        let budgeted = config.backup.max_total_bytes.is_some();
+        self.backup_screen.running_games.extend(running.iter().cloned());
+        let running = std::sync::Arc::new(running);
 
         let mut commands: Vec<Command<Message>> = vec![];
         for key in subjects {
-            let game = all_games.0[&key].clone();
+            let all_games = all_games.clone();
             let config = config.clone();
             let layout = layout.clone();
             let filter = filter.clone();
             let ranking = ranking.clone();
-            let steam_id = game.steam.as_ref().and_then(|x| x.id);
+            let running = running.clone();
             let cancel_flag = self.operation_should_cancel.clone();
             let merge = self.config.backup.merge;
             commands.push(Command::perform(
@@ -149,8 +356,15 @@ impl App {
                         return (None, None, OperationStepDecision::Cancelled);
                     }
 
-                    let scan_info = scan_game_for_backup(
-                        &game,
+                    let game = match all_games.get(&key) {
+                        Some(x) => x,
+                        None => return (None, None, OperationStepDecision::Ignored),
+                    };
+                    let steam_id = game.steam.as_ref().and_then(|x| x.id);
+
+                    let scan_slot = SCAN_THROTTLE.acquire();
+                    let scan_info = scan_game_for_backup_cancellable(
+                        game,
                         &key,
                         &config.roots,
                         &StrictPath::from_std_path_buf(&app_dir()),
@@ -160,18 +374,171 @@ impl App {
                         &ranking,
                         &config.backup.toggled_paths,
                         &config.backup.toggled_registry,
+                        &config.backup.since_days,
+                        &cancel_flag,
+                        &|files| scan_progress::report(&key, files),
                     );
-                    if !config.is_game_enabled_for_backup(&key) {
+                    drop(scan_slot);
+                    let scan_info = match scan_info {
+                        Some(x) => x,
+                        None => return (None, None, OperationStepDecision::Cancelled),
+                    };
+                    if !config.is_game_enabled_for_backup(&key)
+                        || config.exceeds_size_guard(&key, scan_info.sum_bytes(&None))
+                    {
                         return (Some(scan_info), None, OperationStepDecision::Ignored);
                     }
+                    if config.backup.running_games == RunningGamePolicy::Skip && running.contains(&key) {
+                        return (Some(scan_info), None, OperationStepDecision::Running);
+                    }
+                    if budgeted {
+                        return (Some(scan_info), None, OperationStepDecision::Processed);
+                    }
 
                     let backup_info = if !preview {
-                        Some(back_up_game(&scan_info, &key, &layout, merge, &chrono::Utc::now()))
+                        Some(back_up_game(
+                            &scan_info,
+                            &key,
+                            game_store(&scan_info, &config.roots),
+                            &layout,
+                            merge,
+                            config.backup.files,
+                            config.backup.registry,
+                            &chrono::Utc::now(),
+                            &CopyOptions::from(&config.backup),
+                        ))
                     } else {
                         None
                     };
                     (Some(scan_info), backup_info, OperationStepDecision::Processed)
                 },
+                move |(scan_info, backup_info, decision)| {
+                    if budgeted {
+                        Message::BackupScanned {
+                            scan_info,
+                            decision,
+                            preview,
+                        }
+                    } else {
+                        Message::BackupStep {
+                            scan_info,
+                            backup_info,
+                            decision,
+                            preview,
+                        }
+                    }
+                },
+            ));
+        }
+
+        Command::batch(commands)
+    }
+
+    /// Once every game has been scanned, measure the existing backups for the size budget.
+    /// This walks the backup folder, so it shouldn't block the window.
+    fn measure_budget(&mut self, preview: bool) -> Command<Message> {
+        let (layout, games) = match &self.budgeted_backup {
+            Some(budgeted) if budgeted.counts_existing && !budgeted.scanned.is_empty() => (
+                budgeted.layout.clone(),
+                budgeted
+                    .scanned
+                    .iter()
+                    .filter_map(|(scan_info, _)| scan_info.as_ref().map(|x| x.game_name.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => return self.back_up_within_budget(preview, 0, Default::default()),
+        };
+        Command::perform(
+            async move {
+                let total = layout.total_size();
+                let sizes = games
+                    .into_iter()
+                    .map(|game| {
+                        let size = layout.game_size(&game);
+                        (game, size)
+                    })
+                    .collect();
+                (total, sizes)
+            },
+            move |(existing_total, existing_games)| Message::BackupBudgetMeasured {
+                preview,
+                existing_total,
+                existing_games,
+            },
+        )
+    }
+
+    /// Claim the size budget with the most recently played games first, like the CLI,
+    /// and then back up the games that fit.
+    fn back_up_within_budget(
+        &mut self,
+        preview: bool,
+        existing_total: u64,
+        existing_games: std::collections::HashMap<String, u64>,
+    ) -> Command<Message> {
+        let BudgetedBackup {
+            config,
+            layout,
+            counts_existing: _,
+            mut scanned,
+        } = match self.budgeted_backup.take() {
+            Some(x) => x,
+            None => return Command::perform(async move {}, move |_| Message::BackupComplete { preview }),
+        };
+        if scanned.is_empty() {
+            return Command::perform(async move {}, move |_| Message::BackupComplete { preview });
+        }
+
+        let budget = BackupBudget::new(config.backup.max_total_bytes, existing_total);
+        let mut candidates: Vec<_> = scanned
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, decision))| *decision == OperationStepDecision::Processed)
+            .filter_map(|(i, (scan_info, _))| scan_info.as_ref().map(|x| (x.latest_modified(), i)))
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+        for (_, i) in candidates {
+            if let Some(scan_info) = &scanned[i].0 {
+                let existing = existing_games.get(&scan_info.game_name).copied().unwrap_or(0);
+                if !budget.reserve_replacing(scan_info.sum_bytes(&None), existing) {
+                    scanned[i].1 = OperationStepDecision::OverBudget;
+                }
+            }
+        }
+
+        self.progress.current = 0.0;
+        self.progress.max = scanned.len() as f32;
+
+        let mut commands: Vec<Command<Message>> = vec![];
+        for (scan_info, decision) in scanned {
+            let config = config.clone();
+            let layout = layout.clone();
+            let cancel_flag = self.operation_should_cancel.clone();
+            commands.push(Command::perform(
+                async move {
+                    if cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
+                        // TODO: https://github.com/hecrj/iced/issues/436
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        return (None, None, OperationStepDecision::Cancelled);
+                    }
+                    let backup_info = match &scan_info {
+                        Some(scan_info) if !preview && decision == OperationStepDecision::Processed => {
+                            Some(back_up_game(
+                                scan_info,
+                                &scan_info.game_name,
+                                game_store(scan_info, &config.roots),
+                                &layout,
+                                config.backup.merge,
+                                config.backup.files,
+                                config.backup.registry,
+                                &chrono::Utc::now(),
+                                &CopyOptions::from(&config.backup),
+                            ))
+                        }
+                        _ => None,
+                    };
+                    (scan_info, backup_info, decision)
+                },
                 move |(scan_info, backup_info, decision)| Message::BackupStep {
                     scan_info,
                     backup_info,
@@ -184,24 +551,220 @@ impl App {
         Command::batch(commands)
     }
 
-    fn start_restore(&mut self, preview: bool, games: Option<Vec<String>>) -> Command<Message> {
+    /// Send a finished backup to any remote target.
+    fn upload_backup(&mut self, scheduled: bool) -> Command<Message> {
+        let refresh_usage = BackupScreenComponent::refresh_backup_usage(&self.config);
+        if let Some(remote) = SftpTarget::parse(&self.config.backup.path.raw()) {
+            return Command::batch(vec![
+                refresh_usage,
+                Command::perform(async move { remote.upload() }, move |result| Message::BackupUploaded {
+                    preview: false,
+                    scheduled,
+                    result,
+                }),
+            ]);
+        }
+        Command::batch(vec![refresh_usage, self.finish_backup(false, scheduled)])
+    }
+
+    /// Wrap up a backup once any remote target has been updated.
+    fn finish_backup(&mut self, preview: bool, scheduled: bool) -> Command<Message> {
+        if !preview {
+            if let Some(command) = self
+                .config
+                .backup
+                .post_backup_command
+                .clone()
+                .filter(|x| !x.trim().is_empty())
+            {
+                return Command::perform(async move { run_command(&command) }, move |result| {
+                    Message::PostBackupCommandFinished { scheduled, result }
+                });
+            }
+        }
+        self.conclude_backup(preview, scheduled)
+    }
+
+    /// Show the outcome of a backup once any post-backup command has finished.
+    fn conclude_backup(&mut self, preview: bool, scheduled: bool) -> Command<Message> {
+        if !preview {
+            self.backup_screen.recent_found_games.clear();
+            if !scheduled {
+                self.modal_theme = Some(ModalTheme::OperationSummary {
+                    restoring: false,
+                    summary: self.backup_screen.log.summarize(),
+                });
+                return Command::none();
+            }
+        }
+        for entry in &self.backup_screen.log.entries {
+            if let Some(backup_info) = &entry.backup_info {
+                if !backup_info.successful() {
+                    self.modal_theme = Some(ModalTheme::Error {
+                        variant: Error::SomeEntriesFailed,
+                    });
+                    return Command::none();
+                }
+            }
+        }
+        Command::perform(async move {}, move |_| Message::Idle)
+    }
+
+    fn record_history(&mut self, restoring: bool) {
+        let (path, summary) = if restoring {
+            (&self.config.restore.path, self.restore_screen.log.summarize())
+        } else {
+            (&self.config.backup.path, self.backup_screen.log.summarize())
+        };
+        if let Ok(history) = History::record(HistoryEntry {
+            when: chrono::Utc::now(),
+            restoring,
+            path: path.clone(),
+            summary,
+        }) {
+            self.other_screen.history = history;
+        }
+    }
+
+    /// Ask to confirm a backup, listing any old backups that it would prune.
+    fn confirm_backup(&mut self, games: Option<Vec<String>>) {
+        // Without merging, the whole backup folder is replaced anyway.
+        let deletions = if self.config.backup.merge || games.is_some() {
+            let layout = BackupLayout::new(
+                sftp::local_path(&self.config.backup.path),
+                self.config.backup.retention.clone(),
+            )
+            .with_folder_layout(self.config.backup.layout)
+            .with_format(self.config.backup.format)
+            .with_destinations(
+                &self.config.backup.destinations,
+                &self.config.backup.encrypted_destinations,
+            );
+            let subjects: Vec<_> = match &games {
+                Some(games) => games.clone(),
+                None => layout
+                    .restorable_games()
+                    .into_iter()
+                    .filter(|x| self.config.is_game_enabled_for_backup(x))
+                    .collect(),
+            };
+            layout.backups_to_prune(&subjects)
+        } else {
+            DeletionPreview::default()
+        };
+        self.modal_theme = Some(ModalTheme::ConfirmBackup { games, deletions });
+    }
+
+    /// Ask to confirm a restore, listing anything that it would overwrite or delete.
+    /// Comparing against the current files can take a while, so it happens in the background.
+    fn confirm_restore(&mut self, games: Option<Vec<String>>) -> Command<Message> {
+        let restore_path = sftp::local_path(&self.config.restore.path);
+        let layout = BackupLayout::new(
+            restoration_source(&restore_path).unwrap_or(restore_path),
+            self.config.backup.retention.clone(),
+        );
+        let encrypted = layout.any_encrypted();
+        let subjects: Vec<_> = match &games {
+            Some(games) => games.clone(),
+            None => layout
+                .restorable_games()
+                .into_iter()
+                .filter(|x| self.config.is_game_enabled_for_restore(x))
+                .collect(),
+        };
+        let config = self.config.clone();
+
+        Command::perform(
+            async move {
+                let mut review = RestoreReview::default();
+                if config.restore.registry {
+                    review.registry_changes = registry_changes_for_restoration(&layout, &subjects);
+                }
+                // Staged files are only compared when they are applied.
+                if config.restore.files && !config.restore.stage {
+                    let redirects = config.get_redirects();
+                    for name in &subjects {
+                        let scan_info = scan_game_for_restoration(name, &layout);
+                        check_restoration_targets_writable(&scan_info, &redirects)?;
+                        review
+                            .conflicts
+                            .extend(restore_conflicts(&scan_info, &layout, &redirects));
+                        review.deletions.extend(preview_restore_deletions(
+                            &scan_info,
+                            &layout,
+                            &redirects,
+                            true,
+                            config.restore.strategy,
+                        ));
+                    }
+                }
+                Ok(review)
+            },
+            move |result| Message::ConfirmRestoreReviewed {
+                games: games.clone(),
+                encrypted,
+                result,
+            },
+        )
+    }
+
+    fn start_restore(
+        &mut self,
+        preview: bool,
+        games: Option<Vec<String>>,
+        keep: Vec<RestoreConflict>,
+    ) -> Command<Message> {
         if self.operation.is_some() {
             return Command::none();
         }
-
-        let restore_path = &self.config.restore.path;
-        if !restore_path.is_dir() {
+        if !self.config.mode.allows(true, preview) {
             self.modal_theme = Some(ModalTheme::Error {
-                variant: Error::RestorationSourceInvalid {
-                    path: restore_path.clone(),
-                },
+                variant: Error::OperationDisabled { restoring: true },
             });
             return Command::none();
         }
 
+        if let Some(remote) = SftpTarget::parse(&self.config.restore.path.raw()) {
+            self.operation = Some(if preview {
+                OngoingOperation::PreviewRestore
+            } else {
+                OngoingOperation::Restore
+            });
+            return Command::perform(
+                async move { (games, keep, remote.download()) },
+                move |(games, keep, result)| Message::RestorePrepared {
+                    preview,
+                    games,
+                    keep,
+                    result,
+                },
+            );
+        }
+        self.begin_restore(preview, games, keep)
+    }
+
+    /// Continue a restore once any remote source is in sync.
+    fn begin_restore(
+        &mut self,
+        preview: bool,
+        games: Option<Vec<String>>,
+        keep: Vec<RestoreConflict>,
+    ) -> Command<Message> {
+        let restore_path = &match restoration_source(&sftp::local_path(&self.config.restore.path)) {
+            Ok(x) => x,
+            Err(e) => {
+                self.modal_theme = Some(ModalTheme::Error { variant: e });
+                return Command::none();
+            }
+        };
+
+        let passphrase = Some(std::mem::take(&mut self.modal.passphrase)).filter(|x| !preview && !x.is_empty());
         let config = std::sync::Arc::new(self.config.clone());
-        let layout = std::sync::Arc::new(BackupLayout::new(restore_path.clone(), config.backup.retention.clone()));
+        let layout = std::sync::Arc::new(
+            BackupLayout::new(restore_path.clone(), config.backup.retention.clone()).with_passphrase(passphrase, false),
+        );
         let mut restorables = layout.restorable_games();
+        self.restore_screen.source_backup = layout.latest_source_backup();
 
         if let Some(games) = games {
             restorables.retain(|v| games.contains(v));
@@ -224,13 +787,16 @@ impl App {
         } else {
             OngoingOperation::Restore
         });
+        self.status_bar.start();
         self.progress.current = 0.0;
         self.progress.max = restorables.len() as f32;
 
+        let keep = std::sync::Arc::new(keep);
         let mut commands: Vec<Command<Message>> = vec![];
         for name in restorables {
             let config = config.clone();
             let layout = layout.clone();
+            let keep = keep.clone();
             let cancel_flag = self.operation_should_cancel.clone();
             commands.push(Command::perform(
                 async move {
@@ -240,15 +806,33 @@ impl App {
                         return (None, None, OperationStepDecision::Cancelled);
                     }
 
-                    let scan_info = scan_game_for_restoration(&name, &layout);
-                    if !config.is_game_enabled_for_restore(&name) {
+                    let scan_info = scan_game_for_restoration(&name, &layout).without_conflicts(&keep);
+                    let enabled = config.is_game_enabled_for_restore(&name)
+                        && (!config.restore.only_changed
+                            || need_restoration(&scan_info, &layout, &config.get_redirects()));
+                    if !enabled {
                         return (Some(scan_info), None, OperationStepDecision::Ignored);
                     }
 
-                    let backup_info = if !preview {
-                        Some(restore_game(&scan_info, &config.get_redirects()))
-                    } else {
+                    let backup_info = if preview {
                         None
+                    } else if config.restore.stage {
+                        Some(StagedRestoration::stage_game(
+                            &scan_info.only_kinds(config.restore.files, config.restore.registry),
+                            &layout,
+                        ))
+                    } else {
+                        Some(restore_game(
+                            &scan_info,
+                            &layout,
+                            &config.get_redirects(),
+                            config.restore.files,
+                            config.restore.registry,
+                            config.restore.strategy,
+                            config.restore.overwrite_newer_by,
+                            config.restore.line_ending_patterns(&name),
+                            &config.restore.save_slot_filter(&name),
+                        ))
                     };
                     (Some(scan_info), backup_info, OperationStepDecision::Processed)
                 },
@@ -279,8 +863,13 @@ impl Application for App {
                 Config::default()
             }
         };
-        let manifest = match Manifest::load(&mut config, true) {
-            Ok(x) => x,
+        let manifest = match Manifest::load_checked(&mut config, true) {
+            Ok((x, problems)) => {
+                if !problems.is_empty() && modal_theme.is_none() {
+                    modal_theme = Some(ModalTheme::ManifestProblems(problems));
+                }
+                x
+            }
             Err(x) => {
                 modal_theme = Some(ModalTheme::Error { variant: x });
                 match Manifest::load(&mut config, false) {
@@ -290,31 +879,68 @@ impl Application for App {
             }
         };
 
+        set_read_only(config.mode.is_read_only());
+        let tray = config.tray.enabled && tray::start(translator);
+        let layout = BackupLayout::new(sftp::local_path(&config.backup.path), config.backup.retention.clone());
+        let mut backup_screen = BackupScreenComponent::new(&config);
+        backup_screen.changed_games = layout.changed_games();
+        let refresh_usage = BackupScreenComponent::refresh_backup_usage(&config);
+
         (
             Self {
-                backup_screen: BackupScreenComponent::new(&config),
+                tray,
+                hidden: tray && config.tray.start_minimized,
+                next_scheduled_backup: config.backup.schedule.map(|x| std::time::Instant::now() + x),
+                backup_screen,
                 restore_screen: RestoreScreenComponent::new(&config),
+                roots_screen: RootsScreenComponent::new(&config),
                 custom_games_screen: CustomGamesScreenComponent::new(&config),
                 other_screen: OtherScreenComponent::new(&config),
+                last_backups: layout.last_backup_times(),
                 translator,
+                saved_config: config.clone(),
                 config,
                 manifest,
                 modal_theme,
+                staged: StagedRestoration::load(),
                 ..Self::default()
             },
-            Command::none(),
+            refresh_usage,
         )
     }
 
     fn title(&self) -> String {
-        self.translator.window_title()
+        match &self.operation {
+            Some(operation) if self.progress.max > 0.0 => self.translator.window_title_progress(
+                operation,
+                self.progress.current as usize,
+                self.progress.max as usize,
+            ),
+            _ => self.translator.window_title(),
+        }
+    }
+
+    fn mode(&self) -> iced::window::Mode {
+        if self.hidden {
+            iced::window::Mode::Hidden
+        } else {
+            iced::window::Mode::Windowed
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exiting
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Idle => {
                 self.operation = None;
+                self.pending_backup_scan = None;
+                self.status_bar.stop();
                 self.modal_theme = None;
+                self.modal.show_details = false;
+                self.modal.passphrase.clear();
                 self.progress.current = 0.0;
                 self.progress.max = 0.0;
                 self.operation_should_cancel
@@ -323,34 +949,150 @@ impl Application for App {
             }
             Message::Ignore => Command::none(),
             Message::ConfirmBackupStart { games } => {
-                self.modal_theme = Some(ModalTheme::ConfirmBackup { games });
+                self.confirm_backup(games);
                 Command::none()
             }
-            Message::ConfirmRestoreStart { games } => {
-                self.modal_theme = Some(ModalTheme::ConfirmRestore { games });
+            Message::ConfirmRestoreStart { games } => match SftpTarget::parse(&self.config.restore.path.raw()) {
+                Some(remote) => Command::perform(async move { (games, remote.download()) }, |(games, result)| {
+                    Message::ConfirmRestorePrepared { games, result }
+                }),
+                None => self.confirm_restore(games),
+            },
+            Message::ConfirmRestorePrepared { games, result } => match result {
+                Ok(()) => self.confirm_restore(games),
+                Err(e) => {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                    Command::none()
+                }
+            },
+            Message::ConfirmRestoreReviewed {
+                games,
+                encrypted,
+                result,
+            } => {
+                self.modal_theme = Some(match result {
+                    Ok(review) => ModalTheme::ConfirmRestore {
+                        games,
+                        encrypted,
+                        registry_changes: review.registry_changes,
+                        conflicts: review.conflicts.into_iter().map(|conflict| (conflict, false)).collect(),
+                        deletions: review.deletions,
+                    },
+                    Err(e) => ModalTheme::Error { variant: e },
+                });
                 Command::none()
             }
+            Message::PreBackupCommandFinished { games, result } => {
+                self.operation = None;
+                match result {
+                    Ok(()) => self.run_backup(false, games),
+                    Err(e) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: e });
+                        Command::none()
+                    }
+                }
+            }
+            Message::BackupPrepared { preview, games, result } => {
+                self.operation = None;
+                match result {
+                    Ok(()) => self.begin_backup(preview, games),
+                    Err(e) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: e });
+                        Command::none()
+                    }
+                }
+            }
+            Message::RestorePrepared {
+                preview,
+                games,
+                keep,
+                result,
+            } => {
+                self.operation = None;
+                match result {
+                    Ok(()) => self.begin_restore(preview, games, keep),
+                    Err(e) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: e });
+                        Command::none()
+                    }
+                }
+            }
             Message::BackupStart { preview, games } => self.start_backup(preview, games),
-            Message::RestoreStart { preview, games } => self.start_restore(preview, games),
+            Message::RestoreStart { preview, games, keep } => self.start_restore(preview, games, keep),
+            Message::ResolveRestoreConflict { index, keep } => {
+                if let Some(ModalTheme::ConfirmRestore { conflicts, .. }) = &mut self.modal_theme {
+                    if let Some(conflict) = conflicts.get_mut(index) {
+                        conflict.1 = keep;
+                    }
+                }
+                Command::none()
+            }
+            Message::ResolveAllRestoreConflicts { keep } => {
+                if let Some(ModalTheme::ConfirmRestore { conflicts, .. }) = &mut self.modal_theme {
+                    for conflict in conflicts {
+                        conflict.1 = keep;
+                    }
+                }
+                Command::none()
+            }
             Message::BackupStep {
                 scan_info,
                 backup_info,
-                decision: _,
+                decision,
                 preview,
             } => {
                 self.progress.current += 1.0;
                 if let Some(scan_info) = scan_info {
+                    self.backup_screen.finish_scan_progress(&scan_info.game_name);
+                    if preview && self.operation == Some(OngoingOperation::PreviewBackup) {
+                        let changed = self.backup_screen.changed_games.contains(&scan_info.game_name)
+                            || !self.last_backups.contains_key(&scan_info.game_name);
+                        if self.config.apply_default_selection(&scan_info.game_name, changed) {
+                            self.save_config();
+                        }
+                    }
+                    if self
+                        .config
+                        .exceeds_size_guard(&scan_info.game_name, scan_info.sum_bytes(&None))
+                        && self.config.is_game_enabled_for_backup(&scan_info.game_name)
+                    {
+                        // Reflect that it was skipped until the user confirms it by selecting it again.
+                        self.config.disable_game_for_backup(&scan_info.game_name);
+                        self.save_config();
+                    }
                     if scan_info.found_anything() {
+                        if decision == OperationStepDecision::Processed {
+                            self.backup_screen.log_activity(self.translator.backup_activity(
+                                &scan_info.game_name,
+                                scan_info.found_files.iter().filter(|x| !x.ignored).count(),
+                                preview,
+                            ));
+                        }
                         self.backup_screen.duplicate_detector.add_game(&scan_info);
                         self.backup_screen
                             .recent_found_games
                             .insert(scan_info.game_name.clone());
-                        self.backup_screen.log.entries.push(GameListEntry {
-                            scan_info,
-                            backup_info,
-                            ..Default::default()
-                        });
-                        self.backup_screen.log.sort(&self.config.backup.sort);
+                        if backup_info.as_ref().map(|x| x.successful()).unwrap_or(false)
+                            && decision == OperationStepDecision::Processed
+                        {
+                            self.last_backups
+                                .insert(scan_info.game_name.clone(), chrono::Utc::now());
+                            self.backup_screen.changed_games.remove(&scan_info.game_name);
+                        }
+                        self.backup_screen.log.insert_sorted(
+                            GameListEntry {
+                                last_backup: self.last_backups.get(&scan_info.game_name).copied(),
+                                changed: self.backup_screen.changed_games.contains(&scan_info.game_name),
+                                over_budget: decision == OperationStepDecision::OverBudget,
+                                running: self.backup_screen.running_games.contains(&scan_info.game_name),
+                                store: game_store(&scan_info, &self.config.roots),
+                                scan_info,
+                                backup_info,
+                                ..Default::default()
+                            },
+                            &self.config.backup.sort,
+                            &self.config.pinned_games,
+                        );
                     }
                 }
                 if self.progress.complete() {
@@ -359,6 +1101,34 @@ impl Application for App {
                     Command::none()
                 }
             }
+            Message::RunningGamesDetected(running) => self.scan_for_backup(running),
+            Message::BackupBudgetMeasured {
+                preview,
+                existing_total,
+                existing_games,
+            } => self.back_up_within_budget(preview, existing_total, existing_games),
+            Message::BackupUsageMeasured(usage) => {
+                self.backup_screen.backup_usage = usage;
+                Command::none()
+            }
+            Message::BackupScanned {
+                scan_info,
+                decision,
+                preview,
+            } => {
+                self.progress.current += 1.0;
+                if let Some(scan_info) = &scan_info {
+                    self.backup_screen.finish_scan_progress(&scan_info.game_name);
+                }
+                if let Some(budgeted) = self.budgeted_backup.as_mut() {
+                    budgeted.scanned.push((scan_info, decision));
+                }
+                if self.progress.complete() {
+                    self.measure_budget(preview)
+                } else {
+                    Command::none()
+                }
+            }
             Message::RestoreStep {
                 scan_info,
                 backup_info,
@@ -366,14 +1136,23 @@ impl Application for App {
             } => {
                 self.progress.current += 1.0;
                 if let Some(scan_info) = scan_info {
+                    if let Some(backup_info) = &backup_info {
+                        if self.config.restore.stage && scan_info.found_anything() {
+                            self.staged.add_game(&scan_info, backup_info);
+                        }
+                    }
                     if scan_info.found_anything() {
                         self.restore_screen.duplicate_detector.add_game(&scan_info);
-                        self.restore_screen.log.entries.push(GameListEntry {
-                            scan_info,
-                            backup_info,
-                            ..Default::default()
-                        });
-                        self.restore_screen.log.sort(&self.config.restore.sort);
+                        self.restore_screen.log.insert_sorted(
+                            GameListEntry {
+                                store: game_store(&scan_info, &self.config.roots),
+                                scan_info,
+                                backup_info,
+                                ..Default::default()
+                            },
+                            &self.config.restore.sort,
+                            &self.config.pinned_games,
+                        );
                     }
                 }
                 if self.progress.complete() {
@@ -395,68 +1174,429 @@ impl Application for App {
                     Some(OngoingOperation::Restore) => {
                         self.operation = Some(OngoingOperation::CancelRestore);
                     }
-                    Some(OngoingOperation::PreviewRestore) => {
-                        self.operation = Some(OngoingOperation::CancelPreviewRestore);
+                    Some(OngoingOperation::PreviewRestore) => {
+                        self.operation = Some(OngoingOperation::CancelPreviewRestore);
+                    }
+                    _ => {}
+                };
+                Command::none()
+            }
+            Message::BackupComplete { preview } => {
+                self.backup_screen.clear_scan_progress();
+                let scheduled = std::mem::take(&mut self.scheduled_backup_running);
+                if scheduled {
+                    let failed = self
+                        .backup_screen
+                        .log
+                        .entries
+                        .iter()
+                        .any(|x| x.backup_info.as_ref().map(|y| !y.successful()).unwrap_or(false));
+                    self.backup_screen
+                        .log_activity(self.translator.scheduled_backup_finished(failed));
+                }
+                if !preview {
+                    self.record_history(false);
+                    if self.config.backup.dedupe {
+                        // This walks the whole store, so it shouldn't block the window.
+                        let layout = BackupLayout::new(
+                            sftp::local_path(&self.config.backup.path),
+                            self.config.backup.retention.clone(),
+                        )
+                        .with_destinations(
+                            &self.config.backup.destinations,
+                            &self.config.backup.encrypted_destinations,
+                        );
+                        return Command::perform(async move { layout.prune_dedupe_store() }, move |_| {
+                            Message::DedupeStorePruned { scheduled }
+                        });
+                    }
+                    return self.upload_backup(scheduled);
+                }
+                self.finish_backup(preview, scheduled)
+            }
+            Message::DedupeStorePruned { scheduled } => self.upload_backup(scheduled),
+            Message::BackupUploaded {
+                preview,
+                scheduled,
+                result,
+            } => match result {
+                Ok(()) => self.finish_backup(preview, scheduled),
+                Err(e) => {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                    Command::none()
+                }
+            },
+            Message::PostBackupCommandFinished { scheduled, result } => match result {
+                Ok(()) => self.conclude_backup(false, scheduled),
+                Err(e) => {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                    Command::none()
+                }
+            },
+            Message::RestoreComplete => {
+                let save_error = if self.config.restore.stage {
+                    self.staged.save().err()
+                } else {
+                    None
+                };
+                let preview = matches!(
+                    self.operation,
+                    Some(OngoingOperation::PreviewRestore | OngoingOperation::CancelPreviewRestore)
+                );
+                if !preview {
+                    self.record_history(true);
+                    self.modal_theme = Some(match save_error {
+                        Some(e) => ModalTheme::Error { variant: e },
+                        None => ModalTheme::OperationSummary {
+                            restoring: true,
+                            summary: self.restore_screen.log.summarize(),
+                        },
+                    });
+                    return Command::none();
+                }
+                if let Some(e) = save_error {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                    return Command::none();
+                }
+                for entry in &self.restore_screen.log.entries {
+                    if let Some(backup_info) = &entry.backup_info {
+                        if !backup_info.successful() {
+                            self.modal_theme = Some(ModalTheme::Error {
+                                variant: Error::SomeEntriesFailed,
+                            });
+                            return Command::none();
+                        }
+                    }
+                }
+                Command::perform(async move {}, move |_| Message::Idle)
+            }
+            Message::ProcessGameOnDemand { game, restore } => {
+                if restore {
+                    Command::perform(async move {}, move |_| Message::ConfirmRestoreStart {
+                        games: Some(vec![game.clone()]),
+                    })
+                } else {
+                    Command::perform(async move {}, move |_| Message::ConfirmBackupStart {
+                        games: Some(vec![game.clone()]),
+                    })
+                }
+            }
+            Message::EditedBackupTarget(text) => {
+                self.backup_screen.backup_target_history.push(&text);
+                if self.config.restore.link_paths {
+                    self.restore_screen.restore_source_history.push(&text);
+                    self.config.restore.path.reset(text.clone());
+                }
+                self.config.backup.path.reset(text);
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedBackupMerge(enabled) => {
+                self.config.backup.merge = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedBackupEncrypt(enabled) => {
+                self.config.backup.encrypt = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedBackupFiles(enabled) => {
+                self.config.backup.files = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedBackupRegistry(enabled) => {
+                self.config.backup.registry = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreSource(text) => {
+                self.restore_screen.restore_source_history.push(&text);
+                if self.config.restore.link_paths {
+                    self.backup_screen.backup_target_history.push(&text);
+                    self.config.backup.path.reset(text.clone());
+                }
+                self.config.restore.path.reset(text);
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedLinkPaths(enabled) => {
+                self.config.restore.link_paths = enabled;
+                if enabled {
+                    let text = self.config.backup.path.raw();
+                    self.restore_screen.restore_source_history.push(&text);
+                    self.config.restore.path.reset(text);
+                }
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreOnlyChanged(enabled) => {
+                self.config.restore.only_changed = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreStage(enabled) => {
+                self.config.restore.stage = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreMirror(enabled) => {
+                self.config.restore.strategy = if enabled {
+                    RestoreStrategy::Mirror
+                } else {
+                    RestoreStrategy::Merge
+                };
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreFiles(enabled) => {
+                self.config.restore.files = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedRestoreRegistry(enabled) => {
+                self.config.restore.registry = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::ConfirmApplyStagedRestore => {
+                self.modal_theme = Some(ModalTheme::ConfirmApplyStagedRestore);
+                Command::none()
+            }
+            Message::ApplyStagedRestore => {
+                if self.operation.is_some() || !self.config.mode.allows_restore() {
+                    return Command::none();
+                }
+                self.operation = Some(OngoingOperation::Restore);
+                self.status_bar.start();
+                self.modal_theme = None;
+                // This copies every staged file, so it shouldn't block the window.
+                let mut staged = self.staged.clone();
+                let redirects = self.config.get_redirects();
+                Command::perform(
+                    async move {
+                        let result = staged.apply(&redirects);
+                        (staged, result)
+                    },
+                    |(staged, result)| Message::AppliedStagedRestore { staged, result },
+                )
+            }
+            Message::AppliedStagedRestore { staged, result } => {
+                self.operation = None;
+                self.status_bar.stop();
+                self.staged = staged;
+                match result {
+                    Ok(backup_info) if backup_info.successful() => {
+                        self.modal_theme = None;
+                    }
+                    Ok(_) => {
+                        self.modal_theme = Some(ModalTheme::Error {
+                            variant: Error::SomeEntriesFailed,
+                        });
+                    }
+                    Err(e) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: e });
+                    }
+                }
+                Command::none()
+            }
+            Message::SaveConfig => {
+                self.save_config();
+                Command::none()
+            }
+            Message::ReloadConfig => {
+                if self.operation.is_some() {
+                    return Command::none();
+                }
+                // On failure, keep using the current config.
+                let mut config = match Config::load() {
+                    Ok(x) => x,
+                    Err(x) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: x });
+                        return Command::none();
+                    }
+                };
+                let manifest = match Manifest::load_checked(&mut config, false) {
+                    Ok((x, problems)) => {
+                        if !problems.is_empty() {
+                            self.modal_theme = Some(ModalTheme::ManifestProblems(problems));
+                        }
+                        x
+                    }
+                    Err(x) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: x });
+                        return Command::none();
                     }
-                    _ => {}
                 };
+
+                self.backup_screen.reload(&config);
+                self.restore_screen.reload(&config);
+                self.roots_screen = RootsScreenComponent::new(&config);
+                self.custom_games_screen = CustomGamesScreenComponent::new(&config);
+                self.other_screen = OtherScreenComponent::new(&config);
+                let layout = BackupLayout::new(sftp::local_path(&config.backup.path), config.backup.retention.clone());
+                self.last_backups = layout.last_backup_times();
+                self.backup_screen.changed_games = layout.changed_games();
+                self.next_scheduled_backup = config.backup.schedule.map(|x| std::time::Instant::now() + x);
+                set_read_only(config.mode.is_read_only());
+                let refresh_usage = BackupScreenComponent::refresh_backup_usage(&config);
+                self.saved_config = config.clone();
+                self.config = config;
+                self.manifest = manifest;
+                refresh_usage
+            }
+            Message::LintManifest => {
+                self.modal_theme = Some(ModalTheme::ManifestLint(lint_manifest_placeholders(
+                    &self.manifest,
+                    &self.config.roots,
+                )));
                 Command::none()
             }
-            Message::BackupComplete { preview } => {
-                for entry in &self.backup_screen.log.entries {
-                    if let Some(backup_info) = &entry.backup_info {
-                        if !backup_info.successful() {
-                            self.modal_theme = Some(ModalTheme::Error {
-                                variant: Error::SomeEntriesFailed,
-                            });
-                            return Command::none();
-                        }
-                    }
-                }
-                if !preview {
-                    self.backup_screen.recent_found_games.clear();
-                }
-                Command::perform(async move {}, move |_| Message::Idle)
+            Message::ConfirmTestRestore => {
+                let restore_path = sftp::local_path(&self.config.restore.path);
+                let layout = BackupLayout::new(
+                    restoration_source(&restore_path).unwrap_or(restore_path),
+                    self.config.backup.retention.clone(),
+                );
+                self.modal_theme = Some(ModalTheme::ConfirmTestRestore {
+                    encrypted: layout.any_encrypted(),
+                });
+                Command::none()
             }
-            Message::RestoreComplete => {
-                for entry in &self.restore_screen.log.entries {
-                    if let Some(backup_info) = &entry.backup_info {
-                        if !backup_info.successful() {
-                            self.modal_theme = Some(ModalTheme::Error {
-                                variant: Error::SomeEntriesFailed,
-                            });
-                            return Command::none();
-                        }
+            Message::TestRestore => {
+                if self.operation.is_some() || self.other_screen.testing_restore {
+                    return Command::none();
+                }
+
+                let restore_path = match restoration_source(&sftp::local_path(&self.config.restore.path)) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        self.modal_theme = Some(ModalTheme::Error { variant: e });
+                        return Command::none();
                     }
+                };
+
+                self.other_screen.testing_restore = true;
+                let passphrase = Some(std::mem::take(&mut self.modal.passphrase)).filter(|x| !x.is_empty());
+                let layout = BackupLayout::new(restore_path, self.config.backup.retention.clone())
+                    .with_passphrase(passphrase, false);
+                Command::perform(
+                    async move {
+                        let scratch = StrictPath::from(
+                            std::env::temp_dir().join(format!("ludusavi-test-restore-{}", std::process::id())),
+                        );
+                        let mut games = layout.restorable_games();
+                        games.sort();
+
+                        let mut failed = vec![];
+                        for (i, name) in games.iter().enumerate() {
+                            let scan_info = scan_game_for_restoration(name, &layout);
+                            let backup_info = test_restore_game(&scan_info, &layout, &scratch.joined(&i.to_string()));
+                            if !backup_info.successful() {
+                                failed.push((name.clone(), backup_info.failed_files.len()));
+                            }
+                        }
+                        let _ = scratch.remove();
+
+                        (games.len(), failed)
+                    },
+                    |(total, failed)| Message::TestedRestore { total, failed },
+                )
+            }
+            Message::ConfirmPruneOrphans => {
+                if self.operation.is_some() || self.other_screen.pruning_orphans {
+                    return Command::none();
                 }
-                Command::perform(async move {}, move |_| Message::Idle)
+                self.other_screen.pruning_orphans = true;
+                // This reads every game's backups, so it shouldn't block the window.
+                let layout = BackupLayout::new(
+                    sftp::local_path(&self.config.backup.path),
+                    self.config.backup.retention.clone(),
+                )
+                .with_destinations(
+                    &self.config.backup.destinations,
+                    &self.config.backup.encrypted_destinations,
+                );
+                let relevant = relevant_backup_games(&self.config, &self.manifest);
+                Command::perform(
+                    async move { layout.orphaned_games(&relevant) },
+                    Message::FoundOrphanedBackups,
+                )
             }
-            Message::ProcessGameOnDemand { game, restore } => {
-                if restore {
-                    Command::perform(async move {}, move |_| Message::ConfirmRestoreStart {
-                        games: Some(vec![game.clone()]),
-                    })
+            Message::FoundOrphanedBackups(deletions) => {
+                self.other_screen.pruning_orphans = false;
+                self.modal_theme = Some(if deletions.is_empty() {
+                    ModalTheme::NoOrphanedBackups
                 } else {
-                    Command::perform(async move {}, move |_| Message::ConfirmBackupStart {
-                        games: Some(vec![game.clone()]),
-                    })
+                    ModalTheme::ConfirmPruneOrphans { deletions }
+                });
+                Command::none()
+            }
+            Message::PruneOrphans(deletions) => {
+                if self.operation.is_some() || self.other_screen.pruning_orphans {
+                    return Command::none();
                 }
+                if !self.config.mode.allows_backup() {
+                    self.modal_theme = Some(ModalTheme::Error {
+                        variant: Error::OperationDisabled { restoring: false },
+                    });
+                    return Command::none();
+                }
+                self.other_screen.pruning_orphans = true;
+                self.modal_theme = None;
+                let mut layout = BackupLayout::new(
+                    sftp::local_path(&self.config.backup.path),
+                    self.config.backup.retention.clone(),
+                )
+                .with_destinations(
+                    &self.config.backup.destinations,
+                    &self.config.backup.encrypted_destinations,
+                );
+                Command::perform(
+                    async move {
+                        let result = layout.prune_orphans(&deletions);
+                        (result, layout.last_backup_times())
+                    },
+                    |(result, last_backups)| Message::PrunedOrphans { result, last_backups },
+                )
             }
-            Message::EditedBackupTarget(text) => {
-                self.backup_screen.backup_target_history.push(&text);
-                self.config.backup.path.reset(text);
-                self.config.save();
+            Message::PrunedOrphans { result, last_backups } => {
+                self.other_screen.pruning_orphans = false;
+                self.modal_theme = result.err().map(|variant| ModalTheme::Error { variant });
+                self.last_backups = last_backups;
                 Command::none()
             }
-            Message::EditedBackupMerge(enabled) => {
-                self.config.backup.merge = enabled;
-                self.config.save();
+            Message::TestedRestore { total, failed } => {
+                self.other_screen.testing_restore = false;
+                self.modal_theme = Some(ModalTheme::TestedRestore { total, failed });
                 Command::none()
             }
-            Message::EditedRestoreSource(text) => {
-                self.restore_screen.restore_source_history.push(&text);
-                self.config.restore.path.reset(text);
-                self.config.save();
+            Message::EditedMigrationSource(text) => {
+                self.other_screen.migration_source = text;
+                Command::none()
+            }
+            Message::EditedMigrationTarget(text) => {
+                self.other_screen.migration_target = text;
+                Command::none()
+            }
+            Message::MigrateBackup => {
+                if self.operation.is_some() || self.other_screen.migrating {
+                    return Command::none();
+                }
+                self.other_screen.migrating = true;
+                let from = StrictPath::new(self.other_screen.migration_source.clone());
+                let to = StrictPath::new(self.other_screen.migration_target.clone());
+                Command::perform(async move { migrate_backup(&from, &to) }, Message::MigratedBackup)
+            }
+            Message::MigratedBackup(result) => {
+                self.other_screen.migrating = false;
+                self.modal_theme = Some(match result {
+                    Ok(()) => ModalTheme::BackupMigrated {
+                        path: StrictPath::new(self.other_screen.migration_target.clone()),
+                    },
+                    Err(variant) => ModalTheme::Error { variant },
+                });
                 Command::none()
             }
             Message::FindRoots => {
@@ -472,36 +1612,54 @@ impl Application for App {
                 for root in missing {
                     let mut row = RootEditorRow::default();
                     row.text_history.push(&root.path.render());
-                    self.backup_screen.root_editor.rows.push(row);
+                    self.roots_screen.root_editor.rows.push(row);
                     self.config.roots.push(root);
                 }
-                self.config.save();
+                self.save_config();
                 Command::perform(async move {}, move |_| Message::Idle)
             }
             Message::EditedRoot(action) => {
                 match action {
                     EditAction::Add => {
-                        self.backup_screen.root_editor.rows.push(RootEditorRow::default());
+                        self.roots_screen.root_editor.rows.push(RootEditorRow::default());
                         self.config.roots.push(RootsConfig {
                             path: StrictPath::default(),
                             store: Store::Other,
+                            ignore: false,
+                            base_template: None,
                         });
                     }
                     EditAction::Change(index, value) => {
-                        self.backup_screen.root_editor.rows[index].text_history.push(&value);
+                        self.roots_screen.root_editor.rows[index].text_history.push(&value);
                         self.config.roots[index].path.reset(value);
                     }
                     EditAction::Remove(index) => {
-                        self.backup_screen.root_editor.rows.remove(index);
+                        self.roots_screen.root_editor.rows.remove(index);
                         self.config.roots.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
+                Command::none()
+            }
+            Message::MoveRoot(from, to) => {
+                // Keep the config and the editor rows in lockstep so that row indices stay valid.
+                if from != to && from < self.config.roots.len() && to < self.config.roots.len() {
+                    let row = self.roots_screen.root_editor.rows.remove(from);
+                    self.roots_screen.root_editor.rows.insert(to, row);
+                    let root = self.config.roots.remove(from);
+                    self.config.roots.insert(to, root);
+                    self.save_config();
+                }
                 Command::none()
             }
             Message::SelectedRootStore(index, store) => {
                 self.config.roots[index].store = store;
-                self.config.save();
+                self.save_config();
+                Command::none()
+            }
+            Message::ToggleRootEnabled { index, enabled } => {
+                self.config.roots[index].ignore = !enabled;
+                self.save_config();
                 Command::none()
             }
             Message::EditedRedirect(action, field) => {
@@ -533,7 +1691,7 @@ impl Application for App {
                         self.config.restore.redirects.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedCustomGame(action) => {
@@ -556,7 +1714,7 @@ impl Application for App {
                         self.config.custom_games.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedCustomGameFile(game_index, action) => {
@@ -580,7 +1738,7 @@ impl Application for App {
                         self.config.custom_games[game_index].files.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedCustomGameRegistry(game_index, action) => {
@@ -604,17 +1762,27 @@ impl Application for App {
                         self.config.custom_games[game_index].registry.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedExcludeOtherOsData(enabled) => {
                 self.config.backup.filter.exclude_other_os_data = enabled;
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedExcludeStoreScreenshots(enabled) => {
                 self.config.backup.filter.exclude_store_screenshots = enabled;
-                self.config.save();
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedSkipHidden(enabled) => {
+                self.config.backup.filter.skip_hidden = enabled;
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedSteamAccountSettings(enabled) => {
+                self.config.backup.steam_account_settings = enabled;
+                self.save_config();
                 Command::none()
             }
             Message::EditedBackupFilterIgnoredPath(action) => {
@@ -642,7 +1810,7 @@ impl Application for App {
                         self.config.backup.filter.ignored_paths.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedBackupFilterIgnoredRegistry(action) => {
@@ -670,7 +1838,7 @@ impl Application for App {
                         self.config.backup.filter.ignored_registry.remove(index);
                     }
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::SwitchScreen(screen) => {
@@ -722,13 +1890,59 @@ impl Application for App {
                 enabled,
                 restoring,
             } => {
-                match (restoring, enabled) {
-                    (false, false) => self.config.disable_game_for_backup(&name),
-                    (false, true) => self.config.enable_game_for_backup(&name),
-                    (true, false) => self.config.disable_game_for_restore(&name),
-                    (true, true) => self.config.enable_game_for_restore(&name),
+                let log = if restoring {
+                    &mut self.restore_screen.log
+                } else {
+                    &mut self.backup_screen.log
                 };
-                self.config.save();
+                let names = match &log.last_toggled {
+                    Some(last) if self.modifiers.shift() => log.shown_range(last, &name, &self.config, restoring),
+                    _ => vec![name.clone()],
+                };
+                log.last_toggled = Some(name);
+
+                for name in names {
+                    match (restoring, enabled) {
+                        (false, false) => self.config.disable_game_for_backup(&name),
+                        (false, true) => {
+                            self.config.enable_game_for_backup(&name);
+                            if let Some(entry) = self
+                                .backup_screen
+                                .log
+                                .entries
+                                .iter()
+                                .find(|x| x.scan_info.game_name == name)
+                            {
+                                if self.config.exceeds_size_guard(&name, entry.scan_info.sum_bytes(&None)) {
+                                    self.config.confirm_size_guard(&name);
+                                }
+                            }
+                        }
+                        (true, false) => self.config.disable_game_for_restore(&name),
+                        (true, true) => self.config.enable_game_for_restore(&name),
+                    };
+                }
+                self.save_config();
+                Command::none()
+            }
+            Message::ToggleSaveSlot { game, slot, enabled } => {
+                self.config.restore.set_save_slot_skipped(&game, &slot, !enabled);
+                self.save_config();
+                Command::none()
+            }
+            Message::ToggleGameListEntryPinned { name, pinned } => {
+                if pinned {
+                    self.config.pin_game(&name);
+                } else {
+                    self.config.unpin_game(&name);
+                }
+                self.backup_screen
+                    .log
+                    .sort(&self.config.backup.sort, &self.config.pinned_games);
+                self.restore_screen
+                    .log
+                    .sort(&self.config.restore.sort, &self.config.pinned_games);
+                self.save_config();
                 Command::none()
             }
             Message::ToggleCustomGameEnabled { index, enabled } => {
@@ -737,7 +1951,7 @@ impl Application for App {
                 } else {
                     self.config.disable_custom_game(index);
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::ToggleSearch { screen } => {
@@ -752,9 +1966,29 @@ impl Application for App {
                 }
                 Command::none()
             }
+            Message::ToggleViewMode { screen } => {
+                let log = match screen {
+                    Screen::Backup => &mut self.backup_screen.log,
+                    Screen::Restore => &mut self.restore_screen.log,
+                    _ => return Command::none(),
+                };
+                log.view_mode = match log.view_mode {
+                    ViewMode::Standard => ViewMode::Compact,
+                    ViewMode::Compact => ViewMode::Standard,
+                };
+                Command::none()
+            }
+            Message::ToggleRelativePaths { screen } => {
+                match screen {
+                    Screen::Backup => self.backup_screen.log.toggle_relative_paths(),
+                    Screen::Restore => self.restore_screen.log.toggle_relative_paths(),
+                    _ => {}
+                }
+                Command::none()
+            }
             Message::ToggleSpecificBackupPathIgnored { name, path, .. } => {
                 self.config.backup.toggled_paths.toggle(&name, &path);
-                self.config.save();
+                self.save_config();
                 self.backup_screen.log.update_ignored(
                     &name,
                     &self.config.backup.toggled_paths,
@@ -764,7 +1998,7 @@ impl Application for App {
             }
             Message::ToggleSpecificBackupRegistryIgnored { name, path, .. } => {
                 self.config.backup.toggled_registry.toggle(&name, &path);
-                self.config.save();
+                self.save_config();
                 self.backup_screen.log.update_ignored(
                     &name,
                     &self.config.backup.toggled_paths,
@@ -790,30 +2024,51 @@ impl Application for App {
                 match screen {
                     Screen::Backup => {
                         self.config.backup.sort.key = value;
-                        self.backup_screen.log.sort(&self.config.backup.sort);
+                        self.backup_screen
+                            .log
+                            .sort(&self.config.backup.sort, &self.config.pinned_games);
                     }
                     Screen::Restore => {
                         self.config.restore.sort.key = value;
-                        self.restore_screen.log.sort(&self.config.restore.sort);
+                        self.restore_screen
+                            .log
+                            .sort(&self.config.restore.sort, &self.config.pinned_games);
                     }
                     _ => {}
                 }
-                self.config.save();
+                self.save_config();
+                Command::none()
+            }
+            Message::EditedStaleOnly(value) => {
+                self.backup_screen.log.search.stale_only = value;
+                Command::none()
+            }
+            Message::EditedNeverBackedUpOnly(value) => {
+                self.backup_screen.log.search.never_backed_up_only = value;
+                Command::none()
+            }
+            Message::FilterByStore(store) => {
+                self.backup_screen.log.search.store = StoreFilter(store);
+                self.restore_screen.log.search.store = StoreFilter(store);
                 Command::none()
             }
             Message::EditedSortReversed { screen, value } => {
                 match screen {
                     Screen::Backup => {
                         self.config.backup.sort.reversed = value;
-                        self.backup_screen.log.sort(&self.config.backup.sort);
+                        self.backup_screen
+                            .log
+                            .sort(&self.config.backup.sort, &self.config.pinned_games);
                     }
                     Screen::Restore => {
                         self.config.restore.sort.reversed = value;
-                        self.restore_screen.log.sort(&self.config.restore.sort);
+                        self.restore_screen
+                            .log
+                            .sort(&self.config.restore.sort, &self.config.pinned_games);
                     }
                     _ => {}
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::BrowseDir(subject) => Command::perform(
@@ -841,11 +2096,68 @@ impl Application for App {
                         BrowseSubject::BackupFilterIgnoredPath(i) => Message::EditedBackupFilterIgnoredPath(
                             EditAction::Change(i, crate::path::render_pathbuf(&path)),
                         ),
+                        BrowseSubject::MigrationSource => {
+                            Message::EditedMigrationSource(crate::path::render_pathbuf(&path))
+                        }
+                        BrowseSubject::MigrationTarget => {
+                            Message::EditedMigrationTarget(crate::path::render_pathbuf(&path))
+                        }
                     },
                     Ok(None) => Message::Ignore,
                     Err(_) => Message::BrowseDirFailure,
                 },
             ),
+            Message::ExportConfig => Command::perform(
+                async move {
+                    native_dialog::FileDialog::new()
+                        .set_filename("ludusavi-config.yaml")
+                        .add_filter("YAML", &["yaml", "yml"])
+                        .show_save_single_file()
+                },
+                move |choice| match choice {
+                    Ok(Some(path)) => Message::ExportConfigTo(StrictPath::from(path)),
+                    Ok(None) => Message::Ignore,
+                    Err(_) => Message::BrowseDirFailure,
+                },
+            ),
+            Message::ExportConfigTo(path) => {
+                if let Err(e) = self.config.export(&path) {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                }
+                Command::none()
+            }
+            Message::ImportConfig => Command::perform(
+                async move {
+                    native_dialog::FileDialog::new()
+                        .add_filter("YAML", &["yaml", "yml"])
+                        .show_open_single_file()
+                },
+                move |choice| match choice {
+                    Ok(Some(path)) => Message::ImportConfigFrom(StrictPath::from(path)),
+                    Ok(None) => Message::Ignore,
+                    Err(_) => Message::BrowseDirFailure,
+                },
+            ),
+            Message::ImportConfigFrom(path) => {
+                self.modal_theme = Some(match Config::import(&path) {
+                    Ok(config) => ModalTheme::ConfirmImportConfig(Box::new(config)),
+                    Err(e) => ModalTheme::Error { variant: e },
+                });
+                Command::none()
+            }
+            Message::ApplyImportedConfig { config, replace } => {
+                self.modal_theme = None;
+                if self.operation.is_some() {
+                    return Command::none();
+                }
+                if replace {
+                    self.config = *config;
+                } else {
+                    self.config.merge(*config);
+                }
+                self.save_config();
+                self.update(Message::ReloadConfig)
+            }
             Message::BrowseDirFailure => {
                 self.modal_theme = Some(ModalTheme::Error {
                     variant: Error::UnableToBrowseFileSystem,
@@ -871,7 +2183,7 @@ impl Application for App {
                     }
                     _ => {}
                 }
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::DeselectAllGames => {
@@ -893,11 +2205,48 @@ impl Application for App {
                     }
                     _ => {}
                 }
-                self.config.save();
+                self.save_config();
+                Command::none()
+            }
+            Message::SelectChangedGames => {
+                for entry in &self.backup_screen.log.entries {
+                    self.config.disable_game_for_backup(&entry.scan_info.game_name);
+                }
+                for game in &self.backup_screen.changed_games {
+                    self.config.enable_game_for_backup(game);
+                }
+                self.save_config();
+                Command::none()
+            }
+            Message::InvertSelection => {
+                match self.screen {
+                    Screen::Backup => {
+                        for entry in &self.backup_screen.log.entries {
+                            let name = &entry.scan_info.game_name;
+                            if self.config.is_game_enabled_for_backup(name) {
+                                self.config.disable_game_for_backup(name);
+                            } else {
+                                self.config.enable_game_for_backup(name);
+                            }
+                        }
+                    }
+                    Screen::Restore => {
+                        for entry in &self.restore_screen.log.entries {
+                            let name = &entry.scan_info.game_name;
+                            if self.config.is_game_enabled_for_restore(name) {
+                                self.config.disable_game_for_restore(name);
+                            } else {
+                                self.config.enable_game_for_restore(name);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                self.save_config();
                 Command::none()
             }
             Message::CustomizeGame { name } => {
-                let game = if let Some(standard) = self.manifest.0.get(&name) {
+                let game = if let Some(standard) = self.manifest.get(&name) {
                     CustomGame {
                         name: name.clone(),
                         ignore: false,
@@ -923,7 +2272,7 @@ impl Application for App {
                 self.custom_games_screen.games_editor.entries.push(gui_entry);
 
                 self.config.custom_games.push(game);
-                self.config.save();
+                self.save_config();
 
                 self.screen = Screen::CustomGames;
                 Command::none()
@@ -941,6 +2290,45 @@ impl Application for App {
                 });
                 Command::none()
             }
+            Message::ShowQuickBackup => {
+                if self.operation.is_none() && self.modal_theme.is_none() && self.config.mode.allows_backup() {
+                    self.palette.show();
+                }
+                Command::none()
+            }
+            Message::HideQuickBackup => {
+                self.palette.hide();
+                Command::none()
+            }
+            Message::EditedQuickBackupQuery(query) => {
+                let custom_games = self.config.custom_games.iter().filter(|x| !x.ignore).map(|x| &x.name);
+                self.palette.search(query, self.manifest.0.keys().chain(custom_games));
+                Command::none()
+            }
+            Message::QuickBackup(name) => {
+                self.palette.hide();
+                self.screen = Screen::Backup;
+                if self.config.backup_encrypts() || !self.config.backup_commands().is_empty() {
+                    // The confirmation asks for the passphrase and lists any commands that will run.
+                    self.confirm_backup(Some(vec![name]));
+                    Command::none()
+                } else {
+                    self.start_backup(false, Some(vec![name]))
+                }
+            }
+            Message::OpenFile { path } => {
+                let path2 = path.clone();
+                match std::thread::spawn(move || opener::open(path.interpret())).join() {
+                    Ok(Ok(_)) => Command::none(),
+                    _ => Command::perform(async {}, move |_| Message::OpenFileFailure { path: path2.clone() }),
+                }
+            }
+            Message::OpenFileFailure { path } => {
+                self.modal_theme = Some(ModalTheme::Error {
+                    variant: Error::UnableToOpenFile(path),
+                });
+                Command::none()
+            }
             Message::OpenWiki { game } => {
                 let url = format!("https://www.pcgamingwiki.com/wiki/{}", game.replace(' ', "_"));
                 let url2 = url.clone();
@@ -955,7 +2343,90 @@ impl Application for App {
                 });
                 Command::none()
             }
+            Message::ToggleManifestCategory { category, enabled } => {
+                if enabled {
+                    self.config.manifest.excluded_categories.remove(&category);
+                } else {
+                    self.config.manifest.excluded_categories.insert(category);
+                }
+                self.save_config();
+                Command::none()
+            }
+            Message::ScanProgress(progress) => {
+                if matches!(
+                    self.operation,
+                    Some(OngoingOperation::Backup | OngoingOperation::PreviewBackup)
+                ) {
+                    let line = self.translator.scan_progress_activity(&progress.game, progress.files);
+                    self.backup_screen.show_scan_progress(progress.game, line);
+                }
+                Command::none()
+            }
+            Message::Tray(action) => {
+                match action {
+                    TrayAction::BackUp => {
+                        if self.operation.is_none() && self.modal_theme.is_none() {
+                            if self.config.backup_encrypts() || !self.config.backup_commands().is_empty() {
+                                // The passphrase and the confirmation for commands are only in the window.
+                                self.hidden = false;
+                                self.confirm_backup(None);
+                            } else {
+                                return self.start_backup(false, None);
+                            }
+                        }
+                    }
+                    TrayAction::ShowWindow => self.hidden = false,
+                    TrayAction::HideWindow => self.hidden = true,
+                    TrayAction::Quit => {
+                        self.operation_should_cancel
+                            .swap(true, std::sync::atomic::Ordering::Relaxed);
+                        self.exiting = true;
+                    }
+                }
+                Command::none()
+            }
+            Message::CheckSchedule => {
+                let now = std::time::Instant::now();
+                let interval = match (self.config.backup.schedule, self.next_scheduled_backup) {
+                    (Some(interval), Some(due)) if now >= due => interval,
+                    _ => return Command::none(),
+                };
+                self.next_scheduled_backup = Some(now + interval);
+
+                if !self.config.mode.allows_backup() {
+                    return Command::none();
+                }
+                if self.operation.is_some() || self.modal_theme.is_some() {
+                    self.backup_screen
+                        .log_activity(self.translator.scheduled_backup_skipped_busy());
+                    return Command::none();
+                }
+                if self.config.backup_encrypts() {
+                    match std::env::var(PASSPHRASE_ENV_VAR) {
+                        Ok(passphrase) if !passphrase.is_empty() => self.modal.passphrase = passphrase,
+                        _ => {
+                            self.backup_screen
+                                .log_activity(self.translator.scheduled_backup_skipped_passphrase());
+                            return Command::none();
+                        }
+                    }
+                }
+
+                // Enabling the schedule is the user's consent to back up unattended,
+                // so any configured commands run without asking for confirmation.
+                self.scheduled_backup_running = true;
+                let command = self.start_backup(false, None);
+                self.scheduled_backup_running = self.operation.is_some();
+                command
+            }
             Message::SubscribedEvent(event) => {
+                if let iced_native::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(modifiers)) = event {
+                    self.modifiers = modifiers;
+                }
+                if let iced_native::Event::Mouse(iced::mouse::Event::ButtonPressed(_)) = event {
+                    // Clicking takes over from keyboard navigation.
+                    self.focus = None;
+                }
                 if let iced_native::Event::Keyboard(key) = event {
                     if let Some((key_code, modifiers)) = get_key_pressed(key) {
                         let activated = if cfg!(target_os = "mac") {
@@ -963,6 +2434,29 @@ impl Application for App {
                         } else {
                             modifiers.control()
                         };
+
+                        if self.palette.shown {
+                            if key_code == KeyCode::Escape {
+                                self.palette.hide();
+                            }
+                            return Command::none();
+                        }
+                        if key_code == KeyCode::K && activated && !modifiers.shift() {
+                            return self.update(Message::ShowQuickBackup);
+                        }
+
+                        if self.modal_theme.is_none() && !activated && !modifiers.alt() {
+                            match key_code {
+                                KeyCode::Tab => {
+                                    self.move_focus(modifiers.shift());
+                                    return Command::none();
+                                }
+                                KeyCode::Enter | KeyCode::NumpadEnter | KeyCode::Space => {
+                                    return self.activate_focus();
+                                }
+                                _ => {}
+                            }
+                        }
                         let shortcut = match (key_code, activated, modifiers.shift()) {
                             (KeyCode::Z, true, false) => Some(Shortcut::Undo),
                             (KeyCode::Y, true, false) | (KeyCode::Z, true, true) => Some(Shortcut::Redo),
@@ -978,6 +2472,9 @@ impl Application for App {
                                     &mut self.config.backup.path,
                                     &mut self.backup_screen.backup_target_history,
                                 );
+                                if self.config.restore.link_paths {
+                                    self.config.restore.path = self.config.backup.path.clone();
+                                }
                                 matched = true;
                             } else if self.restore_screen.restore_source_input.is_focused() {
                                 apply_shortcut_to_strict_path_field(
@@ -985,6 +2482,9 @@ impl Application for App {
                                     &mut self.config.restore.path,
                                     &mut self.restore_screen.restore_source_history,
                                 );
+                                if self.config.restore.link_paths {
+                                    self.config.backup.path = self.config.restore.path.clone();
+                                }
                                 matched = true;
                             } else if self.backup_screen.log.search.game_name_input.is_focused() {
                                 apply_shortcut_to_string_field(
@@ -1001,7 +2501,7 @@ impl Application for App {
                                 );
                                 matched = true;
                             } else {
-                                for (i, root) in self.backup_screen.root_editor.rows.iter_mut().enumerate() {
+                                for (i, root) in self.roots_screen.root_editor.rows.iter_mut().enumerate() {
                                     if root.text_state.is_focused() {
                                         apply_shortcut_to_strict_path_field(
                                             &shortcut,
@@ -1113,34 +2613,63 @@ impl Application for App {
                             }
 
                             if matched {
-                                self.config.save();
+                                self.save_config();
                             }
                         }
                     }
                 };
                 Command::none()
             }
+            Message::ToggleModalDetails => {
+                self.modal.show_details = !self.modal.show_details;
+                Command::none()
+            }
+            Message::EditedPassphrase(text) => {
+                self.modal.passphrase = text;
+                Command::none()
+            }
+            Message::CopyText(text) => iced::clipboard::write(text),
             Message::EditedFullRetention(value) => {
                 self.config.backup.retention.full = value;
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
             Message::EditedDiffRetention(value) => {
                 self.config.backup.retention.differential = value;
-                self.config.save();
+                self.save_config();
                 Command::none()
             }
         }
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced_native::subscription::events_with(|event, _| Some(event)).map(Message::SubscribedEvent)
+        let events = iced_native::subscription::events_with(|event, _| Some(event)).map(Message::SubscribedEvent);
+        let mut subscriptions = vec![events, scan_progress::subscription()];
+        if self.tray {
+            subscriptions.push(tray::subscription());
+        }
+        if self.config.backup.schedule.is_some() {
+            subscriptions.push(schedule::subscription());
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn view(&mut self) -> Element<Message> {
+        crate::gui::mark_renderer_ready();
+
         if let Some(m) = &self.modal_theme {
             return self.modal.view(m, &self.config, &self.translator).into();
         }
+        if self.palette.shown {
+            return self.palette.view(&self.translator).into();
+        }
+
+        let status_bar = self.status_bar.view(
+            &self.translator,
+            &self.operation,
+            self.progress.current,
+            self.progress.max,
+        );
 
         Column::new()
             .align_items(Alignment::Center)
@@ -1157,10 +2686,13 @@ impl Application for App {
                         )
                         .on_press(Message::SwitchScreen(Screen::Backup))
                         .width(Length::Units(175))
-                        .style(match self.screen {
-                            Screen::Backup => style::NavButton::Active,
-                            _ => style::NavButton::Inactive,
-                        }),
+                        .style(style::focusable(
+                            match self.screen {
+                                Screen::Backup => style::NavButton::Active,
+                                _ => style::NavButton::Inactive,
+                            },
+                            self.focus == Some(FocusTarget::Nav(Screen::Backup)),
+                        )),
                     )
                     .push(
                         Button::new(
@@ -1171,10 +2703,30 @@ impl Application for App {
                         )
                         .on_press(Message::SwitchScreen(Screen::Restore))
                         .width(Length::Units(175))
-                        .style(match self.screen {
-                            Screen::Restore => style::NavButton::Active,
-                            _ => style::NavButton::Inactive,
-                        }),
+                        .style(style::focusable(
+                            match self.screen {
+                                Screen::Restore => style::NavButton::Active,
+                                _ => style::NavButton::Inactive,
+                            },
+                            self.focus == Some(FocusTarget::Nav(Screen::Restore)),
+                        )),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.nav_to_roots_button,
+                            Text::new(self.translator.nav_roots_button())
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center),
+                        )
+                        .on_press(Message::SwitchScreen(Screen::Roots))
+                        .width(Length::Units(175))
+                        .style(style::focusable(
+                            match self.screen {
+                                Screen::Roots => style::NavButton::Active,
+                                _ => style::NavButton::Inactive,
+                            },
+                            self.focus == Some(FocusTarget::Nav(Screen::Roots)),
+                        )),
                     )
                     .push(
                         Button::new(
@@ -1185,10 +2737,13 @@ impl Application for App {
                         )
                         .on_press(Message::SwitchScreen(Screen::CustomGames))
                         .width(Length::Units(175))
-                        .style(match self.screen {
-                            Screen::CustomGames => style::NavButton::Active,
-                            _ => style::NavButton::Inactive,
-                        }),
+                        .style(style::focusable(
+                            match self.screen {
+                                Screen::CustomGames => style::NavButton::Active,
+                                _ => style::NavButton::Inactive,
+                            },
+                            self.focus == Some(FocusTarget::Nav(Screen::CustomGames)),
+                        )),
                     )
                     .push(
                         Button::new(
@@ -1199,32 +2754,92 @@ impl Application for App {
                         )
                         .on_press(Message::SwitchScreen(Screen::Other))
                         .width(Length::Units(175))
-                        .style(match self.screen {
-                            Screen::Other => style::NavButton::Active,
-                            _ => style::NavButton::Inactive,
-                        }),
+                        .style(style::focusable(
+                            match self.screen {
+                                Screen::Other => style::NavButton::Active,
+                                _ => style::NavButton::Inactive,
+                            },
+                            self.focus == Some(FocusTarget::Nav(Screen::Other)),
+                        )),
                     ),
             )
+            .push_some(|| {
+                let unsaved = self.config != self.saved_config;
+                if !unsaved && self.config_save_error.is_none() {
+                    return None;
+                }
+                let text = match &self.config_save_error {
+                    Some(error) => self.translator.handle_error(error),
+                    None => self.translator.unsaved_config_changes(),
+                };
+                Some(
+                    Container::new(
+                        Row::new()
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .push(Text::new(text).width(Length::Fill))
+                            .push_if(
+                                || unsaved,
+                                || {
+                                    Button::new(
+                                        &mut self.save_config_button,
+                                        Text::new(self.translator.save_config_button()),
+                                    )
+                                    .on_press(Message::SaveConfig)
+                                    .style(style::Button::Primary)
+                                },
+                            ),
+                    )
+                    .padding([5, 20, 5, 20])
+                    .width(Length::Fill)
+                    .style(style::Container::Banner),
+                )
+            })
             .push(
                 match self.screen {
-                    Screen::Backup => {
-                        self.backup_screen
-                            .view(&self.config, &self.manifest, &self.translator, &self.operation)
+                    Screen::Backup if !self.config.mode.allows(false, true) => {
+                        disabled_screen(self.translator.mode_disabled_screen(false))
                     }
-                    Screen::Restore => {
-                        self.restore_screen
-                            .view(&self.config, &self.manifest, &self.translator, &self.operation)
+                    Screen::Restore if !self.config.mode.allows(true, true) => {
+                        disabled_screen(self.translator.mode_disabled_screen(true))
+                    }
+                    Screen::Backup => self.backup_screen.view(
+                        &self.config,
+                        &self.manifest,
+                        &self.translator,
+                        &self.operation,
+                        self.focus,
+                    ),
+                    Screen::Restore => self.restore_screen.view(
+                        &self.config,
+                        &self.manifest,
+                        &self.translator,
+                        &self.operation,
+                        &self.staged,
+                        self.focus,
+                    ),
+                    Screen::Roots => {
+                        self.roots_screen
+                            .view(&self.config, &self.translator, &self.operation, self.focus)
                     }
                     Screen::CustomGames => {
                         self.custom_games_screen
-                            .view(&self.config, &self.translator, &self.operation)
+                            .view(&self.config, &self.translator, &self.operation, self.focus)
+                    }
+                    Screen::Other => {
+                        let stats = Stats {
+                            found_games: self.backup_screen.log.entries.len(),
+                            backed_up_games: self.last_backups.len(),
+                        };
+                        self.other_screen
+                            .view(&self.config, &self.manifest, &self.translator, &self.operation, stats)
                     }
-                    Screen::Other => self.other_screen.view(&self.config, &self.translator, &self.operation),
                 }
                 .padding([0, 5, 5, 5])
                 .height(Length::FillPortion(10_000)),
             )
             .push(self.progress.view())
+            .push_if(|| self.operation.is_some(), || status_bar)
             .into()
     }
 }
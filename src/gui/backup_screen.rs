@@ -1,62 +1,114 @@
 use crate::{
     config::Config,
-    gui::{
-        common::*,
-        game_list::GameList,
-        icon::Icon,
-        root_editor::{RootEditor, RootEditorRow},
-        style,
-    },
+    gui::{badge::Badge, common::*, focus::FocusTarget, game_list::GameList, icon::Icon, style},
     lang::Translator,
+    layout::BackupLayout,
     manifest::Manifest,
     prelude::DuplicateDetector,
+    sftp,
     shortcuts::TextHistory,
 };
 
 use iced::{
-    alignment::Horizontal as HorizontalAlignment, button, text_input, Alignment, Button, Checkbox, Column, Container,
-    Length, Row, Text, TextInput,
+    alignment::Horizontal as HorizontalAlignment, button, scrollable, text_input, Alignment, Button, Checkbox, Column,
+    Container, Length, Row, Scrollable, Text, TextInput,
 };
 
+/// How many lines to keep in the activity log, so that it doesn't grow
+/// unbounded for huge libraries.
+const MAX_ACTIVITY_LINES: usize = 100;
+
 #[derive(Default)]
 pub struct BackupScreenComponent {
     pub log: GameList,
     start_button: button::State,
     preview_button: button::State,
-    add_root_button: button::State,
-    find_roots_button: button::State,
     select_all_button: button::State,
+    invert_selection_button: button::State,
     toggle_search_button: button::State,
+    toggle_view_mode_button: button::State,
+    toggle_relative_paths_button: button::State,
+    retry_failed_button: button::State,
     pub backup_target_input: text_input::State,
     pub backup_target_history: TextHistory,
     backup_target_browse_button: button::State,
-    pub root_editor: RootEditor,
+    edit_roots_button: button::State,
+    select_changed_button: button::State,
+    /// Games whose saves were modified since their last backup, as of the last check.
+    pub changed_games: std::collections::HashSet<String>,
+    /// Games that seemed to be running when the last backup or preview started.
+    pub running_games: std::collections::HashSet<String>,
+    /// Current size of the backup folder, when there is a size budget.
+    pub backup_usage: Option<u64>,
     pub recent_found_games: std::collections::HashSet<String>,
     pub duplicate_detector: DuplicateDetector,
     full_retention_input: crate::gui::number_input::NumberInput,
     diff_retention_input: crate::gui::number_input::NumberInput,
+    activity: std::collections::VecDeque<String>,
+    /// Games that are still being scanned, with their latest progress line.
+    scan_progress: std::collections::BTreeMap<String, String>,
+    activity_scroll: scrollable::State,
 }
 
 impl BackupScreenComponent {
     pub fn new(config: &Config) -> Self {
-        let mut root_editor = RootEditor::default();
-        for root in &config.roots {
-            root_editor.rows.push(RootEditorRow::new(&root.path.raw()))
-        }
-
         Self {
-            root_editor,
             backup_target_history: TextHistory::new(&config.backup.path.raw(), 100),
             ..Default::default()
         }
     }
 
+    /// Pick up a reloaded config while keeping the current scan results.
+    pub fn reload(&mut self, config: &Config) {
+        self.backup_target_history = TextHistory::new(&config.backup.path.raw(), 100);
+    }
+
+    /// Measure the backup folder when there is a size budget.
+    /// This walks the whole folder, so it shouldn't block the window.
+    pub fn refresh_backup_usage(config: &Config) -> iced::Command<Message> {
+        let path = sftp::local_path(&config.backup.path);
+        let retention = config.backup.retention.clone();
+        let budgeted = config.backup.max_total_bytes.is_some();
+        iced::Command::perform(
+            async move { budgeted.then(|| BackupLayout::new(path, retention).total_size()) },
+            Message::BackupUsageMeasured,
+        )
+    }
+
+    pub fn log_activity(&mut self, line: String) {
+        self.activity.push_back(line);
+        while self.activity.len() > MAX_ACTIVITY_LINES {
+            self.activity.pop_front();
+        }
+        // Keep the newest line in view.
+        self.activity_scroll.snap_to(1.0);
+    }
+
+    pub fn clear_activity(&mut self) {
+        self.activity.clear();
+        self.scan_progress.clear();
+    }
+
+    pub fn show_scan_progress(&mut self, game: String, line: String) {
+        self.scan_progress.insert(game, line);
+        self.activity_scroll.snap_to(1.0);
+    }
+
+    pub fn finish_scan_progress(&mut self, game: &str) {
+        self.scan_progress.remove(game);
+    }
+
+    pub fn clear_scan_progress(&mut self) {
+        self.scan_progress.clear();
+    }
+
     pub fn view(
         &mut self,
         config: &Config,
         manifest: &Manifest,
         translator: &Translator,
         operation: &Option<OngoingOperation>,
+        focus: Option<FocusTarget>,
     ) -> Container<Message> {
         Container::new(
             Column::new()
@@ -86,11 +138,14 @@ impl BackupScreenComponent {
                                 _ => Message::Ignore,
                             })
                             .width(Length::Units(125))
-                            .style(match operation {
-                                None => style::Button::Primary,
-                                Some(OngoingOperation::PreviewBackup) => style::Button::Negative,
-                                _ => style::Button::Disabled,
-                            }),
+                            .style(style::focusable(
+                                match operation {
+                                    None => style::Button::Primary,
+                                    Some(OngoingOperation::PreviewBackup) => style::Button::Negative,
+                                    _ => style::Button::Disabled,
+                                },
+                                focus == Some(FocusTarget::Preview),
+                            )),
                         )
                         .push(
                             Button::new(
@@ -108,32 +163,31 @@ impl BackupScreenComponent {
                                 _ => Message::Ignore,
                             })
                             .width(Length::Units(125))
-                            .style(match operation {
-                                None => style::Button::Primary,
-                                Some(OngoingOperation::Backup) => style::Button::Negative,
-                                _ => style::Button::Disabled,
-                            }),
-                        )
-                        .push(
-                            Button::new(
-                                &mut self.add_root_button,
-                                Text::new(translator.add_root_button())
-                                    .horizontal_alignment(HorizontalAlignment::Center),
-                            )
-                            .on_press(Message::EditedRoot(EditAction::Add))
-                            .width(Length::Units(125))
-                            .style(style::Button::Primary),
+                            .style(style::focusable(
+                                match operation {
+                                    None => style::Button::Primary,
+                                    Some(OngoingOperation::Backup) => style::Button::Negative,
+                                    _ => style::Button::Disabled,
+                                },
+                                focus == Some(FocusTarget::Start),
+                            )),
                         )
-                        .push(
-                            Button::new(
-                                &mut self.find_roots_button,
-                                Text::new(translator.find_roots_button())
-                                    .horizontal_alignment(HorizontalAlignment::Center),
+                        .push_some(|| {
+                            let failed = self.log.failed_games();
+                            if failed.is_empty() || operation.is_some() {
+                                return None;
+                            }
+                            Some(
+                                Button::new(
+                                    &mut self.retry_failed_button,
+                                    Text::new(translator.retry_failed_button())
+                                        .horizontal_alignment(HorizontalAlignment::Center),
+                                )
+                                .on_press(Message::ConfirmBackupStart { games: Some(failed) })
+                                .width(Length::Units(125))
+                                .style(style::Button::Negative),
                             )
-                            .on_press(Message::FindRoots)
-                            .width(Length::Units(125))
-                            .style(style::Button::Primary),
-                        )
+                        })
                         .push({
                             let restoring = false;
                             Button::new(
@@ -151,23 +205,125 @@ impl BackupScreenComponent {
                                 Message::SelectAllGames
                             })
                             .width(Length::Units(125))
-                            .style(style::Button::Primary)
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::SelectAll),
+                            ))
                         })
+                        .push(
+                            Button::new(
+                                &mut self.invert_selection_button,
+                                Text::new(translator.invert_selection_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::InvertSelection)
+                            .width(Length::Units(125))
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::InvertSelection),
+                            )),
+                        )
                         .push(
                             Button::new(&mut self.toggle_search_button, Icon::Search.as_text())
                                 .on_press(Message::ToggleSearch { screen: Screen::Backup })
-                                .style(if self.log.search.show {
-                                    style::Button::Negative
-                                } else {
-                                    style::Button::Primary
-                                }),
+                                .style(style::focusable(
+                                    if self.log.search.show {
+                                        style::Button::Negative
+                                    } else {
+                                        style::Button::Primary
+                                    },
+                                    focus == Some(FocusTarget::ToggleSearch),
+                                )),
+                        )
+                        .push(
+                            Button::new(
+                                &mut self.toggle_view_mode_button,
+                                match self.log.view_mode {
+                                    ViewMode::Standard => Icon::ViewHeadline.as_text(),
+                                    ViewMode::Compact => Icon::ViewAgenda.as_text(),
+                                },
+                            )
+                            .on_press(Message::ToggleViewMode { screen: Screen::Backup })
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::ToggleViewMode),
+                            )),
+                        )
+                        .push(
+                            Button::new(&mut self.toggle_relative_paths_button, Icon::ShortText.as_text())
+                                .on_press(Message::ToggleRelativePaths { screen: Screen::Backup })
+                                .style(style::focusable(
+                                    if self.log.relative_paths {
+                                        style::Button::Negative
+                                    } else {
+                                        style::Button::Primary
+                                    },
+                                    focus == Some(FocusTarget::ToggleRelativePaths),
+                                )),
                         ),
                 )
-                .push(make_status_row(
-                    translator,
-                    &self.log.compute_operation_status(config, false),
-                    self.duplicate_detector.any_duplicates(),
-                ))
+                .push(
+                    make_status_row(
+                        translator,
+                        &self.log.compute_operation_status(config, false),
+                        self.duplicate_detector.any_duplicates(),
+                    )
+                    .push_some(|| {
+                        let never_backed_up = self.log.count_never_backed_up();
+                        (never_backed_up > 0).then(|| {
+                            Badge::new(&translator.badge_never_backed_up_count(never_backed_up))
+                                .left_margin(15)
+                                .view()
+                        })
+                    })
+                    .push_if(
+                        || !self.changed_games.is_empty(),
+                        || {
+                            Badge::new(&translator.badge_changed_count(self.changed_games.len()))
+                                .left_margin(15)
+                                .view()
+                        },
+                    )
+                    .push_if(
+                        || !self.changed_games.is_empty(),
+                        || {
+                            Button::new(
+                                &mut self.select_changed_button,
+                                Text::new(translator.select_changed_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::SelectChangedGames)
+                            .width(Length::Units(125))
+                            .style(style::Button::Primary)
+                        },
+                    )
+                    .push_some(|| {
+                        config.backup.max_total_bytes.map(|limit| {
+                            Badge::new(&translator.badge_backup_budget(self.backup_usage.unwrap_or(0), limit))
+                                .left_margin(15)
+                                .view()
+                        })
+                    })
+                    .push_some(|| {
+                        config
+                            .backup
+                            .since_days
+                            .map(|days| Badge::new(&translator.badge_since_days(days)).left_margin(15).view())
+                    }),
+                )
+                .push_if(
+                    || !self.activity.is_empty() || !self.scan_progress.is_empty(),
+                    || {
+                        self.activity.iter().chain(self.scan_progress.values()).fold(
+                            Scrollable::new(&mut self.activity_scroll)
+                                .width(Length::Fill)
+                                .height(Length::Units(60))
+                                .padding([0, 20, 0, 20])
+                                .style(style::Scrollable),
+                            |parent, line| parent.push(Text::new(line).size(16)),
+                        )
+                    },
+                )
                 .push(
                     Row::new()
                         .padding([0, 20, 0, 20])
@@ -210,19 +366,58 @@ impl BackupScreenComponent {
                             translator.backup_merge_label(),
                             Message::EditedBackupMerge,
                         ))
+                        .push(Checkbox::new(
+                            config.backup.encrypt,
+                            translator.backup_encrypt_label(),
+                            Message::EditedBackupEncrypt,
+                        ))
+                        .push(Checkbox::new(
+                            config.backup.files,
+                            translator.files_label(),
+                            Message::EditedBackupFiles,
+                        ))
+                        .push(Checkbox::new(
+                            config.backup.registry,
+                            translator.registry_label(),
+                            Message::EditedBackupRegistry,
+                        ))
                         .push(
                             Button::new(&mut self.backup_target_browse_button, Icon::FolderOpen.as_text())
                                 .on_press(match operation {
                                     None => Message::BrowseDir(BrowseSubject::BackupTarget),
                                     Some(_) => Message::Ignore,
                                 })
-                                .style(match operation {
-                                    None => style::Button::Primary,
-                                    Some(_) => style::Button::Disabled,
-                                }),
+                                .style(style::focusable(
+                                    match operation {
+                                        None => style::Button::Primary,
+                                        Some(_) => style::Button::Disabled,
+                                    },
+                                    focus == Some(FocusTarget::BrowsePath),
+                                )),
+                        ),
+                )
+                .push(
+                    Row::new()
+                        .padding([0, 20, 0, 20])
+                        .spacing(20)
+                        .align_items(Alignment::Center)
+                        .push(Text::new(if config.roots.is_empty() {
+                            translator.no_roots_are_configured()
+                        } else {
+                            translator
+                                .roots_summary(config.roots.len(), config.roots.iter().filter(|x| !x.ignore).count())
+                        }))
+                        .push(
+                            Button::new(
+                                &mut self.edit_roots_button,
+                                Text::new(translator.edit_roots_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::SwitchScreen(Screen::Roots))
+                            .width(Length::Units(125))
+                            .style(style::Button::Primary),
                         ),
                 )
-                .push(self.root_editor.view(config, translator, operation))
                 .push(
                     self.log
                         .view(false, translator, config, manifest, &self.duplicate_detector, operation),
@@ -5,6 +5,7 @@ use iced::{Container, Text};
 pub struct Badge {
     text: String,
     left_margin: u16,
+    style: Option<style::Container>,
 }
 
 impl Badge {
@@ -12,6 +13,7 @@ impl Badge {
         Self {
             text: text.to_string(),
             left_margin: 0,
+            style: None,
         }
     }
 
@@ -20,11 +22,16 @@ impl Badge {
         self
     }
 
+    pub fn style(mut self, style: style::Container) -> Self {
+        self.style = Some(style);
+        self
+    }
+
     pub fn view(self) -> Container<'static, Message> {
         Container::new(
             Container::new(Text::new(self.text).size(14))
                 .padding([2, 12, 2, 12])
-                .style(style::Container::Badge),
+                .style(self.style.unwrap_or(style::Container::Badge)),
         )
         .padding([3, 0, 0, self.left_margin])
         .center_x()
@@ -1,13 +1,18 @@
 use crate::{
-    config::{RootsConfig, SortKey},
-    gui::badge::Badge,
+    config::{Config, RootsConfig, SortKey},
+    gui::{badge::Badge, scan_progress::ScanProgress, tray::TrayAction},
     lang::Translator,
     manifest::Store,
-    prelude::{BackupInfo, OperationStatus, OperationStepDecision, RegistryItem, ScanInfo, StrictPath},
+    prelude::{
+        BackupInfo, DeletionPreview, Error, OperationStatus, OperationStepDecision, RegistryChange, RegistryItem,
+        RestoreConflict, ScanInfo, StrictPath,
+    },
     shortcuts::{Shortcut, TextHistory},
 };
 
-use iced::{Alignment, Row, Text};
+use iced::{Alignment, Container, Length, Row, Text};
+
+pub use crate::prelude::{OngoingOperation, OperationSummary};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -26,6 +31,46 @@ pub enum Message {
     RestoreStart {
         preview: bool,
         games: Option<Vec<String>>,
+        keep: Vec<RestoreConflict>,
+    },
+    ConfirmRestorePrepared {
+        games: Option<Vec<String>>,
+        result: Result<(), Error>,
+    },
+    ConfirmRestoreReviewed {
+        games: Option<Vec<String>>,
+        encrypted: bool,
+        result: Result<RestoreReview, Error>,
+    },
+    BackupPrepared {
+        preview: bool,
+        games: Option<Vec<String>>,
+        result: Result<(), Error>,
+    },
+    PreBackupCommandFinished {
+        games: Option<Vec<String>>,
+        result: Result<(), Error>,
+    },
+    RunningGamesDetected(std::collections::HashSet<String>),
+    /// With a size budget, the existing backups have been measured.
+    BackupBudgetMeasured {
+        preview: bool,
+        existing_total: u64,
+        existing_games: std::collections::HashMap<String, u64>,
+    },
+    BackupUsageMeasured(Option<u64>),
+    RestorePrepared {
+        preview: bool,
+        games: Option<Vec<String>>,
+        keep: Vec<RestoreConflict>,
+        result: Result<(), Error>,
+    },
+    ResolveRestoreConflict {
+        index: usize,
+        keep: bool,
+    },
+    ResolveAllRestoreConflicts {
+        keep: bool,
     },
     BackupStep {
         scan_info: Option<ScanInfo>,
@@ -33,6 +78,12 @@ pub enum Message {
         decision: OperationStepDecision,
         preview: bool,
     },
+    /// With a size budget, a game has been scanned, but not backed up yet.
+    BackupScanned {
+        scan_info: Option<ScanInfo>,
+        decision: OperationStepDecision,
+        preview: bool,
+    },
     RestoreStep {
         scan_info: Option<ScanInfo>,
         backup_info: Option<BackupInfo>,
@@ -42,6 +93,18 @@ pub enum Message {
     BackupComplete {
         preview: bool,
     },
+    DedupeStorePruned {
+        scheduled: bool,
+    },
+    BackupUploaded {
+        preview: bool,
+        scheduled: bool,
+        result: Result<(), Error>,
+    },
+    PostBackupCommandFinished {
+        scheduled: bool,
+        result: Result<(), Error>,
+    },
     RestoreComplete,
     ProcessGameOnDemand {
         game: String,
@@ -49,17 +112,67 @@ pub enum Message {
     },
     EditedBackupTarget(String),
     EditedBackupMerge(bool),
+    EditedBackupEncrypt(bool),
+    EditedBackupFiles(bool),
+    EditedBackupRegistry(bool),
     EditedRestoreSource(String),
+    EditedRestoreOnlyChanged(bool),
+    EditedRestoreStage(bool),
+    EditedRestoreMirror(bool),
+    EditedRestoreFiles(bool),
+    EditedRestoreRegistry(bool),
+    EditedLinkPaths(bool),
+    ConfirmApplyStagedRestore,
+    ApplyStagedRestore,
+    AppliedStagedRestore {
+        staged: crate::staging::StagedRestoration,
+        result: Result<BackupInfo, Error>,
+    },
     FindRoots,
+    ReloadConfig,
+    SaveConfig,
+    ExportConfig,
+    ExportConfigTo(StrictPath),
+    ImportConfig,
+    ImportConfigFrom(StrictPath),
+    ApplyImportedConfig {
+        config: Box<Config>,
+        replace: bool,
+    },
+    LintManifest,
+    EditedMigrationSource(String),
+    EditedMigrationTarget(String),
+    MigrateBackup,
+    MigratedBackup(Result<(), Error>),
+    ConfirmTestRestore,
+    TestRestore,
+    ConfirmPruneOrphans,
+    FoundOrphanedBackups(DeletionPreview),
+    PruneOrphans(DeletionPreview),
+    PrunedOrphans {
+        result: Result<(), Error>,
+        last_backups: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>,
+    },
+    TestedRestore {
+        total: usize,
+        failed: Vec<(String, usize)>,
+    },
     ConfirmAddMissingRoots(Vec<RootsConfig>),
     EditedRoot(EditAction),
+    MoveRoot(usize, usize),
     SelectedRootStore(usize, Store),
+    ToggleRootEnabled {
+        index: usize,
+        enabled: bool,
+    },
     EditedRedirect(EditAction, Option<RedirectEditActionField>),
     EditedCustomGame(EditAction),
     EditedCustomGameFile(usize, EditAction),
     EditedCustomGameRegistry(usize, EditAction),
     EditedExcludeOtherOsData(bool),
     EditedExcludeStoreScreenshots(bool),
+    EditedSkipHidden(bool),
+    EditedSteamAccountSettings(bool),
     EditedBackupFilterIgnoredPath(EditAction),
     EditedBackupFilterIgnoredRegistry(EditAction),
     SwitchScreen(Screen),
@@ -75,9 +188,24 @@ pub enum Message {
         enabled: bool,
         restoring: bool,
     },
+    ToggleGameListEntryPinned {
+        name: String,
+        pinned: bool,
+    },
+    ToggleSaveSlot {
+        game: String,
+        slot: String,
+        enabled: bool,
+    },
     ToggleSearch {
         screen: Screen,
     },
+    ToggleViewMode {
+        screen: Screen,
+    },
+    ToggleRelativePaths {
+        screen: Screen,
+    },
     ToggleSpecificBackupPathIgnored {
         name: String,
         path: StrictPath,
@@ -100,6 +228,8 @@ pub enum Message {
         screen: Screen,
         value: SortKey,
     },
+    EditedStaleOnly(bool),
+    EditedNeverBackedUpOnly(bool),
     EditedSortReversed {
         screen: Screen,
         value: bool,
@@ -107,13 +237,25 @@ pub enum Message {
     BrowseDir(BrowseSubject),
     BrowseDirFailure,
     SelectAllGames,
+    InvertSelection,
     DeselectAllGames,
+    SelectChangedGames,
     CustomizeGame {
         name: String,
     },
     OpenDir {
         path: StrictPath,
     },
+    ShowQuickBackup,
+    HideQuickBackup,
+    EditedQuickBackupQuery(String),
+    QuickBackup(String),
+    OpenFile {
+        path: StrictPath,
+    },
+    OpenFileFailure {
+        path: StrictPath,
+    },
     OpenDirFailure {
         path: StrictPath,
     },
@@ -124,30 +266,46 @@ pub enum Message {
         url: String,
     },
     SubscribedEvent(iced_native::Event),
+    Tray(TrayAction),
+    ScanProgress(ScanProgress),
+    ToggleManifestCategory {
+        category: String,
+        enabled: bool,
+    },
+    CheckSchedule,
+    FilterByStore(Option<Store>),
+    ToggleModalDetails,
+    EditedPassphrase(String),
+    CopyText(String),
     EditedFullRetention(u8),
     EditedDiffRetention(u8),
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum OngoingOperation {
-    Backup,
-    CancelBackup,
-    PreviewBackup,
-    CancelPreviewBackup,
-    Restore,
-    CancelRestore,
-    PreviewRestore,
-    CancelPreviewRestore,
+/// What a restore would change, for review before confirming it.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReview {
+    pub registry_changes: Vec<RegistryChange>,
+    pub conflicts: Vec<RestoreConflict>,
+    pub deletions: DeletionPreview,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Screen {
     Backup,
     Restore,
+    Roots,
     CustomGames,
     Other,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ViewMode {
+    #[default]
+    Standard,
+    /// One dense row per game, without the file list unless expanded.
+    Compact,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum EditAction {
     Add,
@@ -170,6 +328,8 @@ pub enum BrowseSubject {
     RedirectTarget(usize),
     CustomGameFile(usize, usize),
     BackupFilterIgnoredPath(usize),
+    MigrationSource,
+    MigrationTarget,
 }
 
 impl Default for Screen {
@@ -215,6 +375,16 @@ pub fn apply_shortcut_to_string_field(shortcut: &Shortcut, config: &mut String,
     }
 }
 
+/// Shown in place of a screen whose operation is turned off by the config's mode.
+pub fn disabled_screen<'a>(text: String) -> Container<'a, Message> {
+    Container::new(Text::new(text))
+        .padding(40)
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .center_x()
+        .center_y()
+}
+
 pub fn make_status_row<'a>(
     translator: &Translator,
     status: &OperationStatus,
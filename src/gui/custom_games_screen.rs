@@ -5,6 +5,7 @@ use crate::{
         common::{EditAction, Message},
         custom_games_editor::CustomGamesEditor,
         custom_games_editor::{CustomGamesEditorEntry, CustomGamesEditorEntryRow},
+        focus::FocusTarget,
         style,
     },
     lang::Translator,
@@ -46,6 +47,7 @@ impl CustomGamesScreenComponent {
         config: &Config,
         translator: &Translator,
         operation: &Option<OngoingOperation>,
+        focus: Option<FocusTarget>,
     ) -> Container<Message> {
         Container::new(
             Column::new()
@@ -64,7 +66,10 @@ impl CustomGamesScreenComponent {
                             )
                             .on_press(Message::EditedCustomGame(EditAction::Add))
                             .width(Length::Units(125))
-                            .style(style::Button::Primary),
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::AddGame),
+                            )),
                         )
                         .push({
                             Button::new(
@@ -82,7 +87,10 @@ impl CustomGamesScreenComponent {
                                 Message::SelectAllGames
                             })
                             .width(Length::Units(125))
-                            .style(style::Button::Primary)
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::SelectAll),
+                            ))
                         }),
                 )
                 .push(self.games_editor.view(config, translator, operation)),
@@ -8,9 +8,13 @@ use crate::{
     },
     lang::Translator,
     path::StrictPath,
-    prelude::{game_file_restoration_target, BackupInfo, DuplicateDetector, RegistryItem, ScanInfo},
+    prelude::{
+        game_file_restoration_target, BackupInfo, DuplicateDetector, RegistryItem, RestorationTargetError, ScanInfo,
+    },
+};
+use iced::{
+    button, tooltip, Alignment, Button, Checkbox, Column, Container, Element, Length, Row, Space, Text, Tooltip,
 };
-use iced::{button, Alignment, Button, Checkbox, Column, Container, Length, Row, Space, Text};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum FileTreeNodeType {
@@ -42,6 +46,7 @@ struct FileTreeNode {
     ignored: bool,
     duplicated: bool,
     redirected_from: Option<StrictPath>,
+    unresolved: Option<RestorationTargetError>,
     node_type: FileTreeNodeType,
 }
 
@@ -67,6 +72,18 @@ impl FileTreeNode {
         false
     }
 
+    /// Files show their full path on hover, since the tree only shows part of it.
+    fn label<'a>(&self, label: &str) -> Element<'a, Message> {
+        match &self.path {
+            Some(FileTreeNodePath::File(path)) => Tooltip::new(Text::new(label), path.render(), tooltip::Position::Top)
+                .size(16)
+                .gap(5)
+                .style(style::Container::Tooltip)
+                .into(),
+            _ => Text::new(label).into(),
+        }
+    }
+
     pub fn view(
         &mut self,
         level: u16,
@@ -110,15 +127,18 @@ impl FileTreeNode {
                 Row::new()
                     .padding([0, 0, 0, 35 * level])
                     .push(
-                        Icon::SubdirectoryArrowRight
-                            .as_text()
-                            .height(Length::Units(25))
-                            .width(Length::Units(25))
-                            .size(25),
+                        (match self.node_type {
+                            FileTreeNodeType::File => Icon::SubdirectoryArrowRight,
+                            FileTreeNodeType::Registry => Icon::Key,
+                        })
+                        .as_text()
+                        .height(Length::Units(25))
+                        .width(Length::Units(25))
+                        .size(25),
                     )
                     .push(Space::new(Length::Units(10), Length::Shrink))
                     .push_some(make_enabler)
-                    .push(Text::new(label))
+                    .push(self.label(label))
                     .push_if(
                         || self.duplicated,
                         || Badge::new(&translator.badge_duplicated()).left_margin(15).view(),
@@ -131,6 +151,11 @@ impl FileTreeNode {
                         self.redirected_from
                             .as_ref()
                             .map(|r| Badge::new(&translator.badge_redirected_from(r)).left_margin(15).view())
+                    })
+                    .push_some(|| {
+                        self.unresolved
+                            .as_ref()
+                            .map(|e| Badge::new(&translator.badge_unresolved(e)).left_margin(15).view())
                     }),
             );
         } else if self.nodes.len() == 1 {
@@ -148,6 +173,7 @@ impl FileTreeNode {
             }
         }
 
+        let label = self.label(label);
         Container::new(
             self.nodes.iter_mut().filter(|(_, v)| v.anything_showable()).fold(
                 Column::new().push(
@@ -176,7 +202,7 @@ impl FileTreeNode {
                         )
                         .push(Space::new(Length::Units(10), Length::Shrink))
                         .push_some(make_enabler)
-                        .push(Text::new(label))
+                        .push(label)
                         .push(Space::new(Length::Units(10), Length::Shrink))
                         .push_some(|| {
                             if let Some(FileTreeNodePath::File(path)) = &self.path {
@@ -210,6 +236,7 @@ impl FileTreeNode {
         successful: bool,
         duplicated: bool,
         redirected_from: Option<StrictPath>,
+        unresolved: Option<RestorationTargetError>,
     ) -> &mut Self {
         let node_type = self.node_type.clone();
         let mut node = self;
@@ -240,6 +267,7 @@ impl FileTreeNode {
         node.successful = successful;
         node.duplicated = duplicated;
         node.redirected_from = redirected_from;
+        node.unresolved = unresolved;
 
         node
     }
@@ -282,9 +310,30 @@ impl FileTreeNode {
     }
 }
 
+/// The deepest folder that contains all of the given paths.
+fn common_directory<'a>(paths: &[Vec<&'a str>]) -> Vec<&'a str> {
+    let mut common: Option<Vec<&str>> = None;
+    for components in paths {
+        let parent = &components[..components.len().saturating_sub(1)];
+        common = Some(match common {
+            None => parent.to_vec(),
+            Some(common) => common
+                .into_iter()
+                .zip(parent.iter())
+                .take_while(|(a, b)| a == *b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    common.unwrap_or_default()
+}
+
 #[derive(Debug, Default)]
 pub struct FileTree {
     nodes: std::collections::BTreeMap<String, FileTreeNode>,
+    /// When showing paths relative to the game's common folder,
+    /// this is the node for that folder, whose contents are shown directly.
+    relative_root: Option<String>,
 }
 
 impl FileTree {
@@ -293,15 +342,27 @@ impl FileTree {
         config: &Config,
         backup_info: &Option<BackupInfo>,
         duplicate_detector: &DuplicateDetector,
+        relative: bool,
     ) -> Self {
         let mut nodes = std::collections::BTreeMap::<String, FileTreeNode>::new();
 
+        let mut files = vec![];
+        let mut rendered = vec![];
         for item in scan_info.found_files.iter() {
             let mut redirected_from = None;
+            let mut unresolved = None;
             let path_to_show = if let Some(original_path) = &item.original_path {
-                let (target, original_target) = game_file_restoration_target(original_path, &config.get_redirects());
-                redirected_from = original_target;
-                target.clone()
+                match game_file_restoration_target(original_path, &config.get_redirects()) {
+                    Ok((target, original_target)) => {
+                        redirected_from = original_target;
+                        target
+                    }
+                    Err(e) => {
+                        // Show it where it came from rather than hiding it.
+                        unresolved = Some(e);
+                        original_path.clone()
+                    }
+                }
             } else {
                 item.path.clone()
             };
@@ -313,18 +374,31 @@ impl FileTree {
                 }
             }
 
-            let rendered = path_to_show.render();
-            let components: Vec<_> = rendered.split('/').collect();
+            files.push((item, successful, redirected_from, unresolved));
+            rendered.push(path_to_show.render());
+        }
+
+        let all_components: Vec<Vec<_>> = rendered.iter().map(|x| x.split('/').collect()).collect();
+        let common = if relative {
+            common_directory(&all_components)
+        } else {
+            vec![]
+        };
+        let relative_root = (!common.is_empty()).then(|| common.join("/"));
+        let skip = if relative_root.is_some() { common.len() } else { 1 };
 
+        for ((item, successful, redirected_from, unresolved), components) in files.into_iter().zip(&all_components) {
+            let root = relative_root.clone().unwrap_or_else(|| components[0].to_string());
             nodes
-                .entry(components[0].to_string())
-                .or_insert_with(|| FileTreeNode::new(vec![components[0].to_string()], None, FileTreeNodeType::File))
+                .entry(root.clone())
+                .or_insert_with(|| FileTreeNode::new(vec![root.clone()], None, FileTreeNodeType::File))
                 .insert_keys(
-                    &components[1..],
-                    &[components[0]],
+                    &components[skip..],
+                    &[root.as_str()],
                     successful,
                     duplicate_detector.is_file_duplicated(item),
                     redirected_from,
+                    unresolved,
                 );
         }
         for item in scan_info.found_registry_keys.iter() {
@@ -346,6 +420,7 @@ impl FileTree {
                     successful,
                     duplicate_detector.is_registry_duplicated(&item.path),
                     None,
+                    None,
                 );
         }
 
@@ -358,7 +433,7 @@ impl FileTree {
             );
         }
 
-        Self { nodes }
+        Self { nodes, relative_root }
     }
 
     pub fn view(
@@ -368,14 +443,43 @@ impl FileTree {
         config: &Config,
         restoring: bool,
     ) -> Container<Message> {
-        Container::new(
-            self.nodes
-                .iter_mut()
-                .filter(|(_, v)| v.anything_showable())
-                .fold(Column::new().spacing(4), |parent, (k, v)| {
-                    parent.push(v.view(0, k, translator, game_name, config, restoring))
-                }),
-        )
+        let relative_root = &self.relative_root;
+        // Registry keys get their own section so that they can't be mistaken for paths.
+        let (registry, files): (Vec<_>, Vec<_>) = self
+            .nodes
+            .iter_mut()
+            .filter(|(_, v)| v.anything_showable())
+            .partition(|(_, v)| v.node_type == FileTreeNodeType::Registry);
+
+        let has_registry = !registry.is_empty();
+
+        let column = files.into_iter().fold(Column::new().spacing(4), |parent, (k, v)| {
+            if relative_root.as_ref() == Some(k) {
+                v.nodes
+                    .iter_mut()
+                    .filter(|(_, v)| v.anything_showable())
+                    .fold(parent, |parent, (k, v)| {
+                        parent.push(v.view(0, k, translator, game_name, config, restoring))
+                    })
+            } else {
+                parent.push(v.view(0, k, translator, game_name, config, restoring))
+            }
+        });
+
+        Container::new(registry.into_iter().fold(
+            column.push_if(
+                || has_registry,
+                || {
+                    Row::new()
+                        .align_items(Alignment::Center)
+                        .padding([5, 0, 0, 0])
+                        .push(Icon::Key.as_text().width(Length::Units(25)).size(20))
+                        .push(Space::new(Length::Units(10), Length::Shrink))
+                        .push(Text::new(translator.registry_section_label()))
+                },
+            ),
+            |parent, (k, v)| parent.push(v.view(0, k, translator, game_name, config, restoring)),
+        ))
     }
 
     pub fn is_empty(&self) -> bool {
@@ -384,6 +488,7 @@ impl FileTree {
 
     pub fn clear(&mut self) {
         self.nodes.clear();
+        self.relative_root = None;
     }
 
     pub fn expand_or_collapse_keys(&mut self, keys: &[String]) {
@@ -404,3 +509,23 @@ impl FileTree {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_find_common_directory() {
+        assert_eq!(Vec::<&str>::new(), common_directory(&[]));
+        assert_eq!(vec!["C:", "game"], common_directory(&[vec!["C:", "game", "file.sav"]]));
+        assert_eq!(
+            vec!["C:", "game"],
+            common_directory(&[vec!["C:", "game", "saves", "1.sav"], vec!["C:", "game", "config.ini"],])
+        );
+        assert_eq!(
+            Vec::<&str>::new(),
+            common_directory(&[vec!["C:", "game", "file.sav"], vec!["D:", "game", "file.sav"]])
+        );
+    }
+}
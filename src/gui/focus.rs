@@ -0,0 +1,145 @@
+use crate::gui::common::{BrowseSubject, EditAction, Message, Screen};
+
+/// Widgets that can be reached with Tab/Shift+Tab, since Iced doesn't
+/// provide keyboard focus for buttons on its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusTarget {
+    Nav(Screen),
+    Preview,
+    Start,
+    AddRoot,
+    FindRoots,
+    AddRedirect,
+    AddGame,
+    SelectAll,
+    InvertSelection,
+    ToggleSearch,
+    ToggleViewMode,
+    ToggleRelativePaths,
+    PathInput,
+    BrowsePath,
+}
+
+impl FocusTarget {
+    /// The tab order for a screen, from top left to bottom right.
+    pub fn order(screen: Screen) -> Vec<Self> {
+        let mut order = vec![
+            Self::Nav(Screen::Backup),
+            Self::Nav(Screen::Restore),
+            Self::Nav(Screen::Roots),
+            Self::Nav(Screen::CustomGames),
+            Self::Nav(Screen::Other),
+        ];
+        match screen {
+            Screen::Backup => order.extend([
+                Self::Preview,
+                Self::Start,
+                Self::SelectAll,
+                Self::InvertSelection,
+                Self::ToggleSearch,
+                Self::ToggleViewMode,
+                Self::ToggleRelativePaths,
+                Self::PathInput,
+                Self::BrowsePath,
+            ]),
+            Screen::Restore => order.extend([
+                Self::Preview,
+                Self::Start,
+                Self::AddRedirect,
+                Self::SelectAll,
+                Self::InvertSelection,
+                Self::ToggleSearch,
+                Self::ToggleViewMode,
+                Self::ToggleRelativePaths,
+                Self::PathInput,
+                Self::BrowsePath,
+            ]),
+            Screen::Roots => order.extend([Self::AddRoot, Self::FindRoots]),
+            Screen::CustomGames => order.extend([Self::AddGame, Self::SelectAll]),
+            Screen::Other => {}
+        }
+        order
+    }
+
+    pub fn next(current: Option<Self>, screen: Screen, backward: bool) -> Option<Self> {
+        let order = Self::order(screen);
+        let position = current.and_then(|x| order.iter().position(|y| *y == x));
+        let index = match (position, backward) {
+            (None, false) => 0,
+            (None, true) => order.len() - 1,
+            (Some(i), false) => (i + 1) % order.len(),
+            (Some(i), true) => (i + order.len() - 1) % order.len(),
+        };
+        order.get(index).copied()
+    }
+
+    pub fn is_text_input(&self) -> bool {
+        matches!(self, Self::PathInput)
+    }
+
+    /// What pressing Enter or Space does while this is focused.
+    pub fn activate(&self, screen: Screen, all_selected: bool) -> Message {
+        let restoring = screen == Screen::Restore;
+        match self {
+            Self::Nav(screen) => Message::SwitchScreen(*screen),
+            Self::Preview if restoring => Message::RestoreStart {
+                preview: true,
+                games: None,
+                keep: vec![],
+            },
+            Self::Preview => Message::BackupStart {
+                preview: true,
+                games: None,
+            },
+            Self::Start if restoring => Message::ConfirmRestoreStart { games: None },
+            Self::Start => Message::ConfirmBackupStart { games: None },
+            Self::AddRoot => Message::EditedRoot(EditAction::Add),
+            Self::FindRoots => Message::FindRoots,
+            Self::AddRedirect => Message::EditedRedirect(EditAction::Add, None),
+            Self::AddGame => Message::EditedCustomGame(EditAction::Add),
+            Self::SelectAll if all_selected => Message::DeselectAllGames,
+            Self::SelectAll => Message::SelectAllGames,
+            Self::InvertSelection => Message::InvertSelection,
+            Self::ToggleSearch => Message::ToggleSearch { screen },
+            Self::ToggleViewMode => Message::ToggleViewMode { screen },
+            Self::ToggleRelativePaths => Message::ToggleRelativePaths { screen },
+            Self::BrowsePath if restoring => Message::BrowseDir(BrowseSubject::RestoreSource),
+            Self::BrowsePath => Message::BrowseDir(BrowseSubject::BackupTarget),
+            Self::PathInput => Message::Ignore,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_cycle_through_focus_order() {
+        assert_eq!(
+            Some(FocusTarget::Nav(Screen::Backup)),
+            FocusTarget::next(None, Screen::CustomGames, false)
+        );
+        assert_eq!(
+            Some(FocusTarget::SelectAll),
+            FocusTarget::next(None, Screen::CustomGames, true)
+        );
+        assert_eq!(
+            Some(FocusTarget::AddGame),
+            FocusTarget::next(Some(FocusTarget::Nav(Screen::Other)), Screen::CustomGames, false)
+        );
+        assert_eq!(
+            Some(FocusTarget::Nav(Screen::Backup)),
+            FocusTarget::next(Some(FocusTarget::SelectAll), Screen::CustomGames, false)
+        );
+        assert_eq!(
+            Some(FocusTarget::SelectAll),
+            FocusTarget::next(Some(FocusTarget::Nav(Screen::Backup)), Screen::CustomGames, true)
+        );
+        assert_eq!(
+            Some(FocusTarget::Nav(Screen::Backup)),
+            FocusTarget::next(Some(FocusTarget::AddRoot), Screen::Other, false)
+        );
+    }
+}
@@ -1,16 +1,16 @@
 use crate::{
-    config::{Config, Sort, SortKey, ToggledPaths, ToggledRegistry},
+    config::{Config, RedirectConfig, Sort, SortKey, ToggledPaths, ToggledRegistry},
     gui::{
         badge::Badge,
-        common::{IcedExtension, Message, Screen},
+        common::{IcedExtension, Message, OperationSummary, Screen, ViewMode},
         file_tree::FileTree,
         icon::Icon,
-        search::SearchComponent,
+        search::{SearchComponent, StoreFilter},
         style,
     },
     lang::Translator,
-    manifest::Manifest,
-    prelude::{BackupInfo, DuplicateDetector, OperationStatus, ScanInfo},
+    manifest::{Manifest, Store},
+    prelude::{game_file_restoration_target, BackupInfo, DuplicateDetector, OperationStatus, ScanInfo},
 };
 
 use fuzzy_matcher::FuzzyMatcher;
@@ -29,12 +29,27 @@ pub struct GameListEntry {
     pub wiki_button: button::State,
     pub customize_button: button::State,
     pub operate_button: button::State,
+    pub pin_button: button::State,
     pub expanded: bool,
     pub tree: FileTree,
     pub duplicates: usize,
+    pub last_backup: Option<chrono::DateTime<chrono::Utc>>,
+    /// Saves were modified after the last backup.
+    pub changed: bool,
+    /// Skipped because the backup would have exceeded the size budget.
+    pub over_budget: bool,
+    /// Seemed to be running during the backup, so the saves may have been mid-write.
+    pub running: bool,
+    pub store: Store,
 }
 
 impl GameListEntry {
+    /// Found on disk, but not in any backup.
+    fn never_backed_up(&self) -> bool {
+        self.last_backup.is_none() && self.scan_info.found_anything()
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn view(
         &mut self,
         restoring: bool,
@@ -43,6 +58,8 @@ impl GameListEntry {
         manifest: &Manifest,
         duplicate_detector: &DuplicateDetector,
         operation: &Option<OngoingOperation>,
+        view_mode: ViewMode,
+        relative_paths: bool,
     ) -> Container<Message> {
         let successful = match &self.backup_info {
             Some(x) => x.successful(),
@@ -52,7 +69,13 @@ impl GameListEntry {
         let duplicates = duplicate_detector.count_duplicates_for(&self.scan_info.game_name);
         if self.expanded {
             if self.tree.is_empty() || duplicates != self.duplicates {
-                self.tree = FileTree::new(self.scan_info.clone(), config, &self.backup_info, duplicate_detector);
+                self.tree = FileTree::new(
+                    self.scan_info.clone(),
+                    config,
+                    &self.backup_info,
+                    duplicate_detector,
+                    relative_paths,
+                );
                 self.duplicates = duplicates;
             }
         } else {
@@ -64,9 +87,95 @@ impl GameListEntry {
         } else {
             config.is_game_enabled_for_backup(&self.scan_info.game_name)
         };
+        let oversized =
+            !restoring && config.exceeds_size_guard(&self.scan_info.game_name, self.scan_info.sum_bytes(&None));
         let customized = config.is_game_customized(&self.scan_info.game_name);
         let customized_pure = customized && !manifest.0.contains_key(&self.scan_info.game_name);
         let name_for_checkbox = self.scan_info.game_name.clone();
+        let never_backed_up = self.never_backed_up();
+        let pinned = config.is_game_pinned(&self.scan_info.game_name);
+        let pin_button = Button::new(
+            &mut self.pin_button,
+            if pinned { Icon::Star } else { Icon::StarBorder }
+                .as_text()
+                .width(Length::Units(45)),
+        )
+        .on_press(Message::ToggleGameListEntryPinned {
+            name: self.scan_info.game_name.clone(),
+            pinned: !pinned,
+        })
+        .style(style::Button::Primary)
+        .padding(2);
+
+        if view_mode == ViewMode::Compact {
+            return Container::new(
+                Column::new()
+                    .padding(2)
+                    .spacing(2)
+                    .push(
+                        Row::new()
+                            .align_items(Alignment::Center)
+                            .push(Checkbox::new(enabled, "", move |enabled| {
+                                Message::ToggleGameListEntryEnabled {
+                                    name: name_for_checkbox.clone(),
+                                    enabled,
+                                    restoring,
+                                }
+                            }))
+                            .push(
+                                Button::new(
+                                    &mut self.expand_button,
+                                    Text::new(self.scan_info.game_name.clone()).size(16),
+                                )
+                                .on_press(Message::ToggleGameListEntryExpanded {
+                                    name: self.scan_info.game_name.clone(),
+                                })
+                                .style(if !enabled {
+                                    style::Button::GameListEntryTitleDisabled
+                                } else if successful {
+                                    style::Button::GameListEntryTitle
+                                } else {
+                                    style::Button::GameListEntryTitleFailed
+                                })
+                                .width(Length::Fill)
+                                .padding(1),
+                            )
+                            .push(pin_button)
+                            .push_if(
+                                || oversized,
+                                || Badge::new(&translator.badge_too_large()).left_margin(15).view(),
+                            )
+                            .push(
+                                Container::new(
+                                    Text::new(translator.processed_subset(
+                                        self.scan_info.total_items(),
+                                        self.scan_info.enabled_items(),
+                                    ))
+                                    .size(16),
+                                )
+                                .width(Length::Units(115))
+                                .center_x(),
+                            )
+                            .push(
+                                Container::new(
+                                    Text::new(translator.adjusted_size(self.scan_info.sum_bytes(&self.backup_info)))
+                                        .size(16),
+                                )
+                                .width(Length::Units(115))
+                                .center_x(),
+                            ),
+                    )
+                    .push_if(
+                        || self.expanded,
+                        || {
+                            self.tree
+                                .view(translator, &self.scan_info.game_name, config, restoring)
+                                .width(Length::Fill)
+                        },
+                    ),
+            )
+            .style(style::Container::GameListEntry);
+        }
 
         Container::new(
             Column::new()
@@ -112,10 +221,56 @@ impl GameListEntry {
                                 .view()
                             },
                         )
+                        .push_some(|| {
+                            self.backup_info
+                                .as_ref()
+                                .and_then(|x| x.failure_category())
+                                .map(|category| {
+                                    Row::new()
+                                        .padding([0, 0, 0, 15])
+                                        .align_items(Alignment::Center)
+                                        .push(Icon::from(category).as_text().width(Length::Shrink).size(16))
+                                        .push(Badge::new(&translator.failure_category(category)).left_margin(5).view())
+                                })
+                        })
+                        .push_if(
+                            || oversized,
+                            || Badge::new(&translator.badge_too_large()).left_margin(15).view(),
+                        )
                         .push_if(
                             || duplicate_detector.is_game_duplicated(&self.scan_info),
                             || Badge::new(&translator.badge_duplicates()).left_margin(15).view(),
                         )
+                        .push_if(
+                            || !restoring && never_backed_up,
+                            || Badge::new(&translator.badge_never_backed_up()).left_margin(15).view(),
+                        )
+                        .push_if(
+                            || !restoring && self.over_budget,
+                            || Badge::new(&translator.badge_over_budget()).left_margin(15).view(),
+                        )
+                        .push_if(
+                            || !restoring && self.running,
+                            || Badge::new(&translator.badge_running()).left_margin(15).view(),
+                        )
+                        .push_some(|| {
+                            config
+                                .backup_destination(&self.scan_info.game_name)
+                                .filter(|_| !restoring)
+                                .map(|path| Badge::new(&translator.badge_destination(path)).left_margin(15).view())
+                        })
+                        .push_if(
+                            || !restoring && self.changed,
+                            || Badge::new(&translator.badge_changed()).left_margin(15).view(),
+                        )
+                        .push_some(|| {
+                            self.last_backup.filter(|_| !restoring).map(|last_backup| {
+                                let stale = config.is_backup_stale(&last_backup, &chrono::Utc::now());
+                                Badge::new(&translator.badge_last_backup(&last_backup, stale))
+                                    .left_margin(15)
+                                    .view()
+                            })
+                        })
                         .push_if(
                             || !successful,
                             || Badge::new(&translator.badge_failed()).left_margin(15).view(),
@@ -185,6 +340,8 @@ impl GameListEntry {
                                 })
                                 .padding(2),
                         ))
+                        .push(Space::new(Length::Units(15), Length::Shrink))
+                        .push(Container::new(pin_button))
                         .push(
                             Container::new(Text::new(
                                 translator.adjusted_size(self.scan_info.sum_bytes(&self.backup_info)),
@@ -193,6 +350,35 @@ impl GameListEntry {
                             .center_x(),
                         ),
                 )
+                .push_some(|| {
+                    let slots = config
+                        .restore
+                        .save_slot_filter(&self.scan_info.game_name)
+                        .slots(&self.scan_info);
+                    (restoring && slots.len() > 1).then(|| {
+                        slots.into_iter().fold(
+                            Row::new()
+                                .spacing(15)
+                                .align_items(Alignment::Center)
+                                .push(Text::new(translator.save_slots_label()).size(16)),
+                            |row, slot| {
+                                let game = self.scan_info.game_name.clone();
+                                let enabled = !config.restore.is_save_slot_skipped(&game, &slot);
+                                row.push(
+                                    Checkbox::new(enabled, translator.save_slot(&slot), move |enabled| {
+                                        Message::ToggleSaveSlot {
+                                            game: game.clone(),
+                                            slot: slot.clone(),
+                                            enabled,
+                                        }
+                                    })
+                                    .size(16)
+                                    .text_size(16),
+                                )
+                            },
+                        )
+                    })
+                })
                 .push_if(
                     || self.expanded,
                     || {
@@ -206,11 +392,57 @@ impl GameListEntry {
     }
 }
 
+/// The active search criteria, copied out of the search widget's state.
+struct EntryFilter {
+    game_name: Option<String>,
+    stale_only: bool,
+    never_backed_up_only: bool,
+    store: StoreFilter,
+    now: chrono::DateTime<chrono::Utc>,
+}
+
+impl EntryFilter {
+    fn new(search: &SearchComponent, restoring: bool) -> Self {
+        Self {
+            game_name: search.show.then(|| search.game_name.clone()),
+            stale_only: search.show && !restoring && search.stale_only,
+            never_backed_up_only: search.show && !restoring && search.never_backed_up_only,
+            store: if search.show {
+                search.store
+            } else {
+                StoreFilter::default()
+            },
+            now: chrono::Utc::now(),
+        }
+    }
+
+    fn matches(&self, entry: &GameListEntry, config: &Config) -> bool {
+        let game_name = match &self.game_name {
+            Some(x) => x,
+            None => return true,
+        };
+        let stale = entry
+            .last_backup
+            .map(|last_backup| config.is_backup_stale(&last_backup, &self.now))
+            .unwrap_or(false);
+        fuzzy_matcher::skim::SkimMatcherV2::default()
+            .fuzzy_match(&entry.scan_info.game_name, game_name)
+            .is_some()
+            && (!self.stale_only || stale)
+            && (!self.never_backed_up_only || entry.never_backed_up())
+            && self.store.matches(entry.store)
+    }
+}
+
 #[derive(Default)]
 pub struct GameList {
     pub entries: Vec<GameListEntry>,
     scroll: scrollable::State,
     pub search: SearchComponent,
+    pub view_mode: ViewMode,
+    pub relative_paths: bool,
+    /// The last game whose checkbox was clicked, for selecting a range with Shift.
+    pub last_toggled: Option<String>,
 }
 
 impl GameList {
@@ -223,8 +455,9 @@ impl GameList {
         duplicate_detector: &DuplicateDetector,
         operation: &Option<OngoingOperation>,
     ) -> Container<Message> {
-        let use_search = self.search.show;
-        let search_game_name = self.search.game_name.clone();
+        let filter = EntryFilter::new(&self.search, restoring);
+        let view_mode = self.view_mode;
+        let relative_paths = self.relative_paths;
 
         Container::new(
             Column::new()
@@ -242,14 +475,10 @@ impl GameList {
                         Scrollable::new(&mut self.scroll)
                             .width(Length::Fill)
                             .padding([0, 15, 5, 15])
-                            .spacing(10)
+                            .spacing(if view_mode == ViewMode::Compact { 2 } else { 10 })
                             .style(style::Scrollable),
                         |parent: Scrollable<'_, Message>, (_i, x)| {
-                            if !use_search
-                                || fuzzy_matcher::skim::SkimMatcherV2::default()
-                                    .fuzzy_match(&x.scan_info.game_name, &search_game_name)
-                                    .is_some()
-                            {
+                            if filter.matches(x, config) {
                                 parent.push(x.view(
                                     restoring,
                                     translator,
@@ -257,6 +486,8 @@ impl GameList {
                                     manifest,
                                     duplicate_detector,
                                     operation,
+                                    view_mode,
+                                    relative_paths,
                                 ))
                             } else {
                                 parent
@@ -277,6 +508,58 @@ impl GameList {
         })
     }
 
+    /// The games currently shown from one game to another, inclusive.
+    /// If the first game isn't shown anymore, then this is just the second game.
+    pub fn shown_range(&self, from: &str, to: &str, config: &Config, restoring: bool) -> Vec<String> {
+        let filter = EntryFilter::new(&self.search, restoring);
+        let shown: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|x| filter.matches(x, config))
+            .map(|x| &x.scan_info.game_name)
+            .collect();
+
+        match (
+            shown.iter().position(|x| *x == from),
+            shown.iter().position(|x| *x == to),
+        ) {
+            (Some(a), Some(b)) => shown[a.min(b)..=a.max(b)].iter().map(|x| x.to_string()).collect(),
+            _ => vec![to.to_string()],
+        }
+    }
+
+    /// How many games were found on disk, but aren't in any backup yet.
+    pub fn count_never_backed_up(&self) -> usize {
+        self.entries.iter().filter(|x| x.never_backed_up()).count()
+    }
+
+    pub fn summarize(&self) -> OperationSummary {
+        let mut summary = OperationSummary::default();
+        for entry in &self.entries {
+            summary.add(entry.backup_info.as_ref());
+        }
+        summary
+    }
+
+    /// Games whose last backup or restore had any failures.
+    pub fn failed_games(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|x| x.backup_info.as_ref().map(|y| !y.successful()).unwrap_or(false))
+            .map(|x| x.scan_info.game_name.clone())
+            .collect()
+    }
+
+    /// How many backed up files have no valid place to be restored.
+    pub fn count_unresolved_restoration_targets(&self, redirects: &[RedirectConfig]) -> usize {
+        self.entries
+            .iter()
+            .flat_map(|x| x.scan_info.found_files.iter())
+            .filter_map(|x| x.original_path.as_ref())
+            .filter(|x| game_file_restoration_target(x, redirects).is_err())
+            .count()
+    }
+
     pub fn compute_operation_status(&self, config: &Config, restoring: bool) -> OperationStatus {
         let mut status = OperationStatus::default();
         for entry in self.entries.iter() {
@@ -292,6 +575,14 @@ impl GameList {
         status
     }
 
+    pub fn toggle_relative_paths(&mut self) {
+        self.relative_paths = !self.relative_paths;
+        // The trees are rebuilt the next time they're shown.
+        for entry in self.entries.iter_mut() {
+            entry.tree.clear();
+        }
+    }
+
     pub fn update_ignored(&mut self, game: &str, ignored_paths: &ToggledPaths, ignored_registry: &ToggledRegistry) {
         for item in self.entries.iter_mut() {
             if item.scan_info.game_name == game {
@@ -301,15 +592,75 @@ impl GameList {
         }
     }
 
-    pub fn sort(&mut self, sort: &Sort) {
-        match sort.key {
-            SortKey::Name => self.entries.sort_by_key(|x| x.scan_info.game_name.clone()),
-            SortKey::Size => self
-                .entries
-                .sort_by_key(|x| (x.scan_info.sum_bytes(&x.backup_info), x.scan_info.game_name.clone())),
+    /// Pinned games always come first, in the same order as the rest.
+    /// Pinned games come first, then the rest in the configured order.
+    fn compare(a: &GameListEntry, b: &GameListEntry, sort: &Sort, pinned: &[String]) -> std::cmp::Ordering {
+        let unpinned = |x: &GameListEntry| !pinned.contains(&x.scan_info.game_name);
+        unpinned(a).cmp(&unpinned(b)).then_with(|| {
+            let ordering = match sort.key {
+                SortKey::Name => a.scan_info.game_name.cmp(&b.scan_info.game_name),
+                SortKey::Size => (a.scan_info.sum_bytes(&a.backup_info), &a.scan_info.game_name)
+                    .cmp(&(b.scan_info.sum_bytes(&b.backup_info), &b.scan_info.game_name)),
+            };
+            if sort.reversed {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+    }
+
+    pub fn sort(&mut self, sort: &Sort, pinned: &[String]) {
+        self.entries.sort_by(|a, b| Self::compare(a, b, sort, pinned));
+    }
+
+    /// Add an entry where it belongs in an already sorted list.
+    /// During a big scan, this is much cheaper than re-sorting after every game.
+    pub fn insert_sorted(&mut self, entry: GameListEntry, sort: &Sort, pinned: &[String]) {
+        let index = self
+            .entries
+            .partition_point(|x| Self::compare(x, &entry, sort, pinned) != std::cmp::Ordering::Greater);
+        self.entries.insert(index, entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::ScannedFile;
+    use pretty_assertions::assert_eq;
+
+    fn entry(name: &str, size: u64) -> GameListEntry {
+        GameListEntry {
+            scan_info: ScanInfo {
+                game_name: name.to_string(),
+                found_files: maplit::hashset! { ScannedFile::new(format!("/{}", name), size) },
+                ..Default::default()
+            },
+            ..Default::default()
         }
-        if sort.reversed {
-            self.entries.reverse();
+    }
+
+    #[test]
+    fn can_insert_entries_in_sorted_position() {
+        let pinned = vec!["d".to_string()];
+        for key in [SortKey::Name, SortKey::Size] {
+            for reversed in [false, true] {
+                let sort = Sort { key, reversed };
+                let mut inserted = GameList::default();
+                let mut sorted = GameList::default();
+                for (name, size) in [("c", 1), ("a", 3), ("d", 2), ("b", 2), ("e", 0)] {
+                    inserted.insert_sorted(entry(name, size), &sort, &pinned);
+                    sorted.entries.push(entry(name, size));
+                }
+                sorted.sort(&sort, &pinned);
+
+                let names = |list: &GameList| -> Vec<String> {
+                    list.entries.iter().map(|x| x.scan_info.game_name.clone()).collect()
+                };
+                assert_eq!(names(&sorted), names(&inserted));
+                assert_eq!("d", names(&inserted)[0]);
+            }
         }
     }
 }
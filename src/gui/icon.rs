@@ -1,5 +1,7 @@
 use iced::{alignment::Horizontal as HorizontalAlignment, Font, Length, Text};
 
+use crate::prelude::FailureCategory;
+
 const ICONS: Font = Font::External {
     name: "Material Icons",
     bytes: include_bytes!("../../assets/MaterialIcons-Regular.ttf"),
@@ -8,18 +10,31 @@ const ICONS: Font = Font::External {
 pub enum Icon {
     Add,
     AddCircle,
+    ArrowDownward,
+    ArrowUpward,
     Edit,
+    ErrorOutline,
     FolderOpen,
+    HelpOutline,
+    HourglassEmpty,
+    Key,
     KeyboardArrowRight,
     KeyboardArrowDown,
     Language,
+    Lock,
     OpenInNew,
     Remove,
     RemoveCircle,
     Search,
+    ShortText,
+    Star,
+    Storage,
+    StarBorder,
     SubdirectoryArrowRight,
     Delete,
     PlayCircleOutline,
+    ViewAgenda,
+    ViewHeadline,
 }
 
 impl Icon {
@@ -27,18 +42,31 @@ impl Icon {
         let character = match self {
             Self::Add => '\u{E145}',
             Self::AddCircle => '\u{E147}',
+            Self::ArrowDownward => '\u{E5DB}',
+            Self::ArrowUpward => '\u{E5D8}',
             Self::Edit => '\u{E150}',
+            Self::ErrorOutline => '\u{E001}',
             Self::FolderOpen => '\u{E2C8}',
+            Self::HelpOutline => '\u{E8FD}',
+            Self::HourglassEmpty => '\u{E88B}',
+            Self::Key => '\u{E0DA}',
             Self::KeyboardArrowRight => '\u{E315}',
             Self::KeyboardArrowDown => '\u{E313}',
             Self::Language => '\u{E894}',
+            Self::Lock => '\u{E897}',
             Self::OpenInNew => '\u{E89E}',
             Self::Remove => '\u{E15B}',
             Self::RemoveCircle => '\u{E15C}',
             Self::Search => '\u{E8B6}',
+            Self::ShortText => '\u{E261}',
+            Self::Star => '\u{E838}',
+            Self::StarBorder => '\u{E83A}',
+            Self::Storage => '\u{E1DB}',
             Self::SubdirectoryArrowRight => '\u{E5DA}',
             Self::Delete => '\u{E872}',
             Self::PlayCircleOutline => '\u{E039}',
+            Self::ViewAgenda => '\u{E8E9}',
+            Self::ViewHeadline => '\u{E8EE}',
         };
         Text::new(&character.to_string())
             .font(ICONS)
@@ -46,3 +74,15 @@ impl Icon {
             .horizontal_alignment(HorizontalAlignment::Center)
     }
 }
+
+impl From<FailureCategory> for Icon {
+    fn from(category: FailureCategory) -> Self {
+        match category {
+            FailureCategory::Permission => Self::Lock,
+            FailureCategory::MissingSource => Self::HelpOutline,
+            FailureCategory::FileInUse => Self::HourglassEmpty,
+            FailureCategory::DiskFull => Self::Storage,
+            FailureCategory::Other => Self::ErrorOutline,
+        }
+    }
+}
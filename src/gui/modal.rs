@@ -1,13 +1,19 @@
 use crate::{
-    config::{Config, RootsConfig},
-    gui::{common::Message, style},
+    config::{Config, RestoreStrategy, RootsConfig},
+    gui::{
+        badge::Badge,
+        common::{IcedExtension, Message, OperationSummary},
+        style,
+    },
     lang::Translator,
-    prelude::Error,
+    manifest::ManifestProblem,
+    prelude::{DeletionPreview, Error, RegistryChange, RestoreConflict, StrictPath, UnresolvedPlaceholders},
+    staging::StagedRestoration,
 };
 
 use iced::{
-    alignment::Horizontal as HorizontalAlignment, button, scrollable, Alignment, Button, Column, Container, Length,
-    Row, Scrollable, Space, Text,
+    alignment::Horizontal as HorizontalAlignment, button, scrollable, text_input, Alignment, Button, Checkbox, Column,
+    Container, Length, Row, Scrollable, Space, Text, TextInput,
 };
 
 pub enum ModalVariant {
@@ -17,47 +23,188 @@ pub enum ModalVariant {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ModalTheme {
-    Error { variant: Error },
-    ConfirmBackup { games: Option<Vec<String>> },
-    ConfirmRestore { games: Option<Vec<String>> },
+    Error {
+        variant: Error,
+    },
+    ConfirmBackup {
+        games: Option<Vec<String>>,
+        /// Old backups that retention would prune.
+        deletions: DeletionPreview,
+    },
+    ConfirmRestore {
+        games: Option<Vec<String>>,
+        encrypted: bool,
+        registry_changes: Vec<RegistryChange>,
+        /// Each conflict and whether to keep the current file.
+        conflicts: Vec<(RestoreConflict, bool)>,
+        /// Extra files that mirroring would remove.
+        deletions: DeletionPreview,
+    },
     NoMissingRoots,
+    NoOrphanedBackups,
+    ConfirmPruneOrphans {
+        deletions: DeletionPreview,
+    },
     ConfirmAddMissingRoots(Vec<RootsConfig>),
+    ConfirmImportConfig(Box<Config>),
+    ConfirmApplyStagedRestore,
+    BackupMigrated {
+        path: StrictPath,
+    },
+    ManifestLint(Vec<UnresolvedPlaceholders>),
+    ManifestProblems(Vec<ManifestProblem>),
+    ConfirmTestRestore {
+        encrypted: bool,
+    },
+    TestedRestore {
+        total: usize,
+        failed: Vec<(String, usize)>,
+    },
+    OperationSummary {
+        restoring: bool,
+        summary: OperationSummary,
+    },
 }
 
 impl ModalTheme {
     pub fn variant(&self) -> ModalVariant {
         match self {
-            Self::Error { .. } | Self::NoMissingRoots => ModalVariant::Info,
-            Self::ConfirmBackup { .. } | Self::ConfirmRestore { .. } | Self::ConfirmAddMissingRoots(..) => {
-                ModalVariant::Confirm
-            }
+            Self::Error { .. }
+            | Self::NoMissingRoots
+            | Self::NoOrphanedBackups
+            | Self::BackupMigrated { .. }
+            | Self::ManifestLint(..)
+            | Self::ManifestProblems(..)
+            | Self::TestedRestore { .. }
+            | Self::OperationSummary { .. } => ModalVariant::Info,
+            Self::ConfirmBackup { .. }
+            | Self::ConfirmRestore { .. }
+            | Self::ConfirmAddMissingRoots(..)
+            | Self::ConfirmImportConfig(..)
+            | Self::ConfirmApplyStagedRestore
+            | Self::ConfirmTestRestore { .. }
+            | Self::ConfirmPruneOrphans { .. } => ModalVariant::Confirm,
         }
     }
 
     pub fn text(&self, config: &Config, translator: &Translator) -> String {
         match self {
             Self::Error { variant } => translator.handle_error(variant),
-            Self::ConfirmBackup { .. } => {
-                translator.modal_confirm_backup(&config.backup.path, config.backup.path.exists(), config.backup.merge)
+            Self::ConfirmBackup { deletions, .. } => {
+                let mut text = translator.modal_confirm_backup(
+                    &config.backup.path,
+                    config.backup.path.exists(),
+                    config.backup.merge,
+                );
+                let commands = config.backup_commands();
+                if !commands.is_empty() {
+                    text = format!("{}\n\n{}", text, translator.modal_backup_commands(&commands));
+                }
+                if !deletions.is_empty() {
+                    text = format!("{}\n\n{}", text, translator.deletion_preview(deletions));
+                }
+                text
+            }
+            Self::ConfirmRestore {
+                registry_changes,
+                conflicts,
+                deletions,
+                ..
+            } => {
+                let mut text = translator.modal_confirm_restore(&config.restore.path);
+                if config.restore.strategy == RestoreStrategy::Mirror && config.restore.files && !config.restore.stage {
+                    text = format!("{}\n\n{}", text, translator.modal_restore_mirror());
+                }
+                if !deletions.is_empty() {
+                    text = format!("{}\n\n{}", text, translator.deletion_preview(deletions));
+                }
+                if !registry_changes.is_empty() {
+                    text = format!("{}\n\n{}", text, translator.modal_registry_changes(registry_changes));
+                }
+                if !conflicts.is_empty() {
+                    text = format!("{}\n\n{}", text, translator.modal_restore_conflicts(conflicts.len()));
+                }
+                text
             }
-            Self::ConfirmRestore { .. } => translator.modal_confirm_restore(&config.restore.path),
             Self::NoMissingRoots => translator.no_missing_roots(),
+            Self::NoOrphanedBackups => translator.no_orphaned_backups(),
+            Self::ConfirmPruneOrphans { deletions } => translator.modal_confirm_prune_orphans(deletions),
             Self::ConfirmAddMissingRoots(missing) => translator.confirm_add_missing_roots(missing),
+            Self::ConfirmImportConfig(_) => translator.confirm_import_config(),
+            Self::ConfirmApplyStagedRestore => translator.modal_confirm_apply_staged_restore(&StagedRestoration::dir()),
+            Self::BackupMigrated { path } => translator.backup_migrated(path),
+            Self::ManifestLint(unresolved) => translator.manifest_lint(unresolved),
+            Self::ManifestProblems(problems) => translator.manifest_problems(problems),
+            Self::ConfirmTestRestore { .. } => translator.modal_confirm_test_restore(&config.restore.path),
+            Self::TestedRestore { total, failed } => translator.tested_restore(*total, failed),
+            Self::OperationSummary { restoring, summary } => translator.operation_summary(*restoring, summary),
         }
     }
 
     pub fn message(&self) -> Message {
         match self {
-            Self::Error { .. } | Self::NoMissingRoots => Message::Idle,
-            Self::ConfirmBackup { games } => Message::BackupStart {
+            Self::Error { .. }
+            | Self::NoMissingRoots
+            | Self::NoOrphanedBackups
+            | Self::BackupMigrated { .. }
+            | Self::ManifestLint(..)
+            | Self::ManifestProblems(..)
+            | Self::TestedRestore { .. }
+            | Self::OperationSummary { .. } => Message::Idle,
+            Self::ConfirmBackup { games, .. } => Message::BackupStart {
                 preview: false,
                 games: games.clone(),
             },
-            Self::ConfirmRestore { games } => Message::RestoreStart {
+            Self::ConfirmRestore { games, conflicts, .. } => Message::RestoreStart {
                 preview: false,
                 games: games.clone(),
+                keep: conflicts
+                    .iter()
+                    .filter(|(_, keep)| *keep)
+                    .map(|(conflict, _)| conflict.clone())
+                    .collect(),
             },
             Self::ConfirmAddMissingRoots(missing) => Message::ConfirmAddMissingRoots(missing.clone()),
+            Self::ConfirmImportConfig(config) => Message::ApplyImportedConfig {
+                config: config.clone(),
+                replace: false,
+            },
+            Self::ConfirmApplyStagedRestore => Message::ApplyStagedRestore,
+            Self::ConfirmTestRestore { .. } => Message::TestRestore,
+            Self::ConfirmPruneOrphans { deletions } => Message::PruneOrphans(deletions.clone()),
+        }
+    }
+
+    /// A second way to confirm, with its button label.
+    pub fn alternative(&self, translator: &Translator) -> Option<(String, Message)> {
+        match self {
+            Self::ConfirmImportConfig(config) => Some((
+                translator.replace_button(),
+                Message::ApplyImportedConfig {
+                    config: config.clone(),
+                    replace: true,
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn needs_passphrase(&self, config: &Config) -> bool {
+        match self {
+            Self::ConfirmBackup { .. } => config.backup_encrypts(),
+            Self::ConfirmRestore { encrypted, .. } | Self::ConfirmTestRestore { encrypted } => *encrypted,
+            _ => false,
+        }
+    }
+
+    /// Technical information to include in bug reports.
+    pub fn details(&self) -> Option<String> {
+        match self {
+            Self::Error { variant } => Some(format!("{:#?}", variant)),
+            Self::ManifestLint(unresolved) if !unresolved.is_empty() => {
+                Some(Translator::default().manifest_lint_report(unresolved))
+            }
+            _ => None,
         }
     }
 }
@@ -66,22 +213,48 @@ impl ModalTheme {
 pub struct ModalComponent {
     positive_button: button::State,
     negative_button: button::State,
+    alternative_button: button::State,
+    keep_all_button: button::State,
+    overwrite_all_button: button::State,
+    details_button: button::State,
+    copy_button: button::State,
     scroll: scrollable::State,
+    passphrase_state: text_input::State,
+    pub show_details: bool,
+    pub passphrase: String,
 }
 
 impl ModalComponent {
     pub fn view(&mut self, theme: &ModalTheme, config: &Config, translator: &Translator) -> Container<Message> {
+        let needs_passphrase = theme.needs_passphrase(config);
+        let ready = !needs_passphrase || !self.passphrase.is_empty();
+
         let positive_button = Button::new(
             &mut self.positive_button,
-            Text::new(match theme.variant() {
-                ModalVariant::Info => translator.okay_button(),
-                ModalVariant::Confirm => translator.continue_button(),
+            Text::new(match (theme, theme.variant()) {
+                (ModalTheme::ConfirmImportConfig(_), _) => translator.merge_button(),
+                (_, ModalVariant::Info) => translator.okay_button(),
+                (_, ModalVariant::Confirm) => translator.continue_button(),
             })
             .horizontal_alignment(HorizontalAlignment::Center),
         )
-        .on_press(theme.message())
+        .on_press(if ready { theme.message() } else { Message::Ignore })
         .width(Length::Units(125))
-        .style(style::Button::Primary);
+        .style(if ready {
+            style::Button::Primary
+        } else {
+            style::Button::Disabled
+        });
+
+        let alternative_button = theme.alternative(translator).map(|(label, message)| {
+            Button::new(
+                &mut self.alternative_button,
+                Text::new(label).horizontal_alignment(HorizontalAlignment::Center),
+            )
+            .on_press(message)
+            .width(Length::Units(125))
+            .style(style::Button::Primary)
+        });
 
         let negative_button = Button::new(
             &mut self.negative_button,
@@ -91,6 +264,27 @@ impl ModalComponent {
         .width(Length::Units(125))
         .style(style::Button::Negative);
 
+        let details = theme.details();
+        let show_details = self.show_details;
+        let details_button = Button::new(
+            &mut self.details_button,
+            Text::new(translator.details_button()).horizontal_alignment(HorizontalAlignment::Center),
+        )
+        .on_press(Message::ToggleModalDetails)
+        .width(Length::Units(125))
+        .style(if show_details {
+            style::Button::Negative
+        } else {
+            style::Button::Primary
+        });
+        let copy_button = Button::new(
+            &mut self.copy_button,
+            Text::new(translator.copy_button()).horizontal_alignment(HorizontalAlignment::Center),
+        )
+        .on_press(Message::CopyText(details.clone().unwrap_or_default()))
+        .width(Length::Units(125))
+        .style(style::Button::Primary);
+
         Container::new(
             Column::new()
                 .padding(5)
@@ -115,7 +309,53 @@ impl ModalComponent {
                                         .width(Length::Fill)
                                         .height(Length::Fill)
                                         .style(style::Scrollable)
-                                        .push(Text::new(theme.text(config, translator)))
+                                        .push(
+                                            Column::new()
+                                                .align_items(Alignment::Center)
+                                                .spacing(20)
+                                                .push_some(|| match theme {
+                                                    ModalTheme::OperationSummary { summary, .. } => Some(
+                                                        Badge::new(&translator.operation_health(summary))
+                                                            .style(style::Container::from(summary))
+                                                            .view(),
+                                                    ),
+                                                    _ => None,
+                                                })
+                                                .push(Text::new(theme.text(config, translator)))
+                                                .push_some(|| match theme {
+                                                    ModalTheme::ConfirmRestore { conflicts, .. }
+                                                        if !conflicts.is_empty() =>
+                                                    {
+                                                        Some(Self::conflict_queue(
+                                                            conflicts,
+                                                            &mut self.keep_all_button,
+                                                            &mut self.overwrite_all_button,
+                                                            translator,
+                                                        ))
+                                                    }
+                                                    _ => None,
+                                                })
+                                                .push_if(
+                                                    || needs_passphrase,
+                                                    || {
+                                                        TextInput::new(
+                                                            &mut self.passphrase_state,
+                                                            &translator.passphrase_placeholder(),
+                                                            &self.passphrase,
+                                                            Message::EditedPassphrase,
+                                                        )
+                                                        .password()
+                                                        .width(Length::Units(400))
+                                                        .padding(5)
+                                                    },
+                                                )
+                                                .push_some(|| {
+                                                    details
+                                                        .as_ref()
+                                                        .filter(|_| show_details)
+                                                        .map(|x| Text::new(x).size(16))
+                                                }),
+                                        )
                                         .align_items(Alignment::Center),
                                 )
                                 .height(Length::Fill),
@@ -123,8 +363,13 @@ impl ModalComponent {
                         .push(
                             match theme.variant() {
                                 ModalVariant::Info => Row::new().push(positive_button),
-                                ModalVariant::Confirm => Row::new().push(positive_button).push(negative_button),
+                                ModalVariant::Confirm => Row::new()
+                                    .push(positive_button)
+                                    .push_some(|| alternative_button)
+                                    .push(negative_button),
                             }
+                            .push_if(|| details.is_some(), || details_button)
+                            .push_if(|| details.is_some(), || copy_button)
                             .padding(40)
                             .spacing(20)
                             .height(Length::Shrink)
@@ -142,4 +387,43 @@ impl ModalComponent {
         .width(Length::Fill)
         .center_x()
     }
+
+    fn conflict_queue<'a>(
+        conflicts: &[(RestoreConflict, bool)],
+        keep_all_button: &'a mut button::State,
+        overwrite_all_button: &'a mut button::State,
+        translator: &Translator,
+    ) -> Column<'a, Message> {
+        let mut column = Column::new().spacing(10).push(
+            Row::new()
+                .spacing(20)
+                .push(
+                    Button::new(
+                        keep_all_button,
+                        Text::new(translator.keep_all_newer_button()).horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .on_press(Message::ResolveAllRestoreConflicts { keep: true })
+                    .style(style::Button::Primary),
+                )
+                .push(
+                    Button::new(
+                        overwrite_all_button,
+                        Text::new(translator.overwrite_all_button()).horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .on_press(Message::ResolveAllRestoreConflicts { keep: false })
+                    .style(style::Button::Negative),
+                ),
+        );
+        for (index, (conflict, keep)) in conflicts.iter().enumerate() {
+            column = column.push(
+                Checkbox::new(
+                    *keep,
+                    format!("{}: {}", conflict.game, conflict.target.render()),
+                    move |keep| Message::ResolveRestoreConflict { index, keep },
+                )
+                .text_size(16),
+            );
+        }
+        column
+    }
 }
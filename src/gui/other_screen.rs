@@ -1,25 +1,62 @@
 use crate::{
     config::Config,
     gui::{
-        common::{Message, OngoingOperation},
+        common::{BrowseSubject, IcedExtension, Message, OngoingOperation},
+        icon::Icon,
         ignored_items_editor::IgnoredItemsEditor,
         style,
     },
+    history::History,
     lang::Translator,
+    manifest::Manifest,
+    prelude::{app_dir, StrictPath},
 };
 
-use iced::{scrollable, Checkbox, Column, Container, Length, Scrollable, Text};
+use iced::{
+    alignment::Horizontal as HorizontalAlignment, button, scrollable, text_input, Alignment, Button, Checkbox, Column,
+    Container, Length, Row, Scrollable, Text, TextInput,
+};
+
+/// Numbers tracked elsewhere in the app, aggregated for the overview panel.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    pub found_games: usize,
+    pub backed_up_games: usize,
+}
 
 #[derive(Default)]
 pub struct OtherScreenComponent {
     scroll: scrollable::State,
+    reload_config_button: button::State,
+    export_config_button: button::State,
+    import_config_button: button::State,
+    config_path_input: text_input::State,
+    open_config_file_button: button::State,
+    open_config_dir_button: button::State,
+    check_manifest_button: button::State,
     pub ignored_items_editor: IgnoredItemsEditor,
+    pub migration_source: String,
+    pub migration_target: String,
+    pub migrating: bool,
+    migration_source_input: text_input::State,
+    migration_source_browse_button: button::State,
+    migration_target_input: text_input::State,
+    migration_target_browse_button: button::State,
+    migrate_button: button::State,
+    pub testing_restore: bool,
+    test_restore_button: button::State,
+    pub pruning_orphans: bool,
+    prune_orphans_button: button::State,
+    pub history: History,
+    history_buttons: Vec<button::State>,
 }
 
 impl OtherScreenComponent {
     pub fn new(config: &Config) -> Self {
         Self {
             ignored_items_editor: IgnoredItemsEditor::new(config),
+            migration_source: config.backup.path.raw(),
+            history: History::load(),
             ..Default::default()
         }
     }
@@ -27,9 +64,38 @@ impl OtherScreenComponent {
     pub fn view(
         &mut self,
         config: &Config,
+        manifest: &Manifest,
         translator: &Translator,
         operation: &Option<OngoingOperation>,
+        stats: Stats,
     ) -> Container<Message> {
+        self.history_buttons
+            .resize_with(self.history.entries.len(), Default::default);
+        let history = self
+            .history
+            .entries
+            .iter()
+            .rev()
+            .zip(self.history_buttons.iter_mut())
+            .fold(
+                Column::new().spacing(5).push(Text::new(translator.history_label())),
+                |parent, (entry, button)| {
+                    parent.push(
+                        Row::new()
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .push(Text::new(translator.history_entry(entry)))
+                            .push(
+                                Button::new(button, Icon::FolderOpen.as_text())
+                                    .on_press(Message::OpenDir {
+                                        path: entry.path.clone(),
+                                    })
+                                    .style(style::Button::Primary),
+                            ),
+                    )
+                },
+            );
+
         Container::new(
             Scrollable::new(&mut self.scroll)
                 .width(Length::Fill)
@@ -38,6 +104,67 @@ impl OtherScreenComponent {
                 .push(
                     Column::new()
                         .spacing(20)
+                        .push(
+                            Container::new(
+                                Row::new()
+                                    .spacing(20)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new(translator.stats_label()))
+                                    .push(Text::new(translator.stats_manifest_games(manifest.0.len())))
+                                    .push(Text::new(translator.stats_roots(config.roots.len())))
+                                    .push(Text::new(translator.stats_found_games(stats.found_games)))
+                                    .push(Text::new(translator.stats_backed_up_games(stats.backed_up_games))),
+                            )
+                            .padding(10)
+                            .width(Length::Fill)
+                            .style(style::Container::GameListEntry),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(20)
+                                .align_items(Alignment::Center)
+                                .push(Text::new(
+                                    translator.manifest_summary(manifest.0.len(), &config.manifest.updated),
+                                ))
+                                .push(
+                                    Button::new(
+                                        &mut self.check_manifest_button,
+                                        Text::new(translator.check_manifest_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(Message::LintManifest)
+                                    .width(Length::Units(150))
+                                    .style(style::Button::Primary),
+                                ),
+                        )
+                        .push_some(|| {
+                            let mut categories = manifest.categories();
+                            for excluded in &config.manifest.excluded_categories {
+                                if !categories.contains(excluded) {
+                                    categories.push(excluded.clone());
+                                }
+                            }
+                            if categories.is_empty() {
+                                return None;
+                            }
+                            Some(
+                                categories.into_iter().fold(
+                                    Row::new()
+                                        .spacing(20)
+                                        .align_items(Alignment::Center)
+                                        .push(Text::new(translator.manifest_categories_label())),
+                                    |parent, category| {
+                                        let enabled = !config.manifest.excluded_categories.contains(&category);
+                                        parent.push(Checkbox::new(enabled, category.clone(), move |enabled| {
+                                            Message::ToggleManifestCategory {
+                                                category: category.clone(),
+                                                enabled,
+                                            }
+                                        }))
+                                    },
+                                ),
+                            )
+                        })
                         .push(Checkbox::new(
                             config.backup.filter.exclude_other_os_data,
                             translator.explanation_for_exclude_other_os_data(),
@@ -48,6 +175,184 @@ impl OtherScreenComponent {
                             translator.explanation_for_exclude_store_screenshots(),
                             Message::EditedExcludeStoreScreenshots,
                         ))
+                        .push(Checkbox::new(
+                            config.backup.filter.skip_hidden,
+                            translator.explanation_for_skip_hidden(),
+                            Message::EditedSkipHidden,
+                        ))
+                        .push(Checkbox::new(
+                            config.backup.steam_account_settings,
+                            translator.explanation_for_steam_account_settings(),
+                            Message::EditedSteamAccountSettings,
+                        ))
+                        .push(
+                            Row::new()
+                                .spacing(20)
+                                .push(
+                                    Button::new(
+                                        &mut self.reload_config_button,
+                                        Text::new(translator.reload_config_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(match operation {
+                                        None => Message::ReloadConfig,
+                                        Some(_) => Message::Ignore,
+                                    })
+                                    .width(Length::Units(125))
+                                    .style(match operation {
+                                        None => style::Button::Primary,
+                                        Some(_) => style::Button::Disabled,
+                                    }),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.export_config_button,
+                                        Text::new(translator.export_config_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(Message::ExportConfig)
+                                    .width(Length::Units(125))
+                                    .style(style::Button::Primary),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.import_config_button,
+                                        Text::new(translator.import_config_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(match operation {
+                                        None => Message::ImportConfig,
+                                        Some(_) => Message::Ignore,
+                                    })
+                                    .width(Length::Units(125))
+                                    .style(match operation {
+                                        None => style::Button::Primary,
+                                        Some(_) => style::Button::Disabled,
+                                    }),
+                                ),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(20)
+                                .align_items(Alignment::Center)
+                                .push(Text::new(translator.config_file_label()))
+                                .push(
+                                    // Read-only, but still selectable for copying.
+                                    TextInput::new(&mut self.config_path_input, "", &Config::path().render(), |_| {
+                                        Message::Ignore
+                                    })
+                                    .padding(5),
+                                )
+                                .push(
+                                    Button::new(&mut self.open_config_file_button, Icon::OpenInNew.as_text())
+                                        .on_press(Message::OpenFile { path: Config::path() })
+                                        .style(style::Button::Primary),
+                                )
+                                .push(
+                                    Button::new(&mut self.open_config_dir_button, Icon::FolderOpen.as_text())
+                                        .on_press(Message::OpenDir {
+                                            path: StrictPath::from_std_path_buf(&app_dir()),
+                                        })
+                                        .style(style::Button::Primary),
+                                ),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(20)
+                                .align_items(Alignment::Center)
+                                .push(Text::new(translator.migrate_backup_label()))
+                                .push(
+                                    TextInput::new(
+                                        &mut self.migration_source_input,
+                                        &translator.migration_source_placeholder(),
+                                        &self.migration_source,
+                                        Message::EditedMigrationSource,
+                                    )
+                                    .padding(5),
+                                )
+                                .push(
+                                    Button::new(&mut self.migration_source_browse_button, Icon::FolderOpen.as_text())
+                                        .on_press(Message::BrowseDir(BrowseSubject::MigrationSource))
+                                        .style(style::Button::Primary),
+                                )
+                                .push(
+                                    TextInput::new(
+                                        &mut self.migration_target_input,
+                                        &translator.migration_target_placeholder(),
+                                        &self.migration_target,
+                                        Message::EditedMigrationTarget,
+                                    )
+                                    .padding(5),
+                                )
+                                .push(
+                                    Button::new(&mut self.migration_target_browse_button, Icon::FolderOpen.as_text())
+                                        .on_press(Message::BrowseDir(BrowseSubject::MigrationTarget))
+                                        .style(style::Button::Primary),
+                                )
+                                .push({
+                                    let ready = operation.is_none()
+                                        && !self.migrating
+                                        && !self.migration_source.trim().is_empty()
+                                        && !self.migration_target.trim().is_empty();
+                                    Button::new(
+                                        &mut self.migrate_button,
+                                        Text::new(translator.migrate_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(if ready { Message::MigrateBackup } else { Message::Ignore })
+                                    .width(Length::Units(125))
+                                    .style(if ready {
+                                        style::Button::Primary
+                                    } else {
+                                        style::Button::Disabled
+                                    })
+                                }),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(20)
+                                .align_items(Alignment::Center)
+                                .push({
+                                    let ready = operation.is_none() && !self.testing_restore;
+                                    Button::new(
+                                        &mut self.test_restore_button,
+                                        Text::new(translator.test_restore_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(if ready {
+                                        Message::ConfirmTestRestore
+                                    } else {
+                                        Message::Ignore
+                                    })
+                                    .width(Length::Units(125))
+                                    .style(if ready {
+                                        style::Button::Primary
+                                    } else {
+                                        style::Button::Disabled
+                                    })
+                                })
+                                .push({
+                                    let ready =
+                                        operation.is_none() && !self.pruning_orphans && config.mode.allows_backup();
+                                    Button::new(
+                                        &mut self.prune_orphans_button,
+                                        Text::new(translator.prune_orphans_button())
+                                            .horizontal_alignment(HorizontalAlignment::Center),
+                                    )
+                                    .on_press(if ready {
+                                        Message::ConfirmPruneOrphans
+                                    } else {
+                                        Message::Ignore
+                                    })
+                                    .width(Length::Units(125))
+                                    .style(if ready {
+                                        style::Button::Primary
+                                    } else {
+                                        style::Button::Disabled
+                                    })
+                                }),
+                        )
+                        .push_if(|| !self.history.entries.is_empty(), || history)
                         .push(
                             Column::new().push(Text::new(translator.ignored_items_label())).push(
                                 self.ignored_items_editor
@@ -0,0 +1,100 @@
+use crate::{
+    gui::{common::Message, style},
+    lang::Translator,
+    prelude::search_game_names,
+};
+
+use iced::{
+    alignment::Horizontal as HorizontalAlignment, button, text_input, Alignment, Button, Column, Container, Length,
+    Space, Text, TextInput,
+};
+
+/// How many matching games to list at once.
+const MAX_MATCHES: usize = 10;
+
+/// An overlay to quickly back up a single game by typing its name.
+#[derive(Default)]
+pub struct PaletteComponent {
+    pub shown: bool,
+    query: String,
+    matches: Vec<String>,
+    query_input: text_input::State,
+    match_buttons: [button::State; MAX_MATCHES],
+    cancel_button: button::State,
+}
+
+impl PaletteComponent {
+    pub fn show(&mut self) {
+        self.shown = true;
+        self.query.clear();
+        self.matches.clear();
+        self.query_input.focus();
+    }
+
+    pub fn hide(&mut self) {
+        self.shown = false;
+        self.query_input.unfocus();
+    }
+
+    pub fn search<'a>(&mut self, query: String, names: impl IntoIterator<Item = &'a String>) {
+        self.matches = search_game_names(&query, names, MAX_MATCHES);
+        self.query = query;
+    }
+
+    pub fn view(&mut self, translator: &Translator) -> Container<'_, Message> {
+        let submit = match self.matches.first() {
+            Some(name) => Message::QuickBackup(name.clone()),
+            None => Message::Ignore,
+        };
+
+        let mut matches = Column::new().spacing(5).align_items(Alignment::Center);
+        for (i, (name, state)) in self.matches.iter().zip(self.match_buttons.iter_mut()).enumerate() {
+            matches = matches.push(
+                Button::new(state, Text::new(name.clone()))
+                    .on_press(Message::QuickBackup(name.clone()))
+                    .width(Length::Units(400))
+                    .style(if i == 0 {
+                        style::Button::Primary
+                    } else {
+                        style::Button::GameListEntryTitle
+                    }),
+            );
+        }
+        if self.matches.is_empty() && !self.query.trim().is_empty() {
+            matches = matches.push(Text::new(translator.quick_backup_no_matches()));
+        }
+
+        Container::new(
+            Column::new()
+                .padding(40)
+                .spacing(20)
+                .width(Length::Fill)
+                .align_items(Alignment::Center)
+                .push(
+                    TextInput::new(
+                        &mut self.query_input,
+                        &translator.quick_backup_placeholder(),
+                        &self.query,
+                        Message::EditedQuickBackupQuery,
+                    )
+                    .on_submit(submit)
+                    .width(Length::Units(400))
+                    .padding(5),
+                )
+                .push(matches)
+                .push(Space::new(Length::Shrink, Length::Fill))
+                .push(
+                    Button::new(
+                        &mut self.cancel_button,
+                        Text::new(translator.cancel_button()).horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .on_press(Message::HideQuickBackup)
+                    .width(Length::Units(125))
+                    .style(style::Button::Negative),
+                ),
+        )
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .center_x()
+    }
+}
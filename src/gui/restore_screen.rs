@@ -1,22 +1,26 @@
 use crate::{
-    config::Config,
+    config::{Config, RestoreStrategy},
     gui::{
+        badge::Badge,
         common::OngoingOperation,
-        common::{make_status_row, BrowseSubject, EditAction, Message, Screen},
+        common::{make_status_row, BrowseSubject, EditAction, IcedExtension, Message, Screen, ViewMode},
+        focus::FocusTarget,
         game_list::GameList,
         icon::Icon,
         redirect_editor::{RedirectEditor, RedirectEditorRow},
         style,
     },
     lang::Translator,
+    layout::SourceBackup,
     manifest::Manifest,
     prelude::DuplicateDetector,
     shortcuts::TextHistory,
+    staging::StagedRestoration,
 };
 
 use iced::{
-    alignment::Horizontal as HorizontalAlignment, button, text_input, Alignment, Button, Column, Container, Length,
-    Row, Text, TextInput,
+    alignment::Horizontal as HorizontalAlignment, button, text_input, Alignment, Button, Checkbox, Column, Container,
+    Length, Row, Text, TextInput,
 };
 
 #[derive(Default)]
@@ -26,12 +30,19 @@ pub struct RestoreScreenComponent {
     preview_button: button::State,
     add_redirect_button: button::State,
     select_all_button: button::State,
+    invert_selection_button: button::State,
     toggle_search_button: button::State,
+    toggle_view_mode_button: button::State,
+    toggle_relative_paths_button: button::State,
+    retry_failed_button: button::State,
     pub restore_source_input: text_input::State,
     pub restore_source_history: TextHistory,
     restore_source_browse_button: button::State,
+    apply_staged_button: button::State,
     pub redirect_editor: RedirectEditor,
     pub duplicate_detector: DuplicateDetector,
+    /// The latest backup in the restoration source, as of the last scan.
+    pub source_backup: Option<SourceBackup>,
 }
 
 impl RestoreScreenComponent {
@@ -50,12 +61,21 @@ impl RestoreScreenComponent {
         }
     }
 
+    /// Pick up a reloaded config while keeping the current scan results.
+    pub fn reload(&mut self, config: &Config) {
+        let fresh = Self::new(config);
+        self.redirect_editor = fresh.redirect_editor;
+        self.restore_source_history = fresh.restore_source_history;
+    }
+
     pub fn view(
         &mut self,
         config: &Config,
         manifest: &Manifest,
         translator: &Translator,
         operation: &Option<OngoingOperation>,
+        staged: &StagedRestoration,
+        focus: Option<FocusTarget>,
     ) -> Container<Message> {
         Container::new(
             Column::new()
@@ -80,16 +100,20 @@ impl RestoreScreenComponent {
                                 None => Message::RestoreStart {
                                     preview: true,
                                     games: None,
+                                    keep: vec![],
                                 },
                                 Some(OngoingOperation::PreviewRestore) => Message::CancelOperation,
                                 _ => Message::Ignore,
                             })
                             .width(Length::Units(125))
-                            .style(match operation {
-                                None => style::Button::Primary,
-                                Some(OngoingOperation::PreviewRestore) => style::Button::Negative,
-                                _ => style::Button::Disabled,
-                            }),
+                            .style(style::focusable(
+                                match operation {
+                                    None => style::Button::Primary,
+                                    Some(OngoingOperation::PreviewRestore) => style::Button::Negative,
+                                    _ => style::Button::Disabled,
+                                },
+                                focus == Some(FocusTarget::Preview),
+                            )),
                         )
                         .push(
                             Button::new(
@@ -107,11 +131,49 @@ impl RestoreScreenComponent {
                                 _ => Message::Ignore,
                             })
                             .width(Length::Units(125))
-                            .style(match operation {
-                                None => style::Button::Primary,
-                                Some(OngoingOperation::Restore) => style::Button::Negative,
-                                _ => style::Button::Disabled,
-                            }),
+                            .style(style::focusable(
+                                match operation {
+                                    None => style::Button::Primary,
+                                    Some(OngoingOperation::Restore) => style::Button::Negative,
+                                    _ => style::Button::Disabled,
+                                },
+                                focus == Some(FocusTarget::Start),
+                            )),
+                        )
+                        .push_some(|| {
+                            let failed = self.log.failed_games();
+                            if failed.is_empty() || operation.is_some() {
+                                return None;
+                            }
+                            Some(
+                                Button::new(
+                                    &mut self.retry_failed_button,
+                                    Text::new(translator.retry_failed_button())
+                                        .horizontal_alignment(HorizontalAlignment::Center),
+                                )
+                                .on_press(Message::ConfirmRestoreStart { games: Some(failed) })
+                                .width(Length::Units(125))
+                                .style(style::Button::Negative),
+                            )
+                        })
+                        .push_if(
+                            || !staged.is_empty(),
+                            || {
+                                Button::new(
+                                    &mut self.apply_staged_button,
+                                    Text::new(translator.apply_staged_button())
+                                        .horizontal_alignment(HorizontalAlignment::Center),
+                                )
+                                .on_press(match operation {
+                                    None => Message::ConfirmApplyStagedRestore,
+                                    Some(_) => Message::Ignore,
+                                })
+                                .width(Length::Units(125))
+                                .style(match operation {
+                                    None => style::Button::Primary,
+                                    Some(_) => style::Button::Disabled,
+                                })
+                            },
                         )
                         .push(
                             Button::new(
@@ -121,7 +183,10 @@ impl RestoreScreenComponent {
                             )
                             .on_press(Message::EditedRedirect(EditAction::Add, None))
                             .width(Length::Units(125))
-                            .style(style::Button::Primary),
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::AddRedirect),
+                            )),
                         )
                         .push({
                             let restoring = true;
@@ -140,25 +205,84 @@ impl RestoreScreenComponent {
                                 Message::SelectAllGames
                             })
                             .width(Length::Units(125))
-                            .style(style::Button::Primary)
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::SelectAll),
+                            ))
                         })
+                        .push(
+                            Button::new(
+                                &mut self.invert_selection_button,
+                                Text::new(translator.invert_selection_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::InvertSelection)
+                            .width(Length::Units(125))
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::InvertSelection),
+                            )),
+                        )
                         .push(
                             Button::new(&mut self.toggle_search_button, Icon::Search.as_text())
                                 .on_press(Message::ToggleSearch {
                                     screen: Screen::Restore,
                                 })
-                                .style(if self.log.search.show {
-                                    style::Button::Negative
-                                } else {
-                                    style::Button::Primary
-                                }),
+                                .style(style::focusable(
+                                    if self.log.search.show {
+                                        style::Button::Negative
+                                    } else {
+                                        style::Button::Primary
+                                    },
+                                    focus == Some(FocusTarget::ToggleSearch),
+                                )),
+                        )
+                        .push(
+                            Button::new(
+                                &mut self.toggle_view_mode_button,
+                                match self.log.view_mode {
+                                    ViewMode::Standard => Icon::ViewHeadline.as_text(),
+                                    ViewMode::Compact => Icon::ViewAgenda.as_text(),
+                                },
+                            )
+                            .on_press(Message::ToggleViewMode {
+                                screen: Screen::Restore,
+                            })
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::ToggleViewMode),
+                            )),
+                        )
+                        .push(
+                            Button::new(&mut self.toggle_relative_paths_button, Icon::ShortText.as_text())
+                                .on_press(Message::ToggleRelativePaths {
+                                    screen: Screen::Restore,
+                                })
+                                .style(style::focusable(
+                                    if self.log.relative_paths {
+                                        style::Button::Negative
+                                    } else {
+                                        style::Button::Primary
+                                    },
+                                    focus == Some(FocusTarget::ToggleRelativePaths),
+                                )),
                         ),
                 )
-                .push(make_status_row(
-                    translator,
-                    &self.log.compute_operation_status(config, true),
-                    self.duplicate_detector.any_duplicates(),
-                ))
+                .push(
+                    make_status_row(
+                        translator,
+                        &self.log.compute_operation_status(config, true),
+                        self.duplicate_detector.any_duplicates(),
+                    )
+                    .push_some(|| {
+                        let unresolved = self.log.count_unresolved_restoration_targets(&config.get_redirects());
+                        (unresolved > 0).then(|| {
+                            Badge::new(&translator.badge_unresolved_count(unresolved))
+                                .left_margin(15)
+                                .view()
+                        })
+                    }),
+                )
                 .push(
                     Row::new()
                         .padding([0, 20, 0, 20])
@@ -174,16 +298,54 @@ impl RestoreScreenComponent {
                             )
                             .padding(5),
                         )
+                        .push_some(|| {
+                            self.source_backup
+                                .as_ref()
+                                .map(|x| Text::new(translator.restore_source_backup(x)))
+                        })
+                        .push(Checkbox::new(
+                            config.restore.link_paths,
+                            translator.link_paths_label(),
+                            Message::EditedLinkPaths,
+                        ))
+                        .push(Checkbox::new(
+                            config.restore.only_changed,
+                            translator.restore_only_changed_label(),
+                            Message::EditedRestoreOnlyChanged,
+                        ))
+                        .push(Checkbox::new(
+                            config.restore.stage,
+                            translator.restore_stage_label(),
+                            Message::EditedRestoreStage,
+                        ))
+                        .push(Checkbox::new(
+                            config.restore.strategy == RestoreStrategy::Mirror,
+                            translator.restore_mirror_label(),
+                            Message::EditedRestoreMirror,
+                        ))
+                        .push(Checkbox::new(
+                            config.restore.files,
+                            translator.files_label(),
+                            Message::EditedRestoreFiles,
+                        ))
+                        .push(Checkbox::new(
+                            config.restore.registry,
+                            translator.registry_label(),
+                            Message::EditedRestoreRegistry,
+                        ))
                         .push(
                             Button::new(&mut self.restore_source_browse_button, Icon::FolderOpen.as_text())
                                 .on_press(match operation {
                                     None => Message::BrowseDir(BrowseSubject::RestoreSource),
                                     Some(_) => Message::Ignore,
                                 })
-                                .style(match operation {
-                                    None => style::Button::Primary,
-                                    Some(_) => style::Button::Disabled,
-                                }),
+                                .style(style::focusable(
+                                    match operation {
+                                        None => style::Button::Primary,
+                                        Some(_) => style::Button::Disabled,
+                                    },
+                                    focus == Some(FocusTarget::BrowsePath),
+                                )),
                         ),
                 )
                 .push(self.redirect_editor.view(config, translator, operation))
@@ -1,8 +1,9 @@
 use crate::{
     config::Config,
     gui::{
+        badge::Badge,
         common::{BrowseSubject, EditAction},
-        common::{Message, OngoingOperation},
+        common::{IcedExtension, Message, OngoingOperation},
         icon::Icon,
         style,
     },
@@ -12,12 +13,15 @@ use crate::{
 };
 
 use iced::{
-    button, pick_list, scrollable, text_input, Button, Container, Length, PickList, Row, Scrollable, Text, TextInput,
+    button, pick_list, scrollable, text_input, Alignment, Button, Checkbox, Container, Length, PickList, Row,
+    Scrollable, Text, TextInput,
 };
 
 #[derive(Default)]
 pub struct RootEditorRow {
     button_state: button::State,
+    move_up_button_state: button::State,
+    move_down_button_state: button::State,
     browse_button_state: button::State,
     pub text_state: text_input::State,
     pub text_history: TextHistory,
@@ -47,6 +51,7 @@ impl RootEditor {
         operation: &Option<OngoingOperation>,
     ) -> Container<Message> {
         let roots = config.roots.clone();
+        let last = roots.len().saturating_sub(1);
         if roots.is_empty() {
             Container::new(Text::new(translator.no_roots_are_configured()))
         } else {
@@ -54,13 +59,7 @@ impl RootEditor {
                 self.rows.iter_mut().enumerate().fold(
                     Scrollable::new(&mut self.scroll)
                         .width(Length::Fill)
-                        // TODO: https://github.com/iced-rs/iced/issues/1388
-                        .height(if config.roots.len() > 3 {
-                            Length::Units(100)
-                        } else {
-                            Length::Shrink
-                        })
-                        .max_height(100)
+                        .height(Length::Fill)
                         .spacing(5)
                         .style(style::Scrollable),
                     |parent: Scrollable<'_, Message>, (i, x)| {
@@ -68,11 +67,41 @@ impl RootEditor {
                             Row::new()
                                 .padding([0, 20, 0, 20])
                                 .spacing(20)
+                                .align_items(Alignment::Center)
+                                .push(Checkbox::new(!roots[i].ignore, "", move |enabled| {
+                                    Message::ToggleRootEnabled { index: i, enabled }
+                                }))
                                 .push(
                                     Button::new(&mut x.button_state, Icon::RemoveCircle.as_text())
                                         .on_press(Message::EditedRoot(EditAction::Remove(i)))
                                         .style(style::Button::Negative),
                                 )
+                                .push(
+                                    Button::new(&mut x.move_up_button_state, Icon::ArrowUpward.as_text())
+                                        .on_press(if i > 0 {
+                                            Message::MoveRoot(i, i - 1)
+                                        } else {
+                                            Message::Ignore
+                                        })
+                                        .style(if i > 0 {
+                                            style::Button::Primary
+                                        } else {
+                                            style::Button::Disabled
+                                        }),
+                                )
+                                .push(
+                                    Button::new(&mut x.move_down_button_state, Icon::ArrowDownward.as_text())
+                                        .on_press(if i < last {
+                                            Message::MoveRoot(i, i + 1)
+                                        } else {
+                                            Message::Ignore
+                                        })
+                                        .style(if i < last {
+                                            style::Button::Primary
+                                        } else {
+                                            style::Button::Disabled
+                                        }),
+                                )
                                 .push(
                                     TextInput::new(&mut x.text_state, "", &roots[i].path.raw(), move |v| {
                                         Message::EditedRoot(EditAction::Change(i, v))
@@ -80,6 +109,14 @@ impl RootEditor {
                                     .width(Length::FillPortion(3))
                                     .padding(5),
                                 )
+                                .push_if(
+                                    || !roots[i].ignore && roots[i].glob().is_empty(),
+                                    || {
+                                        Badge::new(&translator.badge_root_not_found())
+                                            .style(style::Container::BadgeFailed)
+                                            .view()
+                                    },
+                                )
                                 .push(PickList::new(
                                     &mut x.pick_list,
                                     Store::ALL,
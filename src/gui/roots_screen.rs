@@ -0,0 +1,85 @@
+use crate::{
+    config::Config,
+    gui::{
+        common::{EditAction, Message, OngoingOperation},
+        focus::FocusTarget,
+        root_editor::{RootEditor, RootEditorRow},
+        style,
+    },
+    lang::Translator,
+};
+
+use iced::{
+    alignment::Horizontal as HorizontalAlignment, button, Alignment, Button, Column, Container, Length, Row, Text,
+};
+
+#[derive(Default)]
+pub struct RootsScreenComponent {
+    add_root_button: button::State,
+    find_roots_button: button::State,
+    pub root_editor: RootEditor,
+}
+
+impl RootsScreenComponent {
+    pub fn new(config: &Config) -> Self {
+        let mut root_editor = RootEditor::default();
+        for root in &config.roots {
+            root_editor.rows.push(RootEditorRow::new(&root.path.raw()))
+        }
+
+        Self {
+            root_editor,
+            ..Default::default()
+        }
+    }
+
+    pub fn view(
+        &mut self,
+        config: &Config,
+        translator: &Translator,
+        operation: &Option<OngoingOperation>,
+        focus: Option<FocusTarget>,
+    ) -> Container<'_, Message> {
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .align_items(Alignment::Center)
+                .push(
+                    Row::new()
+                        .padding([0, 20, 0, 20])
+                        .spacing(20)
+                        .align_items(Alignment::Center)
+                        .push(
+                            Button::new(
+                                &mut self.add_root_button,
+                                Text::new(translator.add_root_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::EditedRoot(EditAction::Add))
+                            .width(Length::Units(125))
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::AddRoot),
+                            )),
+                        )
+                        .push(
+                            Button::new(
+                                &mut self.find_roots_button,
+                                Text::new(translator.find_roots_button())
+                                    .horizontal_alignment(HorizontalAlignment::Center),
+                            )
+                            .on_press(Message::FindRoots)
+                            .width(Length::Units(125))
+                            .style(style::focusable(
+                                style::Button::Primary,
+                                focus == Some(FocusTarget::FindRoots),
+                            )),
+                        ),
+                )
+                .push(self.root_editor.view(config, translator, operation)),
+        )
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .center_x()
+    }
+}
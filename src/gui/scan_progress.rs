@@ -0,0 +1,65 @@
+use iced::{futures::channel::mpsc, Subscription};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::gui::common::Message;
+
+/// How many files have been found so far while scanning a big game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub game: String,
+    pub files: usize,
+}
+
+type Channel = (
+    mpsc::UnboundedSender<ScanProgress>,
+    Mutex<Option<mpsc::UnboundedReceiver<ScanProgress>>>,
+);
+
+/// Scans report into this from their worker threads, then the subscription
+/// forwards the reports to the app.
+static CHANNEL: Lazy<Channel> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::unbounded();
+    (sender, Mutex::new(Some(receiver)))
+});
+
+pub fn report(game: &str, files: usize) {
+    let _ = CHANNEL.0.unbounded_send(ScanProgress {
+        game: game.to_string(),
+        files,
+    });
+}
+
+enum SubscriptionState {
+    Starting,
+    Ready(mpsc::UnboundedReceiver<ScanProgress>),
+    Finished,
+}
+
+/// Forward scan progress reports to the app.
+pub fn subscription() -> Subscription<Message> {
+    struct ScanProgressReports;
+
+    iced_native::subscription::unfold(
+        std::any::TypeId::of::<ScanProgressReports>(),
+        SubscriptionState::Starting,
+        |state| async move {
+            use iced::futures::StreamExt;
+
+            match state {
+                SubscriptionState::Starting => match CHANNEL.1.lock().unwrap().take() {
+                    Some(receiver) => (None, SubscriptionState::Ready(receiver)),
+                    None => (None, SubscriptionState::Finished),
+                },
+                SubscriptionState::Ready(mut receiver) => match receiver.next().await {
+                    Some(progress) => (
+                        Some(Message::ScanProgress(progress)),
+                        SubscriptionState::Ready(receiver),
+                    ),
+                    None => (None, SubscriptionState::Finished),
+                },
+                SubscriptionState::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
@@ -0,0 +1,22 @@
+use iced::Subscription;
+
+use crate::gui::common::Message;
+
+/// How often to check whether a scheduled backup is due.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Periodically ask the app to check its backup schedule.
+pub fn subscription() -> Subscription<Message> {
+    struct Schedule;
+
+    iced_native::subscription::unfold(std::any::TypeId::of::<Schedule>(), (), |_| async move {
+        let (sender, receiver) = iced::futures::channel::oneshot::channel();
+        // Sleep on a separate thread so that the executor is not blocked.
+        std::thread::spawn(move || {
+            std::thread::sleep(CHECK_INTERVAL);
+            let _ = sender.send(());
+        });
+        let _ = receiver.await;
+        (Some(Message::CheckSchedule), ())
+    })
+}
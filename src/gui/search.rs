@@ -1,7 +1,8 @@
 use crate::{
     config::{Sort, SortKey},
-    gui::common::{Message, Screen},
+    gui::common::{IcedExtension, Message, Screen},
     lang::Translator,
+    manifest::Store,
     shortcuts::TextHistory,
 };
 
@@ -10,10 +11,42 @@ use iced::{
     text_input, Alignment, Checkbox, Container, Length, Row, Space, Text, TextInput,
 };
 
+/// A choice for filtering by store, where `None` means all stores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoreFilter(pub Option<Store>);
+
+impl StoreFilter {
+    fn all() -> Vec<Self> {
+        std::iter::once(Self(None))
+            .chain(Store::ALL.iter().map(|x| Self(Some(*x))))
+            .collect()
+    }
+
+    pub fn matches(&self, store: Store) -> bool {
+        self.0.map(|x| x == store).unwrap_or(true)
+    }
+}
+
+impl std::fmt::Display for StoreFilter {
+    // This is needed for Iced's PickList.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        // TODO: Use display adapter wrapper struct to respect the active language.
+        let translator = Translator::default();
+        match &self.0 {
+            Some(store) => writeln!(f, "{}", translator.store(store)),
+            None => writeln!(f, "{}", translator.all_stores()),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SearchComponent {
     pub show: bool,
     pub game_name: String,
+    pub stale_only: bool,
+    pub never_backed_up_only: bool,
+    pub store: StoreFilter,
+    pub store_state: pick_list::State<StoreFilter>,
     pub game_name_input: text_input::State,
     pub game_name_history: TextHistory,
     pub sort_key_state: pick_list::State<SortKey>,
@@ -48,7 +81,28 @@ impl SearchComponent {
                 ))
                 .push(Checkbox::new(sort.reversed, translator.sort_reversed(), move |value| {
                     Message::EditedSortReversed { screen, value }
-                })),
+                }))
+                .push(Text::new(translator.store_label()))
+                .push(PickList::new(
+                    &mut self.store_state,
+                    StoreFilter::all(),
+                    Some(self.store),
+                    |value| Message::FilterByStore(value.0),
+                ))
+                .push_if(
+                    || screen == Screen::Backup,
+                    || Checkbox::new(self.stale_only, translator.stale_only_label(), Message::EditedStaleOnly),
+                )
+                .push_if(
+                    || screen == Screen::Backup,
+                    || {
+                        Checkbox::new(
+                            self.never_backed_up_only,
+                            translator.never_backed_up_only_label(),
+                            Message::EditedNeverBackedUpOnly,
+                        )
+                    },
+                ),
         )
     }
 }
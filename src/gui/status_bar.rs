@@ -0,0 +1,42 @@
+use crate::{
+    gui::common::{Message, OngoingOperation},
+    lang::Translator,
+};
+
+use iced::{Container, Length, Text};
+
+/// Shows the ongoing operation at the bottom of the window,
+/// so that it stays visible when switching screens.
+#[derive(Default)]
+pub struct StatusBar {
+    started: Option<std::time::Instant>,
+}
+
+impl StatusBar {
+    pub fn start(&mut self) {
+        self.started = Some(std::time::Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    pub fn view(
+        &self,
+        translator: &Translator,
+        operation: &Option<OngoingOperation>,
+        processed: f32,
+        total: f32,
+    ) -> Container<'_, Message> {
+        let text = match (operation, self.started) {
+            (Some(operation), Some(started)) => {
+                translator.status_bar(operation, processed as usize, total as usize, started.elapsed())
+            }
+            _ => "".to_string(),
+        };
+
+        Container::new(Text::new(text).size(16))
+            .padding([2, 10, 2, 10])
+            .width(Length::Fill)
+    }
+}
@@ -1,5 +1,7 @@
 use iced::{button, container, scrollable, Background, Color, Vector};
 
+use crate::prelude::OperationSummary;
+
 pub enum Button {
     Primary,
     Disabled,
@@ -44,6 +46,46 @@ impl button::StyleSheet for Button {
     }
 }
 
+/// Draws a focus ring around another button style for keyboard navigation.
+pub struct Focused<T>(pub T);
+
+impl<T: button::StyleSheet> Focused<T> {
+    fn ring(style: button::Style) -> button::Style {
+        button::Style {
+            border_width: 2.0,
+            border_color: Color::from_rgb8(255, 165, 0),
+            ..style
+        }
+    }
+}
+
+impl<T: button::StyleSheet> button::StyleSheet for Focused<T> {
+    fn active(&self) -> button::Style {
+        Self::ring(self.0.active())
+    }
+
+    fn hovered(&self) -> button::Style {
+        Self::ring(self.0.hovered())
+    }
+
+    fn pressed(&self) -> button::Style {
+        Self::ring(self.0.pressed())
+    }
+
+    fn disabled(&self) -> button::Style {
+        Self::ring(self.0.disabled())
+    }
+}
+
+/// Applies the focus ring only if the button currently has keyboard focus.
+pub fn focusable<'a, T: button::StyleSheet + 'a>(style: T, focused: bool) -> Box<dyn button::StyleSheet + 'a> {
+    if focused {
+        Box::new(Focused(style))
+    } else {
+        Box::new(style)
+    }
+}
+
 pub enum NavButton {
     Active,
     Inactive,
@@ -81,13 +123,34 @@ pub enum Container {
     ModalBackground,
     GameListEntry,
     Badge,
+    BadgeSucceeded,
+    BadgeWarning,
+    BadgeFailed,
+    Tooltip,
+    Banner,
+}
+
+impl From<&OperationSummary> for Container {
+    fn from(summary: &OperationSummary) -> Self {
+        if summary.failed > 0 {
+            Self::BadgeFailed
+        } else if summary.skipped > 0 {
+            Self::BadgeWarning
+        } else {
+            Self::BadgeSucceeded
+        }
+    }
 }
 
 impl container::StyleSheet for Container {
     fn style(&self) -> container::Style {
         container::Style {
             background: match self {
-                Self::ModalBackground => Some(Background::Color(Color::from_rgb8(230, 230, 230))),
+                Self::ModalBackground | Self::Tooltip => Some(Background::Color(Color::from_rgb8(230, 230, 230))),
+                Self::BadgeSucceeded => Some(Background::Color(Color::from_rgb8(46, 160, 67))),
+                Self::BadgeWarning => Some(Background::Color(Color::from_rgb8(230, 170, 0))),
+                Self::BadgeFailed => Some(Background::Color(Color::from_rgb8(220, 50, 50))),
+                Self::Banner => Some(Background::Color(Color::from_rgb8(255, 220, 220))),
                 _ => None,
             },
             border_color: match self {
@@ -95,11 +158,19 @@ impl container::StyleSheet for Container {
                 _ => Color::BLACK,
             },
             border_width: match self {
-                Self::GameListEntry | Self::Badge => 1.0,
+                Self::GameListEntry
+                | Self::Badge
+                | Self::BadgeSucceeded
+                | Self::BadgeWarning
+                | Self::BadgeFailed
+                | Self::Tooltip => 1.0,
                 _ => 0.0,
             },
             border_radius: match self {
-                Self::GameListEntry | Self::Badge => 10.0,
+                Self::GameListEntry | Self::Badge | Self::BadgeSucceeded | Self::BadgeWarning | Self::BadgeFailed => {
+                    10.0
+                }
+                Self::Tooltip => 5.0,
                 _ => 0.0,
             },
             ..container::Style::default()
@@ -0,0 +1,146 @@
+use iced::{futures::channel::mpsc, Subscription};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::{gui::common::Message, lang::Translator};
+
+/// Something the user picked from the tray menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    BackUp,
+    ShowWindow,
+    HideWindow,
+    Quit,
+}
+
+/// Filled in once the tray is running, then handed off to the subscription.
+static RECEIVER: Lazy<Mutex<Option<mpsc::UnboundedReceiver<TrayAction>>>> = Lazy::new(|| Mutex::new(None));
+
+enum SubscriptionState {
+    Starting,
+    Ready(mpsc::UnboundedReceiver<TrayAction>),
+    Finished,
+}
+
+/// Forward the tray menu actions to the app.
+pub fn subscription() -> Subscription<Message> {
+    struct Tray;
+
+    iced_native::subscription::unfold(
+        std::any::TypeId::of::<Tray>(),
+        SubscriptionState::Starting,
+        |state| async move {
+            use iced::futures::StreamExt;
+
+            match state {
+                SubscriptionState::Starting => match RECEIVER.lock().unwrap().take() {
+                    Some(receiver) => (None, SubscriptionState::Ready(receiver)),
+                    None => (None, SubscriptionState::Finished),
+                },
+                SubscriptionState::Ready(mut receiver) => match receiver.next().await {
+                    Some(action) => (Some(Message::Tray(action)), SubscriptionState::Ready(receiver)),
+                    None => (None, SubscriptionState::Finished),
+                },
+                SubscriptionState::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use ksni::blocking::TrayMethods;
+
+    struct LudusaviTray {
+        sender: mpsc::UnboundedSender<TrayAction>,
+        translator: Translator,
+    }
+
+    impl LudusaviTray {
+        fn item(&self, label: String, action: TrayAction) -> ksni::MenuItem<Self> {
+            ksni::menu::StandardItem {
+                label,
+                activate: Box::new(move |this: &mut Self| {
+                    let _ = this.sender.unbounded_send(action);
+                }),
+                ..Default::default()
+            }
+            .into()
+        }
+    }
+
+    impl ksni::Tray for LudusaviTray {
+        fn id(&self) -> String {
+            "ludusavi".to_string()
+        }
+
+        fn title(&self) -> String {
+            self.translator.window_title()
+        }
+
+        fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+            match image::load_from_memory(include_bytes!("../../assets/icon.png")) {
+                Ok(buffer) => {
+                    let buffer = buffer.to_rgba8();
+                    let (width, height) = (buffer.width() as i32, buffer.height() as i32);
+                    // The tray expects ARGB instead of RGBA.
+                    let mut data = buffer.into_raw();
+                    for pixel in data.chunks_exact_mut(4) {
+                        pixel.rotate_right(1);
+                    }
+                    vec![ksni::Icon { width, height, data }]
+                }
+                Err(_) => vec![],
+            }
+        }
+
+        fn activate(&mut self, _x: i32, _y: i32) {
+            let _ = self.sender.unbounded_send(TrayAction::ShowWindow);
+        }
+
+        fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+            vec![
+                self.item(self.translator.tray_back_up(), TrayAction::BackUp),
+                ksni::MenuItem::Separator,
+                self.item(self.translator.tray_show_window(), TrayAction::ShowWindow),
+                self.item(self.translator.tray_hide_window(), TrayAction::HideWindow),
+                ksni::MenuItem::Separator,
+                self.item(self.translator.tray_quit(), TrayAction::Quit),
+            ]
+        }
+    }
+
+    pub fn start(translator: Translator) -> bool {
+        let (sender, receiver) = mpsc::unbounded();
+        // Flatpak's sandbox does not allow owning the usual D-Bus name.
+        let sandboxed = std::env::var_os("FLATPAK_ID").is_some();
+        match (LudusaviTray { sender, translator })
+            .disable_dbus_name(sandboxed)
+            .spawn()
+        {
+            Ok(handle) => {
+                // The tray lives for the rest of the process.
+                std::mem::forget(handle);
+                *RECEIVER.lock().unwrap() = Some(receiver);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::*;
+
+    pub fn start(_translator: Translator) -> bool {
+        false
+    }
+}
+
+/// Show the tray icon, if supported on this system.
+/// Returns whether the tray is available.
+pub fn start(translator: Translator) -> bool {
+    platform::start(translator)
+}
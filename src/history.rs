@@ -0,0 +1,118 @@
+use crate::prelude::{app_dir, Error, OperationSummary, StrictPath};
+
+const HISTORY_FILE_NAME: &str = "history.yaml";
+
+/// Only this many of the most recent operations are kept.
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// A finished backup or restore. Previews are not recorded.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HistoryEntry {
+    pub when: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub restoring: bool,
+    /// The backup folder that was written to or restored from.
+    pub path: StrictPath,
+    pub summary: OperationSummary,
+}
+
+/// Recent operations, oldest first. This is saved in the app folder so that
+/// it includes operations from both the GUI and the CLI.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct History {
+    #[serde(default)]
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    fn file() -> StrictPath {
+        let mut path = app_dir();
+        path.push(HISTORY_FILE_NAME);
+        StrictPath::from_std_path_buf(&path)
+    }
+
+    pub fn load() -> Self {
+        match std::fs::read_to_string(Self::file().interpret()) {
+            Ok(content) => Self::load_from_string(&content).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn load_from_string(content: &str) -> Result<Self, ()> {
+        serde_yaml::from_str(content).map_err(|_| ())
+    }
+
+    pub fn add(&mut self, entry: HistoryEntry) {
+        self.entries.push(entry);
+        if self.entries.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.entries.len() - MAX_HISTORY_ENTRIES;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Add an entry to the saved history and return the updated history.
+    /// Writing the file is best effort, since a missing entry shouldn't fail the operation,
+    /// but nothing is written in read-only mode.
+    pub fn record(entry: HistoryEntry) -> Result<Self, Error> {
+        if crate::prelude::is_read_only() {
+            return Err(Error::ReadOnlyMode);
+        }
+        let mut history = Self::load();
+        history.add(entry);
+        if let Ok(content) = serde_yaml::to_string(&history) {
+            let _ = std::fs::create_dir_all(app_dir());
+            let _ = std::fs::write(Self::file().interpret(), content.as_bytes());
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn entry(succeeded: usize) -> HistoryEntry {
+        HistoryEntry {
+            when: chrono::DateTime::parse_from_rfc3339("2022-08-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            restoring: false,
+            path: StrictPath::new("/backup".to_string()),
+            summary: OperationSummary {
+                succeeded,
+                skipped: 0,
+                failed: 0,
+                failures: Default::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn can_load_history() {
+        let history = History::load_from_string(
+            r#"
+            entries:
+              - when: "2022-08-01T12:00:00Z"
+                path: /backup
+                summary:
+                  succeeded: 3
+                  skipped: 0
+                  failed: 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(vec![entry(3)], history.entries);
+    }
+
+    #[test]
+    fn keeps_only_most_recent_entries() {
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY_ENTRIES + 2 {
+            history.add(entry(i));
+        }
+        assert_eq!(MAX_HISTORY_ENTRIES, history.entries.len());
+        assert_eq!(2, history.entries[0].summary.succeeded);
+    }
+}
@@ -1,23 +1,54 @@
 use byte_unit::Byte;
-use fluent::{bundle::FluentBundle, FluentArgs, FluentResource};
+use fluent::{bundle::FluentBundle, FluentArgs, FluentResource, FluentValue};
 use intl_memoizer::concurrent::IntlLangMemoizer;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::sync::Mutex;
+use std::{collections::HashSet, sync::Mutex};
 use unic_langid::LanguageIdentifier;
 
 use crate::{
     config::SortKey,
-    manifest::Store,
-    prelude::{Error, OperationStatus, OperationStepDecision, StrictPath},
+    history::HistoryEntry,
+    manifest::{ManifestProblem, Store},
+    prelude::{
+        DeletionPreview, Error, FailureCategory, OngoingOperation, OperationStatus, OperationStepDecision,
+        OperationSummary, RegistryChange, RestorationTargetError, StrictPath, UnresolvedPlaceholders,
+    },
+    registry_compat::RegistryChangeKind,
 };
 
+const BACKUP: &str = "backup";
+const CHANGE: &str = "change";
+const COMMAND: &str = "command";
+const DAY: &str = "day";
+const DAYS: &str = "days";
+const ELAPSED: &str = "elapsed";
+const ENABLED: &str = "enabled";
+const FAILED: &str = "failed";
+const FILTER: &str = "filter";
+const GAME: &str = "game";
+const HOST: &str = "host";
+const MONTH: &str = "month";
 const PATH: &str = "path";
+const OPERATION: &str = "operation";
+const PLACEHOLDER: &str = "placeholder";
 const PATH_ACTION: &str = "path-action";
 const PROCESSED_GAMES: &str = "processed-games";
 const PROCESSED_SIZE: &str = "processed-size";
+const PROMPT: &str = "prompt";
+const REASON: &str = "reason";
+const ROOT: &str = "root";
+const SKIPPED: &str = "skipped";
+const SLOT: &str = "slot";
+const STALE: &str = "stale";
+const SUCCEEDED: &str = "succeeded";
+const TOTAL_FILES: &str = "total-files";
 const TOTAL_GAMES: &str = "total-games";
+const TOTAL_ROOTS: &str = "total-roots";
 const TOTAL_SIZE: &str = "total-size";
+const UPDATED: &str = "updated";
+const WHEN: &str = "when";
+const YEAR: &str = "year";
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Language {
@@ -31,6 +62,50 @@ impl Language {
         }
         .to_string()
     }
+
+    fn grouping_separator(&self) -> &'static str {
+        match self {
+            Self::English => ",",
+        }
+    }
+
+    fn decimal_separator(&self) -> &'static str {
+        match self {
+            Self::English => ".",
+        }
+    }
+
+    /// Insert the thousands separator into a whole number.
+    pub fn format_integer(&self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let groups: Vec<_> = digits
+            .as_bytes()
+            .rchunks(3)
+            .rev()
+            .map(|x| String::from_utf8_lossy(x))
+            .collect();
+        let out = groups.join(self.grouping_separator());
+        if value < 0 {
+            format!("-{}", out)
+        } else {
+            out
+        }
+    }
+}
+
+/// Fluent's default number formatting doesn't apply any grouping,
+/// so whole numbers are formatted here instead.
+fn format_fluent_value(value: &FluentValue, _: &IntlLangMemoizer) -> Option<String> {
+    match value {
+        FluentValue::Number(number)
+            if number.options.use_grouping
+                && number.options.minimum_fraction_digits.is_none()
+                && number.value.fract() == 0.0 =>
+        {
+            Some(Language::English.format_integer(number.value as i64))
+        }
+        _ => None,
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -43,6 +118,7 @@ static BUNDLE: Lazy<Mutex<FluentBundle<FluentResource, IntlLangMemoizer>>> = Laz
     let language_id: LanguageIdentifier = Language::English.id().parse().unwrap();
     let mut bundle = FluentBundle::new_concurrent(vec![language_id]);
     bundle.set_use_isolating(false);
+    bundle.set_formatter(Some(format_fluent_value));
 
     bundle
         .add_resource(res)
@@ -111,18 +187,37 @@ impl Translator {
     pub fn handle_error(&self, error: &Error) -> String {
         match error {
             Error::ConfigInvalid { why } => self.config_is_invalid(why),
+            Error::ConfigSaveFailed { path } => self.config_save_failed(path),
+            Error::ConfigExportFailed { path } => self.config_export_failed(path),
             Error::ManifestInvalid { why } => self.manifest_is_invalid(why),
             Error::ManifestCannotBeUpdated => self.manifest_cannot_be_updated(),
+            Error::ManifestDownloadInvalid { why } => self.manifest_download_is_invalid(why),
+            Error::ManifestChecksumMismatch => self.manifest_checksum_mismatch(),
             Error::CliBackupTargetExists { path } => self.cli_backup_target_exists(path),
             Error::CliUnrecognizedGames { games } => self.cli_unrecognized_games(games),
             Error::CliUnableToRequestConfirmation => self.cli_unable_to_request_confirmation(),
             Error::SomeEntriesFailed => self.some_entries_failed(),
             Error::CannotPrepareBackupTarget { path } => self.cannot_prepare_backup_target(path),
+            Error::BackupTargetNotWritable { path } => self.backup_target_not_writable(path),
+            Error::UnknownBackup { game, backup } => self.unknown_backup(game, backup),
+            Error::CannotPruneOrphan { path } => self.cannot_prune_orphan(path),
+            Error::StagedRestoreSaveFailed { path } => self.staged_restore_save_failed(path),
+            Error::BackupTargetInvalid { path } => self.backup_target_invalid(path),
+            Error::BackupTargetHasLockedBackups { path } => self.backup_target_has_locked_backups(path),
+            Error::BackupTargetInsideRoot { path, root } => self.backup_target_inside_root(path, root),
             Error::RestorationSourceInvalid { path } => self.restoration_source_is_invalid(path),
+            Error::RestorationTargetNotWritable { path } => self.restoration_target_not_writable(path),
+            Error::BackupMigrationFailed { path } => self.backup_migration_failed(path),
+            Error::CommandFailed { command, output } => self.command_failed(command, output),
+            Error::OperationDisabled { restoring } => self.operation_disabled(*restoring),
+            Error::ReadOnlyMode => self.read_only_mode(),
+            Error::SftpTransferFailed { target, output } => self.sftp_transfer_failed(target, output),
             Error::RegistryIssue => self.registry_issue(),
             Error::UnableToBrowseFileSystem => self.unable_to_browse_file_system(),
             Error::UnableToOpenDir(path) => self.unable_to_open_dir(path),
+            Error::UnableToOpenFile(path) => self.unable_to_open_file(path),
             Error::UnableToOpenUrl(url) => self.unable_to_open_url(url),
+            Error::GuiUnavailable { why } => self.gui_unavailable(why),
         }
     }
 
@@ -154,10 +249,151 @@ impl Translator {
         format!("{} {}", translate("cli-unable-to-request-confirmation"), extra_note)
     }
 
+    pub fn tui_games(&self, restoring: bool) -> String {
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, if restoring { "restore" } else { "backup" });
+        translate_args("tui-games", &args)
+    }
+
+    pub fn tui_filter(&self, filter: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(FILTER, filter);
+        translate_args("tui-filter", &args)
+    }
+
+    pub fn tui_selection(&self) -> String {
+        translate("tui-selection")
+    }
+
+    pub fn tui_selected_games(&self, games: &std::collections::BTreeSet<String>) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, games.len());
+        let header = translate_args("tui-selected-games", &args);
+        if games.is_empty() {
+            header
+        } else {
+            let lines: Vec<_> = games.iter().map(|x| format!("  - {}", x)).collect();
+            format!("{}\n{}", header, lines.join("\n"))
+        }
+    }
+
+    pub fn tui_keys(&self, restoring: bool, filtering: bool) -> String {
+        if filtering {
+            return translate("tui-keys-filter");
+        }
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, if restoring { "restore" } else { "backup" });
+        translate_args("tui-keys", &args)
+    }
+
+    pub fn tui_confirm(&self, prompt: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PROMPT, prompt);
+        translate_args("tui-confirm", &args)
+    }
+
+    pub fn tui_press_any_key(&self) -> String {
+        translate("tui-press-any-key")
+    }
+
+    pub fn cli_passphrase_prompt(&self) -> String {
+        translate("cli-passphrase-prompt")
+    }
+
     pub fn some_entries_failed(&self) -> String {
         translate("some-entries-failed")
     }
 
+    pub fn operation_health(&self, summary: &OperationSummary) -> String {
+        if summary.failed > 0 {
+            translate("operation-health-failed")
+        } else if summary.skipped > 0 {
+            translate("operation-health-skipped")
+        } else {
+            translate("operation-health-succeeded")
+        }
+    }
+
+    pub fn operation_summary(&self, restoring: bool, summary: &OperationSummary) -> String {
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, if restoring { "restore" } else { "backup" });
+        args.set(SUCCEEDED, summary.succeeded);
+        args.set(SKIPPED, summary.skipped);
+        args.set(FAILED, summary.failed);
+        let text = translate_args("operation-summary", &args);
+        if summary.failed > 0 {
+            let mut text = format!("{}\n\n{}", text, self.some_entries_failed());
+            if !summary.failures.is_empty() {
+                let failures: Vec<_> = summary
+                    .failures
+                    .iter()
+                    .map(|(category, games)| format!("{} ({})", self.failure_category(*category), games))
+                    .collect();
+                text = format!("{}\n\n{}", text, failures.join("\n"));
+            }
+            text
+        } else {
+            text
+        }
+    }
+
+    pub fn failure_category(&self, category: FailureCategory) -> String {
+        let mut args = FluentArgs::new();
+        args.set(
+            REASON,
+            match category {
+                FailureCategory::Permission => "permission",
+                FailureCategory::MissingSource => "missing",
+                FailureCategory::FileInUse => "in-use",
+                FailureCategory::DiskFull => "disk-full",
+                FailureCategory::Other => "other",
+            },
+        );
+        translate_args("failure-category", &args)
+    }
+
+    pub fn history_label(&self) -> String {
+        translate("field-history")
+    }
+
+    pub fn history_entry(&self, entry: &HistoryEntry) -> String {
+        let mut args = FluentArgs::new();
+        args.set(UPDATED, self.date_time(&entry.when));
+        args.set(OPERATION, if entry.restoring { "restore" } else { "backup" });
+        args.set(SUCCEEDED, entry.summary.succeeded);
+        args.set(SKIPPED, entry.summary.skipped);
+        args.set(FAILED, entry.summary.failed);
+        translate_args("history-entry", &args)
+    }
+
+    pub fn stats_label(&self) -> String {
+        translate("field-stats")
+    }
+
+    pub fn stats_manifest_games(&self, total: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, total);
+        translate_args("stats-manifest-games", &args)
+    }
+
+    pub fn stats_roots(&self, total: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_ROOTS, total);
+        translate_args("stats-roots", &args)
+    }
+
+    pub fn stats_found_games(&self, total: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, total);
+        translate_args("stats-found-games", &args)
+    }
+
+    pub fn stats_backed_up_games(&self, total: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, total);
+        translate_args("stats-backed-up-games", &args)
+    }
+
     fn label(&self, text: &str) -> String {
         format!("[{}]", text)
     }
@@ -178,6 +414,16 @@ impl Translator {
         self.label(&self.badge_ignored())
     }
 
+    pub fn save_slots_label(&self) -> String {
+        translate("label-save-slots")
+    }
+
+    pub fn save_slot(&self, slot: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(SLOT, slot);
+        translate_args("save-slot", &args)
+    }
+
     pub fn badge_failed(&self) -> String {
         translate("badge-failed")
     }
@@ -200,6 +446,92 @@ impl Translator {
         translate_args("badge-redirected-from", &args)
     }
 
+    pub fn badge_too_large(&self) -> String {
+        translate("badge-too-large")
+    }
+
+    pub fn badge_unresolved(&self, error: &RestorationTargetError) -> String {
+        let mut args = FluentArgs::new();
+        args.set(
+            REASON,
+            match error {
+                RestorationTargetError::Empty => "empty",
+                RestorationTargetError::NotAbsolute { .. } => "not-absolute",
+            },
+        );
+        translate_args("badge-unresolved", &args)
+    }
+
+    pub fn badge_last_backup(&self, when: &chrono::DateTime<chrono::Utc>, stale: bool) -> String {
+        let mut args = FluentArgs::new();
+        args.set(UPDATED, self.date(when));
+        args.set(STALE, if stale { "yes" } else { "no" });
+        translate_args("badge-last-backup", &args)
+    }
+
+    pub fn badge_unresolved_count(&self, count: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_FILES, count);
+        translate_args("badge-unresolved-count", &args)
+    }
+
+    pub fn badge_root_not_found(&self) -> String {
+        translate("badge-root-not-found")
+    }
+
+    pub fn badge_never_backed_up(&self) -> String {
+        translate("badge-never-backed-up")
+    }
+
+    pub fn badge_never_backed_up_count(&self, count: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, count);
+        translate_args("badge-never-backed-up-count", &args)
+    }
+
+    pub fn badge_over_budget(&self) -> String {
+        translate("badge-over-budget")
+    }
+
+    pub fn badge_running(&self) -> String {
+        translate("badge-running")
+    }
+
+    pub fn badge_destination(&self, path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, path.render());
+        translate_args("badge-destination", &args)
+    }
+
+    pub fn cli_game_running(&self, game: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(GAME, game);
+        translate_args("cli-game-running", &args)
+    }
+
+    pub fn badge_backup_budget(&self, used: u64, limit: u64) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PROCESSED_SIZE, self.adjusted_size(used));
+        args.set(TOTAL_SIZE, self.adjusted_size(limit));
+        translate_args("badge-backup-budget", &args)
+    }
+
+    pub fn badge_changed(&self) -> String {
+        translate("badge-changed")
+    }
+
+    pub fn badge_changed_count(&self, count: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, count);
+        translate_args("badge-changed-count", &args)
+    }
+
+    pub fn badge_since_days(&self, days: u32) -> String {
+        let mut args = FluentArgs::new();
+        args.set(DAYS, days);
+        translate_args("badge-since-days", &args)
+    }
+
     pub fn cli_game_header(
         &self,
         name: &str,
@@ -208,8 +540,11 @@ impl Translator {
         duplicated: bool,
     ) -> String {
         let mut labels = vec![];
-        if *decision == OperationStepDecision::Ignored {
-            labels.push(self.label_ignored());
+        match decision {
+            OperationStepDecision::Ignored => labels.push(self.label_ignored()),
+            OperationStepDecision::OverBudget => labels.push(self.label(&self.badge_over_budget())),
+            OperationStepDecision::Running => labels.push(self.label(&self.badge_running())),
+            OperationStepDecision::Processed | OperationStepDecision::Cancelled => {}
         }
         if duplicated {
             labels.push(self.label_duplicates());
@@ -287,6 +622,18 @@ impl Translator {
         translate("button-nav-other")
     }
 
+    pub fn nav_roots_button(&self) -> String {
+        translate("button-nav-roots")
+    }
+
+    pub fn select_changed_button(&self) -> String {
+        translate("button-select-changed")
+    }
+
+    pub fn edit_roots_button(&self) -> String {
+        translate("button-edit-roots")
+    }
+
     pub fn add_root_button(&self) -> String {
         translate("button-add-root")
     }
@@ -310,6 +657,76 @@ impl Translator {
         msg
     }
 
+    pub fn manifest_lint(&self, unresolved: &[UnresolvedPlaceholders]) -> String {
+        if unresolved.is_empty() {
+            return translate("manifest-lint-clean");
+        }
+
+        let mut args = FluentArgs::new();
+        args.set(
+            TOTAL_GAMES,
+            unresolved.iter().map(|x| &x.game).collect::<HashSet<_>>().len(),
+        );
+        args.set(TOTAL_FILES, unresolved.len());
+        format!(
+            "{}\n\n{}",
+            translate_args("manifest-lint", &args),
+            self.manifest_lint_report(unresolved)
+        )
+    }
+
+    pub fn manifest_lint_report(&self, unresolved: &[UnresolvedPlaceholders]) -> String {
+        let lines: Vec<_> = unresolved
+            .iter()
+            .map(|x| format!("[{}] {} ({})", x.game, x.path, x.placeholders.join(", ")))
+            .collect();
+        lines.join("\n")
+    }
+
+    pub fn manifest_problems(&self, problems: &[ManifestProblem]) -> String {
+        let lines: Vec<_> = problems
+            .iter()
+            .map(|problem| {
+                let mut args = FluentArgs::new();
+                match problem {
+                    ManifestProblem::DuplicateGame { game } => {
+                        args.set(GAME, game.as_str());
+                        translate_args("manifest-problem-duplicate-game", &args)
+                    }
+                    ManifestProblem::MalformedGame { game, why } => {
+                        args.set(GAME, game.as_str());
+                        args.set(REASON, why.as_str());
+                        translate_args("manifest-problem-malformed-game", &args)
+                    }
+                    ManifestProblem::EmptyPath { game } => {
+                        args.set(GAME, game.as_str());
+                        translate_args("manifest-problem-empty-path", &args)
+                    }
+                    ManifestProblem::UnknownPlaceholder {
+                        game,
+                        path,
+                        placeholder,
+                    } => {
+                        args.set(GAME, game.as_str());
+                        args.set(PATH, path.as_str());
+                        args.set(PLACEHOLDER, placeholder.as_str());
+                        translate_args("manifest-problem-unknown-placeholder", &args)
+                    }
+                    ManifestProblem::OverriddenGame { game, source } => {
+                        args.set(GAME, game.as_str());
+                        args.set(PATH, source.as_str());
+                        translate_args("manifest-problem-overridden-game", &args)
+                    }
+                    ManifestProblem::UnavailableSource { source } => {
+                        args.set(PATH, source.as_str());
+                        translate_args("manifest-problem-unavailable-source", &args)
+                    }
+                }
+            })
+            .collect();
+        format!("{}\n\n{}", translate("manifest-has-problems"), lines.join("\n"))
+    }
+
     pub fn add_redirect_button(&self) -> String {
         translate("button-add-redirect")
     }
@@ -334,6 +751,137 @@ impl Translator {
         translate("button-okay")
     }
 
+    pub fn details_button(&self) -> String {
+        translate("button-details")
+    }
+
+    pub fn copy_button(&self) -> String {
+        translate("button-copy")
+    }
+
+    pub fn manifest_summary(&self, games: usize, updated: &Option<chrono::DateTime<chrono::Utc>>) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, games);
+        args.set(
+            UPDATED,
+            match updated {
+                Some(x) => self.date_time(x),
+                None => translate("unknown"),
+            },
+        );
+        translate_args("manifest-summary", &args)
+    }
+
+    pub fn restore_source_backup(&self, source: &crate::layout::SourceBackup) -> String {
+        let unknown = || translate("unknown");
+        let mut args = FluentArgs::new();
+        args.set(
+            WHEN,
+            match &source.when {
+                Some(x) => self.date_time(x),
+                None => unknown(),
+            },
+        );
+        args.set(HOST, source.host.clone().unwrap_or_else(unknown));
+        translate_args("restore-source-backup", &args)
+    }
+
+    pub fn migrate_button(&self) -> String {
+        translate("button-migrate")
+    }
+
+    pub fn scheduled_backup_started(&self) -> String {
+        translate("scheduled-backup-started")
+    }
+
+    pub fn scheduled_backup_skipped_busy(&self) -> String {
+        translate("scheduled-backup-skipped-busy")
+    }
+
+    pub fn scheduled_backup_skipped_passphrase(&self) -> String {
+        translate("scheduled-backup-skipped-passphrase")
+    }
+
+    pub fn scheduled_backup_finished(&self, failed: bool) -> String {
+        if failed {
+            translate("scheduled-backup-finished-with-failures")
+        } else {
+            translate("scheduled-backup-finished")
+        }
+    }
+
+    pub fn tray_back_up(&self) -> String {
+        translate("tray-back-up")
+    }
+
+    pub fn tray_show_window(&self) -> String {
+        translate("tray-show-window")
+    }
+
+    pub fn tray_hide_window(&self) -> String {
+        translate("tray-hide-window")
+    }
+
+    pub fn tray_quit(&self) -> String {
+        translate("tray-quit")
+    }
+
+    pub fn manifest_categories_label(&self) -> String {
+        translate("field-manifest-categories")
+    }
+
+    pub fn check_manifest_button(&self) -> String {
+        translate("button-check-manifest")
+    }
+
+    pub fn test_restore_button(&self) -> String {
+        translate("button-test-restore")
+    }
+
+    pub fn export_config_button(&self) -> String {
+        translate("button-export-config")
+    }
+
+    pub fn import_config_button(&self) -> String {
+        translate("button-import-config")
+    }
+
+    pub fn merge_button(&self) -> String {
+        translate("button-merge")
+    }
+
+    pub fn replace_button(&self) -> String {
+        translate("button-replace")
+    }
+
+    pub fn keep_all_newer_button(&self) -> String {
+        translate("button-keep-all-newer")
+    }
+
+    pub fn overwrite_all_button(&self) -> String {
+        translate("button-overwrite-all")
+    }
+
+    pub fn reload_config_button(&self) -> String {
+        translate("button-reload-config")
+    }
+
+    pub fn save_config_button(&self) -> String {
+        translate("button-save-config")
+    }
+
+    pub fn unsaved_config_changes(&self) -> String {
+        translate("unsaved-config-changes")
+    }
+
+    pub fn retry_failed_button(&self) -> String {
+        translate("button-retry-failed")
+    }
+
+    pub fn apply_staged_button(&self) -> String {
+        translate("button-apply-staged")
+    }
+
     pub fn select_all_button(&self) -> String {
         translate("button-select-all")
     }
@@ -342,6 +890,10 @@ impl Translator {
         translate("button-deselect-all")
     }
 
+    pub fn invert_selection_button(&self) -> String {
+        translate("button-invert-selection")
+    }
+
     pub fn enable_all_button(&self) -> String {
         translate("button-enable-all")
     }
@@ -354,10 +906,33 @@ impl Translator {
         translate("no-roots-are-configured")
     }
 
+    pub fn roots_summary(&self, total: usize, enabled: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_ROOTS, total);
+        args.set(ENABLED, enabled);
+        translate_args("roots-summary", &args)
+    }
+
     pub fn config_is_invalid(&self, why: &str) -> String {
         format!("{}\n{}", translate("config-is-invalid"), why)
     }
 
+    pub fn confirm_import_config(&self) -> String {
+        translate("confirm-import-config")
+    }
+
+    pub fn config_save_failed(&self, path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, path.render());
+        translate_args("config-save-failed", &args)
+    }
+
+    pub fn config_export_failed(&self, path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, path.render());
+        translate_args("config-export-failed", &args)
+    }
+
     pub fn manifest_is_invalid(&self, why: &str) -> String {
         format!("{}\n{}", translate("manifest-is-invalid"), why)
     }
@@ -366,18 +941,143 @@ impl Translator {
         translate("manifest-cannot-be-updated")
     }
 
+    pub fn manifest_download_is_invalid(&self, why: &str) -> String {
+        format!("{}\n{}", translate("manifest-download-is-invalid"), why)
+    }
+
+    pub fn manifest_checksum_mismatch(&self) -> String {
+        translate("manifest-checksum-mismatch")
+    }
+
     pub fn cannot_prepare_backup_target(&self, target: &StrictPath) -> String {
         let mut args = FluentArgs::new();
         args.set(PATH, target.render());
         translate_args("cannot-prepare-backup-target", &args)
     }
 
+    pub fn backup_target_not_writable(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("backup-target-not-writable", &args)
+    }
+
+    pub fn unknown_backup(&self, game: &str, backup: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(GAME, game);
+        args.set(BACKUP, backup);
+        translate_args("unknown-backup", &args)
+    }
+
+    pub fn cannot_prune_orphan(&self, path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, path.render());
+        translate_args("cannot-prune-orphan", &args)
+    }
+
+    pub fn staged_restore_save_failed(&self, path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, path.render());
+        translate_args("staged-restore-save-failed", &args)
+    }
+
+    pub fn backup_target_has_locked_backups(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("backup-target-has-locked-backups", &args)
+    }
+
+    pub fn backup_target_invalid(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("backup-target-invalid", &args)
+    }
+
+    pub fn backup_target_inside_root(&self, target: &StrictPath, root: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        args.set(ROOT, root.render());
+        translate_args("backup-target-inside-root", &args)
+    }
+
     pub fn restoration_source_is_invalid(&self, source: &StrictPath) -> String {
         let mut args = FluentArgs::new();
         args.set(PATH, source.render());
         translate_args("restoration-source-is-invalid", &args)
     }
 
+    pub fn restoration_target_not_writable(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("restoration-target-not-writable", &args)
+    }
+
+    pub fn backup_migration_failed(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("backup-migration-failed", &args)
+    }
+
+    pub fn command_failed(&self, command: &str, output: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(COMMAND, command);
+        format!("{}\n\n{}", translate_args("command-failed", &args), output)
+    }
+
+    pub fn operation_disabled(&self, restoring: bool) -> String {
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, if restoring { "restore" } else { "backup" });
+        translate_args("operation-disabled", &args)
+    }
+
+    pub fn gui_unavailable(&self, why: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(REASON, why);
+        translate_args("gui-unavailable", &args)
+    }
+
+    pub fn read_only_mode(&self) -> String {
+        translate("read-only-mode")
+    }
+
+    pub fn mode_disabled_screen(&self, restoring: bool) -> String {
+        translate(if restoring {
+            "mode-restore-disabled"
+        } else {
+            "mode-backup-disabled"
+        })
+    }
+
+    pub fn sftp_transfer_failed(&self, target: &str, output: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target);
+        format!("{}\n\n{}", translate_args("sftp-transfer-failed", &args), output)
+    }
+
+    pub fn backup_migrated(&self, target: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, target.render());
+        translate_args("backup-migrated", &args)
+    }
+
+    pub fn tested_restore(&self, total: usize, failed: &[(String, usize)]) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, total);
+        args.set(PROCESSED_GAMES, total - failed.len());
+        if failed.is_empty() {
+            return translate_args("test-restore-passed", &args);
+        }
+
+        let mut out = translate_args("test-restore-failed", &args);
+        for (game, files) in failed {
+            let mut args = FluentArgs::new();
+            args.set(GAME, game.as_str());
+            args.set(TOTAL_FILES, *files);
+            out.push('\n');
+            out.push_str(&translate_args("test-restore-failed-game", &args));
+        }
+        out
+    }
+
     pub fn registry_issue(&self) -> String {
         translate("registry-issue")
     }
@@ -390,6 +1090,10 @@ impl Translator {
         format!("{}\n\n{}", translate("unable-to-open-directory"), path.render())
     }
 
+    pub fn unable_to_open_file(&self, path: &StrictPath) -> String {
+        format!("{}\n\n{}", translate("unable-to-open-file"), path.render())
+    }
+
     pub fn unable_to_open_url(&self, url: &str) -> String {
         format!("{}\n\n{}", translate("unable-to-open-url"), url)
     }
@@ -397,7 +1101,96 @@ impl Translator {
     pub fn adjusted_size(&self, bytes: u64) -> String {
         let byte = Byte::from_bytes(bytes.into());
         let adjusted_byte = byte.get_appropriate_unit(true);
-        adjusted_byte.to_string()
+        adjusted_byte
+            .to_string()
+            .replace('.', Language::English.decimal_separator())
+    }
+
+    /// A calendar date in the local time zone.
+    pub fn date(&self, when: &chrono::DateTime<chrono::Utc>) -> String {
+        use chrono::Datelike;
+
+        let local = when.with_timezone(&chrono::Local);
+        let mut args = FluentArgs::new();
+        args.set(MONTH, local.month());
+        // These are passed as text so that the year isn't grouped like a normal number.
+        args.set(DAY, local.day().to_string());
+        args.set(YEAR, local.year().to_string());
+        translate_args("date", &args)
+    }
+
+    /// A calendar date and time of day in the local time zone.
+    pub fn date_time(&self, when: &chrono::DateTime<chrono::Utc>) -> String {
+        format!(
+            "{} {}",
+            self.date(when),
+            when.with_timezone(&chrono::Local).format("%H:%M")
+        )
+    }
+
+    pub fn scan_progress_activity(&self, game: &str, files: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(GAME, game);
+        args.set(TOTAL_FILES, files);
+        translate_args("activity-scanning", &args)
+    }
+
+    pub fn backup_activity(&self, game: &str, files: usize, preview: bool) -> String {
+        let mut args = FluentArgs::new();
+        args.set(GAME, game);
+        args.set(TOTAL_FILES, files);
+        translate_args(
+            if preview {
+                "activity-scanned"
+            } else {
+                "activity-backed-up"
+            },
+            &args,
+        )
+    }
+
+    pub fn status_bar(
+        &self,
+        operation: &OngoingOperation,
+        processed: usize,
+        total: usize,
+        elapsed: std::time::Duration,
+    ) -> String {
+        let secs = elapsed.as_secs();
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, Self::ongoing_operation_key(operation));
+        args.set(PROCESSED_GAMES, processed);
+        args.set(TOTAL_GAMES, total);
+        args.set(
+            ELAPSED,
+            format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60),
+        );
+        translate_args("status-bar", &args)
+    }
+
+    /// Shown in the window title so that progress is visible from the taskbar.
+    pub fn window_title_progress(&self, operation: &OngoingOperation, processed: usize, total: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(OPERATION, Self::ongoing_operation_key(operation));
+        args.set(PROCESSED_GAMES, processed);
+        args.set(TOTAL_GAMES, total);
+        format!(
+            "{} - {}",
+            translate_args("window-title-progress", &args),
+            self.window_title()
+        )
+    }
+
+    fn ongoing_operation_key(operation: &OngoingOperation) -> &'static str {
+        match operation {
+            OngoingOperation::Backup => "backup",
+            OngoingOperation::PreviewBackup | OngoingOperation::PreviewRestore => "preview",
+            OngoingOperation::Restore => "restore",
+            OngoingOperation::CancelBackup
+            | OngoingOperation::CancelPreviewBackup
+            | OngoingOperation::CancelRestore
+            | OngoingOperation::CancelPreviewRestore => "cancel",
+        }
     }
 
     pub fn processed_games(&self, status: &OperationStatus) -> String {
@@ -438,14 +1231,46 @@ impl Translator {
         translate("toggle-backup-merge")
     }
 
+    pub fn backup_encrypt_label(&self) -> String {
+        translate("toggle-backup-encrypt")
+    }
+
     pub fn restore_source_label(&self) -> String {
         translate("field-restore-source")
     }
 
+    pub fn restore_only_changed_label(&self) -> String {
+        translate("toggle-restore-only-changed")
+    }
+
+    pub fn link_paths_label(&self) -> String {
+        translate("toggle-link-paths")
+    }
+
+    pub fn restore_stage_label(&self) -> String {
+        translate("toggle-restore-stage")
+    }
+
+    pub fn restore_mirror_label(&self) -> String {
+        translate("toggle-restore-mirror")
+    }
+
+    pub fn files_label(&self) -> String {
+        translate("toggle-files")
+    }
+
+    pub fn registry_label(&self) -> String {
+        translate("toggle-registry")
+    }
+
     pub fn custom_files_label(&self) -> String {
         translate("field-custom-files")
     }
 
+    pub fn registry_section_label(&self) -> String {
+        translate("registry-section")
+    }
+
     pub fn custom_registry_label(&self) -> String {
         translate("field-custom-registry")
     }
@@ -454,10 +1279,22 @@ impl Translator {
         translate("field-search")
     }
 
+    pub fn stale_only_label(&self) -> String {
+        translate("field-stale-only")
+    }
+
+    pub fn never_backed_up_only_label(&self) -> String {
+        translate("field-never-backed-up-only")
+    }
+
     pub fn sort_label(&self) -> String {
         translate("field-sort")
     }
 
+    pub fn store_label(&self) -> String {
+        translate("field-store")
+    }
+
     pub fn store(&self, store: &Store) -> String {
         translate(match store {
             Store::Epic => "store-epic",
@@ -474,6 +1311,10 @@ impl Translator {
         })
     }
 
+    pub fn all_stores(&self) -> String {
+        translate("store-all")
+    }
+
     pub fn sort_key(&self, key: &SortKey) -> String {
         translate(match key {
             SortKey::Name => "sort-name",
@@ -485,6 +1326,10 @@ impl Translator {
         translate("sort-reversed")
     }
 
+    pub fn passphrase_placeholder(&self) -> String {
+        translate("field-passphrase.placeholder")
+    }
+
     pub fn redirect_source_placeholder(&self) -> String {
         translate("field-redirect-source.placeholder")
     }
@@ -501,6 +1346,14 @@ impl Translator {
         translate("field-search-game-name.placeholder")
     }
 
+    pub fn quick_backup_placeholder(&self) -> String {
+        translate("field-quick-backup.placeholder")
+    }
+
+    pub fn quick_backup_no_matches(&self) -> String {
+        translate("quick-backup-no-matches")
+    }
+
     pub fn explanation_for_exclude_other_os_data(&self) -> String {
         translate("explanation-for-exclude-other-os-data")
     }
@@ -509,6 +1362,30 @@ impl Translator {
         translate("explanation-for-exclude-store-screenshots")
     }
 
+    pub fn explanation_for_skip_hidden(&self) -> String {
+        translate("explanation-for-skip-hidden")
+    }
+
+    pub fn explanation_for_steam_account_settings(&self) -> String {
+        translate("explanation-for-steam-account-settings")
+    }
+
+    pub fn config_file_label(&self) -> String {
+        translate("field-config-file")
+    }
+
+    pub fn migrate_backup_label(&self) -> String {
+        translate("field-migrate-backup")
+    }
+
+    pub fn migration_source_placeholder(&self) -> String {
+        translate("field-migration-source.placeholder")
+    }
+
+    pub fn migration_target_placeholder(&self) -> String {
+        translate("field-migration-target.placeholder")
+    }
+
     pub fn ignored_items_label(&self) -> String {
         translate("field-backup-excluded-items")
     }
@@ -535,9 +1412,120 @@ impl Translator {
         translate_args("confirm-backup", &args)
     }
 
+    pub fn modal_backup_commands(&self, commands: &[&str]) -> String {
+        let lines: Vec<_> = commands.iter().map(|x| format!("  {}", x)).collect();
+        format!("{}\n{}", translate("confirm-backup-commands"), lines.join("\n"))
+    }
+
     pub fn modal_confirm_restore(&self, source: &StrictPath) -> String {
         let mut args = FluentArgs::new();
         args.set(PATH, source.render());
         translate_args("confirm-restore", &args)
     }
+
+    pub fn modal_registry_changes(&self, changes: &[RegistryChange]) -> String {
+        let lines: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                let mut args = FluentArgs::new();
+                args.set(
+                    CHANGE,
+                    match change.kind {
+                        RegistryChangeKind::Added => "added",
+                        RegistryChangeKind::Changed => "changed",
+                        RegistryChangeKind::Removed => "removed",
+                    },
+                );
+                args.set(PATH, format!("{}/{}", change.key.render(), change.value));
+                translate_args("registry-change", &args)
+            })
+            .collect();
+        format!("{}\n\n{}", translate("confirm-registry-changes"), lines.join("\n"))
+    }
+
+    pub fn deletion_preview(&self, deletions: &DeletionPreview) -> String {
+        let lines: Vec<_> = deletions
+            .entries
+            .iter()
+            .map(|(path, bytes)| format!("{} ({})", path.render(), self.adjusted_size(*bytes)))
+            .collect();
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_SIZE, self.adjusted_size(deletions.total_bytes()));
+        format!("{}\n\n{}", translate_args("confirm-deletions", &args), lines.join("\n"))
+    }
+
+    pub fn backup_comparison(&self, comparison: &crate::layout::BackupComparison) -> String {
+        if comparison.is_empty() {
+            return translate("no-backup-changes");
+        }
+        let mut lines = vec![];
+        lines.extend(comparison.added.iter().map(|x| format!("+ {}", x)));
+        lines.extend(comparison.removed.iter().map(|x| format!("- {}", x)));
+        lines.extend(comparison.changed.iter().map(|x| format!("~ {}", x)));
+        lines.join("\n")
+    }
+
+    pub fn prune_orphans_button(&self) -> String {
+        translate("button-prune-orphans")
+    }
+
+    pub fn no_orphaned_backups(&self) -> String {
+        translate("no-orphaned-backups")
+    }
+
+    pub fn modal_confirm_prune_orphans(&self, deletions: &DeletionPreview) -> String {
+        format!(
+            "{}\n\n{}",
+            translate("confirm-prune-orphans"),
+            self.deletion_preview(deletions)
+        )
+    }
+
+    pub fn cli_confirm_deletions(&self) -> String {
+        translate("cli-confirm-deletions")
+    }
+
+    pub fn modal_restore_mirror(&self) -> String {
+        translate("confirm-restore-mirror")
+    }
+
+    pub fn modal_restore_conflicts(&self, files: usize) -> String {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_FILES, files);
+        translate_args("confirm-restore-conflicts", &args)
+    }
+
+    pub fn modal_confirm_apply_staged_restore(&self, staging: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, staging.render());
+        translate_args("confirm-apply-staged-restore", &args)
+    }
+
+    pub fn modal_confirm_test_restore(&self, restore_path: &StrictPath) -> String {
+        let mut args = FluentArgs::new();
+        args.set(PATH, restore_path.render());
+        translate_args("confirm-test-restore", &args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_group_integers() {
+        assert_eq!("0", Language::English.format_integer(0));
+        assert_eq!("999", Language::English.format_integer(999));
+        assert_eq!("1,000", Language::English.format_integer(1000));
+        assert_eq!("1,234,567", Language::English.format_integer(1234567));
+        assert_eq!("-12,345", Language::English.format_integer(-12345));
+    }
+
+    #[test]
+    fn can_group_numbers_in_messages() {
+        let mut args = FluentArgs::new();
+        args.set(TOTAL_GAMES, 12345);
+        assert_eq!("12,345 games", translate_args("processed-games", &args));
+    }
 }
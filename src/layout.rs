@@ -3,18 +3,36 @@ use std::collections::{HashSet, VecDeque};
 use chrono::{Datelike, Timelike};
 
 use crate::{
-    config::Retention,
+    config::{BackupFolderLayout, BackupFormat, Retention},
+    encryption::{Cipher, EncryptionInfo},
+    manifest::Store,
     path::StrictPath,
-    prelude::{BackupInfo, ScanInfo, ScannedFile, ScannedRegistry},
+    prelude::{
+        are_files_identical, copy_file, ensure_writable, is_read_only, retry_io, BackupInfo, CopyOptions,
+        DeletionPreview, Error, FailureCategory, ScanInfo, ScannedFile, ScannedRegistry, IO_THROTTLE, RATE_LIMIT,
+    },
 };
 
 const SAFE: &str = "_";
+const ARCHIVE_FILE: &str = "backup.tar.gz";
+const MAPPING_FILE: &str = "mapping.yaml";
+const SPLIT_CONTENT_FOLDER: &str = "files";
+const SPLIT_META_FOLDER: &str = "meta";
+/// Game folder names never start with a dot, so this can't collide with one.
+const DEDUPE_STORE_FOLDER: &str = ".dedupe";
 
 fn encode_base64_for_folder(name: &str) -> String {
     base64::encode(&name).replace('/', SAFE)
 }
 
-fn escape_folder_name(name: &str) -> String {
+fn store_folder_name(store: Store) -> String {
+    match serde_yaml::to_value(store) {
+        Ok(serde_yaml::Value::String(name)) => name,
+        _ => "other".to_string(),
+    }
+}
+
+pub fn escape_folder_name(name: &str) -> String {
     let mut escaped = String::from(name);
 
     // Technically, dots should be fine as long as the folder name isn't
@@ -49,6 +67,12 @@ fn escape_folder_name(name: &str) -> String {
 pub struct FullBackup {
     pub name: String,
     pub when: chrono::DateTime<chrono::Utc>,
+    /// The machine that made this backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Locked backups are kept regardless of retention and don't count toward it.
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub locked: bool,
     pub children: Vec<DifferentialBackup>,
 }
 
@@ -75,6 +99,9 @@ pub struct BackupOmission {
 pub struct DifferentialBackup {
     pub name: String,
     pub when: chrono::DateTime<chrono::Utc>,
+    /// The machine that made this backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
     pub omit: BackupOmission,
 }
 
@@ -88,14 +115,275 @@ impl DifferentialBackup {
     }
 }
 
+/// How the files in one backup differ from those in an older backup of the same game.
+/// Files are identified by their original paths in rendered form.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize)]
+pub struct BackupComparison {
+    #[serde(skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub added: std::collections::BTreeSet<String>,
+    #[serde(skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub removed: std::collections::BTreeSet<String>,
+    #[serde(skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub changed: std::collections::BTreeSet<String>,
+}
+
+impl BackupComparison {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Context about a backup for display before restoring from it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SourceBackup {
+    pub when: Option<chrono::DateTime<chrono::Utc>>,
+    pub host: Option<String>,
+}
+
+/// Properties of an original file at the time it was backed up,
+/// so that they can be reapplied when restoring.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FileMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+    /// Unix permission bits. This is not tracked on Windows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+impl FileMetadata {
+    pub fn read(file: &StrictPath) -> Self {
+        let metadata = match file.metadata() {
+            Ok(x) => x,
+            Err(_) => return Self::default(),
+        };
+
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(metadata.permissions().mode())
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
+        Self {
+            modified: metadata.modified().ok().map(chrono::DateTime::<chrono::Utc>::from),
+            mode,
+        }
+    }
+
+    pub fn apply(&self, file: &StrictPath) -> std::io::Result<()> {
+        ensure_writable()?;
+        // Setting the modified time only requires owning the file, not write access,
+        // which matters because the restored copy may already be read-only.
+        if let Some(modified) = self.modified {
+            #[cfg(windows)]
+            let handle = {
+                use std::os::windows::fs::OpenOptionsExt;
+                const FILE_WRITE_ATTRIBUTES: u32 = 0x100;
+                std::fs::OpenOptions::new()
+                    .access_mode(FILE_WRITE_ATTRIBUTES)
+                    .open(file.interpret())?
+            };
+            #[cfg(not(windows))]
+            let handle = std::fs::File::open(file.interpret())?;
+            handle.set_modified(modified.into())?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = self.mode {
+                std::fs::set_permissions(file.interpret(), std::fs::Permissions::from_mode(mode))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn folder_size(folder: &StrictPath) -> u64 {
+    walkdir::WalkDir::new(folder.interpret())
+        .into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_file())
+        .filter_map(|x| x.metadata().ok())
+        .map(|x| x.len())
+        .sum()
+}
+
+/// Pack a folder's contents into a new `.tar.gz` file, replacing any old one.
+fn pack_archive(source: &StrictPath, archive: &StrictPath) -> std::io::Result<()> {
+    ensure_writable()?;
+    std::fs::create_dir_all(source.interpret())?;
+    archive.create_parent_dir()?;
+
+    let temp = StrictPath::new(format!("{}.tmp", archive.interpret()));
+    let file = std::fs::File::create(temp.interpret())?;
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", source.interpret())?;
+    builder.into_inner()?.finish()?.sync_all()?;
+
+    std::fs::rename(temp.interpret(), archive.interpret())
+}
+
+/// Unpack a `.tar.gz` file, keeping the permissions and modified times
+/// recorded in the archive.
+fn unpack_archive(archive: &StrictPath, target: &StrictPath) -> std::io::Result<()> {
+    ensure_writable()?;
+    let file = std::fs::File::open(archive.interpret())?;
+    let mut unpacker = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    unpacker.set_preserve_permissions(true);
+    unpacker.set_preserve_mtime(true);
+    std::fs::create_dir_all(target.interpret())?;
+    unpacker.unpack(target.interpret())
+}
+
+/// Whether a path is a whole backup folder packed into one `.tar.gz` file.
+fn is_backup_archive(path: &StrictPath) -> bool {
+    let name = path.render().to_lowercase();
+    path.is_file() && (name.ends_with(".tar.gz") || name.ends_with(".tgz"))
+}
+
+/// Get the backup folder to restore from. Besides a folder, the source may be
+/// a `.tar.gz` file of a whole backup folder, which is unpacked first.
+/// In read-only mode, this fails unless an up-to-date unpacked copy already exists.
+pub fn restoration_source(path: &StrictPath) -> Result<StrictPath, Error> {
+    let invalid = || Error::RestorationSourceInvalid { path: path.clone() };
+    if path.is_blank_or_root() {
+        return Err(invalid());
+    }
+    if path.is_dir() {
+        return Ok(path.clone());
+    }
+    if !is_backup_archive(path) {
+        return Err(invalid());
+    }
+
+    let scratch = GameLayout::archive_scratch(path);
+    let stamp_file = GameLayout::archive_stamp_file(&scratch);
+    let stamp = GameLayout::archive_stamp(path);
+    if stamp.is_none() || !scratch.is_dir() || std::fs::read_to_string(stamp_file.interpret()).ok() != stamp {
+        if is_read_only() {
+            return Err(Error::ReadOnlyMode);
+        }
+        let _ = scratch.remove();
+        let _ = stamp_file.remove();
+        unpack_archive(path, &scratch).map_err(|_| invalid())?;
+        if let Some(stamp) = stamp {
+            let _ = std::fs::write(stamp_file.interpret(), stamp);
+        }
+    }
+
+    // Archives often hold the backup folder itself rather than just its contents.
+    let entries: Vec<_> = std::fs::read_dir(scratch.interpret())
+        .map(|x| x.filter_map(|e| e.ok()).collect())
+        .unwrap_or_default();
+    if let [entry] = entries.as_slice() {
+        let inner = StrictPath::from(entry.path());
+        if inner.is_dir() && !inner.joined(MAPPING_FILE).is_file() {
+            return Ok(inner);
+        }
+    }
+    Ok(scratch)
+}
+
 fn default_backup_list() -> VecDeque<FullBackup> {
     VecDeque::from(vec![FullBackup {
         name: ".".to_string(),
         when: chrono::Utc::now(),
+        host: None,
+        locked: false,
         ..Default::default()
     }])
 }
 
+/// A file entry in the upstream Ludusavi mapping format.
+/// Only its presence matters here, not its hash or size.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UpstreamMappingFile {}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UpstreamMappingRegistry {
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UpstreamDifferentialBackup {
+    name: String,
+    #[serde(default)]
+    when: Option<chrono::DateTime<chrono::Utc>>,
+    /// A null entry means that the file was removed since the full backup.
+    #[serde(default)]
+    files: std::collections::HashMap<String, Option<UpstreamMappingFile>>,
+    /// A null hash means that the registry was removed since the full backup.
+    #[serde(default)]
+    registry: Option<UpstreamMappingRegistry>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UpstreamFullBackup {
+    name: String,
+    #[serde(default)]
+    when: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    children: Vec<UpstreamDifferentialBackup>,
+}
+
+/// The mapping file as written by upstream Ludusavi, which records
+/// differential backups by their changed files instead of their omissions.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UpstreamMapping {
+    name: String,
+    drives: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    backups: Option<VecDeque<UpstreamFullBackup>>,
+}
+
+impl From<UpstreamMapping> for IndividualMapping {
+    fn from(upstream: UpstreamMapping) -> Self {
+        let backups = match upstream.backups {
+            Some(backups) => backups
+                .into_iter()
+                .map(|full| FullBackup {
+                    name: full.name,
+                    when: full.when.unwrap_or_default(),
+                    host: None,
+                    locked: false,
+                    children: full
+                        .children
+                        .into_iter()
+                        .map(|diff| DifferentialBackup {
+                            name: diff.name,
+                            when: diff.when.unwrap_or_default(),
+                            host: None,
+                            omit: BackupOmission {
+                                files: diff
+                                    .files
+                                    .into_iter()
+                                    .filter(|(_, v)| v.is_none())
+                                    .map(|(k, _)| k)
+                                    .collect(),
+                                registry: diff.registry.map(|x| x.hash.is_none()).unwrap_or(false),
+                            },
+                        })
+                        .collect(),
+                })
+                .collect(),
+            None => default_backup_list(),
+        };
+
+        Self {
+            name: upstream.name,
+            drives: upstream.drives,
+            backups,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct IndividualMapping {
     pub name: String,
@@ -103,6 +391,29 @@ pub struct IndividualMapping {
     pub drives: std::collections::HashMap<String, String>,
     #[serde(default = "default_backup_list")]
     pub backups: VecDeque<FullBackup>,
+    /// Keys are original file paths in rendered form.
+    #[serde(
+        default,
+        serialize_with = "crate::serialization::ordered_map",
+        skip_serializing_if = "std::collections::HashMap::is_empty"
+    )]
+    pub metadata: std::collections::HashMap<String, FileMetadata>,
+    /// For deduplicated files, the SHA-256 hash of their content in the store.
+    /// Keys are paths relative to the game's backup folder.
+    #[serde(
+        default,
+        serialize_with = "crate::serialization::ordered_map",
+        skip_serializing_if = "std::collections::HashMap::is_empty"
+    )]
+    pub objects: std::collections::HashMap<String, String>,
+    /// Recorded so that the game folder can be found again for restoration.
+    #[serde(default, skip_serializing_if = "BackupFolderLayout::is_default")]
+    pub layout: BackupFolderLayout,
+    /// Recorded so that the game folder is read back the same way it was written.
+    #[serde(default, skip_serializing_if = "BackupFormat::is_default")]
+    pub format: BackupFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
 }
 
 impl IndividualMapping {
@@ -152,29 +463,76 @@ impl IndividualMapping {
         full.map(|x| (x, x.children.last()))
     }
 
+    fn find_backup(&self, name: &str) -> Option<(&FullBackup, Option<&DifferentialBackup>)> {
+        self.backups.iter().find_map(|full| {
+            if full.name == name {
+                Some((full, None))
+            } else {
+                full.children
+                    .iter()
+                    .find(|diff| diff.name == name)
+                    .map(|diff| (full, Some(diff)))
+            }
+        })
+    }
+
+    pub fn last_backup_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.latest_backup()
+            .map(|(full, diff)| diff.map(|x| x.when).unwrap_or(full.when))
+    }
+
+    /// When and where the latest backup was made, as far as the mapping records.
+    /// Mappings from older versions may not have the machine, and mappings
+    /// converted from the upstream format may not have the time.
+    pub fn last_backup_source(&self) -> Option<SourceBackup> {
+        self.latest_backup().map(|(full, diff)| {
+            let (when, host) = match diff {
+                Some(diff) => (diff.when, &diff.host),
+                None => (full.when, &full.host),
+            };
+            SourceBackup {
+                when: (when.timestamp() != 0).then_some(when),
+                host: host.clone(),
+            }
+        })
+    }
+
+    /// Whether any recorded file has been modified since it was backed up.
+    /// This only checks the files listed in the mapping, so new files are not noticed.
+    pub fn has_changed_files(&self) -> bool {
+        self.metadata.iter().any(|(path, recorded)| match recorded.modified {
+            Some(recorded) => FileMetadata::read(&StrictPath::new(path.clone()))
+                .modified
+                .map(|current| current > recorded)
+                .unwrap_or(false),
+            None => false,
+        })
+    }
+
     fn latest_full_backup_mut(&mut self) -> Option<&mut FullBackup> {
         self.backups.back_mut()
     }
 
-    pub fn save(&self, file: &StrictPath) {
+    pub fn save(&self, file: &StrictPath) -> std::io::Result<()> {
+        ensure_writable()?;
         let new_content = serde_yaml::to_string(&self).unwrap();
 
         if let Ok(old) = Self::load(file) {
             let old_content = serde_yaml::to_string(&old).unwrap();
             if old_content == new_content {
-                return;
+                return Ok(());
             }
         }
 
-        if file.create_parent_dir().is_ok() {
-            std::fs::write(file.interpret(), self.serialize().as_bytes()).unwrap();
-        }
+        file.create_parent_dir()?;
+        std::fs::write(file.interpret(), new_content.as_bytes())
     }
 
     pub fn serialize(&self) -> String {
         serde_yaml::to_string(&self).unwrap()
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn load(file: &StrictPath) -> Result<Self, ()> {
         if !file.is_file() {
             return Err(());
@@ -183,13 +541,39 @@ impl IndividualMapping {
         Self::load_from_string(&content)
     }
 
+    /// Mappings from upstream Ludusavi are converted if they don't fit this format.
+    #[allow(clippy::result_unit_err)]
     pub fn load_from_string(content: &str) -> Result<Self, ()> {
         match serde_yaml::from_str(content) {
             Ok(x) => Ok(x),
-            Err(_) => Err(()),
+            Err(_) => match serde_yaml::from_str::<UpstreamMapping>(content) {
+                Ok(x) => Ok(x.into()),
+                Err(_) => Err(()),
+            },
+        }
+    }
+
+    /// Lock or unlock a backup. Differential backups depend on their full backup,
+    /// so naming a differential backup locks the full backup that it belongs to.
+    /// Returns false if there is no such backup.
+    pub fn set_locked(&mut self, name: &str, locked: bool) -> bool {
+        match self
+            .backups
+            .iter_mut()
+            .find(|full| full.name == name || full.children.iter().any(|diff| diff.name == name))
+        {
+            Some(full) => {
+                full.locked = locked;
+                true
+            }
+            None => false,
         }
     }
 
+    pub fn has_locked_backup(&self) -> bool {
+        self.backups.iter().any(|x| x.locked)
+    }
+
     pub fn has_backup(&self, name: &str) -> bool {
         self.backups
             .iter()
@@ -228,6 +612,79 @@ impl IndividualMapping {
     }
 }
 
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// The full SHA-256 of some content, as lowercase hex.
+pub(crate) fn hash_bytes(bytes: &[u8]) -> String {
+    use sha2::Digest;
+    to_hex(&sha2::Sha256::digest(bytes))
+}
+
+/// The full SHA-256 of a file's content, as lowercase hex.
+pub(crate) fn hash_file(file: &StrictPath) -> std::io::Result<String> {
+    use sha2::Digest;
+    use std::io::Read;
+
+    let mut hasher = sha2::Sha256::new();
+    let mut reader = std::fs::File::open(file.interpret())?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn dedupe_object(store: &StrictPath, hash: &str) -> StrictPath {
+    store.joined(&hash[..2]).joined(hash)
+}
+
+/// Store a file's content once in the deduplication store, keyed by its hash,
+/// and hard link the backup file to it. If the link can't be made (e.g., the store
+/// is on another drive), the content is copied instead. Returns the hash.
+fn store_deduplicated(
+    source: &StrictPath,
+    target: &StrictPath,
+    store: &StrictPath,
+    copy_options: &CopyOptions,
+) -> std::io::Result<String> {
+    ensure_writable()?;
+    let mut hash = hash_file(source)?;
+    let mut object = dedupe_object(store, &hash);
+    if !object.is_file() {
+        // Hash what was actually copied, in case the source changed in the meantime.
+        let incoming = store.joined(&format!("incoming-{}", hash_text(&target.interpret())));
+        incoming.create_parent_dir()?;
+        copy_file(source, &incoming, copy_options)?;
+        hash = hash_file(&incoming)?;
+        object = dedupe_object(store, &hash);
+        object.create_parent_dir()?;
+        if object.is_file() {
+            std::fs::remove_file(incoming.interpret())?;
+        } else {
+            std::fs::rename(incoming.interpret(), object.interpret())?;
+        }
+    }
+
+    if target.exists() {
+        std::fs::remove_file(target.interpret())?;
+    }
+    if std::fs::hard_link(object.interpret(), target.interpret()).is_err() {
+        copy_file(&object, target, copy_options)?;
+    }
+    Ok(hash)
+}
+
+/// A short, stable name derived from some text, for use in file names.
+pub(crate) fn hash_text(text: &str) -> String {
+    hash_bytes(text.as_bytes())[..16].to_string()
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 struct BackupPlan {
     kind: BackupKind,
@@ -243,26 +700,262 @@ pub struct GameLayout {
     mapping: IndividualMapping,
     #[allow(dead_code)]
     retention: Retention,
+    /// Only set once the passphrase has been checked against the mapping.
+    cipher: Option<Cipher>,
+    /// For a `tar.gz` backup, this is the real game folder,
+    /// while `path` is where the archive has been unpacked.
+    archive: Option<StrictPath>,
+    /// The archive exists, but could not be unpacked.
+    archive_unreadable: bool,
+    /// Where to keep a single copy of each unique file, if deduplicating.
+    dedupe_store: Option<StrictPath>,
 }
 
 impl GameLayout {
+    #[allow(clippy::result_unit_err)]
     pub fn load(path: StrictPath, retention: Retention) -> Result<Self, ()> {
         let mapping = Self::mapping_file(&path);
         Ok(Self {
             path,
             mapping: IndividualMapping::load(&mapping)?,
             retention,
+            ..Default::default()
         })
     }
 
-    pub fn save(&self) {
-        self.mapping.save(&Self::mapping_file(&self.path))
+    pub fn save(&self) -> std::io::Result<()> {
+        let folder = self.archive.as_ref().unwrap_or(&self.path);
+        let file = match self.mapping.format {
+            BackupFormat::Split => folder.joined(SPLIT_META_FOLDER).joined(MAPPING_FILE),
+            _ => folder.joined(MAPPING_FILE),
+        };
+        self.mapping.save(&file)
+    }
+
+    /// Where the backed up files are stored. For the split format, this is
+    /// kept apart from the metadata so that each can be synced on its own.
+    fn content(&self) -> StrictPath {
+        match self.mapping.format {
+            BackupFormat::Split => self.path.joined(SPLIT_CONTENT_FOLDER),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// Where a game's archive is unpacked. This is reused between a scan and
+    /// the following restore so that the scanned file paths are still valid,
+    /// then removed so that unpacked copies don't pile up in the temp folder.
+    fn archive_scratch(folder: &StrictPath) -> StrictPath {
+        StrictPath::from(
+            std::env::temp_dir()
+                .join("ludusavi-archives")
+                .join(hash_text(&folder.interpret())),
+        )
+    }
+
+    fn archive_stamp_file(scratch: &StrictPath) -> StrictPath {
+        StrictPath::new(format!("{}.stamp", scratch.interpret()))
+    }
+
+    /// Identifies a version of the archive, so that an unpacked copy can be reused.
+    fn archive_stamp(archive: &StrictPath) -> Option<String> {
+        let metadata = archive.metadata().ok()?;
+        let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+        Some(format!("{}-{}", metadata.len(), modified.as_nanos()))
+    }
+
+    /// Switch to reading and writing an unpacked copy of the game's archive.
+    fn use_archive(&mut self) {
+        let folder = self.path.clone();
+        let archive = folder.joined(ARCHIVE_FILE);
+        self.path = Self::archive_scratch(&folder);
+        self.archive = Some(folder);
+
+        let stamp_file = Self::archive_stamp_file(&self.path);
+        let stamp = Self::archive_stamp(&archive);
+        if stamp.is_some() && self.path.is_dir() && std::fs::read_to_string(stamp_file.interpret()).ok() == stamp {
+            return;
+        }
+
+        let _ = self.path.remove();
+        let _ = stamp_file.remove();
+        if let Some(stamp) = stamp {
+            match unpack_archive(&archive, &self.path) {
+                Ok(()) => {
+                    let _ = std::fs::write(stamp_file.interpret(), stamp);
+                }
+                Err(_) => {
+                    self.archive_unreadable = true;
+                }
+            }
+        }
+    }
+
+    /// Write the unpacked copy back into the game's archive.
+    fn pack(&self) -> std::io::Result<()> {
+        let folder = match &self.archive {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let archive = folder.joined(ARCHIVE_FILE);
+        pack_archive(&self.path, &archive)?;
+        if let Some(stamp) = Self::archive_stamp(&archive) {
+            let _ = std::fs::write(Self::archive_stamp_file(&self.path).interpret(), stamp);
+        }
+        Ok(())
+    }
+
+    /// Remove the unpacked copy of the game's archive, if any.
+    pub fn remove_archive_scratch(&self) {
+        if self.archive.is_some() {
+            let _ = self.path.remove();
+            let _ = Self::archive_stamp_file(&self.path).remove();
+        }
+    }
+
+    pub fn file_metadata(&self, original: &StrictPath) -> Option<&FileMetadata> {
+        self.mapping.metadata.get(&original.render())
+    }
+
+    /// Whether the user has locked any of this game's backups against pruning.
+    pub fn has_locked_backup(&self) -> bool {
+        self.mapping.has_locked_backup()
+    }
+
+    /// The backup is encrypted, but the passphrase was missing or wrong,
+    /// or the backup is archived, but the archive could not be read.
+    pub fn is_locked(&self) -> bool {
+        self.archive_unreadable || (self.mapping.encryption.is_some() && self.cipher.is_none())
+    }
+
+    /// Encrypted files are identified by their path relative to the game folder.
+    fn encryption_key(&self, stored: &StrictPath) -> String {
+        let base = self.content().interpret().replace('\\', "/");
+        let stored = stored.interpret().replace('\\', "/");
+        stored
+            .strip_prefix(&base)
+            .unwrap_or(&stored)
+            .split('/')
+            .filter(|x| !x.is_empty() && *x != ".")
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn stored_nonce(&self, stored: &StrictPath) -> Option<Vec<u8>> {
+        self.mapping.encryption.as_ref()?.nonce(&self.encryption_key(stored))
+    }
+
+    pub fn is_encrypted(&self, stored: &StrictPath) -> bool {
+        self.stored_nonce(stored).is_some()
+    }
+
+    /// Read a backed up file, decrypting it if necessary.
+    pub fn read_stored(&self, stored: &StrictPath) -> std::io::Result<Vec<u8>> {
+        let content = std::fs::read(stored.interpret())?;
+        match self.stored_nonce(stored) {
+            None => Ok(content),
+            Some(nonce) => self
+                .cipher
+                .as_ref()
+                .and_then(|cipher| cipher.decrypt(&nonce, &content))
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "unable to decrypt file")),
+        }
+    }
+
+    /// Compare a backed up file with another file, decrypting it if necessary.
+    pub fn stored_identical(
+        &self,
+        stored: &StrictPath,
+        other: &StrictPath,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.stored_nonce(stored).is_none() {
+            return are_files_identical(stored, other);
+        }
+        Ok(self.read_stored(stored)? == std::fs::read(other.interpret())?)
+    }
+
+    fn stored_same_content(&self, stored: &StrictPath, other: &StrictPath) -> bool {
+        if self.stored_nonce(stored).is_none() {
+            return stored.same_content(other);
+        }
+        self.stored_identical(stored, other).unwrap_or(false)
+    }
+
+    /// Write a backed up file somewhere else, decrypting it if necessary.
+    pub fn restore_stored(&self, stored: &StrictPath, target: &StrictPath) -> std::io::Result<()> {
+        if self.stored_nonce(stored).is_none() {
+            return copy_file(stored, target, &CopyOptions::default());
+        }
+        ensure_writable()?;
+        let content = self.read_stored(stored)?;
+        let _slot = IO_THROTTLE.acquire();
+        std::fs::write(target.interpret(), content)
+    }
+
+    /// Copy a file into the backup, encrypting it if there is a cipher.
+    fn write_stored(
+        &mut self,
+        source: &StrictPath,
+        target: &StrictPath,
+        copy_options: &CopyOptions,
+    ) -> std::io::Result<()> {
+        ensure_writable()?;
+        let key = self.encryption_key(target);
+        if self.mapping.objects.remove(&key).is_some() {
+            // The old file is shared with the store and other backups, so it must not be written through.
+            let _ = target.remove();
+        }
+        match (&self.cipher, self.mapping.encryption.as_mut()) {
+            (Some(cipher), Some(info)) => {
+                let content = std::fs::read(source.interpret())?;
+                let (nonce, encrypted) = cipher
+                    .encrypt(&content)
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "unable to encrypt file"))?;
+                let _slot = IO_THROTTLE.acquire();
+                std::fs::write(target.interpret(), &encrypted)?;
+                if let Some(limit) = copy_options.max_bytes_per_second {
+                    RATE_LIMIT.consume(encrypted.len() as u64, limit);
+                }
+                info.nonces.insert(key, base64::encode(nonce));
+            }
+            (_, info) => {
+                match &self.dedupe_store {
+                    Some(store) => {
+                        let hash = store_deduplicated(source, target, store, copy_options)?;
+                        self.mapping.objects.insert(key.clone(), hash);
+                    }
+                    None => copy_file(source, target, copy_options)?,
+                }
+                if let Some(info) = info {
+                    info.nonces.remove(&key);
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn restorable_files(&self) -> std::collections::HashSet<ScannedFile> {
+        self.restorable_files_from(self.mapping.latest_backup())
+    }
+
+    /// Every file that any backup of this game contains.
+    pub fn previously_backed_up_files(&self) -> std::collections::HashSet<ScannedFile> {
         let mut files = std::collections::HashSet::new();
+        for full in &self.mapping.backups {
+            files.extend(self.restorable_files_from(Some((full, None))));
+            for diff in &full.children {
+                files.extend(self.restorable_files_from(Some((full, Some(diff)))));
+            }
+        }
+        files
+    }
 
-        match self.mapping.latest_backup() {
+    fn restorable_files_from(
+        &self,
+        backup: Option<(&FullBackup, Option<&DifferentialBackup>)>,
+    ) -> std::collections::HashSet<ScannedFile> {
+        let mut files = std::collections::HashSet::new();
+
+        match backup {
             None => {}
             Some((full, None)) => {
                 files.extend(self.restorable_files_in(&full.name));
@@ -288,9 +981,51 @@ impl GameLayout {
         files
     }
 
+    /// Compare what restoring each of two backups would produce.
+    /// Returns None if either backup does not exist.
+    pub fn compare_backups(&self, older: &str, newer: &str) -> Option<BackupComparison> {
+        let index = |backup| -> std::collections::HashMap<String, ScannedFile> {
+            self.restorable_files_from(Some(backup))
+                .into_iter()
+                .filter_map(|file| Some((file.original_path.as_ref()?.render(), file)))
+                .collect()
+        };
+        let older = index(self.mapping.find_backup(older)?);
+        let newer = index(self.mapping.find_backup(newer)?);
+
+        let mut comparison = BackupComparison::default();
+        for (original, newer_file) in &newer {
+            match older.get(original) {
+                None => {
+                    comparison.added.insert(original.clone());
+                }
+                Some(older_file) => {
+                    if older_file.path.same_path(&newer_file.path) {
+                        continue;
+                    }
+                    let same = older_file.size == newer_file.size
+                        && match (self.read_stored(&older_file.path), self.read_stored(&newer_file.path)) {
+                            (Ok(older_content), Ok(newer_content)) => older_content == newer_content,
+                            _ => false,
+                        };
+                    if !same {
+                        comparison.changed.insert(original.clone());
+                    }
+                }
+            }
+        }
+        for original in older.keys() {
+            if !newer.contains_key(original) {
+                comparison.removed.insert(original.clone());
+            }
+        }
+
+        Some(comparison)
+    }
+
     fn restorable_files_in(&self, backup: &str) -> std::collections::HashSet<ScannedFile> {
         let mut files = std::collections::HashSet::new();
-        for drive_dir in walkdir::WalkDir::new(self.path.joined(backup).interpret())
+        for drive_dir in walkdir::WalkDir::new(self.content().joined(backup).interpret())
             .max_depth(1)
             .follow_links(false)
             .into_iter()
@@ -343,7 +1078,7 @@ impl GameLayout {
 
     #[allow(dead_code)]
     pub fn registry_file_in(&self, backup: &str) -> StrictPath {
-        self.path.joined(backup).joined("registry.yaml")
+        self.content().joined(backup).joined("registry.yaml")
     }
 
     fn count_backups(&self) -> (u8, u8) {
@@ -352,7 +1087,36 @@ impl GameLayout {
         (full as u8, differential as u8)
     }
 
-    fn need_backup(&self, scan: &ScanInfo) -> bool {
+    /// Old backups that the next full backup would delete to stay within the retention limit.
+    /// When only one full backup is kept, it is replaced in place, so only its differential backups are listed.
+    /// Locked backups are never listed.
+    pub fn backups_to_prune(&self) -> Vec<StrictPath> {
+        let (fulls, diffs) = self.count_backups();
+        if fulls == 0 || diffs < self.retention.differential {
+            return vec![];
+        }
+
+        let unlocked = self.mapping.backups.iter().filter(|x| !x.locked).count();
+        let excess = (unlocked + 1).saturating_sub(self.retention.full as usize);
+        let content = self.content();
+        self.mapping
+            .backups
+            .iter()
+            .filter(|full| !full.locked)
+            .take(excess)
+            .flat_map(|full| {
+                std::iter::once(full.name.clone())
+                    .filter(|name| name != ".")
+                    .chain(full.children.iter().map(|diff| diff.name.clone()))
+            })
+            .map(|name| content.joined(&name))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Kinds of data that are left out (`files` or `registry` is false) are not compared,
+    /// since the previous backup's copy is carried forward.
+    fn need_backup(&self, scan: &ScanInfo, files: bool, registry: bool) -> bool {
         let mut mapping = self.mapping.clone();
 
         let (full, diff) = match mapping.latest_backup() {
@@ -360,15 +1124,33 @@ impl GameLayout {
             None => return true,
         };
 
+        if files && self.files_changed(&mut mapping, scan, &full, &diff) {
+            return true;
+        }
+
+        if registry && self.registry_changed(scan, &full, &diff) {
+            return true;
+        }
+
+        false
+    }
+
+    fn files_changed(
+        &self,
+        mapping: &mut IndividualMapping,
+        scan: &ScanInfo,
+        full: &FullBackup,
+        diff: &Option<DifferentialBackup>,
+    ) -> bool {
         // If scan contains new or changed files:
         for scanned in scan.found_files.iter().filter(|x| !x.ignored) {
-            if let Some(diff) = &diff {
-                let stored_diff = mapping.game_file(&self.path, &scanned.path, &diff.name);
+            if let Some(diff) = diff {
+                let stored_diff = mapping.game_file(&self.content(), &scanned.path, &diff.name);
 
                 if diff.omits_file(&scanned.path) {
                     return true;
                 } else if stored_diff.is_file() {
-                    if stored_diff.same_content(&scanned.path) {
+                    if self.stored_same_content(&stored_diff, &scanned.path) {
                         continue;
                     } else {
                         return true;
@@ -376,8 +1158,8 @@ impl GameLayout {
                 }
             }
 
-            let stored_full = mapping.game_file(&self.path, &scanned.path, &full.name);
-            if !stored_full.is_file() || !stored_full.same_content(&scanned.path) {
+            let stored_full = mapping.game_file(&self.content(), &scanned.path, &full.name);
+            if !stored_full.is_file() || !self.stored_same_content(&stored_full, &scanned.path) {
                 return true;
             }
         }
@@ -388,7 +1170,7 @@ impl GameLayout {
             .iter()
             .filter_map(|x| x.original_path.as_ref().map(|y| y.interpret()))
             .collect();
-        if let Some(diff) = &diff {
+        if let Some(diff) = diff {
             stored_files.extend(
                 self.restorable_files_in(&diff.name)
                     .iter()
@@ -404,19 +1186,20 @@ impl GameLayout {
             .filter(|x| !x.ignored)
             .map(|x| x.path.interpret())
             .collect();
-        if stored_files != scanned_files {
-            return true;
-        }
+        stored_files != scanned_files
+    }
 
+    #[allow(unused_variables)]
+    fn registry_changed(&self, scan: &ScanInfo, full: &FullBackup, diff: &Option<DifferentialBackup>) -> bool {
         // If scan has new/changed registry or is missing some:
         #[cfg(target_os = "windows")]
         {
             use crate::registry::Hives;
             let scanned_hives = Hives::from(scan);
 
-            let full_reg_file = self.path.joined(&full.name).joined("registry.yaml");
+            let full_reg_file = self.content().joined(&full.name).joined("registry.yaml");
 
-            match &diff {
+            match diff {
                 None => match Hives::load(&full_reg_file) {
                     None => {
                         if !scan.found_registry_keys.is_empty() {
@@ -430,7 +1213,7 @@ impl GameLayout {
                     }
                 },
                 Some(diff) => {
-                    let diff_reg_file = self.path.joined(&diff.name).joined("registry.yaml");
+                    let diff_reg_file = self.content().joined(&diff.name).joined("registry.yaml");
 
                     match (Hives::load(&full_reg_file), Hives::load(&diff_reg_file)) {
                         (None, None) => {
@@ -473,7 +1256,9 @@ impl GameLayout {
     }
 
     fn generate_full_backup_name(&self, now: &chrono::DateTime<chrono::Utc>) -> String {
-        if self.retention.full == 1 {
+        // A locked backup can't be replaced in place.
+        let locked_in_place = self.mapping.backups.iter().any(|x| x.name == "." && x.locked);
+        if self.retention.full == 1 && !locked_in_place {
             ".".to_string()
         } else {
             format!("full-{}", Self::generate_file_friendly_timestamp(now))
@@ -484,12 +1269,18 @@ impl GameLayout {
         format!("diff-{}", Self::generate_file_friendly_timestamp(now))
     }
 
-    fn plan_backup(&self, scan: &ScanInfo, now: &chrono::DateTime<chrono::Utc>) -> Option<BackupPlan> {
+    fn plan_backup(
+        &self,
+        scan: &ScanInfo,
+        files: bool,
+        registry: bool,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> Option<BackupPlan> {
         if !scan.found_anything() {
             return None;
         }
 
-        if !self.need_backup(scan) {
+        if !self.need_backup(scan, files, registry) {
             return None;
         }
 
@@ -515,16 +1306,24 @@ impl GameLayout {
                 plan.mapping.backups.push_back(FullBackup {
                     name: plan.name.clone(),
                     when: *now,
+                    host: Some(whoami::hostname()),
+                    locked: false,
                     children: Default::default(),
                 });
-                while plan.mapping.backups.len() as u8 > self.retention.full {
-                    plan.mapping.backups.pop_front();
+                while plan.mapping.backups.iter().filter(|x| !x.locked).count() > self.retention.full as usize {
+                    match plan.mapping.backups.iter().position(|x| !x.locked) {
+                        Some(oldest) => {
+                            plan.mapping.backups.remove(oldest);
+                        }
+                        None => break,
+                    }
                 }
             }
             BackupKind::Differential => {
                 let new = DifferentialBackup {
                     name: plan.name.clone(),
                     when: *now,
+                    host: Some(whoami::hostname()),
                     omit: Default::default(),
                 };
                 if let Some(latest_full) = plan.mapping.latest_full_backup_mut() {
@@ -540,8 +1339,8 @@ impl GameLayout {
 
             if plan.kind == BackupKind::Differential {
                 if let Some(latest_full) = plan.mapping.backups.back().cloned() {
-                    let stored = plan.mapping.game_file(&self.path, &file.path, &latest_full.name);
-                    if stored.same_content(&file.path) {
+                    let stored = plan.mapping.game_file(&self.content(), &file.path, &latest_full.name);
+                    if self.stored_same_content(&stored, &file.path) {
                         continue;
                     }
                 }
@@ -550,7 +1349,7 @@ impl GameLayout {
             plan.files.insert(file.clone());
         }
 
-        if plan.kind == BackupKind::Differential {
+        if plan.kind == BackupKind::Differential && files {
             if let Some((latest_full, _)) = plan.mapping.latest_backup() {
                 let mut full_file_list: HashSet<_> = self
                     .restorable_files_in(&latest_full.name)
@@ -569,7 +1368,7 @@ impl GameLayout {
         }
 
         #[cfg(target_os = "windows")]
-        {
+        if registry {
             use crate::registry::Hives;
 
             let mut hives = Hives::default();
@@ -610,23 +1409,35 @@ impl GameLayout {
         Some(plan)
     }
 
-    fn execute_backup(&mut self, plan: BackupPlan) -> BackupInfo {
+    fn execute_backup(&mut self, plan: BackupPlan, copy_options: &CopyOptions) -> BackupInfo {
         let mut backup_info = BackupInfo::default();
         self.mapping = plan.mapping;
 
+        if plan.kind == BackupKind::Full {
+            self.mapping.metadata.clear();
+        }
+
         let mut relevant_files = vec![];
         for file in &plan.files {
-            let target_file = self.mapping.game_file(&self.path, &file.path, &plan.name);
-            if file.path.same_content(&target_file) {
+            let target_file = self.mapping.game_file(&self.content(), &file.path, &plan.name);
+            self.mapping
+                .metadata
+                .insert(file.path.render(), FileMetadata::read(&file.path));
+            if self.stored_same_content(&target_file, &file.path) {
                 relevant_files.push(target_file);
                 continue;
             }
-            if target_file.create_parent_dir().is_err() {
-                backup_info.failed_files.insert(file.clone());
+            let written = retry_io(copy_options.attempts, || {
+                target_file.create_parent_dir()?;
+                self.write_stored(&file.path, &target_file, copy_options)
+            });
+            if let Err(e) = written {
+                backup_info.add_failed_file(file.clone(), FailureCategory::from_io(&e));
                 continue;
             }
-            if std::fs::copy(&file.path.interpret(), &target_file.interpret()).is_err() {
-                backup_info.failed_files.insert(file.clone());
+            if copy_options.verify && !self.stored_same_content(&target_file, &file.path) {
+                // The write seemed to work, but the drive didn't keep what we gave it.
+                backup_info.add_failed_file(file.clone(), FailureCategory::Other);
                 continue;
             }
             relevant_files.push(target_file);
@@ -639,7 +1450,11 @@ impl GameLayout {
 
             if !plan.registry.is_empty() {
                 let hives = Hives::from(&plan.registry);
-                hives.save(&target_registry_file);
+                if hives.save(&target_registry_file).is_err() {
+                    for reg_path in plan.registry.iter().filter(|x| !x.ignored) {
+                        backup_info.failed_registry.insert(reg_path.path.clone());
+                    }
+                }
             } else {
                 let _ = target_registry_file.remove();
             }
@@ -649,31 +1464,125 @@ impl GameLayout {
             self.remove_irrelevant_backup_files(&plan.name, &relevant_files);
         }
 
-        for irrelevant_parent in self.mapping.irrelevant_parents(&self.path) {
+        for irrelevant_parent in self.mapping.irrelevant_parents(&self.content()) {
             let _ = irrelevant_parent.remove();
         }
+        self.forget_irrelevant_nonces();
+        self.forget_irrelevant_objects();
+
+        if let Err(e) = self.pack().and_then(|_| self.save()) {
+            let category = FailureCategory::from_io(&e);
+            for file in plan.files {
+                if !backup_info.failed_files.contains(&file) {
+                    backup_info.add_failed_file(file, category);
+                }
+            }
+        }
 
-        self.save();
         backup_info
     }
 
-    pub fn back_up(&mut self, scan: &ScanInfo, now: &chrono::DateTime<chrono::Utc>) -> BackupInfo {
-        match self.plan_backup(scan, now) {
-            None => BackupInfo::default(),
-            Some(plan) => self.execute_backup(plan),
+    fn forget_irrelevant_objects(&mut self) {
+        let mapping = &self.mapping;
+        let mut objects = mapping.objects.clone();
+        objects.retain(|key, _| {
+            let backup = key.split('/').next().unwrap_or_default();
+            mapping.has_backup(backup) || (backup.starts_with("drive-") && mapping.has_backup("."))
+        });
+        self.mapping.objects = objects;
+    }
+
+    fn forget_irrelevant_nonces(&mut self) {
+        let mapping = &self.mapping;
+        if let Some(info) = &mapping.encryption {
+            let mut nonces = info.nonces.clone();
+            nonces.retain(|key, _| {
+                let backup = key.split('/').next().unwrap_or_default();
+                mapping.has_backup(backup) || (backup.starts_with("drive-") && mapping.has_backup("."))
+            });
+            if let Some(info) = self.mapping.encryption.as_mut() {
+                info.nonces = nonces;
+            }
         }
     }
 
-    fn mapping_file(path: &StrictPath) -> StrictPath {
-        path.joined("mapping.yaml")
+    /// Whether a new backup of this kind would lose the latest backup's copy of any data
+    /// that is being left out. A full backup can't carry anything forward, and a differential
+    /// backup can only carry forward what the full backup has, not what the latest differential changed.
+    fn would_drop_left_out_data(&self, kind: &BackupKind, files: bool, registry: bool) -> bool {
+        let (full, diff) = match self.mapping.latest_backup() {
+            Some(x) => x,
+            None => return false,
+        };
+        match kind {
+            BackupKind::Full => {
+                let has_files = !self.restorable_files().is_empty();
+                let has_registry = match diff {
+                    Some(diff) if self.registry_file_in(&diff.name).is_file() => true,
+                    Some(diff) if diff.omits_registry() => false,
+                    _ => self.registry_file_in(&full.name).is_file(),
+                };
+                (!files && has_files) || (!registry && has_registry)
+            }
+            BackupKind::Differential => match diff {
+                None => false,
+                Some(diff) => {
+                    let changed_files = !diff.omit.files.is_empty() || !self.restorable_files_in(&diff.name).is_empty();
+                    let changed_registry = diff.omits_registry() || self.registry_file_in(&diff.name).is_file();
+                    (!files && changed_files) || (!registry && changed_registry)
+                }
+            },
+        }
     }
 
-    fn find_irrelevant_backup_files(&self, backup: &str, relevant_files: &[StrictPath]) -> Vec<StrictPath> {
+    /// Back up the scanned data. When `files` or `registry` is false, that kind of data is
+    /// carried forward from the previous backup. If that isn't possible,
+    /// the backup is refused rather than dropping the previous copy.
+    pub fn back_up(
+        &mut self,
+        scan: &ScanInfo,
+        files: bool,
+        registry: bool,
+        now: &chrono::DateTime<chrono::Utc>,
+        copy_options: &CopyOptions,
+    ) -> BackupInfo {
+        let backup_info = match self.plan_backup(scan, files, registry, now) {
+            None => BackupInfo::default(),
+            Some(plan) if self.would_drop_left_out_data(&plan.kind, files, registry) => {
+                let mut backup_info = BackupInfo::default();
+                for file in plan.files {
+                    backup_info.add_failed_file(file, FailureCategory::Other);
+                }
+                backup_info.failed_registry.extend(
+                    scan.found_registry_keys
+                        .iter()
+                        .filter(|x| !x.ignored)
+                        .map(|x| x.path.clone()),
+                );
+                backup_info
+            }
+            Some(plan) => self.execute_backup(plan, copy_options),
+        };
+        self.remove_archive_scratch();
+        backup_info
+    }
+
+    /// Find a game folder's mapping, which is in a subfolder for the split format.
+    fn mapping_file(path: &StrictPath) -> StrictPath {
+        let split = path.joined(SPLIT_META_FOLDER).joined(MAPPING_FILE);
+        if split.is_file() {
+            split
+        } else {
+            path.joined(MAPPING_FILE)
+        }
+    }
+
+    fn find_irrelevant_backup_files(&self, backup: &str, relevant_files: &[StrictPath]) -> Vec<StrictPath> {
         #[allow(clippy::needless_collect)]
         let relevant_files: Vec<_> = relevant_files.iter().map(|x| x.interpret()).collect();
         let mut irrelevant_files = vec![];
 
-        for drive_dir in walkdir::WalkDir::new(self.path.joined(backup).interpret())
+        for drive_dir in walkdir::WalkDir::new(self.content().joined(backup).interpret())
             .max_depth(1)
             .follow_links(false)
             .into_iter()
@@ -716,30 +1625,195 @@ pub struct BackupLayout {
     pub base: StrictPath,
     games: std::collections::HashMap<String, StrictPath>,
     retention: Retention,
+    folder_layout: BackupFolderLayout,
+    format: BackupFormat,
+    passphrase: Option<String>,
+    encrypt: bool,
+    dedupe: bool,
+    /// Game folders that are outside of the base because of a per-game destination.
+    destinations: std::collections::HashMap<String, StrictPath>,
+    /// Games whose destination should be encrypted, regardless of `encrypt`.
+    encrypted_destinations: HashSet<String>,
+    /// Derived keys by game, since deriving them is deliberately slow.
+    ciphers: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, (EncryptionInfo, Cipher)>>>,
 }
 
 impl BackupLayout {
     pub fn new(base: StrictPath, retention: Retention) -> Self {
         let games = Self::load(&base);
-        Self { base, games, retention }
+        Self {
+            base,
+            games,
+            retention,
+            folder_layout: Default::default(),
+            format: Default::default(),
+            passphrase: None,
+            encrypt: false,
+            dedupe: false,
+            destinations: Default::default(),
+            encrypted_destinations: Default::default(),
+            ciphers: Default::default(),
+        }
+    }
+
+    /// Set how new game folders will be arranged. Existing game folders are
+    /// found regardless of this setting.
+    pub fn with_folder_layout(mut self, folder_layout: BackupFolderLayout) -> Self {
+        self.folder_layout = folder_layout;
+        self
+    }
+
+    /// Set how new game folders will store their data. Existing game folders
+    /// keep the format that they were created with.
+    pub fn with_format(mut self, format: BackupFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Store each unique file once, shared between all game folders.
+    /// Archived and encrypted game folders are not deduplicated.
+    pub fn with_dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    fn dedupe_store(&self) -> StrictPath {
+        self.base.joined(DEDUPE_STORE_FOLDER)
+    }
+
+    /// Delete files from the deduplication store that no backup refers to anymore.
+    /// Backed up files are hard links to the store, so this never loses any data.
+    pub fn prune_dedupe_store(&self) {
+        let store = self.dedupe_store();
+        if !store.is_dir() {
+            return;
+        }
+
+        let mut referenced = HashSet::new();
+        for folder in Self::load(&self.base).values().chain(self.destinations.values()) {
+            if let Ok(mapping) = IndividualMapping::load(&GameLayout::mapping_file(folder)) {
+                referenced.extend(mapping.objects.into_values());
+            }
+        }
+
+        for file in walkdir::WalkDir::new(store.interpret())
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|x| x.file_type().is_file())
+        {
+            if !referenced.contains(file.file_name().to_string_lossy().as_ref()) {
+                let _ = StrictPath::from(&file).remove();
+            }
+        }
+    }
+
+    /// Set the passphrase for encrypted game folders. When `encrypt` is set,
+    /// game folders in the base that are not encrypted yet will start to be.
+    pub fn with_passphrase(mut self, passphrase: Option<String>, encrypt: bool) -> Self {
+        self.passphrase = passphrase;
+        self.encrypt = encrypt;
+        self.ciphers = Default::default();
+        self
+    }
+
+    /// Put some games' folders in their own destination instead of the base.
+    /// Any folder that those games already have in the base is disregarded.
+    /// Folders in one of the `encrypted` destinations will be encrypted
+    /// like `encrypt` does for the base.
+    pub fn with_destinations(
+        mut self,
+        destinations: &std::collections::BTreeMap<String, StrictPath>,
+        encrypted: &std::collections::BTreeSet<StrictPath>,
+    ) -> Self {
+        for (name, destination) in destinations {
+            let folder = destination.joined(&Self::safe_folder_name(name));
+            self.games.remove(name);
+            if GameLayout::mapping_file(&folder).is_file() {
+                self.games.insert(name.clone(), folder.clone());
+            }
+            self.destinations.insert(name.clone(), folder);
+            if encrypted.iter().any(|x| x.same_path(destination)) {
+                self.encrypted_destinations.insert(name.clone());
+            } else {
+                self.encrypted_destinations.remove(name);
+            }
+        }
+        self
+    }
+
+    /// Whether a game's folder should be encrypted if it isn't yet.
+    fn should_encrypt(&self, name: &str) -> bool {
+        if self.destinations.contains_key(name) {
+            self.encrypted_destinations.contains(name)
+        } else {
+            self.encrypt
+        }
+    }
+
+    /// Unlock a game's existing encryption or set up new encryption for it,
+    /// reusing the key from an earlier call for the same game when possible.
+    fn cipher_for(
+        &self,
+        name: &str,
+        passphrase: &str,
+        existing: Option<&EncryptionInfo>,
+    ) -> Option<(EncryptionInfo, Cipher)> {
+        let cached = self.ciphers.lock().unwrap().get(name).cloned();
+        if let Some((info, cipher)) = cached {
+            match existing {
+                Some(existing) if existing.salt == info.salt => return Some((existing.clone(), cipher)),
+                None => return Some((info, cipher)),
+                Some(_) => {}
+            }
+        }
+
+        // Deriving is slow, so don't hold the lock meanwhile.
+        let (info, cipher) = match existing {
+            Some(existing) => (existing.clone(), existing.unlock(passphrase)?),
+            None => EncryptionInfo::new(passphrase),
+        };
+        let fresh = EncryptionInfo {
+            nonces: Default::default(),
+            ..info.clone()
+        };
+        self.ciphers
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), (fresh, cipher.clone()));
+        Some((info, cipher))
+    }
+
+    /// Whether any existing game folder needs a passphrase.
+    pub fn any_encrypted(&self) -> bool {
+        self.games.values().any(|path| {
+            IndividualMapping::load(&GameLayout::mapping_file(path))
+                .map(|x| x.encryption.is_some())
+                .unwrap_or(false)
+        })
     }
 
     pub fn load(base: &StrictPath) -> std::collections::HashMap<String, StrictPath> {
         let mut overall = std::collections::HashMap::new();
 
         for game_dir in walkdir::WalkDir::new(base.interpret())
-            .max_depth(1)
+            .max_depth(2)
             .follow_links(false)
             .into_iter()
             .skip(1) // the base path itself
             .filter_map(|e| e.ok())
             .filter(|x| x.file_type().is_dir())
         {
+            let depth = game_dir.depth();
             let game_dir = StrictPath::from(&game_dir);
-            let mapping_file = game_dir.joined("mapping.yaml");
+            let mapping_file = GameLayout::mapping_file(&game_dir);
             if mapping_file.is_file() {
                 if let Ok(mapping) = IndividualMapping::load(&mapping_file) {
-                    overall.insert(mapping.name.clone(), game_dir);
+                    // At the second level, this could also be a backed up file
+                    // that happens to be named the same as the mapping.
+                    if depth == 1 || mapping.layout == BackupFolderLayout::ByStore {
+                        overall.insert(mapping.name.clone(), game_dir);
+                    }
                 }
             }
         }
@@ -749,40 +1823,234 @@ impl BackupLayout {
 
     pub fn game_layout(&self, name: &str) -> GameLayout {
         let path = self.game_folder(name);
+        self.game_layout_at(name, path)
+    }
 
-        match GameLayout::load(path.clone(), self.retention.clone()) {
+    /// Like `game_layout`, but a new game folder will be placed according to
+    /// the folder layout, which may depend on the store.
+    pub fn game_layout_for_backup(&self, name: &str, store: Store) -> GameLayout {
+        let path = match self.folder_layout {
+            BackupFolderLayout::ByStore if !self.games.contains_key(name) && !self.destinations.contains_key(name) => {
+                self.base
+                    .joined(&store_folder_name(store))
+                    .joined(&Self::safe_folder_name(name))
+            }
+            _ => self.game_folder(name),
+        };
+        self.game_layout_at(name, path)
+    }
+
+    fn game_layout_at(&self, name: &str, path: StrictPath) -> GameLayout {
+        let mut layout = match GameLayout::load(path.clone(), self.retention.clone()) {
             Ok(x) => x,
             Err(_) => GameLayout {
                 path,
-                mapping: IndividualMapping::new(name.to_string()),
+                mapping: IndividualMapping {
+                    layout: self.folder_layout,
+                    format: self.format,
+                    ..IndividualMapping::new(name.to_string())
+                },
                 retention: self.retention.clone(),
+                ..Default::default()
             },
+        };
+
+        if layout.mapping.format == BackupFormat::TarGz {
+            layout.use_archive();
+        } else if self.dedupe {
+            layout.dedupe_store = Some(self.dedupe_store());
         }
+
+        if let Some(passphrase) = &self.passphrase {
+            match &layout.mapping.encryption {
+                Some(info) => {
+                    layout.cipher = self.cipher_for(name, passphrase, Some(info)).map(|(_, cipher)| cipher);
+                }
+                None if self.should_encrypt(name) => {
+                    if let Some((info, cipher)) = self.cipher_for(name, passphrase, None) {
+                        layout.mapping.encryption = Some(info);
+                        layout.cipher = Some(cipher);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        layout
     }
 
     fn generate_total_rename(original_name: &str) -> String {
         format!("ludusavi-renamed-{}", encode_base64_for_folder(original_name))
     }
 
+    fn safe_folder_name(game_name: &str) -> String {
+        let safe_name = escape_folder_name(game_name);
+
+        if safe_name.matches(SAFE).count() == safe_name.len() {
+            // It's unreadable now, so do a total rename.
+            Self::generate_total_rename(game_name)
+        } else {
+            safe_name
+        }
+    }
+
     pub fn game_folder(&self, game_name: &str) -> StrictPath {
+        if let Some(folder) = self.destinations.get(game_name) {
+            return folder.clone();
+        }
         match self.games.get::<str>(game_name) {
             Some(game) => game.clone(),
-            None => {
-                let mut safe_name = escape_folder_name(game_name);
+            None => self.base.joined(&Self::safe_folder_name(game_name)),
+        }
+    }
 
-                if safe_name.matches(SAFE).count() == safe_name.len() {
-                    // It's unreadable now, so do a total rename.
-                    safe_name = Self::generate_total_rename(game_name);
-                }
+    /// When each game in the backup folder was last backed up.
+    pub fn last_backup_times(&self) -> std::collections::HashMap<String, chrono::DateTime<chrono::Utc>> {
+        self.games
+            .iter()
+            .filter_map(|(name, path)| {
+                IndividualMapping::load(&GameLayout::mapping_file(path))
+                    .ok()
+                    .and_then(|x| x.last_backup_time())
+                    .map(|x| (name.clone(), x))
+            })
+            .collect()
+    }
+
+    /// The most recent backup of any game in the backup folder.
+    pub fn latest_source_backup(&self) -> Option<SourceBackup> {
+        self.games
+            .values()
+            .filter_map(|path| IndividualMapping::load(&GameLayout::mapping_file(path)).ok())
+            .filter_map(|x| x.last_backup_source())
+            .max_by_key(|x| x.when)
+    }
+
+    /// Games whose save files have been modified since their last backup.
+    pub fn changed_games(&self) -> std::collections::HashSet<String> {
+        self.games
+            .iter()
+            .filter(|(_, path)| {
+                IndividualMapping::load(&GameLayout::mapping_file(path))
+                    .map(|x| x.has_changed_files())
+                    .unwrap_or(false)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// How many bytes the whole backup folder currently takes up.
+    pub fn total_size(&self) -> u64 {
+        folder_size(&self.base)
+    }
 
-                self.base.joined(&safe_name)
+    /// How much space a game's existing backups take up.
+    pub fn game_size(&self, name: &str) -> u64 {
+        self.games.get(name).map(folder_size).unwrap_or(0)
+    }
+
+    /// Old backups that would be deleted if these games were backed up again.
+    pub fn backups_to_prune(&self, games: &[String]) -> DeletionPreview {
+        let mut preview = DeletionPreview::default();
+        for game in games {
+            if self.games.contains_key(game) {
+                for path in self.game_layout(game).backups_to_prune() {
+                    preview.add(path);
+                }
             }
         }
+        preview
     }
 
     pub fn restorable_games(&self) -> Vec<String> {
         self.games.keys().cloned().collect()
     }
+
+    /// Protect a backup from retention, or allow it to be pruned again.
+    pub fn set_backup_locked(&self, game: &str, backup: &str, locked: bool) -> Result<(), Error> {
+        let unknown = || Error::UnknownBackup {
+            game: game.to_string(),
+            backup: backup.to_string(),
+        };
+        if !self.games.contains_key(game) {
+            return Err(unknown());
+        }
+        let mut layout = self.game_layout(game);
+        if !layout.mapping.set_locked(backup, locked) {
+            return Err(unknown());
+        }
+        layout.save().map_err(|_| {
+            if is_read_only() {
+                Error::ReadOnlyMode
+            } else {
+                Error::CannotPrepareBackupTarget {
+                    path: layout.path.clone(),
+                }
+            }
+        })
+    }
+
+    pub fn compare_backups(&self, game: &str, older: &str, newer: &str) -> Result<BackupComparison, Error> {
+        let unknown = |backup: &str| Error::UnknownBackup {
+            game: game.to_string(),
+            backup: backup.to_string(),
+        };
+        if !self.games.contains_key(game) {
+            return Err(unknown(older));
+        }
+        let layout = self.game_layout(game);
+        for backup in [older, newer] {
+            if !layout.mapping.has_backup(backup) {
+                return Err(unknown(backup));
+            }
+        }
+        layout.compare_backups(older, newer).ok_or_else(|| unknown(older))
+    }
+
+    /// Whether a game folder has any backups that the user has locked.
+    fn has_locked_backup(folder: &StrictPath) -> bool {
+        IndividualMapping::load(&GameLayout::mapping_file(folder))
+            .map(|x| x.has_locked_backup())
+            .unwrap_or(false)
+    }
+
+    /// Whether any game in the backup folder has a locked backup.
+    pub fn has_locked_backups(&self) -> bool {
+        self.games.values().any(Self::has_locked_backup)
+    }
+
+    /// Game folders for games that are no longer known, such as games that
+    /// were removed from the manifest. Folders with locked backups are kept.
+    pub fn orphaned_games(&self, relevant: &HashSet<String>) -> DeletionPreview {
+        let mut preview = DeletionPreview::default();
+        for (name, path) in &self.games {
+            if !relevant.contains(name) && !Self::has_locked_backup(path) {
+                preview.add(path.clone());
+            }
+        }
+        preview
+    }
+
+    /// Delete game folders from `orphaned_games` after the user has reviewed them.
+    /// Anything in `orphans` that isn't a known game folder is left alone,
+    /// as is any folder that has gained a locked backup since the review.
+    pub fn prune_orphans(&mut self, orphans: &DeletionPreview) -> Result<(), Error> {
+        let names: Vec<_> = self
+            .games
+            .iter()
+            .filter(|(_, path)| orphans.entries.contains_key(path) && !Self::has_locked_backup(path))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            if let Some(path) = self.games.get(&name) {
+                if path.remove().is_err() {
+                    return Err(Error::CannotPruneOrphan { path: path.clone() });
+                }
+                self.games.remove(&name);
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -807,6 +2075,322 @@ mod tests {
             assert_eq!("drive-____C", mapping.drive_folder_name(r#"\\?\C:"#));
             assert_eq!("drive-__remote", mapping.drive_folder_name(r#"\\remote"#));
         }
+
+        #[test]
+        fn can_load_file_metadata() {
+            let mapping = IndividualMapping::load_from_string(
+                r#"
+                name: foo
+                drives:
+                  drive-0: ""
+                backups: []
+                metadata:
+                  /file1.txt:
+                    modified: "2000-01-02T03:04:05Z"
+                    mode: 33261
+                  /file2.txt: {}
+                "#,
+            )
+            .unwrap();
+
+            assert_eq!(
+                hashmap! {
+                    "/file1.txt".to_string() => FileMetadata {
+                        modified: Some(chrono::NaiveDate::from_ymd(2000, 1, 2).and_hms(3, 4, 5).and_local_timezone(chrono::Utc).unwrap()),
+                        mode: Some(0o100755),
+                    },
+                    "/file2.txt".to_string() => FileMetadata::default(),
+                },
+                mapping.metadata,
+            );
+        }
+
+        #[test]
+        fn can_detect_changed_files() {
+            let file = format!("{}/tests/root1/game1/subdir/file2.txt", repo());
+            let mut mapping = IndividualMapping::new("foo".to_owned());
+            assert!(!mapping.has_changed_files());
+
+            mapping.metadata.insert(
+                file.clone(),
+                FileMetadata {
+                    modified: Some(chrono::Utc::now() + chrono::Duration::days(1)),
+                    mode: None,
+                },
+            );
+            mapping.metadata.insert(
+                format!("{}/tests/root1/game1/missing.txt", repo()),
+                FileMetadata {
+                    modified: Some(
+                        chrono::NaiveDate::from_ymd(2000, 1, 2)
+                            .and_hms(3, 4, 5)
+                            .and_local_timezone(chrono::Utc)
+                            .unwrap(),
+                    ),
+                    mode: None,
+                },
+            );
+            assert!(!mapping.has_changed_files());
+
+            mapping.metadata.insert(
+                file,
+                FileMetadata {
+                    modified: Some(
+                        chrono::NaiveDate::from_ymd(2000, 1, 2)
+                            .and_hms(3, 4, 5)
+                            .and_local_timezone(chrono::Utc)
+                            .unwrap(),
+                    ),
+                    mode: None,
+                },
+            );
+            assert!(mapping.has_changed_files());
+        }
+
+        #[test]
+        fn can_lock_backups() {
+            let mut mapping = IndividualMapping::new("foo".to_string());
+            mapping.backups.push_back(FullBackup {
+                name: "full-1".to_string(),
+                children: vec![DifferentialBackup {
+                    name: "diff-1".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+
+            assert!(mapping.set_locked("diff-1", true));
+            assert!(mapping.backups[0].locked);
+            assert!(mapping.set_locked("full-1", false));
+            assert!(!mapping.backups[0].locked);
+            assert!(!mapping.set_locked("full-2", true));
+        }
+
+        #[test]
+        fn can_load_upstream_mapping() {
+            let mapping = IndividualMapping::load_from_string(
+                r#"
+                name: foo
+                drives:
+                  drive-0: ""
+                backups:
+                  - name: backup-1
+                    when: "2000-01-02T03:04:05Z"
+                    os: linux
+                    files:
+                      /file1.txt:
+                        hash: abc
+                        size: 1
+                      /file2.txt:
+                        hash: def
+                        size: 2
+                    registry:
+                      hash: ghi
+                    children:
+                      - name: backup-2
+                        when: "2000-01-03T03:04:05Z"
+                        files:
+                          /file1.txt:
+                            hash: xyz
+                            size: 3
+                          /file2.txt: ~
+                        registry:
+                          hash: ~
+                "#,
+            )
+            .unwrap();
+
+            let when = |day| {
+                chrono::NaiveDate::from_ymd(2000, 1, day)
+                    .and_hms(3, 4, 5)
+                    .and_local_timezone(chrono::Utc)
+                    .unwrap()
+            };
+            assert_eq!(
+                IndividualMapping {
+                    name: "foo".to_string(),
+                    drives: hashmap! { "drive-0".to_string() => "".to_string() },
+                    backups: VecDeque::from(vec![FullBackup {
+                        name: "backup-1".to_string(),
+                        when: when(2),
+                        host: None,
+                        locked: false,
+                        children: vec![DifferentialBackup {
+                            name: "backup-2".to_string(),
+                            when: when(3),
+                            host: None,
+                            omit: BackupOmission {
+                                files: hashset! { "/file2.txt".to_string() },
+                                registry: true,
+                            },
+                        }],
+                    }]),
+                    ..Default::default()
+                },
+                mapping,
+            );
+        }
+
+        #[test]
+        fn can_get_last_backup_source() {
+            let when = chrono::NaiveDate::from_ymd(2000, 1, 2)
+                .and_hms(3, 4, 5)
+                .and_local_timezone(chrono::Utc)
+                .unwrap();
+            let mut mapping = IndividualMapping::new("foo".to_string());
+            assert_eq!(None, mapping.last_backup_source());
+
+            mapping.backups.push_back(FullBackup {
+                name: "backup-1".to_string(),
+                when: Default::default(),
+                host: None,
+                locked: false,
+                children: vec![],
+            });
+            assert_eq!(Some(SourceBackup::default()), mapping.last_backup_source());
+
+            mapping.backups.push_back(FullBackup {
+                name: "backup-2".to_string(),
+                when,
+                host: Some("machine".to_string()),
+                locked: false,
+                children: vec![],
+            });
+            assert_eq!(
+                Some(SourceBackup {
+                    when: Some(when),
+                    host: Some("machine".to_string()),
+                }),
+                mapping.last_backup_source(),
+            );
+        }
+
+        #[test]
+        fn serializes_in_a_stable_order() {
+            let when = chrono::NaiveDate::from_ymd(2000, 1, 2)
+                .and_hms(3, 4, 5)
+                .and_local_timezone(chrono::Utc)
+                .unwrap();
+            let mapping = IndividualMapping {
+                name: "foo".to_string(),
+                drives: hashmap! {
+                    "drive-D".to_string() => "D:".to_string(),
+                    "drive-C".to_string() => "C:".to_string(),
+                    "drive-0".to_string() => "".to_string(),
+                },
+                backups: VecDeque::from(vec![FullBackup {
+                    name: "backup-1".to_string(),
+                    when,
+                    host: None,
+                    locked: false,
+                    children: vec![DifferentialBackup {
+                        name: "backup-2".to_string(),
+                        when,
+                        host: None,
+                        omit: BackupOmission {
+                            files: hashset! {
+                                "/b/file.txt".to_string(),
+                                "/a/file.txt".to_string(),
+                                "/c/file.txt".to_string(),
+                            },
+                            registry: false,
+                        },
+                    }],
+                }]),
+                metadata: hashmap! {
+                    "/b/file.txt".to_string() => FileMetadata::default(),
+                    "/c/file.txt".to_string() => FileMetadata::default(),
+                    "/a/file.txt".to_string() => FileMetadata::default(),
+                },
+                ..Default::default()
+            };
+
+            assert_eq!(
+                r#"
+---
+name: foo
+drives:
+  drive-0: ""
+  drive-C: "C:"
+  drive-D: "D:"
+backups:
+  - name: backup-1
+    when: "2000-01-02T03:04:05Z"
+    children:
+      - name: backup-2
+        when: "2000-01-02T03:04:05Z"
+        omit:
+          files:
+            - /a/file.txt
+            - /b/file.txt
+            - /c/file.txt
+metadata:
+  /a/file.txt: {}
+  /b/file.txt: {}
+  /c/file.txt: {}
+"#
+                .trim_start(),
+                mapping.serialize(),
+            );
+        }
+
+        #[test]
+        fn can_read_and_apply_file_metadata() {
+            let file = StrictPath::from(std::env::temp_dir().join("ludusavi-test-file-metadata.txt"));
+
+            // Read-only files should still get their modified time back.
+            #[cfg(unix)]
+            let modes = [Some(0o100600), Some(0o100400)];
+            #[cfg(not(unix))]
+            let modes = [None];
+
+            for mode in modes {
+                let _ = file.unset_readonly();
+                let _ = file.remove();
+                std::fs::write(file.interpret(), "foo").unwrap();
+
+                let expected = FileMetadata {
+                    modified: Some(
+                        chrono::NaiveDate::from_ymd(2000, 1, 2)
+                            .and_hms(3, 4, 5)
+                            .and_local_timezone(chrono::Utc)
+                            .unwrap(),
+                    ),
+                    mode,
+                };
+                let result = expected.apply(&file).map(|_| FileMetadata::read(&file));
+                let _ = file.remove();
+
+                assert_eq!(expected, result.unwrap());
+            }
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn can_apply_file_metadata_to_read_only_target() {
+            use std::os::unix::fs::PermissionsExt;
+
+            let file = StrictPath::from(std::env::temp_dir().join("ludusavi-test-file-metadata-read-only.txt"));
+            let _ = file.unset_readonly();
+            let _ = file.remove();
+            std::fs::write(file.interpret(), "foo").unwrap();
+            std::fs::set_permissions(file.interpret(), std::fs::Permissions::from_mode(0o444)).unwrap();
+
+            let expected = FileMetadata {
+                modified: Some(
+                    chrono::NaiveDate::from_ymd(2000, 1, 2)
+                        .and_hms(3, 4, 5)
+                        .and_local_timezone(chrono::Utc)
+                        .unwrap(),
+                ),
+                mode: Some(0o100444),
+            };
+            let result = expected.apply(&file).map(|_| FileMetadata::read(&file));
+            let _ = file.unset_readonly();
+            let _ = file.remove();
+
+            assert_eq!(expected, result.unwrap());
+        }
     }
 
     mod backup_layout {
@@ -827,7 +2411,63 @@ mod tests {
                 path: StrictPath::new(path.to_string()),
                 mapping: IndividualMapping::new(name.to_string()),
                 retention: Retention::default(),
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
+            }
+        }
+
+        #[test]
+        fn identifies_encrypted_files_relative_to_game_folder() {
+            let layout = game_layout("game1", "/backup/game1");
+            assert_eq!(
+                "drive-0/saves/file.txt",
+                layout.encryption_key(&StrictPath::from("/backup/game1/./drive-0/saves/file.txt"))
+            );
+            assert_eq!(
+                "full-20220101T000000Z/drive-C/file.txt",
+                layout.encryption_key(&StrictPath::from(
+                    "/backup/game1/full-20220101T000000Z/drive-C/file.txt"
+                ))
+            );
+        }
+
+        #[test]
+        fn can_restore_from_backup_archive() {
+            let source = StrictPath::new(format!("{}/tests/backup", repo()));
+            let mut expected = layout().restorable_games();
+            expected.sort();
+
+            for (archive, wrapped) in [
+                ("ludusavi-test-backup.tar.gz", false),
+                ("ludusavi-test-backup-wrapped.tgz", true),
+            ] {
+                let archive = StrictPath::from(std::env::temp_dir().join(archive));
+                if wrapped {
+                    let wrapper = StrictPath::from(std::env::temp_dir().join("ludusavi-test-backup-wrapper"));
+                    let _ = wrapper.remove();
+                    crate::prelude::migrate_backup(&source, &wrapper.joined("backup")).unwrap();
+                    pack_archive(&wrapper, &archive).unwrap();
+                    let _ = wrapper.remove();
+                } else {
+                    pack_archive(&source, &archive).unwrap();
+                }
+
+                let unpacked = restoration_source(&archive).unwrap();
+                let mut actual = BackupLayout::new(unpacked, Retention::default()).restorable_games();
+                actual.sort();
+                assert_eq!(expected, actual);
+
+                let _ = archive.remove();
             }
+
+            assert_eq!(
+                Err(Error::RestorationSourceInvalid {
+                    path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo()))
+                }),
+                restoration_source(&StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())))
+            );
         }
 
         #[test]
@@ -902,6 +2542,68 @@ mod tests {
             );
         }
 
+        #[test]
+        fn can_determine_game_folder_by_store() {
+            let layout = layout().with_folder_layout(BackupFolderLayout::ByStore);
+            assert_eq!(
+                if cfg!(target_os = "windows") {
+                    StrictPath::new(format!("\\\\?\\{}\\tests\\backup/steam/nonexistent", repo()))
+                } else {
+                    StrictPath::new(format!("{}/tests/backup/steam/nonexistent", repo()))
+                },
+                layout.game_layout_for_backup("nonexistent", Store::Steam).path
+            );
+            assert_eq!(
+                if cfg!(target_os = "windows") {
+                    StrictPath::new(format!("\\\\?\\{}\\tests\\backup/game1", repo()))
+                } else {
+                    StrictPath::new(format!("{}/tests/backup/game1", repo()))
+                },
+                layout.game_layout_for_backup("game1", Store::Steam).path
+            );
+        }
+
+        #[test]
+        fn can_determine_game_folder_with_destination() {
+            let destination = StrictPath::new(format!("{}/tests/elsewhere", repo()));
+            let layout = layout()
+                .with_folder_layout(BackupFolderLayout::ByStore)
+                .with_destinations(
+                    &std::collections::BTreeMap::from([("game1".to_string(), destination.clone())]),
+                    &Default::default(),
+                );
+            assert_eq!(
+                destination.joined("game1"),
+                layout.game_layout_for_backup("game1", Store::Steam).path
+            );
+            assert!(!layout.restorable_games().contains(&"game1".to_string()));
+        }
+
+        #[test]
+        fn encrypts_each_destination_according_to_its_own_setting() {
+            let encrypted = StrictPath::new(format!("{}/tests/elsewhere", repo()));
+            let plain = StrictPath::new(format!("{}/tests/plain", repo()));
+            let layout = layout()
+                .with_passphrase(Some("secret".to_string()), false)
+                .with_destinations(
+                    &std::collections::BTreeMap::from([
+                        ("new-game1".to_string(), encrypted.clone()),
+                        ("new-game2".to_string(), plain),
+                    ]),
+                    &std::collections::BTreeSet::from([encrypted]),
+                );
+
+            let first = layout.game_layout("new-game1");
+            assert!(first.mapping.encryption.is_some());
+            assert!(!first.is_locked());
+            assert_eq!(
+                first.mapping.encryption.map(|x| x.salt),
+                layout.game_layout("new-game1").mapping.encryption.map(|x| x.salt),
+            );
+            assert_eq!(None, layout.game_layout("new-game2").mapping.encryption);
+            assert_eq!(None, layout.game_layout("new-game3").mapping.encryption);
+        }
+
         #[test]
         fn can_find_irrelevant_backup_files() {
             assert_eq!(
@@ -981,8 +2683,102 @@ mod tests {
                 path: StrictPath::new(format!("{}/tests/backup/game1", repo())),
                 mapping: IndividualMapping::new("game1".to_string()),
                 retention: Retention::default(),
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
-            assert_eq!(None, layout.plan_backup(&scan, &now()),);
+            assert_eq!(None, layout.plan_backup(&scan, true, true, &now()),);
+        }
+
+        #[test]
+        fn can_find_orphaned_games() {
+            let layout = BackupLayout::new(
+                StrictPath::new(format!("{}/tests/backup", repo())),
+                Retention::default(),
+            );
+            let relevant = layout.restorable_games().into_iter().filter(|x| x != "game1").collect();
+
+            let orphans = layout.orphaned_games(&relevant);
+            assert_eq!(
+                vec![&StrictPath::new(format!("{}/tests/backup/game1", repo()))],
+                orphans.entries.keys().collect::<Vec<_>>(),
+            );
+            assert!(layout
+                .orphaned_games(&layout.restorable_games().into_iter().collect())
+                .is_empty());
+        }
+
+        #[test]
+        fn can_prune_orphans_except_locked_backups() {
+            let base = StrictPath::from(std::env::temp_dir().join("ludusavi-test-prune-orphans"));
+            let _ = base.remove();
+            for (name, locked) in [("game1", false), ("game2", true)] {
+                let mut mapping = IndividualMapping::new(name.to_string());
+                mapping.backups.push_back(FullBackup {
+                    name: ".".to_string(),
+                    when: now(),
+                    locked,
+                    ..Default::default()
+                });
+                let folder = base.joined(name);
+                std::fs::create_dir_all(folder.interpret()).unwrap();
+                mapping.save(&GameLayout::mapping_file(&folder)).unwrap();
+            }
+
+            let mut layout = BackupLayout::new(base.clone(), Retention::default());
+            let orphans = layout.orphaned_games(&HashSet::new());
+            assert_eq!(
+                vec![base.joined("game1").interpret()],
+                orphans.entries.keys().map(|x| x.interpret()).collect::<Vec<_>>(),
+            );
+
+            // Even if the locked folder is requested, it's kept.
+            let mut requested = orphans.clone();
+            requested.add(base.joined("game2"));
+            layout.prune_orphans(&requested).unwrap();
+            assert!(!base.joined("game1").exists());
+            assert!(base.joined("game2").is_dir());
+
+            let _ = base.remove();
+        }
+
+        #[test]
+        fn can_plan_backup_when_full_rollover_with_locked_backup() {
+            let scan = ScanInfo {
+                game_name: "game1".to_string(),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            };
+            let full = |name: &str, locked| FullBackup {
+                name: name.to_string(),
+                when: now(),
+                host: None,
+                locked,
+                children: vec![],
+            };
+            let layout = GameLayout {
+                path: StrictPath::new(format!("{}/tests/backup/game1-locked", repo())),
+                mapping: IndividualMapping {
+                    name: "game1".to_string(),
+                    backups: VecDeque::from(vec![full(".", true), full("full-2", false)]),
+                    ..Default::default()
+                },
+                retention: Retention {
+                    full: 1,
+                    differential: 0,
+                },
+                ..Default::default()
+            };
+
+            let plan = layout.plan_backup(&scan, true, true, &now()).unwrap();
+            assert_eq!(
+                vec![".", "full-20000102T030405Z"],
+                plan.mapping.backups.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(),
+            );
         }
 
         #[test]
@@ -1000,6 +2796,10 @@ mod tests {
                 path: StrictPath::new(format!("{}/tests/backup/game1", repo())),
                 mapping: IndividualMapping::new("game1".to_string()),
                 retention: Retention::default(),
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1008,9 +2808,16 @@ mod tests {
                         name: "game1".to_string(),
                         // Drive mapping will be populated on first backup execution:
                         drives: Default::default(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![FullBackup {
                             name: ".".to_string(),
                             when: now(),
+                            host: Some(whoami::hostname()),
+                            locked: false,
                             children: vec![],
                         }]),
                     },
@@ -1018,7 +2825,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
 
@@ -1038,9 +2845,16 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from_iter(vec![FullBackup {
                         name: ".".to_string(),
                         when: past(),
+                        host: None,
+                        locked: false,
                         children: vec![],
                     }]),
                 },
@@ -1048,6 +2862,10 @@ mod tests {
                     full: 1,
                     differential: 0,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1055,9 +2873,16 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![FullBackup {
                             name: ".".to_string(),
                             when: now(),
+                            host: Some(whoami::hostname()),
+                            locked: false,
                             children: vec![],
                         }]),
                     },
@@ -1065,7 +2890,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
 
@@ -1085,9 +2910,16 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from_iter(vec![FullBackup {
                         name: ".".to_string(),
                         when: past(),
+                        host: None,
+                        locked: false,
                         children: vec![],
                     }]),
                 },
@@ -1095,6 +2927,10 @@ mod tests {
                     full: 2,
                     differential: 0,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1102,15 +2938,24 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![
                             FullBackup {
                                 name: ".".to_string(),
                                 when: past(),
+                                host: None,
+                                locked: false,
                                 children: vec![],
                             },
                             FullBackup {
                                 name: format!("full-{}", now_str()),
                                 when: now(),
+                                host: Some(whoami::hostname()),
+                                locked: false,
                                 children: vec![],
                             },
                         ]),
@@ -1119,8 +2964,228 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
+            );
+        }
+
+        #[test]
+        fn can_find_backups_to_prune() {
+            let path = StrictPath::from(std::env::temp_dir().join("ludusavi-test-prune-preview"));
+            let _ = path.remove();
+            for name in ["full-1", "diff-1", "full-2", "diff-2"] {
+                std::fs::create_dir_all(path.joined(name).interpret()).unwrap();
+            }
+            let diff = |name: &str| DifferentialBackup {
+                name: name.to_string(),
+                when: past(),
+                host: None,
+                omit: Default::default(),
+            };
+            let mut layout = GameLayout {
+                path: path.clone(),
+                mapping: IndividualMapping {
+                    name: "game1".to_string(),
+                    backups: VecDeque::from_iter(vec![
+                        FullBackup {
+                            name: "full-1".to_string(),
+                            when: past(),
+                            host: None,
+                            locked: false,
+                            children: vec![diff("diff-1")],
+                        },
+                        FullBackup {
+                            name: "full-2".to_string(),
+                            when: past(),
+                            host: None,
+                            locked: false,
+                            children: vec![diff("diff-2")],
+                        },
+                    ]),
+                    ..Default::default()
+                },
+                retention: Retention {
+                    full: 2,
+                    differential: 1,
+                },
+                ..Default::default()
+            };
+            assert_eq!(
+                vec![path.joined("full-1"), path.joined("diff-1")],
+                layout.backups_to_prune()
+            );
+
+            // There's still room for another differential backup.
+            layout.retention.differential = 2;
+            assert_eq!(Vec::<StrictPath>::new(), layout.backups_to_prune());
+
+            let _ = path.remove();
+        }
+
+        #[test]
+        fn can_compare_backups() {
+            let path = StrictPath::from(std::env::temp_dir().join("ludusavi-test-compare"));
+            let _ = path.remove();
+            for (backup, file, content) in [
+                ("full-1", "a.txt", "a"),
+                ("full-1", "b.txt", "b"),
+                ("full-1", "c.txt", "c"),
+                ("full-1", "e.txt", "e"),
+                ("diff-1", "b.txt", "b2"),
+                ("diff-1", "d.txt", "d"),
+                ("diff-1", "e.txt", "e"),
+            ] {
+                let file = path.joined(backup).joined("drive-0").joined(file);
+                file.create_parent_dir().unwrap();
+                std::fs::write(file.interpret(), content).unwrap();
+            }
+            let layout = GameLayout {
+                path: path.clone(),
+                mapping: IndividualMapping {
+                    name: "game1".to_string(),
+                    drives: hashmap! { "drive-0".to_string() => "".to_string() },
+                    backups: VecDeque::from_iter(vec![FullBackup {
+                        name: "full-1".to_string(),
+                        when: past(),
+                        host: None,
+                        locked: false,
+                        children: vec![DifferentialBackup {
+                            name: "diff-1".to_string(),
+                            when: past(),
+                            host: None,
+                            omit: BackupOmission {
+                                files: hashset! { "/c.txt".to_string() },
+                                registry: false,
+                            },
+                        }],
+                    }]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            assert_eq!(
+                Some(BackupComparison {
+                    added: btreeset! { "/d.txt".to_string() },
+                    removed: btreeset! { "/c.txt".to_string() },
+                    changed: btreeset! { "/b.txt".to_string() },
+                }),
+                layout.compare_backups("full-1", "diff-1"),
             );
+            assert_eq!(
+                Some(BackupComparison::default()),
+                layout.compare_backups("diff-1", "diff-1")
+            );
+            assert_eq!(None, layout.compare_backups("full-1", "diff-2"));
+
+            let _ = path.remove();
+        }
+
+        #[test]
+        fn can_deduplicate_identical_files_across_games() {
+            let base = StrictPath::from(std::env::temp_dir().join("ludusavi-test-dedupe"));
+            let _ = base.remove();
+            let source = base.joined("source.txt");
+            source.create_parent_dir().unwrap();
+            std::fs::write(source.interpret(), "same").unwrap();
+
+            let layout = BackupLayout::new(base.joined("backup"), Retention::default()).with_dedupe(true);
+            let mut game1 = layout.game_layout("game1");
+            let mut game2 = layout.game_layout("game2");
+            let target1 = game1.path.joined("drive-0/file.txt");
+            let target2 = game2.path.joined("drive-0/file.txt");
+            for (game, target) in [(&mut game1, &target1), (&mut game2, &target2)] {
+                target.create_parent_dir().unwrap();
+                game.write_stored(&source, target, &CopyOptions::default()).unwrap();
+                game.save().unwrap();
+            }
+
+            let hash = hash_file(&source).unwrap();
+            let object = dedupe_object(&layout.dedupe_store(), &hash);
+            assert!(object.is_file());
+            assert_eq!(Some(&hash), game1.mapping.objects.get("drive-0/file.txt"));
+            assert_eq!(Some(&hash), game2.mapping.objects.get("drive-0/file.txt"));
+            assert_eq!("same", std::fs::read_to_string(target1.interpret()).unwrap());
+            assert_eq!("same", std::fs::read_to_string(target2.interpret()).unwrap());
+
+            // Overwriting one game's file must not change the other game's copy.
+            std::fs::write(source.interpret(), "different").unwrap();
+            game1.write_stored(&source, &target1, &CopyOptions::default()).unwrap();
+            game1.save().unwrap();
+            assert_eq!("different", std::fs::read_to_string(target1.interpret()).unwrap());
+            assert_eq!("same", std::fs::read_to_string(target2.interpret()).unwrap());
+
+            layout.prune_dedupe_store();
+            assert!(object.is_file());
+
+            let _ = game2.path.remove();
+            layout.prune_dedupe_store();
+            assert!(!object.is_file());
+
+            let _ = base.remove();
+        }
+
+        #[test]
+        fn backup_without_files_keeps_previously_backed_up_files() {
+            let base = StrictPath::from(std::env::temp_dir().join("ludusavi-test-backup-kinds"));
+            let _ = base.remove();
+            let source = base.joined("source.txt");
+            source.create_parent_dir().unwrap();
+            std::fs::write(source.interpret(), "foo").unwrap();
+
+            let scan = ScanInfo {
+                game_name: "game1".to_string(),
+                found_files: hashset! { ScannedFile::new(source.render(), 3) },
+                found_registry_keys: hashset! { ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game1") },
+                registry_file: None,
+            };
+            let layout = BackupLayout::new(base.joined("backup"), Retention::default());
+            let mut game = layout.game_layout("game1");
+            game.back_up(&scan, true, true, &now(), &CopyOptions::default());
+            assert_eq!(1, game.restorable_files().len());
+
+            assert!(!game.would_drop_left_out_data(&BackupKind::Full, true, true));
+            assert!(game.would_drop_left_out_data(&BackupKind::Full, false, true));
+
+            // A registry-only backup must not replace the full backup and lose its files.
+            game.back_up(
+                &scan.only_kinds(false, true),
+                false,
+                true,
+                &now(),
+                &CopyOptions::default(),
+            );
+            assert_eq!(1, game.restorable_files().len());
+
+            let _ = base.remove();
+        }
+
+        #[test]
+        fn archived_backup_removes_unpacked_copy() {
+            let base = StrictPath::from(std::env::temp_dir().join("ludusavi-test-archive-scratch"));
+            let _ = base.remove();
+            let source = base.joined("source.txt");
+            source.create_parent_dir().unwrap();
+            std::fs::write(source.interpret(), "foo").unwrap();
+
+            let scan = ScanInfo {
+                game_name: "game1".to_string(),
+                found_files: hashset! { ScannedFile::new(source.render(), 3) },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            };
+            let layout =
+                BackupLayout::new(base.joined("backup"), Retention::default()).with_format(BackupFormat::TarGz);
+            let mut game = layout.game_layout("game1");
+            game.back_up(&scan, true, true, &now(), &CopyOptions::default());
+            assert!(base.joined("backup/game1").joined(ARCHIVE_FILE).is_file());
+            assert!(!game.path.exists());
+
+            let game = layout.game_layout("game1");
+            assert_eq!(1, game.restorable_files().len());
+            game.remove_archive_scratch();
+            assert!(!game.path.exists());
+
+            let _ = base.remove();
         }
 
         #[test]
@@ -1139,15 +3204,24 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from_iter(vec![
                         FullBackup {
                             name: ".".to_string(),
                             when: past(),
+                            host: None,
+                            locked: false,
                             children: vec![],
                         },
                         FullBackup {
                             name: format!("full-{}", past2_str()),
                             when: past2(),
+                            host: None,
+                            locked: false,
                             children: vec![],
                         },
                     ]),
@@ -1156,6 +3230,10 @@ mod tests {
                     full: 2,
                     differential: 0,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1163,15 +3241,24 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![
                             FullBackup {
                                 name: format!("full-{}", past2_str()),
                                 when: past2(),
+                                host: None,
+                                locked: false,
                                 children: vec![],
                             },
                             FullBackup {
                                 name: format!("full-{}", now_str()),
                                 when: now(),
+                                host: Some(whoami::hostname()),
+                                locked: false,
                                 children: vec![],
                             },
                         ]),
@@ -1180,7 +3267,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
 
@@ -1200,9 +3287,16 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from_iter(vec![FullBackup {
                         name: ".".to_string(),
                         when: past(),
+                        host: None,
+                        locked: false,
                         children: vec![],
                     }]),
                 },
@@ -1210,6 +3304,10 @@ mod tests {
                     full: 1,
                     differential: 1,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1217,12 +3315,20 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![FullBackup {
                             name: ".".to_string(),
                             when: past(),
+                            host: None,
+                            locked: false,
                             children: vec![DifferentialBackup {
                                 name: format!("diff-{}", now_str()),
                                 when: now(),
+                                host: Some(whoami::hostname()),
                                 omit: Default::default(),
                             },],
                         },]),
@@ -1231,7 +3337,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
 
@@ -1251,12 +3357,20 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from(vec![FullBackup {
                         name: ".".to_string(),
                         when: past(),
+                        host: None,
+                        locked: false,
                         children: vec![DifferentialBackup {
                             name: format!("diff-{}", past2_str()),
                             when: past2(),
+                            host: None,
                             omit: Default::default(),
                         }],
                     }]),
@@ -1265,6 +3379,10 @@ mod tests {
                     full: 2,
                     differential: 1,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1272,19 +3390,29 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![
                             FullBackup {
                                 name: ".".to_string(),
                                 when: past(),
+                                host: None,
+                                locked: false,
                                 children: vec![DifferentialBackup {
                                     name: format!("diff-{}", past2_str()),
                                     when: past2(),
+                                    host: None,
                                     omit: Default::default(),
                                 },],
                             },
                             FullBackup {
                                 name: format!("full-{}", now_str()),
                                 when: now(),
+                                host: Some(whoami::hostname()),
+                                locked: false,
                                 children: vec![],
                             },
                         ]),
@@ -1293,7 +3421,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
 
@@ -1313,12 +3441,20 @@ mod tests {
                 mapping: IndividualMapping {
                     name: "game1".to_string(),
                     drives: drives(),
+                    metadata: Default::default(),
+                    objects: Default::default(),
+                    layout: Default::default(),
+                    format: Default::default(),
+                    encryption: None,
                     backups: VecDeque::from(vec![FullBackup {
                         name: format!("full-{}", past_str()),
                         when: past(),
+                        host: None,
+                        locked: false,
                         children: vec![DifferentialBackup {
                             name: format!("diff-{}", past2_str()),
                             when: past2(),
+                            host: None,
                             omit: Default::default(),
                         }],
                     }]),
@@ -1327,6 +3463,10 @@ mod tests {
                     full: 1,
                     differential: 1,
                 },
+                cipher: None,
+                archive: None,
+                archive_unreadable: false,
+                dedupe_store: None,
             };
             assert_eq!(
                 Some(BackupPlan {
@@ -1334,9 +3474,16 @@ mod tests {
                     mapping: IndividualMapping {
                         name: "game1".to_string(),
                         drives: drives(),
+                        metadata: Default::default(),
+                        objects: Default::default(),
+                        layout: Default::default(),
+                        format: Default::default(),
+                        encryption: None,
                         backups: VecDeque::from(vec![FullBackup {
                             name: ".".to_string(),
                             when: now(),
+                            host: Some(whoami::hostname()),
+                            locked: false,
                             children: vec![],
                         },]),
                     },
@@ -1344,7 +3491,7 @@ mod tests {
                     files: scan.found_files.clone(),
                     registry: hashset! {},
                 }),
-                layout.plan_backup(&scan, &now()),
+                layout.plan_backup(&scan, true, true, &now()),
             );
         }
     }
@@ -0,0 +1,33 @@
+//! Ludusavi's scanning, backup, and restore logic, without the GUI or CLI.
+//!
+//! To use this as a library, disable the default `app` feature so that the
+//! GUI and CLI dependencies aren't pulled in:
+//!
+//! ```toml
+//! ludusavi = { version = "*", default-features = false }
+//! ```
+//!
+//! The main entry points are:
+//!
+//! * [`config::Config`] and [`manifest::Manifest`] for the user's settings and
+//!   the known save locations of each game.
+//! * [`prelude::scan_game_for_backup`] and [`prelude::back_up_game`] to find
+//!   and back up a game's saves, described by a [`prelude::ScanInfo`].
+//! * [`layout::BackupLayout`], [`prelude::scan_game_for_restoration`], and
+//!   [`prelude::restore_game`] to read and restore existing backups.
+
+pub mod config;
+mod encryption;
+pub mod history;
+pub mod lang;
+pub mod layout;
+pub mod manifest;
+pub mod path;
+pub mod prelude;
+pub mod registry_compat;
+pub mod serialization;
+pub mod sftp;
+pub mod staging;
+
+#[cfg(target_os = "windows")]
+pub mod registry;
@@ -1,19 +1,12 @@
 mod cli;
-mod config;
 mod gui;
-mod lang;
-mod layout;
-mod manifest;
-mod path;
-mod prelude;
-mod registry_compat;
-mod serialization;
 mod shortcuts;
+mod tui;
 
-#[cfg(target_os = "windows")]
-mod registry;
+use ludusavi::{config, history, lang, layout, manifest, path, prelude, registry_compat, serialization, sftp, staging};
 
 fn main() {
+    path::capture_startup_dir();
     prelude::migrate_legacy_config();
 
     let args = cli::parse_cli();
@@ -32,7 +25,11 @@ fn main() {
                     std::process::exit(1);
                 }
             }
-            gui::run_gui();
+            if let Err(e) = gui::run_gui() {
+                let translator = crate::lang::Translator::default();
+                eprintln!("{}", translator.handle_error(&e));
+                std::process::exit(1);
+            }
         }
         Some(sub) => {
             if let Err(e) = cli::run_cli(sub) {
@@ -1,5 +1,7 @@
 use crate::{
-    config::{Config, CustomGame},
+    config::{Config, CustomGame, RootsConfig, SecondaryManifestConfig},
+    layout::{hash_bytes, hash_text},
+    path::{find_placeholders, PLACEHOLDERS},
     prelude::{app_dir, Error, StrictPath},
 };
 
@@ -96,6 +98,16 @@ impl Default for Tag {
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Manifest(pub std::collections::HashMap<String, Game>);
 
+/// Name of the pseudo-game for Steam's own per-game settings.
+pub const STEAM_ACCOUNT_SETTINGS: &str = "Steam account settings";
+
+/// Files under a Steam root with per-game settings, like launch options and controller configs.
+const STEAM_ACCOUNT_SETTINGS_FILES: &[&str] = &[
+    "userdata/*/config/localconfig.vdf",
+    "userdata/*/241100/remote/controller_config",
+    "steamapps/common/Steam Controller Configs/*/config",
+];
+
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Game {
     pub files: Option<std::collections::HashMap<String, GameFileEntry>>,
@@ -103,6 +115,9 @@ pub struct Game {
     pub install_dir: Option<std::collections::HashMap<String, GameInstallDirEntry>>,
     pub registry: Option<std::collections::HashMap<String, GameRegistryEntry>>,
     pub steam: Option<SteamMetadata>,
+    /// Labels like `multiplayer`, so that whole kinds of games can be excluded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -136,6 +151,94 @@ pub struct SteamMetadata {
     pub id: Option<u32>,
 }
 
+/// Something wrong with a manifest entry that could be worked around while loading.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ManifestProblem {
+    /// Only the last entry with this name was kept.
+    DuplicateGame { game: String },
+    /// The whole entry was skipped.
+    MalformedGame { game: String, why: String },
+    /// The path was skipped.
+    EmptyPath { game: String },
+    /// The path was skipped.
+    UnknownPlaceholder {
+        game: String,
+        path: String,
+        placeholder: String,
+    },
+    /// A secondary manifest replaced an earlier entry with this name.
+    OverriddenGame { game: String, source: String },
+    /// A secondary manifest could not be read, so it was left out.
+    UnavailableSource { source: String },
+}
+
+/// Find top-level keys that appear more than once, since the YAML parser
+/// would otherwise silently keep only the last one.
+fn find_duplicate_keys(content: &str) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates = std::collections::BTreeSet::new();
+
+    for line in content.lines() {
+        if line.is_empty() || line.starts_with(char::is_whitespace) || line.starts_with(['#', '-', '.']) {
+            continue;
+        }
+        if let Ok(mapping) = serde_yaml::from_str::<serde_yaml::Mapping>(line) {
+            for key in mapping.iter().filter_map(|(k, _)| k.as_str()) {
+                if !seen.insert(key.to_string()) {
+                    duplicates.insert(key.to_string());
+                }
+            }
+        }
+    }
+
+    duplicates.into_iter().collect()
+}
+
+impl Game {
+    pub fn in_any_category(&self, categories: &std::collections::HashSet<String>) -> bool {
+        self.categories
+            .as_ref()
+            .map(|xs| xs.iter().any(|x| categories.contains(x)))
+            .unwrap_or(false)
+    }
+
+    /// Drop any paths that can't be used, and report why.
+    fn remove_invalid_paths(&mut self, name: &str) -> Vec<ManifestProblem> {
+        let mut problems = vec![];
+
+        if let Some(files) = &mut self.files {
+            files.retain(|path, _| {
+                if path.trim().is_empty() {
+                    problems.push(ManifestProblem::EmptyPath { game: name.to_string() });
+                    return false;
+                }
+                match find_placeholders(path).into_iter().find(|x| !PLACEHOLDERS.contains(x)) {
+                    Some(placeholder) => {
+                        problems.push(ManifestProblem::UnknownPlaceholder {
+                            game: name.to_string(),
+                            path: path.to_string(),
+                            placeholder: placeholder.to_string(),
+                        });
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        if let Some(registry) = &mut self.registry {
+            registry.retain(|path, _| {
+                if path.trim().is_empty() {
+                    problems.push(ManifestProblem::EmptyPath { game: name.to_string() });
+                    return false;
+                }
+                true
+            });
+        }
+
+        problems
+    }
+}
+
 impl From<CustomGame> for Game {
     fn from(item: CustomGame) -> Self {
         let file_tuples = item.files.iter().map(|x| (x.to_string(), GameFileEntry::default()));
@@ -152,6 +255,7 @@ impl From<CustomGame> for Game {
             install_dir: None,
             registry: Some(registry),
             steam: None,
+            categories: None,
         }
     }
 }
@@ -164,48 +268,214 @@ impl Manifest {
     }
 
     pub fn load(config: &mut Config, update: bool) -> Result<Self, Error> {
+        Self::load_checked(config, update).map(|(manifest, _)| manifest)
+    }
+
+    /// Where a remote secondary manifest is cached.
+    fn secondary_file(url: &str) -> std::path::PathBuf {
+        let mut path = app_dir();
+        path.push("manifests");
+        path.push(format!("{}.yaml", hash_text(url)));
+        path
+    }
+
+    /// Like `load`, but also returns the problems with any entries that had to be skipped.
+    /// Secondary manifests are layered on top in order, and any of them that
+    /// can't be read are reported instead of failing the whole load.
+    pub fn load_checked(config: &mut Config, update: bool) -> Result<(Self, Vec<ManifestProblem>), Error> {
         if update || !StrictPath::from_std_path_buf(&Self::file()).exists() {
             Self::update(config)?;
         }
         let content = std::fs::read_to_string(Self::file()).unwrap();
-        Self::load_from_string(&content)
+        let (mut manifest, mut problems) = Self::load_from_string_checked(&content)?;
+
+        let mut secondary_updated = false;
+        for secondary in &mut config.manifest.secondary {
+            let source = secondary.source();
+            let content = match secondary {
+                SecondaryManifestConfig::Local { path } => std::fs::read_to_string(path.interpret()).ok(),
+                SecondaryManifestConfig::Remote { url, etag } => {
+                    let file = Self::secondary_file(url);
+                    if update || !file.exists() {
+                        // A failed update can still fall back to the cached copy.
+                        secondary_updated |= Self::download(url, etag, &file, None).unwrap_or(false);
+                    }
+                    std::fs::read_to_string(file).ok()
+                }
+            };
+            match content.map(|x| Self::load_from_string_checked(&x)) {
+                Some(Ok((other, other_problems))) => {
+                    problems.extend(other_problems);
+                    problems.extend(manifest.layer(other, &source));
+                }
+                _ => problems.push(ManifestProblem::UnavailableSource { source }),
+            }
+        }
+        if secondary_updated {
+            config.save()?;
+        }
+
+        problems.sort();
+        Ok((manifest, problems))
+    }
+
+    /// Add the games from another manifest, replacing any with the same name.
+    pub fn layer(&mut self, other: Self, source: &str) -> Vec<ManifestProblem> {
+        let mut problems = vec![];
+        for (name, game) in other.0 {
+            if self.0.contains_key(&name) {
+                problems.push(ManifestProblem::OverriddenGame {
+                    game: name.clone(),
+                    source: source.to_string(),
+                });
+            }
+            self.0.insert(name, game);
+        }
+        problems
     }
 
     pub fn load_from_string(content: &str) -> Result<Self, Error> {
-        serde_yaml::from_str(content).map_err(|e| Error::ManifestInvalid { why: format!("{}", e) })
+        Self::load_from_string_checked(content).map(|(manifest, _)| manifest)
+    }
+
+    /// Bad entries are skipped instead of failing the whole manifest.
+    /// This only fails if the content isn't a mapping of game names at all.
+    pub fn load_from_string_checked(content: &str) -> Result<(Self, Vec<ManifestProblem>), Error> {
+        let raw: std::collections::HashMap<String, serde_yaml::Value> =
+            serde_yaml::from_str(content).map_err(|e| Error::ManifestInvalid { why: format!("{}", e) })?;
+
+        let mut problems: Vec<_> = find_duplicate_keys(content)
+            .into_iter()
+            .map(|game| ManifestProblem::DuplicateGame { game })
+            .collect();
+        let mut games = std::collections::HashMap::new();
+
+        for (name, value) in raw {
+            match serde_yaml::from_value::<Game>(value) {
+                Ok(mut game) => {
+                    problems.extend(game.remove_invalid_paths(&name));
+                    games.insert(name, game);
+                }
+                Err(e) => problems.push(ManifestProblem::MalformedGame {
+                    game: name,
+                    why: e.to_string(),
+                }),
+            }
+        }
+
+        problems.sort();
+        Ok((Self(games), problems))
     }
 
     pub fn update(config: &mut Config) -> Result<(), Error> {
-        let mut req = reqwest::blocking::Client::new().get(&config.manifest.url);
-        if let Some(etag) = &config.manifest.etag {
-            if StrictPath::from_std_path_buf(&Self::file()).exists() {
+        let checksum = match &config.manifest.checksum_url {
+            Some(url) => Some(Self::fetch_checksum(url)?),
+            None => None,
+        };
+        if Self::download(
+            &config.manifest.url,
+            &mut config.manifest.etag,
+            &Self::file(),
+            checksum.as_deref(),
+        )? {
+            config.manifest.updated = Some(chrono::Utc::now());
+            config.save()?;
+        }
+        Ok(())
+    }
+
+    /// Get a published checksum, in the format of `sha256sum` output.
+    fn fetch_checksum(url: &str) -> Result<String, Error> {
+        let text = reqwest::blocking::get(url)
+            .and_then(|x| x.error_for_status())
+            .and_then(|x| x.text())
+            .map_err(|_| Error::ManifestCannotBeUpdated)?;
+        text.split_whitespace()
+            .next()
+            .map(|x| x.to_lowercase())
+            .ok_or(Error::ManifestCannotBeUpdated)
+    }
+
+    /// Check downloaded content before it replaces the local copy.
+    fn verify_download(content: &[u8], checksum: Option<&str>) -> Result<(), Error> {
+        if let Some(checksum) = checksum {
+            if hash_bytes(content) != checksum {
+                return Err(Error::ManifestChecksumMismatch);
+            }
+        }
+        let content = String::from_utf8_lossy(content);
+        Self::load_from_string(&content).map_err(|e| match e {
+            Error::ManifestInvalid { why } => Error::ManifestDownloadInvalid { why },
+            e => e,
+        })?;
+        Ok(())
+    }
+
+    /// Download a manifest to `file`, unless the etag shows that the local copy is current.
+    /// Returns whether the file was downloaded.
+    ///
+    /// The download goes to a temporary file first and only replaces `file`
+    /// once it's been verified, so an interrupted download can't break the local copy.
+    fn download(
+        url: &str,
+        etag: &mut Option<String>,
+        file: &std::path::Path,
+        checksum: Option<&str>,
+    ) -> Result<bool, Error> {
+        let mut req = reqwest::blocking::Client::new().get(url);
+        if let Some(etag) = etag.as_ref() {
+            if file.exists() {
                 req = req.header(reqwest::header::IF_NONE_MATCH, etag);
             }
         }
         let mut res = req.send().map_err(|_e| Error::ManifestCannotBeUpdated)?;
         match res.status() {
             reqwest::StatusCode::OK => {
-                std::fs::create_dir_all(app_dir()).map_err(|_| Error::ManifestCannotBeUpdated)?;
-                let mut file = std::fs::File::create(Self::file()).map_err(|_| Error::ManifestCannotBeUpdated)?;
-                res.copy_to(&mut file).map_err(|_| Error::ManifestCannotBeUpdated)?;
-
-                if let Some(etag) = res.headers().get(reqwest::header::ETAG) {
-                    match &config.manifest.etag {
-                        Some(old_etag) if etag == old_etag => (),
-                        _ => {
-                            config.manifest.etag = Some(String::from_utf8_lossy(etag.as_bytes()).to_string());
-                            config.save();
-                        }
-                    }
+                if let Some(parent) = file.parent() {
+                    std::fs::create_dir_all(parent).map_err(|_| Error::ManifestCannotBeUpdated)?;
+                }
+                let mut content = vec![];
+                res.copy_to(&mut content).map_err(|_| Error::ManifestCannotBeUpdated)?;
+                Self::verify_download(&content, checksum)?;
+
+                let partial = file.with_extension("yaml.part");
+                std::fs::write(&partial, &content).map_err(|_| Error::ManifestCannotBeUpdated)?;
+                if std::fs::rename(&partial, file).is_err() {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(Error::ManifestCannotBeUpdated);
                 }
 
-                Ok(())
+                if let Some(new_etag) = res.headers().get(reqwest::header::ETAG) {
+                    *etag = Some(String::from_utf8_lossy(new_etag.as_bytes()).to_string());
+                }
+                Ok(true)
             }
-            reqwest::StatusCode::NOT_MODIFIED => Ok(()),
+            reqwest::StatusCode::NOT_MODIFIED => Ok(false),
             _ => Err(Error::ManifestCannotBeUpdated),
         }
     }
 
+    pub fn get(&self, key: &str) -> Option<&Game> {
+        self.0.get(key)
+    }
+
+    /// Every category used by some game, sorted.
+    pub fn categories(&self) -> Vec<String> {
+        let categories: std::collections::BTreeSet<_> = self
+            .0
+            .values()
+            .flat_map(|game| game.categories.iter().flatten().cloned())
+            .collect();
+        categories.into_iter().collect()
+    }
+
+    /// Drop games in any of these categories.
+    pub fn exclude_categories(&mut self, categories: &std::collections::HashSet<String>) {
+        if !categories.is_empty() {
+            self.0.retain(|_, game| !game.in_any_category(categories));
+        }
+    }
+
     pub fn map_steam_ids_to_names(&self) -> std::collections::HashMap<u32, String> {
         self.0
             .iter()
@@ -219,18 +489,44 @@ impl Manifest {
     pub fn add_custom_game(&mut self, custom: CustomGame) {
         let name = custom.name.clone();
         let mut game: Game = custom.into();
+        // The editor can leave blank rows, which aren't worth reporting.
+        game.remove_invalid_paths(&name);
         if let Some(existing) = self.0.get(&name) {
             game.steam = existing.steam.clone();
             game.install_dir = existing.install_dir.clone();
         }
         self.0.insert(name, game);
     }
+
+    /// Add a pseudo-game for Steam's own per-game settings in each Steam root.
+    /// This does nothing if there are no Steam roots.
+    pub fn add_steam_account_settings(&mut self, roots: &[RootsConfig]) {
+        let files: std::collections::HashMap<_, _> = roots
+            .iter()
+            .filter(|root| root.store == Store::Steam && !root.ignore)
+            .flat_map(|root| {
+                STEAM_ACCOUNT_SETTINGS_FILES
+                    .iter()
+                    .map(move |file| (format!("{}/{}", root.path.interpret(), file), GameFileEntry::default()))
+            })
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+        self.0.insert(
+            STEAM_ACCOUNT_SETTINGS.to_string(),
+            Game {
+                files: Some(files),
+                ..Default::default()
+            },
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use maplit::hashmap;
+    use maplit::{hashmap, hashset};
     use pretty_assertions::assert_eq;
 
     fn s(text: &str) -> String {
@@ -252,11 +548,33 @@ mod tests {
                 install_dir: None,
                 registry: None,
                 steam: None,
+                categories: None,
             },
             manifest.0["game"],
         );
     }
 
+    #[test]
+    fn can_get_game_by_name() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game:
+              steam:
+                id: 123
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(&Game {
+                steam: Some(SteamMetadata { id: Some(123) }),
+                ..Default::default()
+            }),
+            manifest.get("game"),
+        );
+        assert_eq!(None, manifest.get("other"));
+    }
+
     #[test]
     fn can_parse_game_with_all_fields() {
         let manifest = Manifest::load_from_string(
@@ -310,6 +628,48 @@ mod tests {
                     },
                 }),
                 steam: Some(SteamMetadata { id: Some(123) }),
+                categories: None,
+            },
+            manifest.0["game"],
+        );
+    }
+
+    #[test]
+    fn can_parse_game_with_unknown_fields_and_values() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game:
+              files:
+                foo:
+                  when:
+                    - os: dos
+                      store: itch
+                      bitness: 64
+                  tags:
+                    - screenshot
+                  notes: Extended schema
+              gog:
+                id: 456
+              cloud:
+                steam: true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Game {
+                files: Some(hashmap! {
+                    s("foo") => GameFileEntry {
+                        when: Some(vec![
+                            GameFileConstraint {
+                                os: Some(Os::Other),
+                                store: Some(Store::Other),
+                            }
+                        ]),
+                        tags: Some(vec![Tag::Other]),
+                    }
+                }),
+                ..Default::default()
             },
             manifest.0["game"],
         );
@@ -483,4 +843,151 @@ mod tests {
 
         assert_eq!(&SteamMetadata { id: None }, manifest.0["game"].steam.as_ref().unwrap());
     }
+
+    #[test]
+    fn skips_invalid_entries_and_reports_them() {
+        let (manifest, problems) = Manifest::load_from_string_checked(
+            r#"
+game1:
+  files:
+    <base>/file.txt: {}
+    "": {}
+    <bogus>/file.txt: {}
+game2:
+  files: 5
+game1:
+  files:
+    <base>/file.txt: {}
+    "": {}
+    <bogus>/file.txt: {}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Manifest(hashmap! {
+                s("game1") => Game {
+                    files: Some(hashmap! {
+                        s("<base>/file.txt") => GameFileEntry::default(),
+                    }),
+                    ..Default::default()
+                },
+            }),
+            manifest,
+        );
+        assert_eq!(4, problems.len());
+        assert_eq!(ManifestProblem::DuplicateGame { game: s("game1") }, problems[0]);
+        assert!(matches!(&problems[1], ManifestProblem::MalformedGame { game, .. } if game == "game2"));
+        assert_eq!(ManifestProblem::EmptyPath { game: s("game1") }, problems[2]);
+        assert_eq!(
+            ManifestProblem::UnknownPlaceholder {
+                game: s("game1"),
+                path: s("<bogus>/file.txt"),
+                placeholder: s("<bogus>"),
+            },
+            problems[3],
+        );
+    }
+
+    #[test]
+    fn can_exclude_games_by_category() {
+        let mut manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              categories: [multiplayer]
+            game2:
+              categories: [demo, singleplayer]
+            game3: {}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![s("demo"), s("multiplayer"), s("singleplayer")],
+            manifest.categories()
+        );
+
+        manifest.exclude_categories(&hashset! { s("multiplayer"), s("demo") });
+        let mut remaining: Vec<_> = manifest.0.keys().cloned().collect();
+        remaining.sort();
+        assert_eq!(vec![s("game3")], remaining);
+    }
+
+    #[test]
+    fn can_layer_secondary_manifest() {
+        let mut manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              steam:
+                id: 1
+            game2: {}
+            "#,
+        )
+        .unwrap();
+        let other = Manifest::load_from_string(
+            r#"
+            game1:
+              steam:
+                id: 2
+            game3: {}
+            "#,
+        )
+        .unwrap();
+
+        let problems = manifest.layer(other, "extra.yaml");
+
+        assert_eq!(
+            vec![ManifestProblem::OverriddenGame {
+                game: s("game1"),
+                source: s("extra.yaml"),
+            }],
+            problems,
+        );
+        assert_eq!(Some(2), manifest.0["game1"].steam.as_ref().and_then(|x| x.id));
+        assert!(manifest.0.contains_key("game2"));
+        assert!(manifest.0.contains_key("game3"));
+    }
+
+    #[test]
+    fn can_verify_downloaded_manifest() {
+        let content = b"game1: {}\n";
+        // Output of `printf 'game1: {}\n' | sha256sum`.
+        let checksum = "29bb993ccf7d13a41245f7fe82c5567092217343c6503a46d481d9c97d73da47";
+
+        assert_eq!(Ok(()), Manifest::verify_download(content, None));
+        assert_eq!(Ok(()), Manifest::verify_download(content, Some(checksum)));
+        assert_eq!(
+            Err(Error::ManifestChecksumMismatch),
+            Manifest::verify_download(b"game2: {}\n", Some(checksum))
+        );
+        assert!(matches!(
+            Manifest::verify_download(b"game1: [", None),
+            Err(Error::ManifestDownloadInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn can_add_steam_account_settings() {
+        let mut manifest = Manifest::default();
+        manifest.add_steam_account_settings(&[RootsConfig {
+            path: StrictPath::new(s("/other")),
+            store: Store::Other,
+            ignore: false,
+            base_template: None,
+        }]);
+        assert!(manifest.0.is_empty());
+
+        manifest.add_steam_account_settings(&[RootsConfig {
+            path: StrictPath::new(s("/steam")),
+            store: Store::Steam,
+            ignore: false,
+            base_template: None,
+        }]);
+        let files = manifest.0[STEAM_ACCOUNT_SETTINGS].files.as_ref().unwrap();
+        assert!(files.contains_key(&format!(
+            "{}/userdata/*/config/localconfig.vdf",
+            StrictPath::new(s("/steam")).interpret()
+        )));
+        assert_eq!(STEAM_ACCOUNT_SETTINGS_FILES.len(), files.len());
+    }
 }
@@ -1,3 +1,13 @@
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    config::RootsConfig,
+    manifest::{Os, Store},
+    prelude::get_os,
+};
+
 #[cfg(target_os = "windows")]
 const TYPICAL_SEPARATOR: &str = "\\";
 #[cfg(target_os = "windows")]
@@ -12,6 +22,21 @@ const ATYPICAL_SEPARATOR: &str = "\\";
 const UNC_PREFIX: &str = "\\\\";
 #[allow(dead_code)]
 const UNC_LOCAL_PREFIX: &str = "\\\\?\\";
+/// Extended-length form of a network path like `\\server\share`.
+const UNC_NETWORK_PREFIX: &str = "\\\\?\\UNC\\";
+
+/// Used in place of manifest placeholders that do not apply to the current situation.
+pub const SKIP: &str = "<skip>";
+
+/// Relative paths without a basis are resolved against the working directory
+/// from startup, so that their meaning can't change partway through a session.
+static STARTUP_DIR: Lazy<std::path::PathBuf> = Lazy::new(|| std::env::current_dir().unwrap_or_default());
+
+/// Capture the working directory for resolving relative paths.
+/// This should be called before anything could change the working directory.
+pub fn capture_startup_dir() {
+    Lazy::force(&STARTUP_DIR);
+}
 
 fn parse_home(path: &str) -> String {
     if path == "~" || path.starts_with("~/") || path.starts_with("~\\") {
@@ -26,7 +51,7 @@ fn normalize(path: &str) -> String {
 
     #[cfg(target_os = "windows")]
     if path.starts_with('/') {
-        let drive = &render_pathbuf(&std::env::current_dir().unwrap())[..2];
+        let drive = &render_pathbuf(&STARTUP_DIR)[..2];
         path = format!("{}{}", drive, path)
     }
 
@@ -85,7 +110,8 @@ fn parse_dots(path: &str, basis: &str) -> String {
 }
 
 /// Convert a raw, possibly user-provided path into a suitable form for internal use.
-/// On Windows, this produces UNC paths.
+/// On Windows, this produces UNC paths, which are not subject to the usual
+/// 260-character limit on path length.
 fn interpret<P: Into<String>>(path: P, basis: &Option<String>) -> String {
     let normalized = normalize(&path.into());
     if normalized.is_empty() {
@@ -96,40 +122,41 @@ fn interpret<P: Into<String>>(path: P, basis: &Option<String>) -> String {
     } else {
         render_pathbuf(
             &match basis {
-                None => std::env::current_dir().unwrap(),
+                None => STARTUP_DIR.clone(),
                 Some(b) => std::path::Path::new(&normalize(b)).to_path_buf(),
             }
             .join(normalized),
         )
     };
 
-    match std::fs::canonicalize(&absolutized) {
-        Ok(x) => render_pathbuf(&x),
-        Err(_) => {
-            let dedotted = parse_dots(
-                &absolutized,
-                &render_pathbuf(&match basis {
-                    None => std::env::current_dir().unwrap(),
-                    Some(b) => std::path::Path::new(&normalize(b)).to_path_buf(),
-                }),
-            );
-            format!(
-                "{}{}",
-                if cfg!(target_os = "windows") && !dedotted.starts_with(UNC_LOCAL_PREFIX) {
-                    UNC_LOCAL_PREFIX
-                } else {
-                    ""
-                },
-                dedotted.replace(ATYPICAL_SEPARATOR, TYPICAL_SEPARATOR)
-            )
-        }
+    // This is purely lexical: links are left as-is, so that paths are shown and stored
+    // the way the user wrote them. The backup scan resolves links where it needs to.
+    let dedotted = parse_dots(
+        &absolutized,
+        &render_pathbuf(&match basis {
+            None => STARTUP_DIR.clone(),
+            Some(b) => std::path::Path::new(&normalize(b)).to_path_buf(),
+        }),
+    );
+    let dedotted = dedotted.replace(ATYPICAL_SEPARATOR, TYPICAL_SEPARATOR);
+    if !cfg!(target_os = "windows") || dedotted.starts_with(UNC_LOCAL_PREFIX) {
+        dedotted
+    } else if let Some(remote) = dedotted.strip_prefix(UNC_PREFIX) {
+        format!("{}{}", UNC_NETWORK_PREFIX, remote)
+    } else {
+        format!("{}{}", UNC_LOCAL_PREFIX, dedotted)
     }
 }
 
 /// Convert a path into a nice form for display and storage.
 /// On Windows, this produces non-UNC paths.
 fn render<P: Into<String>>(path: P) -> String {
-    path.into().replace(UNC_LOCAL_PREFIX, "").replace('\\', "/")
+    let path = path.into();
+    let path = match path.strip_prefix(UNC_NETWORK_PREFIX) {
+        Some(remote) => format!("{}{}", UNC_PREFIX, remote),
+        None => path,
+    };
+    path.replace(UNC_LOCAL_PREFIX, "").replace('\\', "/")
 }
 
 pub fn render_pathbuf(value: &std::path::Path) -> String {
@@ -219,6 +246,13 @@ impl StrictPath {
         std::path::Path::new(&self.interpret()).is_dir()
     }
 
+    /// Whether this is unusable as a backup target or restoration source:
+    /// a blank path would resolve to the working directory,
+    /// and a filesystem root is never a reasonable place for either.
+    pub fn is_blank_or_root(&self) -> bool {
+        self.raw().trim().is_empty() || std::path::Path::new(&self.interpret()).parent().is_none()
+    }
+
     pub fn exists(&self) -> bool {
         self.is_file() || self.is_dir()
     }
@@ -228,6 +262,7 @@ impl StrictPath {
     }
 
     pub fn remove(&self) -> Result<(), Box<dyn std::error::Error>> {
+        crate::prelude::ensure_writable()?;
         if self.is_file() {
             std::fs::remove_file(&self.interpret())?;
         } else if self.is_dir() {
@@ -241,6 +276,7 @@ impl StrictPath {
     }
 
     pub fn create_parent_dir(&self) -> std::io::Result<()> {
+        crate::prelude::ensure_writable()?;
         let mut pb = self.as_std_path_buf();
         pb.pop();
         std::fs::create_dir_all(&pb)?;
@@ -254,7 +290,13 @@ impl StrictPath {
     pub fn split_drive(&self) -> (String, String) {
         let interpreted = self.interpret();
 
-        if let Some(stripped) = interpreted.strip_prefix(UNC_LOCAL_PREFIX) {
+        if let Some(stripped) = interpreted.strip_prefix(UNC_NETWORK_PREFIX) {
+            // Extended-length remote UNC path - record it like a normal remote path:
+            let split: Vec<_> = stripped.splitn(2, '\\').collect();
+            if split.len() == 2 {
+                return (format!("{}{}", UNC_PREFIX, split[0]), split[1].replace('\\', "/"));
+            }
+        } else if let Some(stripped) = interpreted.strip_prefix(UNC_LOCAL_PREFIX) {
             // Local UNC path - simplify to a classic drive for user-friendliness:
             let split: Vec<_> = stripped.splitn(2, '\\').collect();
             if split.len() == 2 {
@@ -284,6 +326,16 @@ impl StrictPath {
         )
     }
 
+    /// Whether the raw path is absolute on any OS, since backups may come
+    /// from a different OS than the current one. Unlike `interpret()`,
+    /// this does not resolve relative paths against the working directory.
+    pub fn is_absolute(&self) -> bool {
+        let path = parse_home(self.raw.trim()).replace('\\', "/");
+        let bytes = path.as_bytes();
+        path.starts_with('/') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+    }
+
+    #[allow(clippy::result_unit_err)]
     pub fn unset_readonly(&self) -> Result<(), ()> {
         let interpreted = self.interpret();
         if self.is_file() {
@@ -423,6 +475,201 @@ impl<'de> serde::Deserialize<'de> for StrictPath {
     }
 }
 
+fn check_path(path: Option<std::path::PathBuf>) -> String {
+    path.unwrap_or_else(|| SKIP.into()).to_string_lossy().to_string()
+}
+
+fn check_windows_path(path: Option<std::path::PathBuf>) -> String {
+    match get_os() {
+        Os::Windows => check_path(path),
+        _ => SKIP.to_string(),
+    }
+}
+
+fn check_windows_path_str(path: &str) -> String {
+    check_windows_path(Some(std::path::PathBuf::from(path)))
+}
+
+fn check_nonwindows_path(path: Option<std::path::PathBuf>) -> String {
+    match get_os() {
+        Os::Windows => SKIP.to_string(),
+        _ => check_path(path),
+    }
+}
+
+fn check_nonwindows_path_str(path: &str) -> String {
+    check_nonwindows_path(Some(std::path::PathBuf::from(path)))
+}
+
+/// Every placeholder that `expand_manifest_placeholders` understands.
+pub const PLACEHOLDERS: &[&str] = &[
+    "<root>",
+    "<game>",
+    "<base>",
+    "<home>",
+    "<storeUserId>",
+    "<osUserName>",
+    "<winAppData>",
+    "<winLocalAppData>",
+    "<winDocuments>",
+    "<winPublic>",
+    "<winProgramData>",
+    "<winDir>",
+    "<xdgData>",
+    "<xdgConfig>",
+    "<regHkcu>",
+    "<regHklm>",
+];
+
+/// Find everything in a path that looks like a placeholder, like `<base>`.
+pub fn find_placeholders(path: &str) -> Vec<&str> {
+    let mut placeholders = vec![];
+    let mut rest = path;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) => {
+                placeholders.push(&rest[start..start + end + 1]);
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+    placeholders
+}
+
+/// Expand the placeholders that can appear in manifest and custom game paths,
+/// like `<base>` or `<winAppData>`. A single path may expand into several
+/// candidates (e.g., for Proton and Wine prefixes). Placeholders that do not
+/// apply to the current system or root are replaced with `<skip>`.
+pub fn expand_manifest_placeholders(
+    path: &str,
+    root: &RootsConfig,
+    install_dir: &Option<String>,
+    steam_id: &Option<u32>,
+) -> HashSet<String> {
+    let mut paths = HashSet::new();
+
+    let install_dir = match install_dir {
+        Some(d) => d,
+        None => SKIP,
+    };
+
+    paths.insert(
+        path.replace("<root>", &root.path.interpret())
+            .replace("<game>", install_dir)
+            .replace("<base>", &root.game_base(install_dir))
+            .replace(
+                "<home>",
+                &dirs::home_dir().unwrap_or_else(|| SKIP.into()).to_string_lossy(),
+            )
+            .replace("<storeUserId>", "*")
+            .replace("<osUserName>", &whoami::username())
+            .replace("<winAppData>", &check_windows_path(dirs::data_dir()))
+            .replace("<winLocalAppData>", &check_windows_path(dirs::data_local_dir()))
+            .replace("<winDocuments>", &check_windows_path(dirs::document_dir()))
+            .replace("<winPublic>", &check_windows_path(dirs::public_dir()))
+            .replace("<winProgramData>", &check_windows_path_str("C:/Windows/ProgramData"))
+            .replace("<winDir>", &check_windows_path_str("C:/Windows"))
+            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
+            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
+            .replace("<regHkcu>", SKIP)
+            .replace("<regHklm>", SKIP),
+    );
+    if root.store == Store::OtherHome {
+        paths.insert(
+            path.replace("<root>", &root.path.interpret())
+                .replace("<game>", install_dir)
+                .replace("<base>", &root.game_base(install_dir))
+                .replace("<storeUserId>", SKIP)
+                .replace("<osUserName>", &whoami::username())
+                .replace("<winAppData>", &check_windows_path_str("<home>/AppData/Roaming"))
+                .replace("<winLocalAppData>", &check_windows_path_str("<home>/AppData/Local"))
+                .replace("<winDocuments>", &check_windows_path_str("<home>/Documents"))
+                .replace("<winPublic>", &check_windows_path(dirs::public_dir()))
+                .replace("<winProgramData>", &check_windows_path_str("C:/Windows/ProgramData"))
+                .replace("<winDir>", &check_windows_path_str("C:/Windows"))
+                .replace("<xdgData>", &check_nonwindows_path_str("<home>/.local/share"))
+                .replace("<xdgConfig>", &check_nonwindows_path_str("<home>/.config"))
+                .replace("<regHkcu>", SKIP)
+                .replace("<regHklm>", SKIP)
+                .replace("<home>", &root.path.interpret()),
+        );
+    }
+    if get_os() == Os::Linux && root.store == Store::Steam && steam_id.is_some() {
+        let prefix = format!(
+            "{}/steamapps/compatdata/{}/pfx/drive_c",
+            root.path.interpret(),
+            steam_id.unwrap()
+        );
+        let path2 = path
+            .replace("<root>", &root.path.interpret())
+            .replace("<game>", install_dir)
+            .replace("<base>", &root.game_base(install_dir))
+            .replace("<home>", &format!("{}/users/steamuser", prefix))
+            .replace("<storeUserId>", "*")
+            .replace("<osUserName>", "steamuser")
+            .replace("<winPublic>", &format!("{}/users/Public", prefix))
+            .replace("<winProgramData>", &format!("{}/ProgramData", prefix))
+            .replace("<winDir>", &format!("{}/windows", prefix))
+            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
+            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
+            .replace("<regHkcu>", SKIP)
+            .replace("<regHklm>", SKIP);
+        paths.insert(
+            path2
+                .replace("<winDocuments>", &format!("{}/users/steamuser/Documents", prefix))
+                .replace("<winAppData>", &format!("{}/users/steamuser/AppData/Roaming", prefix))
+                .replace(
+                    "<winLocalAppData>",
+                    &format!("{}/users/steamuser/AppData/Local", prefix),
+                ),
+        );
+        paths.insert(
+            path2
+                .replace("<winDocuments>", &format!("{}/users/steamuser/My Documents", prefix))
+                .replace("<winAppData>", &format!("{}/users/steamuser/Application Data", prefix))
+                .replace(
+                    "<winLocalAppData>",
+                    &format!("{}/users/steamuser/Local Settings/Application Data", prefix),
+                ),
+        );
+    }
+    if root.store == Store::OtherWine {
+        let prefix = format!("{}/drive_*", root.path.interpret());
+        let path2 = path
+            .replace("<root>", &root.path.interpret())
+            .replace("<game>", install_dir)
+            .replace("<base>", &root.game_base(install_dir))
+            .replace("<home>", &format!("{}/users/*", prefix))
+            .replace("<storeUserId>", "*")
+            .replace("<osUserName>", "*")
+            .replace("<winPublic>", &format!("{}/users/Public", prefix))
+            .replace("<winProgramData>", &format!("{}/ProgramData", prefix))
+            .replace("<winDir>", &format!("{}/windows", prefix))
+            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
+            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
+            .replace("<regHkcu>", SKIP)
+            .replace("<regHklm>", SKIP);
+        paths.insert(
+            path2
+                .replace("<winDocuments>", &format!("{}/users/*/Documents", prefix))
+                .replace("<winAppData>", &format!("{}/users/*/AppData/Roaming", prefix))
+                .replace("<winLocalAppData>", &format!("{}/users/*/AppData/Local", prefix)),
+        );
+        paths.insert(
+            path2
+                .replace("<winDocuments>", &format!("{}/users/*/My Documents", prefix))
+                .replace("<winAppData>", &format!("{}/users/*/Application Data", prefix))
+                .replace(
+                    "<winLocalAppData>",
+                    &format!("{}/users/*/Local Settings/Application Data", prefix),
+                ),
+        );
+    }
+
+    paths
+}
+
 #[allow(dead_code)]
 pub fn is_raw_path_relative(path: &str) -> bool {
     let path = path.replace('\\', "/");
@@ -457,6 +704,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn expands_all_manifest_placeholders() {
+        let root = RootsConfig {
+            path: StrictPath::new(s("/root")),
+            store: Store::OtherHome,
+            ignore: false,
+            base_template: None,
+        };
+        for placeholder in [
+            "<root>",
+            "<game>",
+            "<base>",
+            "<home>",
+            "<storeUserId>",
+            "<osUserName>",
+            "<winAppData>",
+            "<winLocalAppData>",
+            "<winDocuments>",
+            "<winPublic>",
+            "<winProgramData>",
+            "<winDir>",
+            "<xdgData>",
+            "<xdgConfig>",
+            "<regHkcu>",
+            "<regHklm>",
+        ] {
+            let expanded =
+                expand_manifest_placeholders(&format!("{}/file.txt", placeholder), &root, &Some(s("Game")), &None);
+            assert!(!expanded.is_empty());
+            assert!(expanded.iter().all(|x| !x.contains(placeholder)), "{}", placeholder);
+        }
+    }
+
+    #[test]
+    fn expands_base_with_template_for_wine_roots() {
+        let root = RootsConfig {
+            path: StrictPath::new(s("/root")),
+            store: Store::OtherWine,
+            ignore: false,
+            base_template: Some(s("<root>/games/<game>")),
+        };
+        let expanded = expand_manifest_placeholders("<base>/save.dat", &root, &Some(s("Game")), &None);
+        assert!(expanded.contains(&format!("{}/save.dat", root.game_base("Game"))));
+    }
+
     mod strict_path {
         use super::*;
         use pretty_assertions::assert_eq;
@@ -506,6 +798,15 @@ mod tests {
             assert_eq!(r#"\\?\C:\"#.to_string(), interpret(r#"\\?\C:"#, &None));
         }
 
+        #[test]
+        #[cfg(target_os = "windows")]
+        fn can_interpret_network_path_as_extended_length() {
+            assert_eq!(
+                r#"\\?\UNC\ludusavi-fake-server\share\foo"#.to_string(),
+                interpret(r#"\\ludusavi-fake-server\share\foo"#, &None)
+            );
+        }
+
         #[test]
         fn can_render() {
             assert_eq!("".to_string(), render(""));
@@ -514,6 +815,8 @@ mod tests {
             assert_eq!("/foo/bar".to_string(), render("/foo/bar"));
             assert_eq!("/foo/bar/".to_string(), render("\\foo/bar/"));
             assert_eq!("C:/foo".to_string(), render("C:/foo"));
+            assert_eq!("C:/foo".to_string(), render(r#"\\?\C:\foo"#));
+            assert_eq!("//server/share/foo".to_string(), render(r#"\\?\UNC\server\share\foo"#));
         }
 
         #[test]
@@ -755,6 +1058,15 @@ mod tests {
             assert_eq!((s(""), s("foo/bar")), StrictPath::new(s("/foo/bar")).split_drive());
         }
 
+        #[test]
+        fn is_blank_or_root() {
+            assert!(StrictPath::new(s("")).is_blank_or_root());
+            assert!(StrictPath::new(s("  ")).is_blank_or_root());
+            assert!(StrictPath::new(s("/")).is_blank_or_root());
+            assert!(!StrictPath::new(s("/foo")).is_blank_or_root());
+            assert!(!StrictPath::new(s("foo")).is_blank_or_root());
+        }
+
         #[test]
         fn is_prefix_of() {
             assert!(StrictPath::new(s("/")).is_prefix_of(&StrictPath::new(s("/foo"))));
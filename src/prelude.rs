@@ -1,5 +1,5 @@
 use crate::{
-    config::{BackupFilter, RedirectConfig, RootsConfig, ToggledPaths, ToggledRegistry},
+    config::{BackupFilter, RedirectConfig, RestoreStrategy, RootsConfig, ToggledPaths, ToggledRegistry},
     layout::BackupLayout,
     manifest::{Game, GameFileConstraint, Os, Store},
 };
@@ -8,16 +8,22 @@ use rayon::prelude::*;
 use std::io::Read;
 
 pub use crate::path::StrictPath;
-pub use crate::registry_compat::RegistryItem;
+use crate::path::{expand_manifest_placeholders, find_placeholders, SKIP};
+pub use crate::registry_compat::{RegistryChange, RegistryItem};
 
 const WINDOWS: bool = cfg!(target_os = "windows");
 const MAC: bool = cfg!(target_os = "macos");
 const LINUX: bool = cfg!(target_os = "linux");
 pub const CASE_INSENSITIVE_OS: bool = WINDOWS || MAC;
-const SKIP: &str = "<skip>";
 const APP_DIR_NAME: &str = "ludusavi";
 const PORTABLE_FLAG_FILE_NAME: &str = "ludusavi.portable";
 const MIGRATION_FLAG_FILE_NAME: &str = ".flag_migrated_legacy_config";
+/// Scripts and scheduled backups can set this instead of answering a prompt.
+pub const PASSPHRASE_ENV_VAR: &str = "LUDUSAVI_PASSPHRASE";
+/// How many files a backup scan finds between progress reports.
+const SCAN_PROGRESS_INTERVAL: usize = 1000;
+/// Matches names like `slot1`, `save_2`, or `Profile 3`, capturing the number.
+pub const DEFAULT_SAVE_SLOT_PATTERN: &str = r"(?i)(?:slot|save|profile)[ _-]?(\d+)";
 
 #[derive(Clone, Debug, PartialEq, thiserror::Error)]
 pub enum Error {
@@ -27,9 +33,21 @@ pub enum Error {
     #[error("Unable to download an update to the manifest file")]
     ManifestCannotBeUpdated,
 
+    #[error("The downloaded manifest file is invalid: {why:?}")]
+    ManifestDownloadInvalid { why: String },
+
+    #[error("The downloaded manifest file does not match its published checksum")]
+    ManifestChecksumMismatch,
+
     #[error("The config file is invalid: {why:?}")]
     ConfigInvalid { why: String },
 
+    #[error("Unable to save the config")]
+    ConfigSaveFailed { path: StrictPath },
+
+    #[error("Unable to export the config")]
+    ConfigExportFailed { path: StrictPath },
+
     #[error("Target already exists")]
     CliBackupTargetExists { path: StrictPath },
 
@@ -45,9 +63,48 @@ pub enum Error {
     #[error("Cannot prepare the backup target")]
     CannotPrepareBackupTarget { path: StrictPath },
 
+    #[error("The backup target is not writable")]
+    BackupTargetNotWritable { path: StrictPath },
+
+    #[error("The backup target is blank or a filesystem root")]
+    BackupTargetInvalid { path: StrictPath },
+
+    #[error("The backup target has locked backups, so it can't be cleared")]
+    BackupTargetHasLockedBackups { path: StrictPath },
+
+    #[error("There is no such backup")]
+    UnknownBackup { game: String, backup: String },
+
+    #[error("Unable to delete an orphaned backup")]
+    CannotPruneOrphan { path: StrictPath },
+
+    #[error("Unable to save the staged restoration")]
+    StagedRestoreSaveFailed { path: StrictPath },
+
+    #[error("The backup target is inside of a root or the restoration source")]
+    BackupTargetInsideRoot { path: StrictPath, root: StrictPath },
+
     #[error("Cannot prepare the backup target")]
     RestorationSourceInvalid { path: StrictPath },
 
+    #[error("The restoration target is not writable")]
+    RestorationTargetNotWritable { path: StrictPath },
+
+    #[error("Unable to copy or verify the backup")]
+    BackupMigrationFailed { path: StrictPath },
+
+    #[error("A configured command failed")]
+    CommandFailed { command: String, output: String },
+
+    #[error("The configured mode does not allow this operation")]
+    OperationDisabled { restoring: bool },
+
+    #[error("Read-only mode does not allow writing files")]
+    ReadOnlyMode,
+
+    #[error("Unable to transfer files over SFTP")]
+    SftpTransferFailed { target: String, output: String },
+
     #[allow(dead_code)]
     #[error("Error while working with the registry")]
     RegistryIssue,
@@ -58,8 +115,14 @@ pub enum Error {
     #[error("Unable to open directory")]
     UnableToOpenDir(StrictPath),
 
+    #[error("Unable to open file")]
+    UnableToOpenFile(StrictPath),
+
     #[error("Unable to open URL")]
     UnableToOpenUrl(String),
+
+    #[error("Unable to start the GUI")]
+    GuiUnavailable { why: String },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -71,7 +134,6 @@ pub struct ScannedFile {
     pub ignored: bool,
 }
 
-#[cfg(test)]
 impl ScannedFile {
     pub fn new<T: AsRef<str> + ToString>(path: T, size: u64) -> Self {
         Self {
@@ -94,7 +156,6 @@ pub struct ScannedRegistry {
     pub ignored: bool,
 }
 
-#[cfg(test)]
 impl ScannedRegistry {
     pub fn new<T: AsRef<str> + ToString>(path: T) -> Self {
         Self {
@@ -118,6 +179,35 @@ pub struct ScanInfo {
 }
 
 impl ScanInfo {
+    /// A copy that only includes the enabled kinds of data.
+    pub fn only_kinds(&self, files: bool, registry: bool) -> Self {
+        Self {
+            game_name: self.game_name.clone(),
+            found_files: if files {
+                self.found_files.clone()
+            } else {
+                Default::default()
+            },
+            found_registry_keys: if registry {
+                self.found_registry_keys.clone()
+            } else {
+                Default::default()
+            },
+            registry_file: if registry { self.registry_file.clone() } else { None },
+        }
+    }
+
+    /// A copy without the files that should be left as they are during restoration.
+    pub fn without_conflicts(&self, kept: &[RestoreConflict]) -> Self {
+        let mut info = self.clone();
+        info.found_files.retain(|file| {
+            !kept
+                .iter()
+                .any(|x| x.game == self.game_name && Some(&x.original) == file.original_path.as_ref())
+        });
+        info
+    }
+
     pub fn sum_bytes(&self, backup_info: &Option<BackupInfo>) -> u64 {
         let successful_bytes = self
             .found_files
@@ -141,6 +231,15 @@ impl ScanInfo {
         !self.found_files.is_empty() || !self.found_registry_keys.is_empty()
     }
 
+    /// When any of the included files was last modified.
+    pub fn latest_modified(&self) -> Option<std::time::SystemTime> {
+        self.found_files
+            .iter()
+            .filter(|x| !x.ignored)
+            .filter_map(|x| x.path.metadata().and_then(|m| m.modified()).ok())
+            .max()
+    }
+
     pub fn found_anything_processable(&self) -> bool {
         self.found_files.iter().any(|x| !x.ignored) || self.found_registry_keys.iter().any(|x| !x.ignored)
     }
@@ -180,16 +279,82 @@ impl ScanInfo {
     }
 }
 
+/// Why something couldn't be backed up or restored, so that the user knows what to fix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+pub enum FailureCategory {
+    #[serde(rename = "permission")]
+    Permission,
+    #[serde(rename = "missing")]
+    MissingSource,
+    #[serde(rename = "inUse")]
+    FileInUse,
+    #[serde(rename = "diskFull")]
+    DiskFull,
+    #[serde(rename = "other")]
+    Other,
+}
+
+impl FailureCategory {
+    pub fn from_io(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => Self::Permission,
+            std::io::ErrorKind::NotFound => Self::MissingSource,
+            _ => match error.raw_os_error() {
+                // ENOSPC, then EBUSY and ETXTBSY.
+                #[cfg(unix)]
+                Some(28) => Self::DiskFull,
+                #[cfg(unix)]
+                Some(16 | 26) => Self::FileInUse,
+                // ERROR_HANDLE_DISK_FULL and ERROR_DISK_FULL,
+                // then ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION.
+                #[cfg(windows)]
+                Some(39 | 112) => Self::DiskFull,
+                #[cfg(windows)]
+                Some(32 | 33) => Self::FileInUse,
+                _ => Self::Other,
+            },
+        }
+    }
+
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        match error.downcast_ref::<std::io::Error>() {
+            Some(e) => Self::from_io(e),
+            None => Self::Other,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct BackupInfo {
     pub failed_files: std::collections::HashSet<ScannedFile>,
     pub failed_registry: std::collections::HashSet<RegistryItem>,
+    /// How many of the failed files fell into each category.
+    pub failure_categories: std::collections::BTreeMap<FailureCategory, usize>,
 }
 
 impl BackupInfo {
     pub fn successful(&self) -> bool {
         self.failed_files.is_empty() && self.failed_registry.is_empty()
     }
+
+    pub fn add_failed_file(&mut self, file: ScannedFile, category: FailureCategory) {
+        self.failed_files.insert(file);
+        *self.failure_categories.entry(category).or_default() += 1;
+    }
+
+    /// The most common reason for failure, if anything failed.
+    pub fn failure_category(&self) -> Option<FailureCategory> {
+        if self.successful() {
+            return None;
+        }
+        Some(
+            self.failure_categories
+                .iter()
+                .max_by_key(|(_, count)| **count)
+                .map(|(category, _)| *category)
+                .unwrap_or(FailureCategory::Other),
+        )
+    }
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize)]
@@ -232,6 +397,10 @@ pub enum OperationStepDecision {
     Processed,
     Cancelled,
     Ignored,
+    /// Skipped because the backup would exceed `maxTotalBytes`.
+    OverBudget,
+    /// Skipped because the game seemed to be running, per `backup.runningGames`.
+    Running,
 }
 
 impl Default for OperationStepDecision {
@@ -240,6 +409,85 @@ impl Default for OperationStepDecision {
     }
 }
 
+/// How each game turned out after a backup or restore.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OperationSummary {
+    pub succeeded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// How many failed games fell into each category.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub failures: std::collections::BTreeMap<FailureCategory, usize>,
+}
+
+impl OperationSummary {
+    /// Count a game, which was skipped if it has no backup info.
+    pub fn add(&mut self, backup_info: Option<&BackupInfo>) {
+        match backup_info {
+            Some(x) if x.successful() => self.succeeded += 1,
+            Some(x) => {
+                self.failed += 1;
+                if let Some(category) = x.failure_category() {
+                    *self.failures.entry(category).or_default() += 1;
+                }
+            }
+            None => self.skipped += 1,
+        }
+    }
+}
+
+/// Groups a game's files into save slots by a pattern on their original paths,
+/// and decides which slots to restore. Files outside of any slot are always restored.
+#[derive(Clone, Debug, Default)]
+pub struct SaveSlotFilter {
+    pattern: Option<regex::Regex>,
+    skipped: std::collections::BTreeSet<String>,
+}
+
+impl SaveSlotFilter {
+    /// The pattern's first capture group names the slot, or else the whole match does.
+    /// An invalid pattern puts every file outside of any slot.
+    pub fn new(pattern: &str, skipped: std::collections::BTreeSet<String>) -> Self {
+        Self {
+            pattern: regex::Regex::new(pattern).ok(),
+            skipped,
+        }
+    }
+
+    pub fn slot(&self, file: &ScannedFile) -> Option<String> {
+        let path = file.original_path.as_ref().unwrap_or(&file.path).render();
+        // The last match is the one closest to the file itself.
+        let captures = self.pattern.as_ref()?.captures_iter(&path).last()?;
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|x| x.as_str().to_string())
+    }
+
+    pub fn slots(&self, info: &ScanInfo) -> std::collections::BTreeSet<String> {
+        info.found_files.iter().filter_map(|x| self.slot(x)).collect()
+    }
+
+    pub fn allows(&self, file: &ScannedFile) -> bool {
+        match self.slot(file) {
+            Some(slot) => !self.skipped.contains(&slot),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OngoingOperation {
+    Backup,
+    CancelBackup,
+    PreviewBackup,
+    CancelPreviewBackup,
+    Restore,
+    CancelRestore,
+    PreviewRestore,
+    CancelPreviewRestore,
+}
+
 pub fn app_dir() -> std::path::PathBuf {
     if let Ok(mut flag) = std::env::current_exe() {
         flag.pop();
@@ -289,11 +537,31 @@ pub fn migrate_legacy_config() {
     }
 }
 
+/// Why a backed up file's restoration target could not be determined.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RestorationTargetError {
+    #[error("The target path is empty")]
+    Empty,
+
+    #[error("The target path is not absolute")]
+    NotAbsolute { path: StrictPath },
+}
+
 /// Returns the effective target and the original target (if different)
 pub fn game_file_restoration_target(
     original_target: &StrictPath,
     redirects: &[RedirectConfig],
-) -> (StrictPath, Option<StrictPath>) {
+) -> Result<(StrictPath, Option<StrictPath>), RestorationTargetError> {
+    if original_target.raw().trim().is_empty() {
+        return Err(RestorationTargetError::Empty);
+    }
+    // Relative paths would otherwise be resolved against the working directory.
+    if !original_target.is_absolute() {
+        return Err(RestorationTargetError::NotAbsolute {
+            path: original_target.clone(),
+        });
+    }
+
     let mut redirected_target = original_target.render();
     for redirect in redirects {
         if redirect.source.raw().trim().is_empty() || redirect.target.raw().trim().is_empty() {
@@ -302,15 +570,20 @@ pub fn game_file_restoration_target(
         let source = redirect.source.render();
         let target = redirect.target.render();
         if !source.is_empty() && !target.is_empty() && redirected_target.starts_with(&source) {
+            if !redirect.target.is_absolute() {
+                return Err(RestorationTargetError::NotAbsolute {
+                    path: redirect.target.clone(),
+                });
+            }
             redirected_target = redirected_target.replacen(&source, &target, 1);
         }
     }
 
     let redirected_target = StrictPath::new(redirected_target);
     if original_target.render() != redirected_target.render() {
-        (redirected_target, Some(original_target.clone()))
+        Ok((redirected_target, Some(original_target.clone())))
     } else {
-        (original_target.clone(), None)
+        Ok((original_target.clone(), None))
     }
 }
 
@@ -326,32 +599,6 @@ pub fn get_os() -> Os {
     }
 }
 
-fn check_path(path: Option<std::path::PathBuf>) -> String {
-    path.unwrap_or_else(|| SKIP.into()).to_string_lossy().to_string()
-}
-
-fn check_windows_path(path: Option<std::path::PathBuf>) -> String {
-    match get_os() {
-        Os::Windows => check_path(path),
-        _ => SKIP.to_string(),
-    }
-}
-
-fn check_windows_path_str(path: &str) -> String {
-    check_windows_path(Some(std::path::PathBuf::from(path)))
-}
-
-fn check_nonwindows_path(path: Option<std::path::PathBuf>) -> String {
-    match get_os() {
-        Os::Windows => SKIP.to_string(),
-        _ => check_path(path),
-    }
-}
-
-fn check_nonwindows_path_str(path: &str) -> String {
-    check_nonwindows_path(Some(std::path::PathBuf::from(path)))
-}
-
 pub fn parse_paths(
     path: &str,
     root: &RootsConfig,
@@ -359,136 +606,7 @@ pub fn parse_paths(
     steam_id: &Option<u32>,
     manifest_dir: &StrictPath,
 ) -> std::collections::HashSet<StrictPath> {
-    let mut paths = std::collections::HashSet::new();
-
-    let install_dir = match install_dir {
-        Some(d) => d,
-        None => SKIP,
-    };
-
-    paths.insert(
-        path.replace("<root>", &root.path.interpret())
-            .replace("<game>", install_dir)
-            .replace(
-                "<base>",
-                &match root.store {
-                    Store::Steam => format!("{}/steamapps/common/{}", root.path.interpret(), install_dir),
-                    _ => format!("{}/{}", root.path.interpret(), install_dir),
-                },
-            )
-            .replace(
-                "<home>",
-                &dirs::home_dir().unwrap_or_else(|| SKIP.into()).to_string_lossy(),
-            )
-            .replace("<storeUserId>", "*")
-            .replace("<osUserName>", &whoami::username())
-            .replace("<winAppData>", &check_windows_path(dirs::data_dir()))
-            .replace("<winLocalAppData>", &check_windows_path(dirs::data_local_dir()))
-            .replace("<winDocuments>", &check_windows_path(dirs::document_dir()))
-            .replace("<winPublic>", &check_windows_path(dirs::public_dir()))
-            .replace("<winProgramData>", &check_windows_path_str("C:/Windows/ProgramData"))
-            .replace("<winDir>", &check_windows_path_str("C:/Windows"))
-            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
-            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
-            .replace("<regHkcu>", SKIP)
-            .replace("<regHklm>", SKIP),
-    );
-    if root.store == Store::OtherHome {
-        paths.insert(
-            path.replace("<root>", &root.path.interpret())
-                .replace("<game>", install_dir)
-                .replace("<base>", &format!("{}/{}", root.path.interpret(), install_dir))
-                .replace("<storeUserId>", SKIP)
-                .replace("<osUserName>", &whoami::username())
-                .replace("<winAppData>", &check_windows_path_str("<home>/AppData/Roaming"))
-                .replace("<winLocalAppData>", &check_windows_path_str("<home>/AppData/Local"))
-                .replace("<winDocuments>", &check_windows_path_str("<home>/Documents"))
-                .replace("<winPublic>", &check_windows_path(dirs::public_dir()))
-                .replace("<winProgramData>", &check_windows_path_str("C:/Windows/ProgramData"))
-                .replace("<winDir>", &check_windows_path_str("C:/Windows"))
-                .replace("<xdgData>", &check_nonwindows_path_str("<home>/.local/share"))
-                .replace("<xdgConfig>", &check_nonwindows_path_str("<home>/.config"))
-                .replace("<regHkcu>", SKIP)
-                .replace("<regHklm>", SKIP)
-                .replace("<home>", &root.path.interpret()),
-        );
-    }
-    if get_os() == Os::Linux && root.store == Store::Steam && steam_id.is_some() {
-        let prefix = format!(
-            "{}/steamapps/compatdata/{}/pfx/drive_c",
-            root.path.interpret(),
-            steam_id.unwrap()
-        );
-        let path2 = path
-            .replace("<root>", &root.path.interpret())
-            .replace("<game>", install_dir)
-            .replace(
-                "<base>",
-                &format!("{}/steamapps/common/{}", root.path.interpret(), install_dir),
-            )
-            .replace("<home>", &format!("{}/users/steamuser", prefix))
-            .replace("<storeUserId>", "*")
-            .replace("<osUserName>", "steamuser")
-            .replace("<winPublic>", &format!("{}/users/Public", prefix))
-            .replace("<winProgramData>", &format!("{}/ProgramData", prefix))
-            .replace("<winDir>", &format!("{}/windows", prefix))
-            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
-            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
-            .replace("<regHkcu>", SKIP)
-            .replace("<regHklm>", SKIP);
-        paths.insert(
-            path2
-                .replace("<winDocuments>", &format!("{}/users/steamuser/Documents", prefix))
-                .replace("<winAppData>", &format!("{}/users/steamuser/AppData/Roaming", prefix))
-                .replace(
-                    "<winLocalAppData>",
-                    &format!("{}/users/steamuser/AppData/Local", prefix),
-                ),
-        );
-        paths.insert(
-            path2
-                .replace("<winDocuments>", &format!("{}/users/steamuser/My Documents", prefix))
-                .replace("<winAppData>", &format!("{}/users/steamuser/Application Data", prefix))
-                .replace(
-                    "<winLocalAppData>",
-                    &format!("{}/users/steamuser/Local Settings/Application Data", prefix),
-                ),
-        );
-    }
-    if root.store == Store::OtherWine {
-        let prefix = format!("{}/drive_*", root.path.interpret());
-        let path2 = path
-            .replace("<root>", &root.path.interpret())
-            .replace("<game>", install_dir)
-            .replace("<base>", &format!("{}/{}", root.path.interpret(), install_dir))
-            .replace("<home>", &format!("{}/users/*", prefix))
-            .replace("<storeUserId>", "*")
-            .replace("<osUserName>", "*")
-            .replace("<winPublic>", &format!("{}/users/Public", prefix))
-            .replace("<winProgramData>", &format!("{}/ProgramData", prefix))
-            .replace("<winDir>", &format!("{}/windows", prefix))
-            .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
-            .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
-            .replace("<regHkcu>", SKIP)
-            .replace("<regHklm>", SKIP);
-        paths.insert(
-            path2
-                .replace("<winDocuments>", &format!("{}/users/*/Documents", prefix))
-                .replace("<winAppData>", &format!("{}/users/*/AppData/Roaming", prefix))
-                .replace("<winLocalAppData>", &format!("{}/users/*/AppData/Local", prefix)),
-        );
-        paths.insert(
-            path2
-                .replace("<winDocuments>", &format!("{}/users/*/My Documents", prefix))
-                .replace("<winAppData>", &format!("{}/users/*/Application Data", prefix))
-                .replace(
-                    "<winLocalAppData>",
-                    &format!("{}/users/*/Local Settings/Application Data", prefix),
-                ),
-        );
-    }
-
-    paths
+    expand_manifest_placeholders(path, root, install_dir, steam_id)
         .iter()
         .map(|x| StrictPath::relative(x.to_string(), Some(manifest_dir.interpret())))
         .collect()
@@ -512,6 +630,16 @@ fn should_exclude_as_other_os_data(constraints: &[GameFileConstraint], host: Os,
     constrained && !unconstrained_by_os && !matches_os && !suitable_for_proton
 }
 
+/// Paths limited to certain stores only apply in roots of those stores.
+/// Generic roots don't say which store their games came from, so they keep everything.
+fn should_exclude_as_other_store_data(constraints: &[GameFileConstraint], store: Store) -> bool {
+    let specific_store = !matches!(store, Store::Other | Store::OtherHome | Store::OtherWine);
+    let constrained = !constraints.is_empty();
+    let unconstrained_by_store = constraints.iter().any(|x| x.store.is_none());
+    let matches_store = constraints.iter().any(|x| x.store == Some(store));
+    specific_store && constrained && !unconstrained_by_store && !matches_store
+}
+
 #[derive(Clone, Default)]
 pub struct InstallDirRanking(std::collections::HashMap<(RootsConfig, String), (i64, String)>);
 
@@ -533,17 +661,14 @@ impl InstallDirRanking {
 
     pub fn scan(roots: &[RootsConfig], manifest: &crate::manifest::Manifest, subjects: &[String]) -> Self {
         let mut ranking = Self::default();
-        for root in roots.iter().flat_map(|x| x.glob()) {
+        for root in roots.iter().filter(|x| !x.ignore).flat_map(|x| x.glob()) {
             ranking.scan_root(&root, manifest, subjects);
         }
         ranking
     }
 
     fn scan_root(&mut self, root: &RootsConfig, manifest: &crate::manifest::Manifest, subjects: &[String]) {
-        let install_parent = match root.store {
-            Store::Steam => root.path.joined("steamapps/common"),
-            _ => root.path.clone(),
-        };
+        let install_parent = root.install_parent();
         let matcher = make_fuzzy_matcher();
 
         let actual_dirs: Vec<_> = std::fs::read_dir(install_parent.interpret())
@@ -610,25 +735,142 @@ pub fn scan_game_for_backup(
     wine_prefix: &Option<StrictPath>,
     ranking: &InstallDirRanking,
     ignored_paths: &ToggledPaths,
-    #[allow(unused_variables)] ignored_registry: &ToggledRegistry,
+    ignored_registry: &ToggledRegistry,
+    since_days: &Option<u32>,
 ) -> ScanInfo {
+    scan_game_for_backup_cancellable(
+        game,
+        name,
+        roots,
+        manifest_dir,
+        steam_id,
+        filter,
+        wine_prefix,
+        ranking,
+        ignored_paths,
+        ignored_registry,
+        since_days,
+        &std::sync::atomic::AtomicBool::new(false),
+        &|_| {},
+    )
+    .unwrap_or_default()
+}
+
+/// The leading placeholders of a manifest path, like `<winDocuments>` in `<winDocuments>/Game/save.dat`.
+/// A whole directory is only backed up when it is below the folder that these expand to,
+/// since the placeholders stand for shared folders like Documents or AppData.
+fn whole_directory_floor(raw_path: &str) -> Option<String> {
+    let floor: Vec<_> = raw_path
+        .split(['/', '\\'])
+        .take_while(|x| !find_placeholders(x).is_empty())
+        .collect();
+    if floor.is_empty() {
+        None
+    } else {
+        Some(floor.join("/"))
+    }
+}
+
+/// The folder to back up in full when a game opts into `wholeDirectories`.
+/// To avoid runaway matches, this refuses folders that are a root,
+/// the home folder, or the top of a drive, as well as folders that are not strictly
+/// inside the expansion of the path's leading placeholders (see `whole_directory_floor`).
+/// In that case, only the file itself is kept.
+fn whole_directory_for(file: &StrictPath, floors: &[StrictPath], roots: &[RootsConfig]) -> Option<StrictPath> {
+    let folder = file.as_std_path_buf().parent()?.to_path_buf();
+    folder.parent()?;
+
+    if !floors.is_empty() {
+        let options = glob::MatchOptions {
+            case_sensitive: CASE_INSENSITIVE_OS,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let patterns: Vec<_> = floors
+            .iter()
+            .filter_map(|x| glob::Pattern::new(&x.render()).ok())
+            .collect();
+        let below_floor = folder.ancestors().skip(1).any(|ancestor| {
+            let ancestor = StrictPath::from(ancestor).render();
+            patterns.iter().any(|x| x.matches_with(&ancestor, options))
+        });
+        if !below_floor {
+            return None;
+        }
+    }
+
+    let folder = StrictPath::from(folder);
+    if let Some(home) = dirs::home_dir() {
+        if folder.same_path(&StrictPath::from(home)) {
+            return None;
+        }
+    }
+    if roots.iter().any(|root| folder.same_path(&root.path)) {
+        return None;
+    }
+    Some(folder)
+}
+
+/// Follow symlinks and Windows junctions to the real location of a matched path,
+/// so that a save reachable through a link and through its real folder is only found once.
+fn resolve_links(path: StrictPath) -> StrictPath {
+    match std::fs::canonicalize(path.interpret()) {
+        Ok(x) => StrictPath::from(x).rendered(),
+        Err(_) => path,
+    }
+}
+
+/// Like `scan_game_for_backup`, but gives up partway through when `cancel` is set.
+/// A cancelled scan returns nothing, since a partial list of files would be misleading.
+/// For big games, `progress` is called with the number of files found so far
+/// every `SCAN_PROGRESS_INTERVAL` files.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_game_for_backup_cancellable(
+    game: &Game,
+    name: &str,
+    roots: &[RootsConfig],
+    manifest_dir: &StrictPath,
+    steam_id: &Option<u32>,
+    filter: &BackupFilter,
+    wine_prefix: &Option<StrictPath>,
+    ranking: &InstallDirRanking,
+    ignored_paths: &ToggledPaths,
+    #[allow(unused_variables)] ignored_registry: &ToggledRegistry,
+    since_days: &Option<u32>,
+    cancel: &std::sync::atomic::AtomicBool,
+    progress: &dyn Fn(usize),
+) -> Option<ScanInfo> {
+    let cancelled = || cancel.load(std::sync::atomic::Ordering::Relaxed);
+    let cutoff = since_days.and_then(|days| {
+        std::time::SystemTime::now().checked_sub(std::time::Duration::from_secs(days as u64 * 24 * 60 * 60))
+    });
+    let too_old = |metadata: &std::io::Result<std::fs::Metadata>| match (cutoff, metadata) {
+        (Some(cutoff), Ok(m)) => m.modified().map(|x| x < cutoff).unwrap_or(false),
+        _ => false,
+    };
     let mut found_files = std::collections::HashSet::new();
     #[allow(unused_mut)]
     let mut found_registry_keys = std::collections::HashSet::new();
 
     let mut paths_to_check = std::collections::HashSet::<StrictPath>::new();
+    let whole_directory = filter.is_whole_directory(name);
+    let mut whole_directory_floors = std::collections::HashMap::<StrictPath, Vec<StrictPath>>::new();
 
     // Add a dummy root for checking paths without `<root>`.
     let mut roots_to_check: Vec<RootsConfig> = vec![RootsConfig {
         path: StrictPath::new(SKIP.to_string()),
         store: Store::Other,
+        ignore: false,
+        base_template: None,
     }];
-    roots_to_check.extend(roots.iter().cloned());
+    roots_to_check.extend(roots.iter().filter(|x| !x.ignore).cloned());
 
     if let Some(wp) = wine_prefix {
         roots_to_check.push(RootsConfig {
             path: wp.clone(),
             store: Store::OtherWine,
+            ignore: false,
+            base_template: None,
         });
 
         // We can add this for Wine prefixes from the CLI because they're
@@ -652,18 +894,34 @@ pub fn scan_game_for_backup(
                 if raw_path.trim().is_empty() {
                     continue;
                 }
-                if filter.exclude_other_os_data {
-                    if let Some(constraints) = &path_info.when {
-                        if should_exclude_as_other_os_data(constraints, get_os(), maybe_proton) {
-                            continue;
-                        }
+                if let Some(constraints) = &path_info.when {
+                    if filter.exclude_other_os_data
+                        && should_exclude_as_other_os_data(constraints, get_os(), maybe_proton)
+                    {
+                        continue;
+                    }
+                    if should_exclude_as_other_store_data(constraints, root.store) {
+                        continue;
                     }
                 }
                 let candidates = parse_paths(raw_path, &root, &install_dir, steam_id, manifest_dir);
+                let floors: Vec<_> = match whole_directory_floor(raw_path) {
+                    Some(floor) if whole_directory => parse_paths(&floor, &root, &install_dir, steam_id, manifest_dir)
+                        .into_iter()
+                        .filter(|x| !x.raw().contains(SKIP))
+                        .collect(),
+                    _ => vec![],
+                };
                 for candidate in candidates {
                     if candidate.raw().contains(SKIP) {
                         continue;
                     }
+                    if whole_directory {
+                        whole_directory_floors
+                            .entry(candidate.clone())
+                            .or_default()
+                            .extend(floors.iter().cloned());
+                    }
                     paths_to_check.insert(candidate);
                 }
             }
@@ -702,6 +960,8 @@ pub fn scan_game_for_backup(
         }
     }
 
+    let mut whole_directories_walked = std::collections::HashSet::<StrictPath>::new();
+
     for path in paths_to_check {
         if filter.is_path_ignored(&path) {
             continue;
@@ -711,13 +971,29 @@ pub fn scan_game_for_backup(
             Err(_) => continue,
         };
         for entry in entries.filter_map(|r| r.ok()) {
-            let p = StrictPath::from(entry).rendered();
+            if cancelled() {
+                return None;
+            }
+            let entry = StrictPath::from(entry);
+            let mut p = resolve_links(entry.clone());
+            if let (true, Some(floors)) = (p.is_file(), whole_directory_floors.get(&path)) {
+                if let Some(folder) = whole_directory_for(&entry, floors, &roots_to_check) {
+                    let folder = resolve_links(folder);
+                    if !whole_directories_walked.insert(folder.clone()) {
+                        continue;
+                    }
+                    p = folder;
+                }
+            }
             if p.is_file() {
-                if filter.is_path_ignored(&p) {
+                if filter.is_path_ignored(&p) || filter.is_junk(&p) || !filter.is_file_allowed(name, &p) {
                     continue;
                 }
-                let ignored = ignored_paths.is_ignored(name, &p);
                 let metadata = p.metadata();
+                if too_old(&metadata) {
+                    continue;
+                }
+                let ignored = ignored_paths.is_ignored(name, &p);
                 found_files.insert(ScannedFile {
                     path: p,
                     size: match metadata {
@@ -727,6 +1003,9 @@ pub fn scan_game_for_backup(
                     original_path: None,
                     ignored,
                 });
+                if found_files.len() % SCAN_PROGRESS_INTERVAL == 0 {
+                    progress(found_files.len());
+                }
             } else if p.is_dir() {
                 for child in walkdir::WalkDir::new(p.as_std_path_buf())
                     .max_depth(100)
@@ -734,13 +1013,22 @@ pub fn scan_game_for_backup(
                     .into_iter()
                     .filter_map(|e| e.ok())
                 {
+                    if cancelled() {
+                        return None;
+                    }
                     if child.file_type().is_file() {
-                        let child = StrictPath::from(&child).rendered();
-                        if filter.is_path_ignored(&child) {
+                        let child = resolve_links(StrictPath::from(&child));
+                        if filter.is_path_ignored(&child)
+                            || filter.is_junk(&child)
+                            || !filter.is_file_allowed(name, &child)
+                        {
                             continue;
                         }
-                        let ignored = ignored_paths.is_ignored(name, &child);
                         let metadata = child.metadata();
+                        if too_old(&metadata) {
+                            continue;
+                        }
+                        let ignored = ignored_paths.is_ignored(name, &child);
                         found_files.insert(ScannedFile {
                             path: child,
                             size: match metadata {
@@ -750,6 +1038,9 @@ pub fn scan_game_for_backup(
                             original_path: None,
                             ignored,
                         });
+                        if found_files.len() % SCAN_PROGRESS_INTERVAL == 0 {
+                            progress(found_files.len());
+                        }
                     }
                 }
             }
@@ -770,12 +1061,16 @@ pub fn scan_game_for_backup(
         }
     }
 
-    ScanInfo {
+    if cancelled() {
+        return None;
+    }
+
+    Some(ScanInfo {
         game_name: name.to_string(),
         found_files,
         found_registry_keys,
         registry_file: None,
-    }
+    })
 }
 
 pub fn scan_game_for_restoration(name: &str, layout: &BackupLayout) -> ScanInfo {
@@ -814,275 +1109,1360 @@ pub fn scan_game_for_restoration(name: &str, layout: &BackupLayout) -> ScanInfo
     }
 }
 
-pub fn prepare_backup_target(target: &StrictPath, merge: bool) -> Result<(), Error> {
+/// Names of the games that the manifest or custom games know about,
+/// whether or not they're currently selected. Backups of any other game are orphans.
+pub fn relevant_backup_games(
+    config: &crate::config::Config,
+    manifest: &crate::manifest::Manifest,
+) -> std::collections::HashSet<String> {
+    let mut names: std::collections::HashSet<_> = manifest.0.keys().cloned().collect();
+    names.extend(config.custom_games.iter().map(|x| x.name.clone()));
+    names.insert(crate::manifest::STEAM_ACCOUNT_SETTINGS.to_string());
+    names
+}
+
+pub fn prepare_backup_target(
+    target: &StrictPath,
+    merge: bool,
+    roots: &[RootsConfig],
+    restore_source: &StrictPath,
+) -> Result<(), Error> {
+    if target.is_blank_or_root() {
+        return Err(Error::BackupTargetInvalid { path: target.clone() });
+    }
+
+    // Otherwise, the backup could end up scanning and copying its own output.
+    // Home folder roots are exempt since they are expected to contain the
+    // backup target (e.g., the default `~/ludusavi-backup`), and games only
+    // use specific subfolders of them.
+    for root in roots {
+        if root.store != Store::OtherHome && (root.path.same_path(target) || root.path.is_prefix_of(target)) {
+            return Err(Error::BackupTargetInsideRoot {
+                path: target.clone(),
+                root: root.path.clone(),
+            });
+        }
+    }
+    // It's normal to back up and restore with the same folder, but not to nest them.
+    if restore_source.is_prefix_of(target) {
+        return Err(Error::BackupTargetInsideRoot {
+            path: target.clone(),
+            root: restore_source.clone(),
+        });
+    }
+
+    if is_read_only() {
+        return Err(Error::ReadOnlyMode);
+    }
+
+    let failed = |e: Option<&std::io::Error>| {
+        if e.map(is_permission_denied).unwrap_or(false) {
+            Error::BackupTargetNotWritable { path: target.clone() }
+        } else {
+            Error::CannotPrepareBackupTarget { path: target.clone() }
+        }
+    };
+
     if !merge {
-        target
-            .remove()
-            .map_err(|_| Error::CannotPrepareBackupTarget { path: target.clone() })?;
+        // Clearing the target would delete backups that the user locked to keep them safe.
+        if BackupLayout::new(target.clone(), Default::default()).has_locked_backups() {
+            return Err(Error::BackupTargetHasLockedBackups { path: target.clone() });
+        }
+        target.remove().map_err(|e| failed(e.downcast_ref()))?;
     } else if target.exists() && !target.is_dir() {
         return Err(Error::CannotPrepareBackupTarget { path: target.clone() });
     }
 
     let p = target.as_std_path_buf();
-    std::fs::create_dir_all(&p).map_err(|_| Error::CannotPrepareBackupTarget { path: target.clone() })?;
+    std::fs::create_dir_all(&p).map_err(|e| failed(Some(&e)))?;
+    // An existing folder is accepted by `create_dir_all` even if we can't write to it.
+    probe_writable(&p).map_err(|e| failed(Some(&e)))?;
 
     Ok(())
 }
 
-pub fn are_files_identical(file1: &StrictPath, file2: &StrictPath) -> Result<bool, Box<dyn std::error::Error>> {
-    let f1 = std::fs::File::open(file1.interpret())?;
-    let mut f1r = std::io::BufReader::new(f1);
-    let f2 = std::fs::File::open(file2.interpret())?;
-    let mut f2r = std::io::BufReader::new(f2);
+fn is_permission_denied(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::PermissionDenied
+}
 
-    let mut f1b = [0; 1024];
-    let mut f2b = [0; 1024];
-    loop {
-        let f1n = f1r.read(&mut f1b[..])?;
-        let f2n = f2r.read(&mut f2b[..])?;
+/// Create and remove a scratch file, so that a folder we can't write to is
+/// reported once instead of as a failure for every individual file.
+fn probe_writable(folder: &std::path::Path) -> std::io::Result<()> {
+    let probe = folder.join(".ludusavi-write-check");
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
 
-        if f1n != f2n || f1b.iter().zip(f2b.iter()).any(|(a, b)| a != b) {
-            return Ok(false);
+/// Check that the folders a game's files would be restored into can be written to.
+/// Folders that don't exist yet are checked via their closest existing ancestor.
+/// Only permission problems are reported here; anything else is left to fail per file.
+pub fn check_restoration_targets_writable(info: &ScanInfo, redirects: &[RedirectConfig]) -> Result<(), Error> {
+    let mut checked = std::collections::HashSet::new();
+    for file in info.found_files.iter().filter(|x| !x.ignored) {
+        let target = match file
+            .original_path
+            .as_ref()
+            .and_then(|x| game_file_restoration_target(x, redirects).ok())
+        {
+            Some((target, _)) => target.as_std_path_buf(),
+            None => continue,
+        };
+        let mut folder = match target.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => continue,
+        };
+        while !folder.exists() {
+            if !folder.pop() {
+                break;
+            }
         }
-        if f1n == 0 || f2n == 0 {
-            break;
+        if !folder.is_dir() || !checked.insert(folder.clone()) {
+            continue;
+        }
+        if let Err(e) = probe_writable(&folder) {
+            if is_permission_denied(&e) {
+                return Err(Error::RestorationTargetNotWritable {
+                    path: StrictPath::from(folder),
+                });
+            }
         }
     }
-    Ok(true)
+    Ok(())
 }
 
-pub fn back_up_game(
-    info: &ScanInfo,
-    name: &str,
-    layout: &BackupLayout,
-    merge: bool,
-    now: &chrono::DateTime<chrono::Utc>,
-) -> BackupInfo {
-    let mut layout = layout.game_layout(name);
-
-    let able_to_prepare = info.found_anything_processable()
-        && (merge || (layout.path.unset_readonly().is_ok() && layout.path.remove().is_ok()))
-        && std::fs::create_dir_all(layout.path.interpret()).is_ok();
-
-    if able_to_prepare {
-        layout.back_up(info, now)
-    } else {
-        let mut backup_info = BackupInfo::default();
+/// A manifest path that contains placeholders which can't be expanded
+/// on this system with the configured roots.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UnresolvedPlaceholders {
+    pub game: String,
+    pub path: String,
+    pub placeholders: Vec<String>,
+}
 
-        for file in &info.found_files {
-            if file.ignored {
+/// Find the manifest paths that would silently contribute nothing to a backup
+/// because some of their placeholders don't expand on this OS or with these roots.
+pub fn lint_manifest_placeholders(
+    manifest: &crate::manifest::Manifest,
+    roots: &[RootsConfig],
+) -> Vec<UnresolvedPlaceholders> {
+    let mut roots_to_check: Vec<RootsConfig> = vec![RootsConfig {
+        path: StrictPath::new(SKIP.to_string()),
+        store: Store::Other,
+        ignore: false,
+        base_template: None,
+    }];
+    roots_to_check.extend(roots.iter().filter(|x| !x.ignore).cloned());
+
+    let mut unresolved = vec![];
+    for (name, game) in &manifest.0 {
+        let steam_id = game.steam.as_ref().and_then(|x| x.id);
+        let install_dir = Some(name.clone());
+        for path in game.files.iter().flat_map(|x| x.keys()) {
+            let mut placeholders: Vec<_> = find_placeholders(path)
+                .into_iter()
+                .filter(|placeholder| {
+                    !roots_to_check.iter().any(|root| {
+                        expand_manifest_placeholders(placeholder, root, &install_dir, &steam_id)
+                            .iter()
+                            .any(|x| !x.contains(SKIP) && !x.contains(placeholder))
+                    })
+                })
+                .map(|x| x.to_string())
+                .collect();
+            if placeholders.is_empty() {
                 continue;
             }
-            backup_info.failed_files.insert(file.clone());
+            placeholders.sort();
+            placeholders.dedup();
+            unresolved.push(UnresolvedPlaceholders {
+                game: name.clone(),
+                path: path.clone(),
+                placeholders,
+            });
         }
-        for reg_path in &info.found_registry_keys {
-            if reg_path.ignored {
-                continue;
-            }
-            backup_info.failed_registry.insert(reg_path.path.clone());
-        }
-
-        backup_info
     }
+
+    unresolved.sort_by(|x, y| (&x.game, &x.path).cmp(&(&y.game, &y.path)));
+    unresolved
 }
 
-pub fn restore_game(info: &ScanInfo, redirects: &[RedirectConfig]) -> BackupInfo {
-    let mut failed_files = std::collections::HashSet::new();
-    let failed_registry = std::collections::HashSet::new();
+/// Run a user-configured command through the system shell.
+/// On failure, the error includes whatever the command printed.
+pub fn run_command(command: &str) -> Result<(), Error> {
+    #[cfg(target_os = "windows")]
+    let output = std::process::Command::new("cmd").args(["/C", command]).output();
+    #[cfg(not(target_os = "windows"))]
+    let output = std::process::Command::new("sh").args(["-c", command]).output();
+
+    let output = match output {
+        Ok(x) if x.status.success() => return Ok(()),
+        Ok(x) => format!(
+            "{}{}",
+            String::from_utf8_lossy(&x.stdout),
+            String::from_utf8_lossy(&x.stderr)
+        ),
+        Err(e) => e.to_string(),
+    };
 
-    'outer: for file in &info.found_files {
-        let original_path = match &file.original_path {
-            Some(x) => x,
-            None => continue,
-        };
-        let (target, _) = game_file_restoration_target(original_path, redirects);
+    Err(Error::CommandFailed {
+        command: command.to_string(),
+        output: output.trim().to_string(),
+    })
+}
 
-        if target.exists() {
-            match are_files_identical(&file.path, &target) {
-                Ok(true) => continue,
-                Ok(false) => (),
-                Err(_) => {
-                    failed_files.insert(file.clone());
-                    continue;
-                }
-            }
-        }
+/// Copy a whole backup folder, including the mappings, to a new location.
+/// Every copied file is compared against the original, and the copy must
+/// contain the same restorable games as the original. Any entry that can't be
+/// read fails the migration rather than being left out.
+pub fn migrate_backup(from: &StrictPath, to: &StrictPath) -> Result<(), Error> {
+    if is_read_only() {
+        return Err(Error::ReadOnlyMode);
+    }
+    if !from.is_dir() {
+        return Err(Error::RestorationSourceInvalid { path: from.clone() });
+    }
+    if from.same_path(to) || from.is_prefix_of(to) {
+        return Err(Error::BackupTargetInsideRoot {
+            path: to.clone(),
+            root: from.clone(),
+        });
+    }
+    if to.exists() && !to.is_dir() {
+        return Err(Error::CannotPrepareBackupTarget { path: to.clone() });
+    }
 
-        if target.create_parent_dir().is_err() {
-            failed_files.insert(file.clone());
+    let failed = || Error::BackupMigrationFailed { path: to.clone() };
+    let source_base = from.as_std_path_buf();
+
+    for entry in walkdir::WalkDir::new(&source_base).follow_links(false) {
+        // Skipping an unreadable entry would silently leave it out of the copy.
+        let entry = entry.map_err(|e| Error::BackupMigrationFailed {
+            path: e
+                .path()
+                .map(|x| StrictPath::from(x.to_path_buf()))
+                .unwrap_or_else(|| from.clone()),
+        })?;
+        if !entry.file_type().is_file() {
             continue;
         }
-        for i in 0..99 {
-            if target.unset_readonly().is_ok() && std::fs::copy(&file.path.interpret(), &target.interpret()).is_ok() {
-                continue 'outer;
-            }
-            // File might be busy, especially if multiple games share a file,
-            // like in a collection, so retry after a delay:
-            std::thread::sleep(std::time::Duration::from_millis(i * info.game_name.len() as u64));
+        let relative = entry.path().strip_prefix(&source_base).map_err(|_| failed())?;
+        let source = StrictPath::from(&entry);
+        let target = StrictPath::from(to.as_std_path_buf().join(relative));
+
+        if target.create_parent_dir().is_err() || copy_file(&source, &target, &CopyOptions::default()).is_err() {
+            return Err(failed());
+        }
+        if !matches!(are_files_identical(&source, &target), Ok(true)) {
+            return Err(failed());
         }
-        failed_files.insert(file.clone());
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        if let Some(registry_file) = &info.registry_file {
-            if let Some(hives) = crate::registry::Hives::load(registry_file) {
-                // TODO: Track failed keys.
-                let _ = hives.restore();
-            }
-        }
+    let mut expected = BackupLayout::new(from.clone(), Default::default()).restorable_games();
+    let mut actual = BackupLayout::new(to.clone(), Default::default()).restorable_games();
+    expected.sort();
+    actual.sort();
+    if expected != actual {
+        return Err(failed());
     }
 
-    BackupInfo {
-        failed_files,
-        failed_registry,
+    Ok(())
+}
+
+pub fn are_files_identical(file1: &StrictPath, file2: &StrictPath) -> Result<bool, Box<dyn std::error::Error>> {
+    let f1 = std::fs::File::open(file1.interpret())?;
+    let mut f1r = std::io::BufReader::new(f1);
+    let f2 = std::fs::File::open(file2.interpret())?;
+    let mut f2r = std::io::BufReader::new(f2);
+
+    let mut f1b = [0; 1024];
+    let mut f2b = [0; 1024];
+    loop {
+        let f1n = f1r.read(&mut f1b[..])?;
+        let f2n = f2r.read(&mut f2b[..])?;
+
+        if f1n != f2n || f1b.iter().zip(f2b.iter()).any(|(a, b)| a != b) {
+            return Ok(false);
+        }
+        if f1n == 0 || f2n == 0 {
+            break;
+        }
     }
+    Ok(true)
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct DuplicateDetector {
-    files: std::collections::HashMap<StrictPath, std::collections::HashSet<String>>,
-    registry: std::collections::HashMap<RegistryItem, std::collections::HashSet<String>>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CopyOptions {
+    pub reflink: bool,
+    pub buffer_size: Option<usize>,
+    /// Check that each copy matches its source afterward.
+    pub verify: bool,
+    /// Pace copies with `RATE_LIMIT` so that they share this overall throughput.
+    pub max_bytes_per_second: Option<u64>,
+    /// How many times to try each copy. See `retry_io`.
+    pub attempts: u32,
 }
 
-impl DuplicateDetector {
-    pub fn add_game(&mut self, scan_info: &ScanInfo) {
-        for item in scan_info.found_files.iter() {
-            self.files
-                .entry(self.pick_path(item))
-                .or_insert_with(Default::default)
-                .insert(scan_info.game_name.clone());
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            reflink: true,
+            buffer_size: None,
+            verify: false,
+            max_bytes_per_second: None,
+            attempts: 1,
         }
-        for item in scan_info.found_registry_keys.iter() {
-            self.registry
-                .entry(item.path.clone())
-                .or_insert_with(Default::default)
-                .insert(scan_info.game_name.clone());
+    }
+}
+
+impl From<&crate::config::BackupConfig> for CopyOptions {
+    fn from(config: &crate::config::BackupConfig) -> Self {
+        Self {
+            reflink: config.reflink,
+            buffer_size: config.copy_buffer_size,
+            verify: config.verify_after_write,
+            max_bytes_per_second: config.max_bytes_per_second.filter(|x| *x > 0),
+            attempts: config.copy_attempts.max(1),
         }
     }
+}
 
-    pub fn is_game_duplicated(&self, scan_info: &ScanInfo) -> bool {
-        for item in scan_info.found_files.iter() {
-            if self.file(item).len() > 1 {
-                return true;
-            }
+/// Caps how many threads may do a certain kind of work at the same time.
+pub struct Throttle {
+    limit: std::sync::atomic::AtomicUsize,
+    active: std::sync::Mutex<usize>,
+    available: std::sync::Condvar,
+}
+
+impl Throttle {
+    pub const fn new() -> Self {
+        Self {
+            limit: std::sync::atomic::AtomicUsize::new(0),
+            active: std::sync::Mutex::new(0),
+            available: std::sync::Condvar::new(),
         }
-        for item in scan_info.found_registry_keys.iter() {
-            if self.registry(&item.path).len() > 1 {
-                return true;
+    }
+
+    /// Zero means no limit.
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, std::sync::atomic::Ordering::Relaxed);
+        self.available.notify_all();
+    }
+
+    /// Wait until there's room, then hold a slot until the guard is dropped.
+    pub fn acquire(&self) -> ThrottleGuard<'_> {
+        let mut active = self.active.lock().unwrap();
+        loop {
+            let limit = self.limit.load(std::sync::atomic::Ordering::Relaxed);
+            if limit == 0 || *active < limit {
+                break;
             }
+            active = self.available.wait(active).unwrap();
         }
-        false
+        *active += 1;
+        ThrottleGuard { throttle: self }
     }
+}
 
-    fn pick_path(&self, file: &ScannedFile) -> StrictPath {
-        match &file.original_path {
-            Some(op) => op.clone(),
-            None => file.path.clone(),
+pub struct ThrottleGuard<'a> {
+    throttle: &'a Throttle,
+}
+
+impl Drop for ThrottleGuard<'_> {
+    fn drop(&mut self) {
+        *self.throttle.active.lock().unwrap() -= 1;
+        self.throttle.available.notify_one();
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Set for read-only mode. Operations are already blocked up front in that mode,
+/// so this is a backstop that turns any write that slips through into an error.
+static READ_ONLY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+pub fn set_read_only(enabled: bool) {
+    READ_ONLY.store(enabled, std::sync::atomic::Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Fail if read-only mode is on. Call this before writing or deleting anything.
+pub fn ensure_writable() -> std::io::Result<()> {
+    if is_read_only() {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            Error::ReadOnlyMode.to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Limits file copies, since parallel copies can thrash slow drives.
+pub static IO_THROTTLE: Throttle = Throttle::new();
+/// Limits game scans, which are stat-heavy rather than bandwidth-heavy.
+pub static SCAN_THROTTLE: Throttle = Throttle::new();
+
+/// Spreads writes out over time so that all copies together stay under a throughput limit.
+pub struct RateLimiter {
+    /// When the bytes already handed out will have been "paid for".
+    busy_until: std::sync::Mutex<Option<std::time::Instant>>,
+}
+
+impl RateLimiter {
+    pub const fn new() -> Self {
+        Self {
+            busy_until: std::sync::Mutex::new(None),
         }
     }
 
-    pub fn file(&self, file: &ScannedFile) -> std::collections::HashSet<String> {
-        match self.files.get(&self.pick_path(file)) {
-            Some(games) => games.clone(),
-            None => Default::default(),
+    /// Account for some bytes that were just written, then wait until they fit under the limit.
+    pub fn consume(&self, bytes: u64, bytes_per_second: u64) {
+        if bytes_per_second == 0 {
+            return;
         }
+        let cost = std::time::Duration::from_secs_f64(bytes as f64 / bytes_per_second as f64);
+        let now = std::time::Instant::now();
+        let until = {
+            let mut busy_until = self.busy_until.lock().unwrap();
+            let until = busy_until.filter(|x| *x > now).unwrap_or(now) + cost;
+            *busy_until = Some(until);
+            until
+        };
+        std::thread::sleep(until.saturating_duration_since(now));
     }
+}
 
-    pub fn is_file_duplicated(&self, file: &ScannedFile) -> bool {
-        self.file(file).len() > 1
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn registry(&self, path: &RegistryItem) -> std::collections::HashSet<String> {
-        match self.registry.get(path) {
-            Some(games) => games.clone(),
-            None => Default::default(),
+/// Shared by all copies that have `CopyOptions::max_bytes_per_second`.
+pub static RATE_LIMIT: RateLimiter = RateLimiter::new();
+
+/// Keeps track of how much room is left under `maxTotalBytes` during a backup.
+/// Games claim their size up front, so concurrent backups can't overshoot the limit.
+pub struct BackupBudget {
+    limit: Option<u64>,
+    used: std::sync::atomic::AtomicU64,
+}
+
+impl BackupBudget {
+    pub fn new(limit: Option<u64>, used: u64) -> Self {
+        Self {
+            limit,
+            used: std::sync::atomic::AtomicU64::new(used),
         }
     }
 
-    pub fn is_registry_duplicated(&self, path: &RegistryItem) -> bool {
-        self.registry(path).len() > 1
+    /// Returns false, without claiming anything, if the game would not fit.
+    pub fn reserve(&self, bytes: u64) -> bool {
+        use std::sync::atomic::Ordering;
+
+        match self.limit {
+            None => {
+                self.used.fetch_add(bytes, Ordering::SeqCst);
+                true
+            }
+            Some(limit) => self
+                .used
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                    Some(used + bytes).filter(|x| *x <= limit)
+                })
+                .is_ok(),
+        }
     }
 
-    pub fn clear(&mut self) {
-        self.files.clear();
-        self.registry.clear();
+    /// Like `reserve`, but for a game whose new backup replaces `existing` bytes
+    /// that are already counted, so only the growth is claimed.
+    pub fn reserve_replacing(&self, bytes: u64, existing: u64) -> bool {
+        self.reserve(bytes.saturating_sub(existing))
     }
+}
 
-    pub fn any_duplicates(&self) -> bool {
-        for item in self.files.values() {
-            if item.len() > 1 {
-                return true;
+/// Apply the concurrency settings before starting a backup.
+pub fn configure_throttles(config: &crate::config::BackupConfig) {
+    IO_THROTTLE.set_limit(config.io_concurrency);
+    SCAN_THROTTLE.set_limit(config.scan_concurrency.unwrap_or(0));
+}
+
+/// How long to wait before the first retry of a failed copy. This doubles for each later retry.
+const COPY_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+/// The longest wait between copy attempts, so that many attempts can't stall a file for hours.
+const MAX_COPY_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Run an I/O operation up to `attempts` times, backing off between tries.
+/// This helps with network drives, where an occasional write fails but works right after.
+/// Errors that won't go away by waiting, like a missing file or no permission, are returned immediately.
+pub fn retry_io<T>(attempts: u32, mut operation: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    let mut delay = COPY_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Ok(x) => return Ok(x),
+            Err(e)
+                if attempt >= attempts
+                    || matches!(
+                        e.kind(),
+                        std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied
+                    ) =>
+            {
+                return Err(e)
             }
-        }
-        for item in self.registry.values() {
-            if item.len() > 1 {
-                return true;
+            Err(_) => {
+                std::thread::sleep(delay);
+                delay = (delay * 2).min(MAX_COPY_RETRY_DELAY);
+                attempt += 1;
             }
         }
-        false
     }
+}
 
-    pub fn count_duplicates_for(&self, game: &str) -> usize {
-        let mut tally = 0;
-        for item in self.files.values() {
-            if item.contains(game) && item.len() > 1 {
-                tally += 1;
-            }
+/// How much to copy between pauses when copies are rate limited.
+const PACED_COPY_CHUNK: usize = 64 * 1024;
+
+/// Copy a file, preferring a copy-on-write clone when the file system supports it.
+pub fn copy_file(source: &StrictPath, target: &StrictPath, options: &CopyOptions) -> std::io::Result<()> {
+    ensure_writable()?;
+    let _slot = IO_THROTTLE.acquire();
+    let source = source.as_std_path_buf();
+    let target = target.as_std_path_buf();
+
+    if options.reflink {
+        // Reflinking will not overwrite an existing file, so we link into a sibling
+        // and only then replace the target, in case the link is not supported.
+        let mut staged = target.clone().into_os_string();
+        staged.push(".ludusavi-reflink");
+        let staged = std::path::PathBuf::from(staged);
+        let _ = std::fs::remove_file(&staged);
+        if reflink::reflink(&source, &staged).is_ok() && std::fs::rename(&staged, &target).is_ok() {
+            return Ok(());
         }
-        for item in self.registry.values() {
-            if item.contains(game) && item.len() > 1 {
-                tally += 1;
+        let _ = std::fs::remove_file(&staged);
+    }
+
+    match (options.buffer_size, options.max_bytes_per_second) {
+        (size, Some(limit)) => {
+            use std::io::{Read, Write};
+
+            let mut reader = std::fs::File::open(&source)?;
+            let mut writer = std::fs::File::create(&target)?;
+            let mut buffer = vec![0; size.unwrap_or(PACED_COPY_CHUNK).max(1)];
+            loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..read])?;
+                RATE_LIMIT.consume(read as u64, limit);
             }
+            writer.flush()?;
+            std::fs::set_permissions(&target, std::fs::metadata(&source)?.permissions())?;
+        }
+        (None, None) => {
+            std::fs::copy(&source, &target)?;
+        }
+        (Some(size), None) => {
+            use std::io::Write;
+
+            let mut reader = std::io::BufReader::with_capacity(size.max(1), std::fs::File::open(&source)?);
+            let mut writer = std::io::BufWriter::with_capacity(size.max(1), std::fs::File::create(&target)?);
+            std::io::copy(&mut reader, &mut writer)?;
+            writer.flush()?;
+            std::fs::set_permissions(&target, std::fs::metadata(&source)?.permissions())?;
         }
-        tally
     }
+
+    Ok(())
 }
 
-fn make_fuzzy_matcher() -> fuzzy_matcher::skim::SkimMatcherV2 {
-    fuzzy_matcher::skim::SkimMatcherV2::default()
-        .ignore_case()
-        .score_config(fuzzy_matcher::skim::SkimScoreConfig {
-            penalty_case_mismatch: 0,
-            ..Default::default()
-        })
+/// Programs that were running when checked, so that we can avoid backing up
+/// a game while it might be in the middle of writing its saves.
+/// Detection is best effort and varies by platform.
+#[derive(Clone, Debug, Default)]
+pub struct RunningProcesses {
+    pub executables: Vec<StrictPath>,
+    /// Steam sets `SteamAppId` for the games that it launches.
+    pub steam_ids: std::collections::HashSet<u32>,
 }
 
-pub fn fuzzy_match(
-    matcher: &fuzzy_matcher::skim::SkimMatcherV2,
-    reference: &str,
-    candidate: &str,
-    ideal: &Option<i64>,
-) -> Option<i64> {
-    if reference == candidate {
-        return Some(i64::MAX);
-    }
-    let actual = matcher.fuzzy_match(reference, &candidate.replace('_', " ").replace('-', " "));
-    if let (Some(ideal), Some(actual)) = (ideal, actual) {
-        if actual == *ideal {
-            return Some(i64::MAX);
-        } else if actual > (ideal / 4 * 3) {
-            return Some(actual);
+impl RunningProcesses {
+    pub fn detect() -> Self {
+        let mut processes = Self::default();
+
+        #[cfg(target_os = "linux")]
+        for entry in std::fs::read_dir("/proc").into_iter().flatten().filter_map(|x| x.ok()) {
+            if !entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            if let Ok(exe) = std::fs::read_link(entry.path().join("exe")) {
+                processes.executables.push(StrictPath::from(exe));
+            }
+            // Under Wine, the real program is usually the first argument, with `Z:` for `/`.
+            if let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) {
+                if let Some(first) = cmdline.split(|x| *x == 0).next() {
+                    let first = String::from_utf8_lossy(first);
+                    if let Some(unix) = first.strip_prefix("Z:") {
+                        processes.executables.push(StrictPath::new(unix.replace('\\', "/")));
+                    }
+                }
+            }
+            if let Ok(environ) = std::fs::read(entry.path().join("environ")) {
+                for variable in environ.split(|x| *x == 0) {
+                    let variable = String::from_utf8_lossy(variable);
+                    if let Some(id) = variable.strip_prefix("SteamAppId=") {
+                        if let Ok(id) = id.parse() {
+                            processes.steam_ids.insert(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        if let Ok(output) = std::process::Command::new("ps").args(["-axo", "comm="]).output() {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                processes.executables.push(StrictPath::new(line.trim().to_string()));
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+            if let Ok(output) = std::process::Command::new("powershell")
+                .args([
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    "Get-Process | ForEach-Object { $_.Path }",
+                ])
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+            {
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    if !line.trim().is_empty() {
+                        processes.executables.push(StrictPath::new(line.trim().to_string()));
+                    }
+                }
+            }
         }
+
+        processes
     }
-    None
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::{Config, Retention};
-    use crate::manifest::Manifest;
-    use maplit::*;
-    use pretty_assertions::assert_eq;
+/// Games that seem to be running, either because a program is running from
+/// inside of their install folder or because Steam launched them.
+pub fn find_running_games(
+    processes: &RunningProcesses,
+    manifest: &crate::manifest::Manifest,
+    ranking: &InstallDirRanking,
+) -> std::collections::HashSet<String> {
+    let executables: Vec<_> = processes.executables.iter().map(|x| x.render()).collect();
+    let mut running = std::collections::HashSet::new();
+
+    for ((root, name), (_, install_dir)) in &ranking.0 {
+        let base = format!(
+            "{}/",
+            StrictPath::new(root.game_base(install_dir))
+                .render()
+                .trim_end_matches('/')
+        );
+        if executables.iter().any(|x| x.starts_with(&base)) && ranking.get(root, name).as_ref() == Some(install_dir) {
+            running.insert(name.clone());
+        }
+    }
 
-    #[test]
-    fn fuzzy_matching() {
-        let matcher = make_fuzzy_matcher();
+    if !processes.steam_ids.is_empty() {
+        for (name, game) in &manifest.0 {
+            if let Some(id) = game.steam.as_ref().and_then(|x| x.id) {
+                if processes.steam_ids.contains(&id) {
+                    running.insert(name.clone());
+                }
+            }
+        }
+    }
 
-        for (reference, candidate, output) in vec![
-            ("a", "a", Some(i64::MAX)),
-            ("a", "b", None),
+    running
+}
+
+/// Which store's root contains the game's files (by original location when restoring).
+pub fn game_store(info: &ScanInfo, roots: &[RootsConfig]) -> Store {
+    let files: Vec<_> = info
+        .found_files
+        .iter()
+        .map(|x| x.original_path.as_ref().unwrap_or(&x.path).render())
+        .collect();
+    for root in roots.iter().flat_map(|x| x.glob()) {
+        let prefix = format!("{}/", root.path.render().trim_end_matches('/'));
+        if files.iter().any(|x| x.starts_with(&prefix)) {
+            return root.store;
+        }
+    }
+    Store::Other
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn back_up_game(
+    info: &ScanInfo,
+    name: &str,
+    store: Store,
+    layout: &BackupLayout,
+    merge: bool,
+    files: bool,
+    registry: bool,
+    now: &chrono::DateTime<chrono::Utc>,
+    copy_options: &CopyOptions,
+) -> BackupInfo {
+    let info = &info.only_kinds(files, registry);
+    if !info.found_anything_processable() {
+        return BackupInfo::default();
+    }
+    let mut layout = layout.game_layout_for_backup(name, store);
+    // Locked backups are never cleared out, so such games are always merged.
+    let merge = merge || layout.has_locked_backup();
+
+    let able_to_prepare = !is_read_only()
+        && !layout.is_locked()
+        && (merge || (layout.path.unset_readonly().is_ok() && layout.path.remove().is_ok()))
+        && std::fs::create_dir_all(layout.path.interpret()).is_ok();
+
+    if able_to_prepare {
+        layout.back_up(info, files, registry, now, copy_options)
+    } else {
+        let mut backup_info = BackupInfo::default();
+
+        for file in &info.found_files {
+            if file.ignored {
+                continue;
+            }
+            backup_info.failed_files.insert(file.clone());
+        }
+        for reg_path in &info.found_registry_keys {
+            if reg_path.ignored {
+                continue;
+            }
+            backup_info.failed_registry.insert(reg_path.path.clone());
+        }
+
+        backup_info
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn restore_game(
+    info: &ScanInfo,
+    layout: &BackupLayout,
+    redirects: &[RedirectConfig],
+    files: bool,
+    registry: bool,
+    strategy: RestoreStrategy,
+    overwrite_newer_by: Option<u64>,
+    line_ending_patterns: &[String],
+    slots: &SaveSlotFilter,
+) -> BackupInfo {
+    let info = &info.only_kinds(files, registry);
+    let mut backup_info = BackupInfo::default();
+    let layout = layout.game_layout(&info.game_name);
+
+    if is_read_only() {
+        return BackupInfo {
+            failed_files: info.found_files.iter().filter(|x| !x.ignored).cloned().collect(),
+            failed_registry: info
+                .found_registry_keys
+                .iter()
+                .filter(|x| !x.ignored)
+                .map(|x| x.path.clone())
+                .collect(),
+            ..Default::default()
+        };
+    }
+
+    if strategy == RestoreStrategy::Mirror && files {
+        for extra in files_not_in_backup(info, &layout, redirects) {
+            if let Err(e) = extra.remove() {
+                let file = ScannedFile {
+                    path: extra.clone(),
+                    size: 0,
+                    original_path: Some(extra),
+                    ignored: false,
+                };
+                backup_info.add_failed_file(file, FailureCategory::from_error(e.as_ref()));
+            }
+        }
+    }
+
+    'outer: for file in &info.found_files {
+        if !slots.allows(file) {
+            continue;
+        }
+        let original_path = match &file.original_path {
+            Some(x) => x,
+            None => continue,
+        };
+        let target = match game_file_restoration_target(original_path, redirects) {
+            Ok((target, _)) => target,
+            Err(_) => {
+                backup_info.add_failed_file(file.clone(), FailureCategory::Other);
+                continue;
+            }
+        };
+        let apply_metadata = || {
+            if let Some(metadata) = layout.file_metadata(original_path) {
+                // This is best effort, since the content itself was restored.
+                let _ = metadata.apply(&target);
+            }
+        };
+
+        if target.exists() {
+            match layout.stored_identical(&file.path, &target) {
+                Ok(true) => {
+                    apply_metadata();
+                    continue;
+                }
+                Ok(false) => {
+                    let stored = stored_modified(&layout, original_path, &file.path);
+                    let current = crate::layout::FileMetadata::read(&target).modified;
+                    if !should_overwrite(stored, current, overwrite_newer_by) {
+                        continue;
+                    }
+                }
+                Err(e) => {
+                    backup_info.add_failed_file(file.clone(), FailureCategory::from_error(e.as_ref()));
+                    continue;
+                }
+            }
+        }
+
+        if let Err(e) = target.create_parent_dir() {
+            backup_info.add_failed_file(file.clone(), FailureCategory::from_io(&e));
+            continue;
+        }
+        let mut category = FailureCategory::Other;
+        for i in 0..99 {
+            if target.unset_readonly().is_err() {
+                category = FailureCategory::Permission;
+            } else {
+                match layout.restore_stored(&file.path, &target) {
+                    Ok(()) => {
+                        if crate::config::file_name_matches(original_path, line_ending_patterns) {
+                            // This is best effort, since the content itself was restored.
+                            let _ = normalize_line_endings_in(&target);
+                        }
+                        apply_metadata();
+                        continue 'outer;
+                    }
+                    Err(e) => category = FailureCategory::from_io(&e),
+                }
+            }
+            // File might be busy, especially if multiple games share a file,
+            // like in a collection, so retry after a delay:
+            std::thread::sleep(std::time::Duration::from_millis(i * info.game_name.len() as u64));
+        }
+        backup_info.add_failed_file(file.clone(), category);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(registry_file) = &info.registry_file {
+            if let Some(hives) = crate::registry::Hives::load(registry_file) {
+                // TODO: Track failed keys.
+                let _ = hives.restore();
+            }
+        }
+    }
+
+    layout.remove_archive_scratch();
+    backup_info
+}
+
+/// For mirroring, find the files that an earlier backup of this game recorded,
+/// but that aren't in the backup being restored. Saves often live in folders
+/// shared with other games or the user's own files, so nothing else is touched.
+/// Files that are in the backup, but not being restored this time
+/// (e.g., deselected or kept as newer), are left alone,
+/// so this checks the backup's full contents rather than the scan.
+fn files_not_in_backup(
+    info: &ScanInfo,
+    layout: &crate::layout::GameLayout,
+    redirects: &[RedirectConfig],
+) -> Vec<StrictPath> {
+    let target = |file: &ScannedFile| {
+        file.original_path
+            .as_ref()
+            .and_then(|x| game_file_restoration_target(x, redirects).ok())
+            .map(|(target, _)| target)
+    };
+
+    let in_backup: std::collections::HashSet<_> = layout
+        .restorable_files()
+        .iter()
+        .chain(info.found_files.iter())
+        .filter_map(target)
+        .map(|x| x.render())
+        .collect();
+
+    let mut extras: Vec<_> = layout
+        .previously_backed_up_files()
+        .iter()
+        .filter_map(target)
+        .filter(|x| x.is_file() && !in_backup.contains(&x.render()))
+        .collect();
+    extras.sort();
+    extras.dedup_by(|x, y| x.render() == y.render());
+    extras
+}
+
+/// What `restore_game` would delete, without restoring anything.
+pub fn preview_restore_deletions(
+    info: &ScanInfo,
+    layout: &BackupLayout,
+    redirects: &[RedirectConfig],
+    files: bool,
+    strategy: RestoreStrategy,
+) -> DeletionPreview {
+    let mut preview = DeletionPreview::default();
+    if strategy == RestoreStrategy::Mirror && files {
+        let layout = layout.game_layout(&info.game_name);
+        for extra in files_not_in_backup(&info.only_kinds(true, false), &layout, redirects) {
+            preview.add(extra);
+        }
+    }
+    preview
+}
+
+/// Restore a game's files into a scratch folder instead of their real locations,
+/// then check that each one matches the backup. The scratch folder is removed
+/// afterward. Registry data is not restored, since it has no harmless location.
+pub fn test_restore_game(info: &ScanInfo, layout: &BackupLayout, scratch: &StrictPath) -> BackupInfo {
+    let layout = layout.game_layout(&info.game_name);
+    let mut failed_files = std::collections::HashSet::new();
+
+    for (i, file) in info.found_files.iter().enumerate() {
+        let target = scratch.joined(&i.to_string());
+        let restored = target.create_parent_dir().is_ok() && layout.restore_stored(&file.path, &target).is_ok();
+        if !restored || !matches!(layout.stored_identical(&file.path, &target), Ok(true)) {
+            failed_files.insert(file.clone());
+        }
+    }
+
+    let _ = scratch.remove();
+    BackupInfo {
+        failed_files,
+        ..Default::default()
+    }
+}
+
+/// Files and folders that an operation would delete, so that they can be reviewed beforehand.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeletionPreview {
+    /// Each path and how many bytes it takes up.
+    pub entries: std::collections::BTreeMap<StrictPath, u64>,
+}
+
+impl DeletionPreview {
+    pub fn add(&mut self, path: StrictPath) {
+        let bytes = walkdir::WalkDir::new(path.interpret())
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|x| x.ok())
+            .filter(|x| x.file_type().is_file())
+            .filter_map(|x| x.metadata().ok())
+            .map(|x| x.len())
+            .sum();
+        self.entries.insert(path, bytes);
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.values().sum()
+    }
+}
+
+/// A file that restoring would overwrite, even though the current copy is newer than the backup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RestoreConflict {
+    pub game: String,
+    pub original: StrictPath,
+    pub target: StrictPath,
+}
+
+/// When the backed up file was last modified, preferring the time recorded during the backup.
+fn stored_modified(
+    layout: &crate::layout::GameLayout,
+    original_path: &StrictPath,
+    stored: &StrictPath,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    layout
+        .file_metadata(original_path)
+        .and_then(|x| x.modified)
+        .or_else(|| crate::layout::FileMetadata::read(stored).modified)
+}
+
+#[cfg(target_os = "windows")]
+const NATIVE_LINE_ENDING: &[u8] = b"\r\n";
+#[cfg(not(target_os = "windows"))]
+const NATIVE_LINE_ENDING: &[u8] = b"\n";
+
+/// Convert CRLF and LF line endings to the given style.
+/// Returns None for content that looks binary (i.e., has null bytes),
+/// so that non-text files are never modified.
+fn normalize_line_endings(content: &[u8], line_ending: &[u8]) -> Option<Vec<u8>> {
+    if content.contains(&0) {
+        return None;
+    }
+    let mut normalized = Vec::with_capacity(content.len());
+    let mut i = 0;
+    while i < content.len() {
+        if content[i..].starts_with(b"\r\n") {
+            normalized.extend_from_slice(line_ending);
+            i += 2;
+        } else if content[i] == b'\n' {
+            normalized.extend_from_slice(line_ending);
+            i += 1;
+        } else {
+            normalized.push(content[i]);
+            i += 1;
+        }
+    }
+    Some(normalized)
+}
+
+fn normalize_line_endings_in(file: &StrictPath) -> std::io::Result<()> {
+    ensure_writable()?;
+    let content = std::fs::read(file.interpret())?;
+    match normalize_line_endings(&content, NATIVE_LINE_ENDING) {
+        Some(normalized) if normalized != content => std::fs::write(file.interpret(), normalized),
+        _ => Ok(()),
+    }
+}
+
+/// With a threshold, an existing file is only replaced if the backup is newer by at least that
+/// many seconds. This keeps small clock differences between machines or coarse file system
+/// timestamps from making files flip back and forth. Without both times, the file is replaced.
+fn should_overwrite(
+    stored: Option<chrono::DateTime<chrono::Utc>>,
+    current: Option<chrono::DateTime<chrono::Utc>>,
+    threshold: Option<u64>,
+) -> bool {
+    match (stored, current, threshold) {
+        (Some(stored), Some(current), Some(threshold)) => {
+            stored - current >= chrono::Duration::seconds(threshold as i64)
+        }
+        _ => true,
+    }
+}
+
+/// Find the files that restoring a game would overwrite with an older copy.
+/// The backup's age comes from its recorded metadata, if any, or else from the stored file.
+pub fn restore_conflicts(info: &ScanInfo, layout: &BackupLayout, redirects: &[RedirectConfig]) -> Vec<RestoreConflict> {
+    let layout = layout.game_layout(&info.game_name);
+    let mut conflicts = vec![];
+
+    for file in &info.found_files {
+        let original_path = match &file.original_path {
+            Some(x) => x,
+            None => continue,
+        };
+        let target = match game_file_restoration_target(original_path, redirects) {
+            Ok((target, _)) => target,
+            Err(_) => continue,
+        };
+        if !target.is_file() || matches!(layout.stored_identical(&file.path, &target), Ok(true)) {
+            continue;
+        }
+
+        let stored = stored_modified(&layout, original_path, &file.path);
+        let current = crate::layout::FileMetadata::read(&target).modified;
+        if let (Some(stored), Some(current)) = (stored, current) {
+            if current > stored {
+                conflicts.push(RestoreConflict {
+                    game: info.game_name.clone(),
+                    original: original_path.clone(),
+                    target,
+                });
+            }
+        }
+    }
+
+    conflicts.sort_by(|x, y| x.target.cmp(&y.target));
+    conflicts
+}
+
+/// Registry values that a restoration would affect, so that they can be reviewed first.
+pub fn registry_changes_for_restoration(layout: &BackupLayout, games: &[String]) -> Vec<RegistryChange> {
+    #[allow(unused_mut)]
+    let mut changes = vec![];
+
+    #[cfg(target_os = "windows")]
+    {
+        for game in games {
+            if let Some(hives) = crate::registry::Hives::load(&layout.game_layout(game).registry_file()) {
+                changes.extend(hives.diff(&hives.current()));
+            }
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = (layout, games);
+
+    changes
+}
+
+/// Check whether restoring a game would change anything on disk,
+/// i.e., whether its current state differs from the backup.
+pub fn need_restoration(info: &ScanInfo, layout: &BackupLayout, redirects: &[RedirectConfig]) -> bool {
+    let layout = layout.game_layout(&info.game_name);
+
+    for file in &info.found_files {
+        let original_path = match &file.original_path {
+            Some(x) => x,
+            None => continue,
+        };
+        let target = match game_file_restoration_target(original_path, redirects) {
+            Ok((target, _)) => target,
+            // There's nowhere to restore it, so it can't be considered changed.
+            Err(_) => continue,
+        };
+
+        if !target.is_file() {
+            return true;
+        }
+        if !layout.is_encrypted(&file.path) {
+            match (file.path.metadata(), target.metadata()) {
+                (Ok(stored), Ok(current)) if stored.len() != current.len() => return true,
+                _ => {}
+            }
+        }
+        if !matches!(layout.stored_identical(&file.path, &target), Ok(true)) {
+            return true;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use crate::registry::Hives;
+
+        if let Some(registry_file) = &info.registry_file {
+            if let Some(stored) = Hives::load(registry_file) {
+                let mut current = Hives::default();
+                for (hive_name, keys) in stored.0.iter() {
+                    for key_name in keys.0.keys() {
+                        let _ = current.store_key_from_full_path(&format!("{}\\{}", hive_name, key_name));
+                    }
+                }
+                if !stored.same_content(&current) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateDetector {
+    files: std::collections::HashMap<StrictPath, std::collections::HashSet<String>>,
+    registry: std::collections::HashMap<RegistryItem, std::collections::HashSet<String>>,
+}
+
+impl DuplicateDetector {
+    pub fn add_game(&mut self, scan_info: &ScanInfo) {
+        for item in scan_info.found_files.iter() {
+            self.files
+                .entry(self.pick_path(item))
+                .or_insert_with(Default::default)
+                .insert(scan_info.game_name.clone());
+        }
+        for item in scan_info.found_registry_keys.iter() {
+            self.registry
+                .entry(item.path.clone())
+                .or_insert_with(Default::default)
+                .insert(scan_info.game_name.clone());
+        }
+    }
+
+    pub fn is_game_duplicated(&self, scan_info: &ScanInfo) -> bool {
+        for item in scan_info.found_files.iter() {
+            if self.file(item).len() > 1 {
+                return true;
+            }
+        }
+        for item in scan_info.found_registry_keys.iter() {
+            if self.registry(&item.path).len() > 1 {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn pick_path(&self, file: &ScannedFile) -> StrictPath {
+        match &file.original_path {
+            Some(op) => op.clone(),
+            None => file.path.clone(),
+        }
+    }
+
+    pub fn file(&self, file: &ScannedFile) -> std::collections::HashSet<String> {
+        match self.files.get(&self.pick_path(file)) {
+            Some(games) => games.clone(),
+            None => Default::default(),
+        }
+    }
+
+    pub fn is_file_duplicated(&self, file: &ScannedFile) -> bool {
+        self.file(file).len() > 1
+    }
+
+    pub fn registry(&self, path: &RegistryItem) -> std::collections::HashSet<String> {
+        match self.registry.get(path) {
+            Some(games) => games.clone(),
+            None => Default::default(),
+        }
+    }
+
+    pub fn is_registry_duplicated(&self, path: &RegistryItem) -> bool {
+        self.registry(path).len() > 1
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.registry.clear();
+    }
+
+    pub fn any_duplicates(&self) -> bool {
+        for item in self.files.values() {
+            if item.len() > 1 {
+                return true;
+            }
+        }
+        for item in self.registry.values() {
+            if item.len() > 1 {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn count_duplicates_for(&self, game: &str) -> usize {
+        let mut tally = 0;
+        for item in self.files.values() {
+            if item.contains(game) && item.len() > 1 {
+                tally += 1;
+            }
+        }
+        for item in self.registry.values() {
+            if item.contains(game) && item.len() > 1 {
+                tally += 1;
+            }
+        }
+        tally
+    }
+}
+
+fn make_fuzzy_matcher() -> fuzzy_matcher::skim::SkimMatcherV2 {
+    fuzzy_matcher::skim::SkimMatcherV2::default()
+        .ignore_case()
+        .score_config(fuzzy_matcher::skim::SkimScoreConfig {
+            penalty_case_mismatch: 0,
+            ..Default::default()
+        })
+}
+
+pub fn fuzzy_match(
+    matcher: &fuzzy_matcher::skim::SkimMatcherV2,
+    reference: &str,
+    candidate: &str,
+    ideal: &Option<i64>,
+) -> Option<i64> {
+    if reference == candidate {
+        return Some(i64::MAX);
+    }
+    let actual = matcher.fuzzy_match(reference, &candidate.replace('_', " ").replace('-', " "));
+    if let (Some(ideal), Some(actual)) = (ideal, actual) {
+        if actual == *ideal {
+            return Some(i64::MAX);
+        } else if actual > (ideal / 4 * 3) {
+            return Some(actual);
+        }
+    }
+    None
+}
+
+/// Find the game names that best match what the user typed, best first.
+pub fn search_game_names<'a>(query: &str, names: impl IntoIterator<Item = &'a String>, limit: usize) -> Vec<String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    let matcher = make_fuzzy_matcher();
+    let mut scored: Vec<_> = names
+        .into_iter()
+        .filter_map(|name| {
+            if name.eq_ignore_ascii_case(query) {
+                return Some((i64::MAX, name));
+            }
+            matcher.fuzzy_match(name, query).map(|score| (score, name))
+        })
+        .collect();
+    scored.sort_by(|(score1, name1), (score2, name2)| score2.cmp(score1).then_with(|| name1.cmp(name2)));
+    scored.into_iter().take(limit).map(|(_, name)| name.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, Retention};
+    use crate::manifest::Manifest;
+    use maplit::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_search_game_names() {
+        let names = vec![s("Game"), s("Another Game"), s("Gamer Tag"), s("Unrelated")];
+
+        assert_eq!(Vec::<String>::new(), search_game_names(" ", &names, 10));
+        assert_eq!(vec![s("Game"), s("Gamer Tag")], search_game_names("game", &names, 2));
+        assert_eq!(vec![s("Another Game")], search_game_names("anthr", &names, 10));
+        assert_eq!(Vec::<String>::new(), search_game_names("xyz", &names, 10));
+    }
+
+    #[test]
+    fn deselected_games_are_still_relevant_for_orphans() {
+        let mut config = config();
+        config.disable_game_for_backup("game1");
+
+        let relevant = relevant_backup_games(&config, &manifest());
+        assert!(relevant.contains("game1"));
+        assert!(relevant.contains("game 2"));
+        assert!(!relevant.contains("unknown"));
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        let matcher = make_fuzzy_matcher();
+
+        for (reference, candidate, output) in vec![
+            ("a", "a", Some(i64::MAX)),
+            ("a", "b", None),
             ("Something", "Something", Some(i64::MAX)),
             // Too short:
             ("ab", "a", None),
@@ -1100,654 +2480,1818 @@ mod tests {
             ("A Fun Game 2", "A Fun Game", Some(219)),
         ] {
             assert_eq!(
-                output,
-                fuzzy_match(
-                    &matcher,
-                    reference,
-                    candidate,
-                    &matcher.fuzzy_match(reference, reference)
+                output,
+                fuzzy_match(
+                    &matcher,
+                    reference,
+                    candidate,
+                    &matcher.fuzzy_match(reference, reference)
+                )
+            );
+        }
+    }
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn repo() -> String {
+        env!("CARGO_MANIFEST_DIR").replace('\\', "/")
+    }
+
+    fn config() -> Config {
+        Config::load_from_string(&format!(
+            r#"
+            manifest:
+              url: example.com
+              etag: null
+            roots:
+              - path: {0}/tests/root1
+                store: other
+              - path: {0}/tests/root2
+                store: other
+            backup:
+              path: ~/backup
+            restore:
+              path: ~/restore
+            "#,
+            repo()
+        ))
+        .unwrap()
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <base>/file1.txt: {}
+                <base>/subdir: {}
+            game 2:
+              files:
+                <root>/<game>: {}
+              installDir:
+                game2: {}
+            game3:
+              registry:
+                HKEY_CURRENT_USER/Software/Ludusavi/game3: {}
+                HKEY_CURRENT_USER/Software/Ludusavi/fake: {}
+            game3-outer:
+              registry:
+                HKEY_CURRENT_USER/Software/Ludusavi: {}
+            game4:
+              files:
+                <home>/data.txt: {}
+                <winAppData>/winAppData.txt: {}
+                <winLocalAppData>/winLocalAppData.txt: {}
+                <winDocuments>/winDocuments.txt: {}
+                <xdgConfig>/xdgConfig.txt: {}
+                <xdgData>/xdgData.txt: {}
+            game5:
+              files:
+                <base>: {}
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_find_running_games() {
+        let mut manifest = manifest();
+        manifest.0.get_mut("game3").unwrap().steam = Some(crate::manifest::SteamMetadata { id: Some(123) });
+        let subjects: Vec<_> = manifest.0.keys().cloned().collect();
+        let ranking = InstallDirRanking::scan(&config().roots, &manifest, &subjects);
+
+        assert_eq!(
+            hashset! {},
+            find_running_games(&RunningProcesses::default(), &manifest, &ranking)
+        );
+        assert_eq!(
+            hashset! { s("game 2"), s("game3") },
+            find_running_games(
+                &RunningProcesses {
+                    executables: vec![
+                        StrictPath::new(format!("{}/tests/root2/game2/game.exe", repo())),
+                        StrictPath::new(format!("{}/tests/root2-other/game.exe", repo())),
+                    ],
+                    steam_ids: hashset! { 123 },
+                },
+                &manifest,
+                &ranking
+            )
+        );
+    }
+
+    #[test]
+    fn should_not_exclude_as_other_os_data_when_os_matches() {
+        assert!(!should_exclude_as_other_os_data(
+            &[GameFileConstraint {
+                os: Some(Os::Windows),
+                store: None
+            }],
+            Os::Windows,
+            false
+        ));
+    }
+
+    #[test]
+    fn should_exclude_as_other_os_data_when_os_does_not_match() {
+        assert!(should_exclude_as_other_os_data(
+            &[GameFileConstraint {
+                os: Some(Os::Linux),
+                store: None
+            }],
+            Os::Windows,
+            false
+        ));
+    }
+
+    #[test]
+    fn should_not_exclude_as_other_os_data_when_no_os_constraint() {
+        assert!(!should_exclude_as_other_os_data(
+            &[GameFileConstraint {
+                os: None,
+                store: Some(Store::Steam)
+            }],
+            Os::Windows,
+            false
+        ));
+    }
+
+    #[test]
+    fn should_not_exclude_as_other_os_data_when_any_constraint_lacks_os() {
+        assert!(!should_exclude_as_other_os_data(
+            &[
+                GameFileConstraint {
+                    os: Some(Os::Linux),
+                    store: None
+                },
+                GameFileConstraint {
+                    os: None,
+                    store: Some(Store::Steam)
+                }
+            ],
+            Os::Windows,
+            false
+        ));
+    }
+
+    #[test]
+    fn should_exclude_as_other_os_data_when_constraint_has_store_and_other_os() {
+        assert!(should_exclude_as_other_os_data(
+            &[GameFileConstraint {
+                os: Some(Os::Linux),
+                store: Some(Store::Steam)
+            }],
+            Os::Windows,
+            false
+        ));
+    }
+
+    #[test]
+    fn can_exclude_as_other_store_data() {
+        let constraint = |os, store| GameFileConstraint { os, store };
+
+        assert!(should_exclude_as_other_store_data(
+            &[constraint(None, Some(Store::Gog))],
+            Store::Steam
+        ));
+        assert!(should_exclude_as_other_store_data(
+            &[constraint(Some(Os::Windows), Some(Store::Gog))],
+            Store::Steam
+        ));
+        assert!(!should_exclude_as_other_store_data(
+            &[constraint(None, Some(Store::Steam))],
+            Store::Steam
+        ));
+        assert!(!should_exclude_as_other_store_data(
+            &[constraint(None, Some(Store::Gog)), constraint(Some(Os::Windows), None)],
+            Store::Steam
+        ));
+        assert!(!should_exclude_as_other_store_data(&[], Store::Steam));
+        assert!(!should_exclude_as_other_store_data(
+            &[constraint(None, Some(Store::Gog))],
+            Store::Other
+        ));
+    }
+
+    #[test]
+    fn should_not_exclude_as_other_os_data_when_no_constraints() {
+        assert!(!should_exclude_as_other_os_data(&[], Os::Windows, false));
+    }
+
+    #[test]
+    fn should_not_exclude_as_other_os_data_when_suitable_for_proton() {
+        assert!(!should_exclude_as_other_os_data(
+            &[GameFileConstraint {
+                os: Some(Os::Windows),
+                store: Some(Store::Steam)
+            }],
+            Os::Linux,
+            true
+        ));
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_file_matches() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2),
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game 2"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root2/game2/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game 2"],
+                "game 2",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game 2".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_only_recently_modified_files() {
+        let scan = |since_days| {
+            scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &since_days,
+            )
+        };
+
+        assert_eq!(2, scan(Some(36500)).found_files.len());
+        // Everything in the repository was modified before this instant.
+        assert_eq!(0, scan(Some(0)).found_files.len());
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_allowed_files() {
+        let filter = BackupFilter {
+            allowed_files: btreemap! {
+                s("game1") => vec![s("file2.*")],
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &filter,
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_whole_directories() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <root>/game1/ignored.txt: {}
+            "#,
+        )
+        .unwrap();
+        let scan = |filter: &BackupFilter| {
+            scan_game_for_backup(
+                &manifest.0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                filter,
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest, &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            )
+            .found_files
+        };
+
+        assert_eq!(
+            hashset! {
+                ScannedFile::new(format!("{}/tests/root1/game1/ignored.txt", repo()), 0),
+            },
+            scan(&BackupFilter::default()),
+        );
+        assert_eq!(
+            hashset! {
+                ScannedFile::new(format!("{}/tests/root1/game1/ignored.txt", repo()), 0),
+                ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2),
+            },
+            scan(&BackupFilter {
+                whole_directories: vec![s("game1")],
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_whole_directories_only_below_placeholders() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <base>/ignored.txt: {}
+            "#,
+        )
+        .unwrap();
+        let found = scan_game_for_backup(
+            &manifest.0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter {
+                whole_directories: vec![s("game1")],
+                ..Default::default()
+            },
+            &None,
+            &InstallDirRanking::scan(&config().roots, &manifest, &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        assert_eq!(
+            hashset! {
+                ScannedFile::new(format!("{}/tests/root1/game1/ignored.txt", repo()), 0),
+            },
+            found.found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_skipping_ignored_roots() {
+        let roots: Vec<_> = config()
+            .roots
+            .into_iter()
+            .map(|root| RootsConfig { ignore: true, ..root })
+            .collect();
+        let found = scan_game_for_backup(
+            &manifest().0["game1"],
+            "game1",
+            &roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::scan(&roots, &manifest(), &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        assert_eq!(0, found.found_files.len());
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_deduplicating_symlinks() {
+        let roots = &[RootsConfig {
+            path: StrictPath::new(format!("{}/tests/root3", repo())),
+            store: Store::Other,
+            ignore: false,
+            base_template: None,
+        }];
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game5"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root3/game5/data/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game5"],
+                "game5",
+                roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(roots, &manifest(), &["game5".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_reserve_backup_budget() {
+        let budget = BackupBudget::new(Some(10), 3);
+        assert!(budget.reserve(5));
+        assert!(!budget.reserve(3));
+        assert!(budget.reserve(2));
+        assert!(!budget.reserve(1));
+
+        // An unchanged game's existing backup is already part of what's used.
+        let budget = BackupBudget::new(Some(10), 8);
+        assert!(budget.reserve_replacing(8, 8));
+        assert!(budget.reserve_replacing(9, 8));
+        assert!(!budget.reserve_replacing(10, 8));
+
+        let unlimited = BackupBudget::new(None, 3);
+        assert!(unlimited.reserve(u32::MAX as u64));
+    }
+
+    /// Create a directory link the way the OS normally would for redirected saves:
+    /// a junction on Windows and a symlink elsewhere.
+    fn make_dir_link(target: &std::path::Path, link: &std::path::Path) {
+        #[cfg(target_os = "windows")]
+        {
+            let status = std::process::Command::new("cmd")
+                .arg("/C")
+                .arg("mklink")
+                .arg("/J")
+                .arg(link)
+                .arg(target)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        }
+        #[cfg(not(target_os = "windows"))]
+        std::os::unix::fs::symlink(target, link).unwrap();
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_through_linked_save_folder() {
+        let dir = std::env::temp_dir().join("ludusavi-test-linked-saves");
+        let _ = std::fs::remove_dir_all(&dir);
+        let real = dir.join("real");
+        let link = dir.join("link");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("save.dat"), "x").unwrap();
+        make_dir_link(&real, &link);
+
+        let game = Game::from(crate::config::CustomGame {
+            name: s("linked"),
+            ignore: false,
+            files: vec![
+                crate::path::render_pathbuf(&link),
+                format!("{}/*.dat", crate::path::render_pathbuf(&link)),
+                crate::path::render_pathbuf(&real),
+            ],
+            registry: vec![],
+        });
+        let found = scan_game_for_backup(
+            &game,
+            "linked",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::default(),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        assert_eq!(
+            hashset! {
+                ScannedFile::new(StrictPath::from(real.join("save.dat")).render(), 1),
+            },
+            found.found_files,
+        );
+        // Outside of the scan, paths keep the links that the user wrote.
+        assert_ne!(
+            StrictPath::from(real.join("new.dat")).interpret(),
+            StrictPath::from(link.join("new.dat")).interpret(),
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_fuzzy_matched_install_dir() {
+        let roots = &[RootsConfig {
+            path: StrictPath::new(format!("{}/tests/root3", repo())),
+            store: Store::Other,
+            ignore: false,
+            base_template: None,
+        }];
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game 2"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root3/game_2/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game 2"],
+                "game 2",
+                roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(roots, &manifest(), &["game 2".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_root_base_template() {
+        let roots = &[RootsConfig {
+            path: StrictPath::new(format!("{}/tests", repo())),
+            store: Store::Gog,
+            ignore: false,
+            base_template: Some(s("<root>/root2/<game>")),
+        }];
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(roots, &manifest(), &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn cancelled_scan_for_backup_reports_nothing() {
+        assert_eq!(
+            None,
+            scan_game_for_backup_cancellable(
+                &manifest().0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+                &std::sync::atomic::AtomicBool::new(true),
+                &|_| {},
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_file_matches_in_custom_home_folder() {
+        let roots = &[RootsConfig {
+            path: StrictPath::new(format!("{}/tests/home", repo())),
+            store: Store::OtherHome,
+            ignore: false,
+            base_template: None,
+        }];
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game4"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/home/data.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/home/AppData/Roaming/winAppData.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/home/AppData/Local/winLocalAppData.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/home/Documents/winDocuments.txt", repo()), 0),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game4"],
+                "game4",
+                roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(roots, &manifest(), &["game4".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_scan_game_for_backup_with_file_matches_in_custom_home_folder() {
+        let roots = &[RootsConfig {
+            path: StrictPath::new(format!("{}/tests/home", repo())),
+            store: Store::OtherHome,
+            ignore: false,
+            base_template: None,
+        }];
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game4"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/home/data.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/home/.config/xdgConfig.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/home/.local/share/xdgData.txt", repo()), 0),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game4"],
+                "game4",
+                roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(roots, &manifest(), &["game4".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_file_matches_in_wine_prefix() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game4"),
+                found_files: hashset! {
+                    ScannedFile::new(format!("{}/tests/wine-prefix/drive_c/users/anyone/data.txt", repo()), 0),
+                    ScannedFile::new(format!("{}/tests/wine-prefix/user.reg", repo()), 37),
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game4"],
+                "game4",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &Some(StrictPath::new(format!("{}/tests/wine-prefix", repo()))),
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game4".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_file_matches_and_ignores() {
+        let cases = [
+            (
+                BackupFilter {
+                    ignored_paths: vec![StrictPath::new(format!("{}\\tests/root1/game1/subdir", repo()))],
+                    ..Default::default()
+                },
+                ToggledPaths::default(),
+                hashset! {
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+            ),
+            (
+                BackupFilter::default(),
+                ToggledPaths::new(btreemap! {
+                    s("game1") => btreemap! {
+                        StrictPath::new(format!("{}\\tests/root1/game1/subdir", repo())) => false
+                    }
+                }),
+                hashset! {
+                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2).ignored(),
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+            ),
+            (
+                BackupFilter::default(),
+                ToggledPaths::new(btreemap! {
+                    s("game1") => btreemap! {
+                        StrictPath::new(format!("{}\\tests/root1/game1/subdir/file2.txt", repo())) => false
+                    }
+                }),
+                hashset! {
+                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2).ignored(),
+                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
+                },
+            ),
+        ];
+
+        for (filter, ignored, found) in cases {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game1"),
+                    found_files: found,
+                    found_registry_keys: hashset! {},
+                    registry_file: None,
+                },
+                scan_game_for_backup(
+                    &manifest().0["game1"],
+                    "game1",
+                    &config().roots,
+                    &StrictPath::new(repo()),
+                    &None,
+                    &filter,
+                    &None,
+                    &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                    &ignored,
+                    &ToggledRegistry::default(),
+                    &None,
+                ),
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_registry_matches_on_leaf_key_with_values() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game3"),
+                found_files: hashset! {},
+                found_registry_keys: hashset! {
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3")
+                },
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game3"],
+                "game3",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game3".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_registry_matches_on_parent_key_without_values() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game3-outer"),
+                found_files: hashset! {},
+                found_registry_keys: hashset! {
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other"),
+                },
+                registry_file: None,
+            },
+            scan_game_for_backup(
+                &manifest().0["game3-outer"],
+                "game3-outer",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &None,
+                &InstallDirRanking::scan(&config().roots, &manifest(), &["game3-outer".to_string()]),
+                &ToggledPaths::default(),
+                &ToggledRegistry::default(),
+                &None,
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_registry_matches_and_ignores() {
+        let cases = vec![
+            (
+                BackupFilter {
+                    ignored_registry: vec![RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi/other"))],
+                    ..Default::default()
+                },
+                ToggledRegistry::default(),
+                hashset! {
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
+                },
+            ),
+            (
+                BackupFilter::default(),
+                ToggledRegistry::new(btreemap! {
+                    s("game3-outer") => btreemap! {
+                        RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi")) => false
+                    }
+                }),
+                hashset! {
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi").ignored(),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3").ignored(),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other").ignored(),
+                },
+            ),
+            (
+                BackupFilter::default(),
+                ToggledRegistry::new(btreemap! {
+                    s("game3-outer") => btreemap! {
+                        RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi/other")) => false
+                    }
+                }),
+                hashset! {
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
+                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other").ignored(),
+                },
+            ),
+        ];
+
+        for (filter, ignored, found) in cases {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game3-outer"),
+                    found_files: hashset! {},
+                    found_registry_keys: found,
+                    registry_file: None,
+                },
+                scan_game_for_backup(
+                    &manifest().0["game3-outer"],
+                    "game3-outer",
+                    &config().roots,
+                    &StrictPath::new(repo()),
+                    &None,
+                    &filter,
+                    &None,
+                    &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+                    &ToggledPaths::default(),
+                    &ignored,
+                    &None,
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn can_scan_game_for_restoration_with_files() {
+        let make_path = |x| {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(format!(
+                    "\\\\?\\{}\\tests\\backup\\game1\\drive-X\\{}",
+                    repo().replace('/', "\\"),
+                    x
+                ))
+            } else {
+                StrictPath::new(format!("{}/tests/backup/game1/drive-X/{}", repo(), x))
+            }
+        };
+
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile {
+                        path: make_path("file1.txt"),
+                        size: 1,
+                        original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file1.txt" } else { "X:/file1.txt" }))),
+                        ignored: false,
+                    },
+                    ScannedFile {
+                        path: make_path("file2.txt"),
+                        size: 2,
+                        original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file2.txt" } else { "X:/file2.txt" }))),
+                        ignored: false,
+                    },
+                },
+                ..Default::default()
+            },
+            scan_game_for_restoration(
+                "game1",
+                &BackupLayout::new(
+                    StrictPath::new(format!("{}/tests/backup", repo())),
+                    Retention::default()
                 )
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_restoration_with_registry() {
+        if cfg!(target_os = "windows") {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game3"),
+                    found_registry_keys: hashset! {
+                        ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3")
+                    },
+                    registry_file: Some(StrictPath::new(format!(
+                        "\\\\?\\{}\\tests\\backup\\game3-renamed/registry.yaml",
+                        repo().replace('/', "\\")
+                    ))),
+                    ..Default::default()
+                },
+                scan_game_for_restoration(
+                    "game3",
+                    &BackupLayout::new(
+                        StrictPath::new(format!("{}/tests/backup", repo())),
+                        Retention::default()
+                    )
+                ),
+            );
+        } else {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game3"),
+                    ..Default::default()
+                },
+                scan_game_for_restoration(
+                    "game3",
+                    &BackupLayout::new(
+                        StrictPath::new(format!("{}/tests/backup", repo())),
+                        Retention::default()
+                    )
+                ),
             );
         }
     }
 
-    fn s(text: &str) -> String {
-        text.to_string()
+    #[test]
+    fn checks_if_files_are_identical() {
+        assert!(are_files_identical(
+            &StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+            &StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())),
+        )
+        .unwrap());
+        assert!(!are_files_identical(
+            &StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+            &StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+        )
+        .unwrap());
+        assert!(are_files_identical(
+            &StrictPath::new(format!("{}/tests/root1/game1/file1.txt", repo())),
+            &StrictPath::new(format!("{}/nonexistent.txt", repo())),
+        )
+        .is_err());
     }
 
-    fn repo() -> String {
-        env!("CARGO_MANIFEST_DIR").replace('\\', "/")
+    #[test]
+    fn rate_limiter_paces_writes() {
+        let limiter = RateLimiter::new();
+        let start = std::time::Instant::now();
+        limiter.consume(1_000, 10_000);
+        limiter.consume(1_000, 10_000);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
     }
 
-    fn config() -> Config {
-        Config::load_from_string(&format!(
-            r#"
-            manifest:
-              url: example.com
-              etag: null
-            roots:
-              - path: {0}/tests/root1
-                store: other
-              - path: {0}/tests/root2
-                store: other
-            backup:
-              path: ~/backup
-            restore:
-              path: ~/restore
-            "#,
-            repo()
-        ))
-        .unwrap()
-    }
+    #[test]
+    fn can_copy_file_with_buffer() {
+        let source = StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()));
+        let target = StrictPath::from(std::env::temp_dir().join("ludusavi-test-copy-file-with-buffer.txt"));
+
+        for options in [
+            CopyOptions {
+                reflink: false,
+                buffer_size: Some(1),
+                verify: false,
+                max_bytes_per_second: None,
+                attempts: 1,
+            },
+            CopyOptions {
+                reflink: false,
+                buffer_size: Some(1),
+                verify: false,
+                max_bytes_per_second: Some(1_000_000),
+                attempts: 1,
+            },
+            CopyOptions::default(),
+        ] {
+            copy_file(&source, &target, &options).unwrap();
+            assert!(are_files_identical(&source, &target).unwrap());
+        }
 
-    fn manifest() -> Manifest {
-        Manifest::load_from_string(
-            r#"
-            game1:
-              files:
-                <base>/file1.txt: {}
-                <base>/subdir: {}
-            game 2:
-              files:
-                <root>/<game>: {}
-              installDir:
-                game2: {}
-            game3:
-              registry:
-                HKEY_CURRENT_USER/Software/Ludusavi/game3: {}
-                HKEY_CURRENT_USER/Software/Ludusavi/fake: {}
-            game3-outer:
-              registry:
-                HKEY_CURRENT_USER/Software/Ludusavi: {}
-            game4:
-              files:
-                <home>/data.txt: {}
-                <winAppData>/winAppData.txt: {}
-                <winLocalAppData>/winLocalAppData.txt: {}
-                <winDocuments>/winDocuments.txt: {}
-                <xdgConfig>/xdgConfig.txt: {}
-                <xdgData>/xdgData.txt: {}
-            game5:
-              files:
-                <base>: {}
-            "#,
-        )
-        .unwrap()
+        target.remove().unwrap();
     }
 
     #[test]
-    fn should_not_exclude_as_other_os_data_when_os_matches() {
-        assert!(!should_exclude_as_other_os_data(
-            &[GameFileConstraint {
-                os: Some(Os::Windows),
-                store: None
-            }],
-            Os::Windows,
-            false
-        ));
+    fn failed_reflink_copy_keeps_existing_target() {
+        let source = StrictPath::new(format!("{}/nonexistent.txt", repo()));
+        let target = StrictPath::from(std::env::temp_dir().join("ludusavi-test-copy-file-keeps-target.txt"));
+        std::fs::write(target.interpret(), "original").unwrap();
+
+        assert!(copy_file(&source, &target, &CopyOptions::default()).is_err());
+        assert_eq!("original", std::fs::read_to_string(target.interpret()).unwrap());
+
+        target.remove().unwrap();
     }
 
     #[test]
-    fn should_exclude_as_other_os_data_when_os_does_not_match() {
-        assert!(should_exclude_as_other_os_data(
-            &[GameFileConstraint {
-                os: Some(Os::Linux),
-                store: None
-            }],
-            Os::Windows,
-            false
-        ));
+    fn retries_transient_io_errors() {
+        let mut calls = 0;
+        let result = retry_io(3, || {
+            calls += 1;
+            if calls < 3 {
+                Err(std::io::Error::other("flaky"))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(3, result.unwrap());
+
+        let mut calls = 0;
+        let result: std::io::Result<()> = retry_io(3, || {
+            calls += 1;
+            Err(std::io::Error::other("flaky"))
+        });
+        assert!(result.is_err());
+        assert_eq!(3, calls);
+
+        let mut calls = 0;
+        let result: std::io::Result<()> = retry_io(3, || {
+            calls += 1;
+            Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, calls);
     }
 
     #[test]
-    fn should_not_exclude_as_other_os_data_when_no_os_constraint() {
-        assert!(!should_exclude_as_other_os_data(
-            &[GameFileConstraint {
-                os: None,
-                store: Some(Store::Steam)
-            }],
-            Os::Windows,
-            false
-        ));
+    fn can_decide_whether_to_overwrite_by_age() {
+        let now = chrono::Utc::now();
+        let hour_ago = now - chrono::Duration::hours(1);
+
+        assert!(should_overwrite(Some(hour_ago), Some(now), None));
+        assert!(should_overwrite(Some(now), Some(hour_ago), Some(3600)));
+        assert!(!should_overwrite(Some(now), Some(hour_ago), Some(3601)));
+        assert!(!should_overwrite(Some(hour_ago), Some(now), Some(0)));
+        assert!(should_overwrite(None, Some(now), Some(3600)));
     }
 
     #[test]
-    fn should_not_exclude_as_other_os_data_when_any_constraint_lacks_os() {
-        assert!(!should_exclude_as_other_os_data(
-            &[
-                GameFileConstraint {
-                    os: Some(Os::Linux),
-                    store: None
-                },
-                GameFileConstraint {
-                    os: None,
-                    store: Some(Store::Steam)
-                }
-            ],
-            Os::Windows,
-            false
-        ));
+    fn can_group_files_into_save_slots() {
+        let file = |path: &str| ScannedFile {
+            path: StrictPath::new(s("/backup/file")),
+            size: 1,
+            original_path: Some(StrictPath::new(s(path))),
+            ignored: false,
+        };
+        let filter = SaveSlotFilter::new(DEFAULT_SAVE_SLOT_PATTERN, btreeset! { s("2") });
+
+        assert_eq!(Some(s("1")), filter.slot(&file("/saves/Slot1/data.bin")));
+        assert_eq!(Some(s("2")), filter.slot(&file("/saves/profile_1/save-2.dat")));
+        assert_eq!(None, filter.slot(&file("/saves/settings.ini")));
+
+        assert!(filter.allows(&file("/saves/Slot1/data.bin")));
+        assert!(!filter.allows(&file("/saves/slot 2/data.bin")));
+        assert!(filter.allows(&file("/saves/settings.ini")));
+
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                file("/saves/slot1.sav"),
+                file("/saves/slot2.sav"),
+                file("/saves/settings.ini"),
+            },
+            ..Default::default()
+        };
+        assert_eq!(btreeset! { s("1"), s("2") }, filter.slots(&info));
+
+        let custom = SaveSlotFilter::new(r"world-([a-z]+)", Default::default());
+        assert_eq!(Some(s("alpha")), custom.slot(&file("/saves/world-alpha/level.dat")));
+        assert_eq!(
+            None,
+            SaveSlotFilter::new("(", Default::default()).slot(&file("/saves/slot1.sav"))
+        );
     }
 
     #[test]
-    fn should_exclude_as_other_os_data_when_constraint_has_store_and_other_os() {
-        assert!(should_exclude_as_other_os_data(
-            &[GameFileConstraint {
-                os: Some(Os::Linux),
-                store: Some(Store::Steam)
-            }],
-            Os::Windows,
-            false
-        ));
+    fn can_normalize_line_endings() {
+        assert_eq!(
+            Some(b"a\nb\nc\r".to_vec()),
+            normalize_line_endings(b"a\r\nb\nc\r", b"\n")
+        );
+        assert_eq!(
+            Some(b"a\r\nb\r\n".to_vec()),
+            normalize_line_endings(b"a\r\nb\n", b"\r\n")
+        );
+        assert_eq!(None, normalize_line_endings(b"a\r\n\0b", b"\n"));
     }
 
     #[test]
-    fn should_not_exclude_as_other_os_data_when_no_constraints() {
-        assert!(!should_exclude_as_other_os_data(&[], Os::Windows, false));
+    fn rejects_backup_target_inside_root() {
+        let roots = vec![
+            RootsConfig {
+                path: StrictPath::new(s("/games/steam")),
+                store: Store::Steam,
+                ignore: false,
+                base_template: None,
+            },
+            RootsConfig {
+                path: StrictPath::new(s("/home/user")),
+                store: Store::OtherHome,
+                ignore: false,
+                base_template: None,
+            },
+        ];
+        let restore_source = StrictPath::new(s("/backups"));
+
+        assert_eq!(
+            Err(Error::BackupTargetInsideRoot {
+                path: StrictPath::new(s("/games/steam/backup")),
+                root: StrictPath::new(s("/games/steam")),
+            }),
+            prepare_backup_target(
+                &StrictPath::new(s("/games/steam/backup")),
+                true,
+                &roots,
+                &restore_source
+            ),
+        );
+        assert_eq!(
+            Err(Error::BackupTargetInsideRoot {
+                path: StrictPath::new(s("/backups/nested")),
+                root: StrictPath::new(s("/backups")),
+            }),
+            prepare_backup_target(&StrictPath::new(s("/backups/nested")), true, &roots, &restore_source),
+        );
     }
 
     #[test]
-    fn should_not_exclude_as_other_os_data_when_suitable_for_proton() {
-        assert!(!should_exclude_as_other_os_data(
-            &[GameFileConstraint {
-                os: Some(Os::Windows),
-                store: Some(Store::Steam)
-            }],
-            Os::Linux,
-            true
-        ));
+    fn can_reject_blank_or_root_backup_target() {
+        let restore_source = StrictPath::new(s("/backups"));
+        for target in ["", "/"] {
+            assert_eq!(
+                Err(Error::BackupTargetInvalid {
+                    path: StrictPath::new(s(target)),
+                }),
+                prepare_backup_target(&StrictPath::new(s(target)), true, &[], &restore_source),
+            );
+        }
     }
 
     #[test]
-    fn can_scan_game_for_backup_with_file_matches() {
+    fn refuses_to_replace_backup_target_with_locked_backups() {
+        let dir = std::env::temp_dir().join("ludusavi-test-locked-backup-target");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("game1")).unwrap();
+        std::fs::write(
+            dir.join("game1/mapping.yaml"),
+            r#"
+name: game1
+drives: {}
+backups:
+  - name: backup-20000101T000000Z
+    when: "2000-01-01T00:00:00Z"
+    locked: true
+    children: []
+"#,
+        )
+        .unwrap();
+
+        let target = StrictPath::from(dir.clone());
         assert_eq!(
-            ScanInfo {
-                game_name: s("game1"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2),
-                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game1"],
-                "game1",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+            Err(Error::BackupTargetHasLockedBackups { path: target.clone() }),
+            prepare_backup_target(&target, false, &[], &StrictPath::new(s("/backups"))),
         );
+        assert!(dir.join("game1/mapping.yaml").is_file());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn can_check_restoration_targets_for_missing_folders() {
+        let dir = std::env::temp_dir().join("ludusavi-test-restoration-targets");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut file = ScannedFile::new("/backup/file.txt", 1);
+        file.original_path = Some(StrictPath::from(dir.join("missing/nested/file.txt")));
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! { file },
+            found_registry_keys: hashset! {},
+            registry_file: None,
+        };
+
+        assert_eq!(Ok(()), check_restoration_targets_writable(&info, &[]));
+        // The probe is cleaned up and the missing folders are not created.
+        assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn can_lint_manifest_placeholders() {
+        // Deserialize directly, since loading would already drop unknown placeholders.
+        let manifest: Manifest = serde_yaml::from_str(
+            r#"
+            game1:
+              files:
+                <home>/<bogus>/<bogus>.txt: {}
+                <home>/fine.txt: {}
+            game2:
+              files:
+                <root>/save.txt: {}
+            "#,
+        )
+        .unwrap();
 
         assert_eq!(
-            ScanInfo {
-                game_name: s("game 2"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/root2/game2/file1.txt", repo()), 1),
+            vec![
+                UnresolvedPlaceholders {
+                    game: s("game1"),
+                    path: s("<home>/<bogus>/<bogus>.txt"),
+                    placeholders: vec![s("<bogus>")],
                 },
-                found_registry_keys: hashset! {},
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game 2"],
-                "game 2",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(&config().roots, &manifest(), &["game 2".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+                UnresolvedPlaceholders {
+                    game: s("game2"),
+                    path: s("<root>/save.txt"),
+                    placeholders: vec![s("<root>")],
+                },
+            ],
+            lint_manifest_placeholders(&manifest, &[]),
         );
+        assert_eq!(1, lint_manifest_placeholders(&manifest, &config().roots).len());
     }
 
     #[test]
-    fn can_scan_game_for_backup_deduplicating_symlinks() {
-        let roots = &[RootsConfig {
-            path: StrictPath::new(format!("{}/tests/root3", repo())),
-            store: Store::Other,
-        }];
+    #[cfg(not(target_os = "windows"))]
+    fn can_run_command() {
+        assert_eq!(Ok(()), run_command("true"));
         assert_eq!(
-            ScanInfo {
-                game_name: s("game5"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/root3/game5/data/file1.txt", repo()), 1),
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game5"],
-                "game5",
-                roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(roots, &manifest(), &["game5".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+            Err(Error::CommandFailed {
+                command: s("echo oops && false"),
+                output: s("oops"),
+            }),
+            run_command("echo oops && false"),
         );
     }
 
     #[test]
-    fn can_scan_game_for_backup_with_fuzzy_matched_install_dir() {
-        let roots = &[RootsConfig {
-            path: StrictPath::new(format!("{}/tests/root3", repo())),
-            store: Store::Other,
-        }];
-        assert_eq!(
-            ScanInfo {
-                game_name: s("game 2"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/root3/game_2/file1.txt", repo()), 1),
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game 2"],
-                "game 2",
-                roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(roots, &manifest(), &["game 2".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+    fn can_migrate_backup() {
+        let from = StrictPath::new(format!("{}/tests/backup", repo()));
+        let to = StrictPath::from(std::env::temp_dir().join("ludusavi-test-migrate-backup"));
+        let _ = to.remove();
+
+        assert_eq!(Ok(()), migrate_backup(&from, &to));
+        assert!(to
+            .joined("game1/mapping.yaml")
+            .same_content(&from.joined("game1/mapping.yaml")));
+
+        let _ = to.remove();
+    }
+
+    #[test]
+    fn can_test_restore_game() {
+        let layout = BackupLayout::new(
+            StrictPath::new(format!("{}/tests/backup", repo())),
+            Retention::default(),
         );
+        let scratch = StrictPath::from(std::env::temp_dir().join("ludusavi-test-test-restore"));
+        let info = scan_game_for_restoration("game1", &layout);
+
+        let backup_info = test_restore_game(&info, &layout, &scratch);
+        assert!(backup_info.successful());
+        assert!(!scratch.exists());
     }
 
     #[test]
-    #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_file_matches_in_custom_home_folder() {
-        let roots = &[RootsConfig {
-            path: StrictPath::new(format!("{}/tests/home", repo())),
-            store: Store::OtherHome,
-        }];
-        assert_eq!(
-            ScanInfo {
-                game_name: s("game4"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/home/data.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/home/AppData/Roaming/winAppData.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/home/AppData/Local/winLocalAppData.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/home/Documents/winDocuments.txt", repo()), 0),
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
+    fn can_back_up_game_with_verification() {
+        let target = StrictPath::from(std::env::temp_dir().join("ludusavi-test-verify-after-write"));
+        let _ = target.remove();
+        let layout = BackupLayout::new(target.clone(), Retention::default());
+        let scan = scan_game_for_backup(
+            &manifest().0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        let backup_info = back_up_game(
+            &scan,
+            "game1",
+            Store::Other,
+            &layout,
+            false,
+            true,
+            true,
+            &chrono::Utc::now(),
+            &CopyOptions {
+                verify: true,
+                ..Default::default()
             },
-            scan_game_for_backup(
-                &manifest().0["game4"],
-                "game4",
-                roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(roots, &manifest(), &["game4".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
         );
+        assert!(!scan.found_files.is_empty());
+        assert!(backup_info.successful());
+
+        let _ = target.remove();
     }
 
     #[test]
-    #[cfg(not(target_os = "windows"))]
-    fn can_scan_game_for_backup_with_file_matches_in_custom_home_folder() {
-        let roots = &[RootsConfig {
-            path: StrictPath::new(format!("{}/tests/home", repo())),
-            store: Store::OtherHome,
-        }];
-        assert_eq!(
-            ScanInfo {
-                game_name: s("game4"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/home/data.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/home/.config/xdgConfig.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/home/.local/share/xdgData.txt", repo()), 0),
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game4"],
-                "game4",
-                roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(roots, &manifest(), &["game4".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+    fn can_back_up_and_restore_game_as_tar_gz() {
+        let target = StrictPath::from(std::env::temp_dir().join("ludusavi-test-tar-gz"));
+        let _ = target.remove();
+        let layout =
+            || BackupLayout::new(target.clone(), Retention::default()).with_format(crate::config::BackupFormat::TarGz);
+        let scan = scan_game_for_backup(
+            &manifest().0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        let backup_info = back_up_game(
+            &scan,
+            "game1",
+            Store::Other,
+            &layout(),
+            false,
+            true,
+            true,
+            &chrono::Utc::now(),
+            &CopyOptions::default(),
         );
+        assert!(backup_info.successful());
+        assert!(target.joined("game1/backup.tar.gz").is_file());
+        assert!(!target.joined("game1/drive-0").exists());
+
+        let restored = scan_game_for_restoration("game1", &layout());
+        assert_eq!(scan.found_files.len(), restored.found_files.len());
+        let scratch = StrictPath::from(std::env::temp_dir().join("ludusavi-test-tar-gz-restore"));
+        assert!(test_restore_game(&restored, &layout(), &scratch).successful());
+
+        let _ = target.remove();
     }
 
     #[test]
-    fn can_scan_game_for_backup_with_file_matches_in_wine_prefix() {
+    fn can_back_up_and_restore_game_with_split_metadata() {
+        let target = StrictPath::from(std::env::temp_dir().join("ludusavi-test-split"));
+        let _ = target.remove();
+        let layout =
+            || BackupLayout::new(target.clone(), Retention::default()).with_format(crate::config::BackupFormat::Split);
+        let scan = scan_game_for_backup(
+            &manifest().0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
+
+        let backup_info = back_up_game(
+            &scan,
+            "game1",
+            Store::Other,
+            &layout(),
+            false,
+            true,
+            true,
+            &chrono::Utc::now(),
+            &CopyOptions::default(),
+        );
+        assert!(backup_info.successful());
+        assert!(target.joined("game1/meta/mapping.yaml").is_file());
+        assert!(target.joined("game1/files/drive-0").is_dir());
+        assert!(!target.joined("game1/mapping.yaml").exists());
+        assert!(!target.joined("game1/drive-0").exists());
+
+        let restored = scan_game_for_restoration("game1", &layout());
+        assert_eq!(scan.found_files.len(), restored.found_files.len());
+        let scratch = StrictPath::from(std::env::temp_dir().join("ludusavi-test-split-restore"));
+        assert!(test_restore_game(&restored, &layout(), &scratch).successful());
+
+        let _ = target.remove();
+    }
+
+    #[test]
+    fn rejects_migrating_backup_into_itself() {
+        let from = StrictPath::new(format!("{}/tests/backup", repo()));
+        let to = from.joined("nested");
+
         assert_eq!(
-            ScanInfo {
-                game_name: s("game4"),
-                found_files: hashset! {
-                    ScannedFile::new(format!("{}/tests/wine-prefix/drive_c/users/anyone/data.txt", repo()), 0),
-                    ScannedFile::new(format!("{}/tests/wine-prefix/user.reg", repo()), 37),
+            Err(Error::BackupTargetInsideRoot {
+                path: to.clone(),
+                root: from.clone(),
+            }),
+            migrate_backup(&from, &to),
+        );
+    }
+
+    #[test]
+    fn checks_if_restoration_is_needed() {
+        let make_info = |original: &str| ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                    size: 1,
+                    original_path: Some(StrictPath::new(format!("{}/{}", repo(), original))),
+                    ignored: false,
                 },
-                found_registry_keys: hashset! {},
-                registry_file: None,
             },
-            scan_game_for_backup(
-                &manifest().0["game4"],
-                "game4",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &Some(StrictPath::new(format!("{}/tests/wine-prefix", repo()))),
-                &InstallDirRanking::scan(&config().roots, &manifest(), &["game4".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+            ..Default::default()
+        };
+
+        let layout = BackupLayout::new(
+            StrictPath::new(format!("{}/tests/backup", repo())),
+            Retention::default(),
         );
+
+        assert!(!need_restoration(
+            &make_info("tests/root2/game2/file1.txt"),
+            &layout,
+            &[]
+        ));
+        assert!(need_restoration(
+            &make_info("tests/root1/game1/subdir/file2.txt"),
+            &layout,
+            &[]
+        ));
+        assert!(need_restoration(&make_info("tests/nonexistent.txt"), &layout, &[]));
+        assert!(!need_restoration(
+            &make_info("tests/nonexistent/file1.txt"),
+            &layout,
+            &[RedirectConfig {
+                source: StrictPath::new(format!("{}/tests/nonexistent", repo())),
+                target: StrictPath::new(format!("{}/tests/root2/game2", repo())),
+            }]
+        ));
     }
 
     #[test]
-    fn can_scan_game_for_backup_with_file_matches_and_ignores() {
-        let cases = [
-            (
-                BackupFilter {
-                    ignored_paths: vec![StrictPath::new(format!("{}\\tests/root1/game1/subdir", repo()))],
-                    ..Default::default()
-                },
-                ToggledPaths::default(),
-                hashset! {
-                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
-                },
-            ),
-            (
-                BackupFilter::default(),
-                ToggledPaths::new(btreemap! {
-                    s("game1") => btreemap! {
-                        StrictPath::new(format!("{}\\tests/root1/game1/subdir", repo())) => false
-                    }
-                }),
-                hashset! {
-                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2).ignored(),
-                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
-                },
-            ),
-            (
-                BackupFilter::default(),
-                ToggledPaths::new(btreemap! {
-                    s("game1") => btreemap! {
-                        StrictPath::new(format!("{}\\tests/root1/game1/subdir/file2.txt", repo())) => false
-                    }
-                }),
-                hashset! {
-                    ScannedFile::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo()), 2).ignored(),
-                    ScannedFile::new(format!("{}/tests/root2/game1/file1.txt", repo()), 1),
-                },
-            ),
-        ];
+    fn can_restore_game_by_merging_or_mirroring() {
+        let dir = StrictPath::from(std::env::temp_dir().join("ludusavi-test-restore-strategy"));
+        let _ = dir.remove();
+        let target = dir.joined("backup");
+        let layout = BackupLayout::new(
+            target.clone(),
+            Retention {
+                full: 2,
+                differential: 0,
+            },
+        );
+        let scan = scan_game_for_backup(
+            &manifest().0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &None,
+            &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
+            &ToggledPaths::default(),
+            &ToggledRegistry::default(),
+            &None,
+        );
 
-        for (filter, ignored, found) in cases {
-            assert_eq!(
-                ScanInfo {
-                    game_name: s("game1"),
-                    found_files: found,
-                    found_registry_keys: hashset! {},
-                    registry_file: None,
-                },
-                scan_game_for_backup(
-                    &manifest().0["game1"],
-                    "game1",
-                    &config().roots,
-                    &StrictPath::new(repo()),
-                    &None,
-                    &filter,
-                    &None,
-                    &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
-                    &ignored,
-                    &ToggledRegistry::default(),
-                ),
+        // The first backup has a file that the game has since deleted.
+        let old = dir.joined("old/extra.txt");
+        old.create_parent_dir().unwrap();
+        std::fs::write(old.interpret(), "extra").unwrap();
+        let mut earlier = scan.clone();
+        earlier.found_files.insert(ScannedFile::new(old.render(), 5));
+
+        let now = chrono::Utc::now();
+        for (scan, when) in [(&earlier, now - chrono::Duration::hours(1)), (&scan, now)] {
+            back_up_game(
+                scan,
+                "game1",
+                Store::Other,
+                &layout,
+                true,
+                true,
+                true,
+                &when,
+                &CopyOptions::default(),
+            );
+        }
+        let layout = BackupLayout::new(
+            target,
+            Retention {
+                full: 2,
+                differential: 0,
+            },
+        );
+        let restored = scan_game_for_restoration("game1", &layout);
+
+        let redirects = vec![
+            RedirectConfig {
+                source: StrictPath::new(format!("{}/tests", repo())),
+                target: dir.joined("restore"),
+            },
+            RedirectConfig {
+                source: dir.joined("old"),
+                target: dir.joined("restore/root2/game1"),
+            },
+        ];
+        let extra = dir.joined("restore/root2/game1/extra.txt");
+        // This shares a folder with the game's saves, but was never backed up.
+        let unrelated = dir.joined("restore/root2/game1/unrelated.txt");
+
+        for (strategy, extra_kept) in [(RestoreStrategy::Merge, true), (RestoreStrategy::Mirror, false)] {
+            extra.create_parent_dir().unwrap();
+            std::fs::write(extra.interpret(), "extra").unwrap();
+            std::fs::write(unrelated.interpret(), "unrelated").unwrap();
+
+            let preview = preview_restore_deletions(&restored, &layout, &redirects, true, strategy);
+            if extra_kept {
+                assert!(preview.is_empty());
+            } else {
+                assert_eq!(
+                    vec![extra.interpret()],
+                    preview.entries.keys().map(|x| x.interpret()).collect::<Vec<_>>()
+                );
+                assert_eq!(5, preview.total_bytes());
+            }
+
+            let info = restore_game(
+                &restored,
+                &layout,
+                &redirects,
+                true,
+                true,
+                strategy,
+                None,
+                &[],
+                &SaveSlotFilter::default(),
             );
+            assert!(info.successful());
+            assert!(dir.joined("restore/root2/game1/file1.txt").is_file());
+            assert_eq!(extra_kept, extra.is_file());
+            assert!(unrelated.is_file());
         }
+
+        // A file kept as newer in the conflict queue is neither overwritten nor deleted.
+        let file1 = dir.joined("restore/root2/game1/file1.txt");
+        std::fs::write(file1.interpret(), "newer").unwrap();
+        let kept: Vec<_> = restored
+            .found_files
+            .iter()
+            .filter_map(|x| x.original_path.clone())
+            .filter(|x| x.render().ends_with("root2/game1/file1.txt"))
+            .map(|original| RestoreConflict {
+                game: s("game1"),
+                original,
+                target: file1.clone(),
+            })
+            .collect();
+        assert_eq!(1, kept.len());
+        let info = restore_game(
+            &restored.without_conflicts(&kept),
+            &layout,
+            &redirects,
+            true,
+            true,
+            RestoreStrategy::Mirror,
+            None,
+            &[],
+            &SaveSlotFilter::default(),
+        );
+        assert!(info.successful());
+        assert_eq!("newer", std::fs::read_to_string(file1.interpret()).unwrap());
+
+        let _ = dir.remove();
     }
 
     #[test]
-    #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_registry_matches_on_leaf_key_with_values() {
-        assert_eq!(
-            ScanInfo {
-                game_name: s("game3"),
-                found_files: hashset! {},
-                found_registry_keys: hashset! {
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3")
+    fn can_find_restore_conflicts() {
+        let newer = StrictPath::from(std::env::temp_dir().join("ludusavi-test-restore-conflict.txt"));
+        std::fs::write(newer.interpret(), "newer").unwrap();
+
+        let make_info = |original: &StrictPath| ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                    size: 1,
+                    original_path: Some(original.clone()),
+                    ignored: false,
                 },
-                registry_file: None,
             },
-            scan_game_for_backup(
-                &manifest().0["game3"],
-                "game3",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(&config().roots, &manifest(), &["game3".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+            ..Default::default()
+        };
+
+        let layout = BackupLayout::new(
+            StrictPath::new(format!("{}/tests/backup", repo())),
+            Retention::default(),
         );
-    }
 
-    #[test]
-    #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_registry_matches_on_parent_key_without_values() {
         assert_eq!(
-            ScanInfo {
-                game_name: s("game3-outer"),
-                found_files: hashset! {},
-                found_registry_keys: hashset! {
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other"),
-                },
-                registry_file: None,
-            },
-            scan_game_for_backup(
-                &manifest().0["game3-outer"],
-                "game3-outer",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-                &None,
-                &InstallDirRanking::scan(&config().roots, &manifest(), &["game3-outer".to_string()]),
-                &ToggledPaths::default(),
-                &ToggledRegistry::default(),
-            ),
+            vec![RestoreConflict {
+                game: s("game1"),
+                original: newer.clone(),
+                target: newer.clone(),
+            }],
+            restore_conflicts(&make_info(&newer), &layout, &[]),
         );
-    }
+        assert!(restore_conflicts(
+            &make_info(&StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo()))),
+            &layout,
+            &[]
+        )
+        .is_empty());
+        assert!(restore_conflicts(
+            &make_info(&StrictPath::new(format!("{}/tests/nonexistent.txt", repo()))),
+            &layout,
+            &[]
+        )
+        .is_empty());
 
-    #[test]
-    #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_registry_matches_and_ignores() {
-        let cases = vec![
-            (
-                BackupFilter {
-                    ignored_registry: vec![RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi/other"))],
-                    ..Default::default()
-                },
-                ToggledRegistry::default(),
-                hashset! {
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
-                },
-            ),
-            (
-                BackupFilter::default(),
-                ToggledRegistry::new(btreemap! {
-                    s("game3-outer") => btreemap! {
-                        RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi")) => false
-                    }
-                }),
-                hashset! {
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi").ignored(),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3").ignored(),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other").ignored(),
-                },
-            ),
-            (
-                BackupFilter::default(),
-                ToggledRegistry::new(btreemap! {
-                    s("game3-outer") => btreemap! {
-                        RegistryItem::new(s("HKEY_CURRENT_USER\\Software/Ludusavi/other")) => false
-                    }
-                }),
-                hashset! {
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi"),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3"),
-                    ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/other").ignored(),
-                },
-            ),
-        ];
+        let info = make_info(&newer);
+        assert!(info
+            .without_conflicts(&restore_conflicts(&info, &layout, &[]))
+            .found_files
+            .is_empty());
 
-        for (filter, ignored, found) in cases {
-            assert_eq!(
-                ScanInfo {
-                    game_name: s("game3-outer"),
-                    found_files: hashset! {},
-                    found_registry_keys: found,
-                    registry_file: None,
-                },
-                scan_game_for_backup(
-                    &manifest().0["game3-outer"],
-                    "game3-outer",
-                    &config().roots,
-                    &StrictPath::new(repo()),
-                    &None,
-                    &filter,
-                    &None,
-                    &InstallDirRanking::scan(&config().roots, &manifest(), &["game1".to_string()]),
-                    &ToggledPaths::default(),
-                    &ignored,
-                ),
-            );
-        }
+        let _ = newer.remove();
     }
 
     #[test]
-    fn can_scan_game_for_restoration_with_files() {
-        let make_path = |x| {
-            if cfg!(target_os = "windows") {
-                StrictPath::new(format!(
-                    "\\\\?\\{}\\tests\\backup\\game1\\drive-X\\{}",
-                    repo().replace('/', "\\"),
-                    x
-                ))
-            } else {
-                StrictPath::new(format!("{}/tests/backup/game1/drive-X/{}", repo(), x))
-            }
+    fn can_limit_scan_info_to_certain_kinds_of_data() {
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! { ScannedFile::new("file1.txt", 1) },
+            found_registry_keys: hashset! { ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi") },
+            registry_file: Some(StrictPath::new(s("registry.yaml"))),
         };
 
+        assert_eq!(info, info.only_kinds(true, true));
         assert_eq!(
             ScanInfo {
                 game_name: s("game1"),
-                found_files: hashset! {
-                    ScannedFile {
-                        path: make_path("file1.txt"),
-                        size: 1,
-                        original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file1.txt" } else { "X:/file1.txt" }))),
-                        ignored: false,
-                    },
-                    ScannedFile {
-                        path: make_path("file2.txt"),
-                        size: 2,
-                        original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file2.txt" } else { "X:/file2.txt" }))),
-                        ignored: false,
-                    },
-                },
+                found_files: hashset! { ScannedFile::new("file1.txt", 1) },
                 ..Default::default()
             },
-            scan_game_for_restoration(
-                "game1",
-                &BackupLayout::new(
-                    StrictPath::new(format!("{}/tests/backup", repo())),
-                    Retention::default()
-                )
-            ),
+            info.only_kinds(true, false),
+        );
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_registry_keys: hashset! { ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi") },
+                registry_file: Some(StrictPath::new(s("registry.yaml"))),
+                ..Default::default()
+            },
+            info.only_kinds(false, true),
         );
     }
 
     #[test]
-    fn can_scan_game_for_restoration_with_registry() {
-        if cfg!(target_os = "windows") {
-            assert_eq!(
-                ScanInfo {
-                    game_name: s("game3"),
-                    found_registry_keys: hashset! {
-                        ScannedRegistry::new("HKEY_CURRENT_USER/Software/Ludusavi/game3")
-                    },
-                    registry_file: Some(StrictPath::new(format!(
-                        "\\\\?\\{}\\tests\\backup\\game3-renamed/registry.yaml",
-                        repo().replace('/', "\\")
-                    ))),
-                    ..Default::default()
-                },
-                scan_game_for_restoration(
-                    "game3",
-                    &BackupLayout::new(
-                        StrictPath::new(format!("{}/tests/backup", repo())),
-                        Retention::default()
-                    )
-                ),
-            );
-        } else {
-            assert_eq!(
-                ScanInfo {
-                    game_name: s("game3"),
-                    ..Default::default()
-                },
-                scan_game_for_restoration(
-                    "game3",
-                    &BackupLayout::new(
-                        StrictPath::new(format!("{}/tests/backup", repo())),
-                        Retention::default()
-                    )
-                ),
-            );
-        }
+    fn reports_unresolvable_restoration_targets() {
+        let original = StrictPath::new(s("/games/game1/save.dat"));
+
+        assert_eq!(
+            Ok((original.clone(), None)),
+            game_file_restoration_target(&original, &[])
+        );
+        assert_eq!(
+            Ok((StrictPath::new(s("/other/game1/save.dat")), Some(original.clone()))),
+            game_file_restoration_target(
+                &original,
+                &[RedirectConfig {
+                    source: StrictPath::new(s("/games")),
+                    target: StrictPath::new(s("/other")),
+                }]
+            ),
+        );
+        assert_eq!(
+            Err(RestorationTargetError::NotAbsolute {
+                path: StrictPath::new(s("relative")),
+            }),
+            game_file_restoration_target(
+                &original,
+                &[RedirectConfig {
+                    source: StrictPath::new(s("/games")),
+                    target: StrictPath::new(s("relative")),
+                }]
+            ),
+        );
+        assert_eq!(
+            Err(RestorationTargetError::NotAbsolute {
+                path: StrictPath::new(s("save.dat")),
+            }),
+            game_file_restoration_target(&StrictPath::new(s("save.dat")), &[]),
+        );
+        assert_eq!(
+            Err(RestorationTargetError::Empty),
+            game_file_restoration_target(&StrictPath::new(s("")), &[]),
+        );
     }
 
     #[test]
-    fn checks_if_files_are_identical() {
-        assert!(are_files_identical(
-            &StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
-            &StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())),
-        )
-        .unwrap());
-        assert!(!are_files_identical(
-            &StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
-            &StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
-        )
-        .unwrap());
-        assert!(are_files_identical(
-            &StrictPath::new(format!("{}/tests/root1/game1/file1.txt", repo())),
-            &StrictPath::new(format!("{}/nonexistent.txt", repo())),
-        )
-        .is_err());
+    fn throttle_limits_concurrent_work() {
+        let throttle = std::sync::Arc::new(Throttle::new());
+        throttle.set_limit(2);
+        let current = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let throttle = throttle.clone();
+                let current = current.clone();
+                let peak = peak.clone();
+                std::thread::spawn(move || {
+                    let _slot = throttle.acquire();
+                    let now = current.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    current.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(2, peak.load(std::sync::atomic::Ordering::SeqCst));
     }
 
     mod duplicate_detector {
@@ -1843,4 +4387,51 @@ mod tests {
             }));
         }
     }
+
+    mod failures {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn categorizes_io_errors() {
+            assert_eq!(
+                FailureCategory::Permission,
+                FailureCategory::from_io(&std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            );
+            assert_eq!(
+                FailureCategory::MissingSource,
+                FailureCategory::from_io(&std::io::Error::from(std::io::ErrorKind::NotFound))
+            );
+            assert_eq!(
+                FailureCategory::Other,
+                FailureCategory::from_io(&std::io::Error::from(std::io::ErrorKind::InvalidData))
+            );
+        }
+
+        #[test]
+        fn summarizes_the_most_common_category_per_game() {
+            let mut info = BackupInfo::default();
+            info.add_failed_file(ScannedFile::new("a", 1), FailureCategory::DiskFull);
+            info.add_failed_file(ScannedFile::new("b", 1), FailureCategory::Permission);
+            info.add_failed_file(ScannedFile::new("c", 1), FailureCategory::Permission);
+            assert_eq!(Some(FailureCategory::Permission), info.failure_category());
+
+            let mut summary = OperationSummary::default();
+            summary.add(Some(&info));
+            summary.add(Some(&BackupInfo {
+                failed_registry: hashset! { RegistryItem::new(s("HKEY_CURRENT_USER/Software/Foo")) },
+                ..Default::default()
+            }));
+            summary.add(Some(&BackupInfo::default()));
+            summary.add(None);
+
+            assert_eq!(1, summary.succeeded);
+            assert_eq!(1, summary.skipped);
+            assert_eq!(2, summary.failed);
+            assert_eq!(
+                maplit::btreemap! { FailureCategory::Permission => 1, FailureCategory::Other => 1 },
+                summary.failures
+            );
+        }
+    }
 }
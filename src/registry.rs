@@ -3,6 +3,7 @@ use std::collections::HashSet;
 use crate::{
     config::{BackupFilter, ToggledRegistry},
     prelude::{Error, RegistryItem, ScanInfo, ScannedRegistry, StrictPath},
+    registry_compat::{RegistryChange, RegistryChangeKind},
 };
 use winreg::types::{FromRegValue, ToRegValue};
 
@@ -91,19 +92,19 @@ impl Hives {
         }
     }
 
-    pub fn save(&self, file: &StrictPath) {
+    pub fn save(&self, file: &StrictPath) -> std::io::Result<()> {
+        crate::prelude::ensure_writable()?;
         let new_content = serde_yaml::to_string(&self).unwrap();
 
         if let Some(old) = Self::load(file) {
             let old_content = serde_yaml::to_string(&old).unwrap();
             if old_content == new_content {
-                return;
+                return Ok(());
             }
         }
 
-        if file.create_parent_dir().is_ok() {
-            std::fs::write(file.interpret(), self.serialize().as_bytes()).unwrap();
-        }
+        file.create_parent_dir()?;
+        std::fs::write(file.interpret(), new_content.as_bytes())
     }
 
     pub fn serialize(&self) -> String {
@@ -176,44 +177,105 @@ impl Hives {
         Ok(())
     }
 
+    /// The current values of the keys that are in this set of hives.
+    pub fn current(&self) -> Self {
+        let mut current = Self::default();
+        for (hive_name, keys) in self.0.iter() {
+            if let Some(hive) = get_hkey_from_name(hive_name) {
+                for key_name in keys.0.keys() {
+                    let _ = current.store_key(hive, hive_name, key_name);
+                }
+            }
+        }
+        current
+    }
+
+    /// Compare backed up values against the current ones.
+    pub fn diff(&self, current: &Self) -> Vec<RegistryChange> {
+        let mut changes = vec![];
+
+        for (hive_name, keys) in self.0.iter() {
+            for (key_name, entries) in keys.0.iter() {
+                let key = RegistryItem::new(format!("{}\\{}", hive_name, key_name)).rendered();
+                let current_entries = current.0.get(hive_name).and_then(|x| x.0.get(key_name));
+
+                for (entry_name, entry) in entries.0.iter() {
+                    let kind = match current_entries.and_then(|x| x.0.get(entry_name)) {
+                        None => RegistryChangeKind::Added,
+                        Some(current_entry) if current_entry != entry => RegistryChangeKind::Changed,
+                        Some(_) => continue,
+                    };
+                    changes.push(RegistryChange {
+                        key: key.clone(),
+                        value: entry_name.clone(),
+                        kind,
+                    });
+                }
+
+                for entry_name in current_entries.iter().flat_map(|x| x.0.keys()) {
+                    if !entries.0.contains_key(entry_name) {
+                        changes.push(RegistryChange {
+                            key: key.clone(),
+                            value: entry_name.clone(),
+                            kind: RegistryChangeKind::Removed,
+                        });
+                    }
+                }
+            }
+        }
+
+        changes.sort();
+        changes
+    }
+
     pub fn restore(&self) -> Result<(), Error> {
-        let mut failed = false;
+        if self.restore_tracking_failures().is_empty() {
+            Ok(())
+        } else {
+            Err(Error::RegistryIssue)
+        }
+    }
+
+    /// Restore all keys, returning the ones that could not be fully restored.
+    pub fn restore_tracking_failures(&self) -> HashSet<RegistryItem> {
+        let mut failed = HashSet::new();
 
         for (hive_name, keys) in self.0.iter() {
             let hive = match get_hkey_from_name(hive_name) {
                 Some(x) => winreg::RegKey::predef(x),
                 None => {
-                    failed = true;
+                    failed.extend(
+                        keys.0
+                            .keys()
+                            .map(|key_name| RegistryItem::new(format!("{}\\{}", hive_name, key_name)).rendered()),
+                    );
                     continue;
                 }
             };
 
             for (key_name, entries) in keys.0.iter() {
+                let item = RegistryItem::new(format!("{}\\{}", hive_name, key_name)).rendered();
                 let (key, _) = match hive.create_subkey(key_name) {
                     Ok(x) => x,
                     Err(_) => {
-                        failed = true;
+                        failed.insert(item);
                         continue;
                     }
                 };
 
                 for (entry_name, entry) in entries.0.iter() {
-                    if let Some(value) = Option::<winreg::RegValue>::from(entry) {
-                        if key.set_raw_value(entry_name, &value).is_err() {
-                            failed = true;
-                        }
-                    } else {
-                        failed = true;
+                    let restored = match Option::<winreg::RegValue>::from(entry) {
+                        Some(value) => key.set_raw_value(entry_name, &value).is_ok(),
+                        None => false,
+                    };
+                    if !restored {
+                        failed.insert(item.clone());
                     }
                 }
             }
         }
 
-        if failed {
-            return Err(Error::RegistryIssue);
-        }
-
-        Ok(())
+        failed
     }
 }
 
@@ -311,6 +373,45 @@ mod tests {
         text.to_string()
     }
 
+    #[test]
+    fn can_diff_against_current_values() {
+        let entry = |sz: &str| Entry {
+            sz: Some(s(sz)),
+            ..Default::default()
+        };
+        let backup = Hives(hashmap! {
+            s("HKEY_CURRENT_USER") => Keys(hashmap! {
+                s("Software\\Ludusavi") => Entries(hashmap! {
+                    s("new") => entry("a"),
+                    s("changed") => entry("b"),
+                    s("same") => entry("c"),
+                }),
+            }),
+        });
+        let current = Hives(hashmap! {
+            s("HKEY_CURRENT_USER") => Keys(hashmap! {
+                s("Software\\Ludusavi") => Entries(hashmap! {
+                    s("changed") => entry("x"),
+                    s("same") => entry("c"),
+                    s("extra") => entry("d"),
+                }),
+            }),
+        });
+        let change = |value: &str, kind| RegistryChange {
+            key: RegistryItem::new(s("HKEY_CURRENT_USER/Software/Ludusavi")),
+            value: s(value),
+            kind,
+        };
+        assert_eq!(
+            vec![
+                change("changed", RegistryChangeKind::Changed),
+                change("extra", RegistryChangeKind::Removed),
+                change("new", RegistryChangeKind::Added),
+            ],
+            backup.diff(&current),
+        );
+    }
+
     #[test]
     fn can_store_key_from_full_path_of_leaf_key_with_values() {
         let mut hives = Hives::default();
@@ -86,6 +86,26 @@ impl RegistryItem {
     }
 }
 
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RegistryChangeKind {
+    /// The backup has a value that is not currently set.
+    Added,
+    /// The backup has a different value than what is currently set.
+    Changed,
+    /// A value is currently set, but is not in the backup.
+    /// Restoration leaves these alone.
+    Removed,
+}
+
+/// How a registry value differs between a backup and the current registry.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RegistryChange {
+    pub key: RegistryItem,
+    pub value: String,
+    pub kind: RegistryChangeKind,
+}
+
 // Based on:
 // https://github.com/serde-rs/serde/issues/751#issuecomment-277580700
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -31,3 +31,29 @@ pub fn is_empty_set<T>(v: &std::collections::HashSet<T>) -> bool {
 pub const fn default_true() -> bool {
     true
 }
+
+/// Store an optional duration as a number of hours.
+pub mod optional_hours {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(x) => serializer.serialize_f64(x.as_secs_f64() / 3600.0),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hours = Option::<f64>::deserialize(deserializer)?;
+        Ok(hours
+            .filter(|x| x.is_finite() && *x > 0.0)
+            .map(|x| Duration::from_secs_f64(x * 3600.0)))
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::BTreeMap;
+
+use crate::prelude::{app_dir, Error, StrictPath};
+
+const SCHEME: &str = "sftp://";
+const DEFAULT_PORT: u16 = 22;
+
+/// A backup folder on an SFTP server, written like `sftp://user@host:port/path`.
+/// The path is absolute, unless it starts with `/~/`, in which case it is
+/// relative to the remote user's home folder.
+///
+/// Backups are written to a local working copy of the remote folder, which is
+/// kept in sync over SSH: only files that changed since the last sync are
+/// transferred in either direction, and deletions are carried over as well.
+/// Changes on both sides to the same file are reported as conflicts.
+/// Authentication goes through the SSH agent or a default key in `~/.ssh`,
+/// and the server must already be in `~/.ssh/known_hosts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SftpTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+impl SftpTarget {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let rest = raw.trim().strip_prefix(SCHEME)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let (user, host_and_port) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+        let (host, port) = match host_and_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (host_and_port, None),
+        };
+        if host.is_empty() {
+            return None;
+        }
+
+        let path = match path.strip_prefix("/~") {
+            Some(relative) => relative.trim_start_matches('/'),
+            None => path,
+        };
+        let path = match path.trim_end_matches('/') {
+            "" if path.starts_with('/') => "/",
+            "" => ".",
+            x => x,
+        };
+
+        Some(Self {
+            user: user.filter(|x| !x.is_empty()),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// Where backups for this target are kept locally before uploading.
+    pub fn mirror(&self) -> StrictPath {
+        let name: String = format!(
+            "{}@{}_{}_{}",
+            self.user.as_deref().unwrap_or(""),
+            self.host,
+            self.port.map(|x| x.to_string()).unwrap_or_default(),
+            self.path
+        )
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+        StrictPath::from(app_dir().join("sftp").join(name))
+    }
+
+    /// What the remote folder contained after the last sync.
+    fn state_file(&self) -> StrictPath {
+        StrictPath::new(format!("{}.sync.yaml", self.mirror().interpret()))
+    }
+
+    fn load_state(&self) -> SyncState {
+        std::fs::read_to_string(self.state_file().interpret())
+            .ok()
+            .and_then(|x| serde_yaml::from_str(&x).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &SyncState) -> Result<(), Error> {
+        crate::prelude::ensure_writable().map_err(|e| self.failed(e))?;
+        let content = serde_yaml::to_string(state).map_err(|e| self.failed(e))?;
+        std::fs::write(self.state_file().interpret(), content).map_err(|e| self.failed(e))
+    }
+
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    fn failed(&self, output: impl ToString) -> Error {
+        Error::SftpTransferFailed {
+            target: self.to_string(),
+            output: output.to_string(),
+        }
+    }
+
+    fn remote_path(&self, relative: &str) -> std::path::PathBuf {
+        if relative.is_empty() {
+            std::path::PathBuf::from(&self.path)
+        } else {
+            std::path::PathBuf::from(format!("{}/{}", self.path.trim_end_matches('/'), relative))
+        }
+    }
+
+    fn connect(&self) -> Result<ssh2::Sftp, Error> {
+        let port = self.port.unwrap_or(DEFAULT_PORT);
+        let tcp = std::net::TcpStream::connect((self.host.as_str(), port)).map_err(|e| self.failed(e))?;
+        let mut session = ssh2::Session::new().map_err(|e| self.failed(e))?;
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(|e| self.failed(e))?;
+        self.verify_host_key(&session, port)?;
+        self.authenticate(&session)?;
+        session.sftp().map_err(|e| self.failed(e))
+    }
+
+    fn verify_host_key(&self, session: &ssh2::Session, port: u16) -> Result<(), Error> {
+        let key = match session.host_key() {
+            Some((key, _)) => key,
+            None => return Err(self.failed("The server did not send a host key.")),
+        };
+        let mut known_hosts = session.known_hosts().map_err(|e| self.failed(e))?;
+        if let Some(file) = dirs::home_dir().map(|x| x.join(".ssh").join("known_hosts")) {
+            if file.is_file() {
+                known_hosts
+                    .read_file(&file, ssh2::KnownHostFileKind::OpenSSH)
+                    .map_err(|e| self.failed(e))?;
+            }
+        }
+        match known_hosts.check_port(&self.host, port, key) {
+            ssh2::CheckResult::Match => Ok(()),
+            ssh2::CheckResult::Mismatch => Err(self.failed("The server's host key does not match ~/.ssh/known_hosts.")),
+            ssh2::CheckResult::NotFound | ssh2::CheckResult::Failure => Err(self.failed(
+                "The server is not in ~/.ssh/known_hosts. Connect to it once with `ssh` to confirm its host key.",
+            )),
+        }
+    }
+
+    fn authenticate(&self, session: &ssh2::Session) -> Result<(), Error> {
+        let user = self.user.clone().unwrap_or_else(whoami::username);
+        if session.userauth_agent(&user).is_ok() && session.authenticated() {
+            return Ok(());
+        }
+        if let Some(folder) = dirs::home_dir().map(|x| x.join(".ssh")) {
+            for name in ["id_ed25519", "id_ecdsa", "id_rsa"] {
+                let key = folder.join(name);
+                if key.is_file()
+                    && session.userauth_pubkey_file(&user, None, &key, None).is_ok()
+                    && session.authenticated()
+                {
+                    return Ok(());
+                }
+            }
+        }
+        Err(self.failed("Unable to authenticate with the SSH agent or a default key in ~/.ssh."))
+    }
+
+    /// Every file in the remote folder. A missing folder counts as empty.
+    fn list_remote(&self, sftp: &ssh2::Sftp) -> Result<SyncState, Error> {
+        let mut files = SyncState::new();
+        if sftp.stat(&self.remote_path("")).is_err() {
+            return Ok(files);
+        }
+
+        let mut pending = vec![String::new()];
+        while let Some(folder) = pending.pop() {
+            for (path, stat) in sftp.readdir(self.remote_path(&folder)).map_err(|e| self.failed(e))? {
+                let name = match path.file_name() {
+                    Some(x) => x.to_string_lossy().to_string(),
+                    None => continue,
+                };
+                let relative = if folder.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", folder, name)
+                };
+                if stat.is_dir() {
+                    pending.push(relative);
+                } else if stat.is_file() {
+                    files.insert(
+                        relative,
+                        FileStamp {
+                            size: stat.size.unwrap_or_default(),
+                            modified: stat.mtime.unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Bring the local working copy up to date with the remote folder.
+    /// Only new and changed files are transferred, and local files that were
+    /// deleted on the server since the last sync are removed. Local changes that
+    /// haven't been uploaded yet (e.g., because an upload failed) are kept for the
+    /// next upload. This fails without changing anything if the same file changed
+    /// on both sides since the last sync.
+    pub fn download(&self) -> Result<(), Error> {
+        crate::prelude::ensure_writable().map_err(|e| self.failed(e))?;
+        let sftp = self.connect()?;
+        let remote = self.list_remote(&sftp)?;
+        let mirror = self.mirror();
+        let local = list_local(&mirror);
+        let plan = plan_download(&self.load_state(), &remote, &local).map_err(|conflict| {
+            self.failed(format!(
+                "{}: changed both locally and on the server since the last sync.",
+                conflict
+            ))
+        })?;
+
+        for relative in &plan.delete {
+            std::fs::remove_file(mirror.joined(relative).interpret()).map_err(|e| self.failed(e))?;
+        }
+        for relative in &plan.get {
+            let stamp = &remote[relative];
+            self.download_file(&sftp, relative, stamp, &mirror.joined(relative))
+                .map_err(|e| self.failed(format!("{}: {}", relative, e)))?;
+        }
+        remove_empty_folders(&mirror);
+
+        self.save_state(&remote)
+    }
+
+    fn download_file(
+        &self,
+        sftp: &ssh2::Sftp,
+        relative: &str,
+        stamp: &FileStamp,
+        target: &StrictPath,
+    ) -> std::io::Result<()> {
+        target.create_parent_dir()?;
+        let temp = StrictPath::new(format!("{}.download", target.interpret()));
+        let mut source = sftp.open(self.remote_path(relative))?;
+        let mut file = std::fs::File::create(temp.interpret())?;
+        std::io::copy(&mut source, &mut file)?;
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(stamp.modified))?;
+        drop(file);
+        std::fs::rename(temp.interpret(), target.interpret())
+    }
+
+    /// Send local changes to the remote folder, including deletions
+    /// (e.g., from retention). This fails without changing anything if another
+    /// machine has modified the same files since the last download.
+    pub fn upload(&self) -> Result<(), Error> {
+        crate::prelude::ensure_writable().map_err(|e| self.failed(e))?;
+        let sftp = self.connect()?;
+        let remote = self.list_remote(&sftp)?;
+        let mirror = self.mirror();
+        let local = list_local(&mirror);
+        let plan = plan_upload(&self.load_state(), &remote, &local)
+            .map_err(|conflict| self.failed(format!("{}: changed on the server since the last download.", conflict)))?;
+
+        let mut state = remote;
+        let mut created = std::collections::HashSet::new();
+        if !plan.put.is_empty() {
+            // It's fine if this already exists.
+            let _ = sftp.mkdir(&self.remote_path(""), 0o755);
+        }
+        for relative in &plan.put {
+            let mut folder = String::new();
+            for part in relative
+                .split('/')
+                .collect::<Vec<_>>()
+                .split_last()
+                .map(|x| x.1)
+                .unwrap_or_default()
+            {
+                folder = if folder.is_empty() {
+                    part.to_string()
+                } else {
+                    format!("{}/{}", folder, part)
+                };
+                if created.insert(folder.clone()) {
+                    let _ = sftp.mkdir(&self.remote_path(&folder), 0o755);
+                }
+            }
+
+            let stamp = local[relative];
+            self.upload_file(&sftp, relative, &stamp, &mirror.joined(relative))
+                .map_err(|e| self.failed(format!("{}: {}", relative, e)))?;
+            state.insert(relative.clone(), stamp);
+        }
+
+        let mut emptied = std::collections::BTreeSet::new();
+        for relative in &plan.delete {
+            sftp.unlink(&self.remote_path(relative))
+                .map_err(|e| self.failed(format!("{}: {}", relative, e)))?;
+            state.remove(relative);
+            let mut folder = relative.as_str();
+            while let Some((parent, _)) = folder.rsplit_once('/') {
+                emptied.insert(parent.to_string());
+                folder = parent;
+            }
+        }
+        // Deepest folders come last, so go in reverse. Folders that still have files are kept.
+        for folder in emptied.iter().rev() {
+            let _ = sftp.rmdir(&self.remote_path(folder));
+        }
+
+        self.save_state(&state)
+    }
+
+    fn upload_file(
+        &self,
+        sftp: &ssh2::Sftp,
+        relative: &str,
+        stamp: &FileStamp,
+        source: &StrictPath,
+    ) -> std::io::Result<()> {
+        let target = self.remote_path(relative);
+        let mut file = std::fs::File::open(source.interpret())?;
+        let mut remote = sftp.create(&target)?;
+        std::io::copy(&mut file, &mut remote)?;
+        drop(remote);
+        sftp.setstat(
+            &target,
+            ssh2::FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: None,
+                atime: Some(stamp.modified),
+                mtime: Some(stamp.modified),
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// The size and modified time (in seconds since the Unix epoch) of a synced file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct FileStamp {
+    size: u64,
+    modified: u64,
+}
+
+/// Files by their path relative to the backup folder, using `/` as the separator.
+type SyncState = BTreeMap<String, FileStamp>;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UploadPlan {
+    put: Vec<String>,
+    delete: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DownloadPlan {
+    get: Vec<String>,
+    delete: Vec<String>,
+}
+
+/// Decide what to fetch and delete locally, given what the remote folder had at the last sync,
+/// what it has now, and what the local working copy has now. Only files that are unchanged
+/// locally since the last sync follow the server. If a file changed on both sides,
+/// this returns that file instead.
+fn plan_download(synced: &SyncState, remote: &SyncState, local: &SyncState) -> Result<DownloadPlan, String> {
+    let mut plan = DownloadPlan::default();
+
+    let all: std::collections::BTreeSet<_> = local.keys().chain(remote.keys()).collect();
+    for relative in all {
+        let (local, remote, synced) = (local.get(relative), remote.get(relative), synced.get(relative));
+        if local == remote {
+            continue;
+        }
+        if local == synced {
+            match remote {
+                Some(_) => plan.get.push(relative.clone()),
+                None => plan.delete.push(relative.clone()),
+            }
+        } else if remote != synced {
+            return Err(relative.clone());
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Decide what to send and delete, given what the remote folder had at the last sync,
+/// what it has now, and what the local working copy has now. If another machine changed
+/// a file that would be overwritten or deleted, this returns that file instead.
+fn plan_upload(synced: &SyncState, remote: &SyncState, local: &SyncState) -> Result<UploadPlan, String> {
+    let mut plan = UploadPlan::default();
+
+    for (relative, stamp) in local {
+        let current = remote.get(relative);
+        if current == Some(stamp) {
+            continue;
+        }
+        if current.is_some() && current != synced.get(relative) {
+            return Err(relative.clone());
+        }
+        plan.put.push(relative.clone());
+    }
+
+    for (relative, stamp) in synced {
+        if local.contains_key(relative) {
+            continue;
+        }
+        match remote.get(relative) {
+            None => {}
+            Some(current) if current == stamp => plan.delete.push(relative.clone()),
+            Some(_) => return Err(relative.clone()),
+        }
+    }
+
+    Ok(plan)
+}
+
+fn list_local(folder: &StrictPath) -> SyncState {
+    let base = folder.as_std_path_buf();
+    walkdir::WalkDir::new(&base)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|x| x.file_type().is_file())
+        .filter_map(|x| {
+            let relative = x.path().strip_prefix(&base).ok()?;
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            let metadata = x.metadata().ok()?;
+            let modified = metadata
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs();
+            Some((
+                relative,
+                FileStamp {
+                    size: metadata.len(),
+                    modified,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn remove_empty_folders(folder: &StrictPath) {
+    for entry in walkdir::WalkDir::new(folder.interpret())
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|x| x.file_type().is_dir())
+    {
+        // This only succeeds for empty folders.
+        let _ = std::fs::remove_dir(entry.path());
+    }
+}
+
+impl std::fmt::Display for SftpTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", SCHEME, self.destination())?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        if self.path.starts_with('/') {
+            write!(f, "{}", self.path)
+        } else {
+            write!(f, "/~/{}", self.path)
+        }
+    }
+}
+
+/// The local folder to use for a configured backup path.
+/// For SFTP targets, this is the local mirror.
+pub fn local_path(path: &StrictPath) -> StrictPath {
+    match SftpTarget::parse(&path.raw()) {
+        Some(remote) => remote.mirror(),
+        None => path.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_parse_sftp_target() {
+        assert_eq!(None, SftpTarget::parse("/home/user/backup"));
+        assert_eq!(None, SftpTarget::parse("sftp:///backup"));
+        assert_eq!(None, SftpTarget::parse("sftp://host:port/backup"));
+        assert_eq!(
+            Some(SftpTarget {
+                user: Some("user".to_string()),
+                host: "host".to_string(),
+                port: Some(2222),
+                path: "/srv/backup".to_string(),
+            }),
+            SftpTarget::parse("sftp://user@host:2222/srv/backup/")
+        );
+        assert_eq!(
+            Some(SftpTarget {
+                user: None,
+                host: "host".to_string(),
+                port: None,
+                path: "backup".to_string(),
+            }),
+            SftpTarget::parse("sftp://host/~/backup")
+        );
+        assert_eq!(
+            Some(SftpTarget {
+                user: None,
+                host: "host".to_string(),
+                port: None,
+                path: ".".to_string(),
+            }),
+            SftpTarget::parse("sftp://host")
+        );
+    }
+
+    fn stamp(size: u64, modified: u64) -> FileStamp {
+        FileStamp { size, modified }
+    }
+
+    #[test]
+    fn can_plan_upload() {
+        let synced = maplit::btreemap! {
+            "game/mapping.yaml".to_string() => stamp(1, 1),
+            "game/old.txt".to_string() => stamp(1, 1),
+            "other/mapping.yaml".to_string() => stamp(1, 1),
+        };
+        let local = maplit::btreemap! {
+            "game/mapping.yaml".to_string() => stamp(2, 2),
+            "game/new.txt".to_string() => stamp(1, 2),
+            "other/mapping.yaml".to_string() => stamp(1, 1),
+        };
+
+        assert_eq!(
+            Ok(UploadPlan {
+                put: vec!["game/mapping.yaml".to_string(), "game/new.txt".to_string()],
+                delete: vec!["game/old.txt".to_string()],
+            }),
+            plan_upload(&synced, &synced, &local)
+        );
+
+        // Another machine added a game in the meantime, which is left alone.
+        let mut remote = synced.clone();
+        remote.insert("third/mapping.yaml".to_string(), stamp(1, 3));
+        assert_eq!(
+            vec!["game/old.txt".to_string()],
+            plan_upload(&synced, &remote, &local).unwrap().delete
+        );
+
+        // Another machine updated the same game in the meantime.
+        let mut remote = synced.clone();
+        remote.insert("game/mapping.yaml".to_string(), stamp(3, 3));
+        assert_eq!(
+            Err("game/mapping.yaml".to_string()),
+            plan_upload(&synced, &remote, &local)
+        );
+    }
+
+    #[test]
+    fn can_plan_download() {
+        let synced = maplit::btreemap! {
+            "game/mapping.yaml".to_string() => stamp(1, 1),
+            "game/old.txt".to_string() => stamp(1, 1),
+            "game/pending-delete.txt".to_string() => stamp(1, 1),
+        };
+        let remote = maplit::btreemap! {
+            "game/mapping.yaml".to_string() => stamp(2, 2),
+            "game/pending-delete.txt".to_string() => stamp(1, 1),
+            "other/mapping.yaml".to_string() => stamp(1, 2),
+        };
+        // A failed upload left a new file and a deletion that only exist locally.
+        let local = maplit::btreemap! {
+            "game/mapping.yaml".to_string() => stamp(1, 1),
+            "game/old.txt".to_string() => stamp(1, 1),
+            "game/not-uploaded.txt".to_string() => stamp(1, 3),
+        };
+
+        assert_eq!(
+            Ok(DownloadPlan {
+                get: vec!["game/mapping.yaml".to_string(), "other/mapping.yaml".to_string()],
+                delete: vec!["game/old.txt".to_string()],
+            }),
+            plan_download(&synced, &remote, &local)
+        );
+
+        // The same file changed locally and on the server.
+        let mut local = local.clone();
+        local.insert("game/mapping.yaml".to_string(), stamp(3, 3));
+        assert_eq!(
+            Err("game/mapping.yaml".to_string()),
+            plan_download(&synced, &remote, &local)
+        );
+    }
+
+    #[test]
+    fn can_display_sftp_target() {
+        for raw in ["sftp://user@host:2222/srv/backup", "sftp://host/~/backup"] {
+            assert_eq!(raw, SftpTarget::parse(raw).unwrap().to_string());
+        }
+    }
+}
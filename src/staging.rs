@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    config::RedirectConfig,
+    layout::{escape_folder_name, BackupLayout, FileMetadata},
+    prelude::{
+        app_dir, copy_file, game_file_restoration_target, BackupInfo, CopyOptions, Error, FailureCategory, ScanInfo,
+        ScannedFile, StrictPath,
+    },
+};
+
+const STAGING_DIR_NAME: &str = "staging";
+const STAGING_FILE_NAME: &str = "staged.yaml";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StagedGame {
+    /// Strings are StrictPath in rendered form, without redirects applied.
+    #[serde(
+        default,
+        serialize_with = "crate::serialization::ordered_set",
+        skip_serializing_if = "crate::serialization::is_empty_set"
+    )]
+    pub files: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "registryFile")]
+    pub registry_file: Option<StrictPath>,
+}
+
+/// Restored data that has been written to the staging area, but not yet
+/// moved to its real location. This is saved to disk so that it survives
+/// between the two phases of a staged restore.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StagedRestoration {
+    #[serde(default, serialize_with = "crate::serialization::ordered_map")]
+    pub games: HashMap<String, StagedGame>,
+}
+
+impl StagedRestoration {
+    pub fn dir() -> StrictPath {
+        let mut path = app_dir();
+        path.push(STAGING_DIR_NAME);
+        StrictPath::from_std_path_buf(&path)
+    }
+
+    fn file() -> StrictPath {
+        Self::dir().joined(STAGING_FILE_NAME)
+    }
+
+    pub fn load() -> Self {
+        let file = Self::file();
+        if !file.is_file() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(file.interpret()) {
+            Ok(content) => Self::load_from_string(&content).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn load_from_string(content: &str) -> Result<Self, ()> {
+        serde_yaml::from_str(content).map_err(|_| ())
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        if crate::prelude::is_read_only() {
+            return Err(Error::ReadOnlyMode);
+        }
+        let file = Self::file();
+        if self.is_empty() {
+            let _ = Self::dir().remove();
+            return Ok(());
+        }
+        file.create_parent_dir()
+            .and_then(|_| std::fs::write(file.interpret(), self.serialize().as_bytes()))
+            .map_err(|_| Error::StagedRestoreSaveFailed { path: file })
+    }
+
+    pub fn serialize(&self) -> String {
+        serde_yaml::to_string(&self).unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Where a file is placed in the staging area.
+    pub fn staged_file(game: &str, original: &StrictPath) -> StrictPath {
+        let (drive, plain_path) = original.split_drive();
+        let drive_folder = if drive.is_empty() {
+            "drive-0".to_string()
+        } else {
+            format!("drive-{}", escape_folder_name(&drive.replace(':', "")))
+        };
+        Self::dir().joined(&format!("{}/{}/{}", escape_folder_name(game), drive_folder, plain_path))
+    }
+
+    fn staged_registry_file(game: &str) -> StrictPath {
+        Self::dir().joined(&format!("{}/registry.yaml", escape_folder_name(game)))
+    }
+
+    /// Copy a game's backed up files into the staging area instead of their real locations.
+    pub fn stage_game(info: &ScanInfo, layout: &BackupLayout) -> BackupInfo {
+        let mut backup_info = BackupInfo::default();
+        let layout = layout.game_layout(&info.game_name);
+
+        for file in &info.found_files {
+            let original_path = match &file.original_path {
+                Some(x) => x,
+                None => continue,
+            };
+            let staged = Self::staged_file(&info.game_name, original_path);
+            let restored = staged
+                .create_parent_dir()
+                .and_then(|_| layout.restore_stored(&file.path, &staged));
+            if let Err(e) = restored {
+                backup_info.add_failed_file(file.clone(), FailureCategory::from_io(&e));
+            } else if let Some(metadata) = layout.file_metadata(original_path) {
+                let _ = metadata.apply(&staged);
+            }
+        }
+
+        if let Some(registry_file) = &info.registry_file {
+            let staged = Self::staged_registry_file(&info.game_name);
+            let copied = staged
+                .create_parent_dir()
+                .and_then(|_| copy_file(registry_file, &staged, &CopyOptions::default()));
+            if copied.is_err() {
+                backup_info
+                    .failed_registry
+                    .extend(info.found_registry_keys.iter().map(|x| x.path.clone()));
+            }
+        }
+
+        backup_info
+    }
+
+    pub fn add_game(&mut self, info: &ScanInfo, backup_info: &BackupInfo) {
+        let registry_file = Self::staged_registry_file(&info.game_name);
+        let files = info
+            .found_files
+            .iter()
+            .filter(|x| !backup_info.failed_files.contains(x))
+            .filter_map(|x| x.original_path.as_ref().map(|y| y.render()))
+            .collect();
+
+        self.games.insert(
+            info.game_name.clone(),
+            StagedGame {
+                files,
+                registry_file: if info.registry_file.is_some() && registry_file.is_file() {
+                    Some(registry_file)
+                } else {
+                    None
+                },
+            },
+        );
+    }
+
+    /// Move the staged files to their real targets. Anything that fails is kept staged,
+    /// and everything else is removed from the staging area.
+    pub fn apply(&mut self, redirects: &[RedirectConfig]) -> Result<BackupInfo, Error> {
+        let mut backup_info = BackupInfo::default();
+
+        for (game, staged_game) in self.games.iter_mut() {
+            let mut remaining = HashSet::new();
+
+            'outer: for original in &staged_game.files {
+                let original_path = StrictPath::new(original.clone());
+                let staged = Self::staged_file(game, &original_path);
+                if let Ok((target, _)) = game_file_restoration_target(&original_path, redirects) {
+                    if staged.is_file() && target.create_parent_dir().is_ok() {
+                        for _ in 0..3 {
+                            if target.unset_readonly().is_ok()
+                                && copy_file(&staged, &target, &CopyOptions::default()).is_ok()
+                            {
+                                let _ = FileMetadata::read(&staged).apply(&target);
+                                let _ = staged.remove();
+                                continue 'outer;
+                            }
+                        }
+                    }
+                }
+
+                remaining.insert(original.clone());
+                backup_info.failed_files.insert(ScannedFile {
+                    size: staged.metadata().map(|x| x.len()).unwrap_or_default(),
+                    path: staged,
+                    original_path: Some(original_path),
+                    ignored: false,
+                });
+            }
+
+            // Registry data that can't be restored stays staged for another try.
+            #[cfg(target_os = "windows")]
+            let registry_failed = match &staged_game.registry_file {
+                Some(registry_file) => match crate::registry::Hives::load(registry_file) {
+                    Some(hives) => {
+                        let failed = hives.restore_tracking_failures();
+                        let any_failed = !failed.is_empty();
+                        backup_info.failed_registry.extend(failed);
+                        any_failed
+                    }
+                    None => {
+                        backup_info.failed_files.insert(ScannedFile {
+                            size: registry_file.metadata().map(|x| x.len()).unwrap_or_default(),
+                            path: registry_file.clone(),
+                            original_path: None,
+                            ignored: false,
+                        });
+                        true
+                    }
+                },
+                None => false,
+            };
+            #[cfg(not(target_os = "windows"))]
+            let registry_failed = false;
+
+            staged_game.files = remaining;
+            if !registry_failed {
+                staged_game.registry_file = None;
+            }
+            if staged_game.files.is_empty() && staged_game.registry_file.is_none() {
+                let _ = Self::dir().joined(&escape_folder_name(game)).remove();
+            }
+        }
+
+        self.games
+            .retain(|_, x| !x.files.is_empty() || x.registry_file.is_some());
+        self.save()?;
+
+        Ok(backup_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::*;
+    use pretty_assertions::assert_eq;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    #[test]
+    fn can_be_serialized() {
+        assert_eq!(
+            r#"
+---
+games:
+  game1:
+    files:
+      - /a/file1.txt
+      - /a/file2.txt
+  game2: {}
+"#
+            .trim(),
+            StagedRestoration {
+                games: hashmap! {
+                    s("game2") => StagedGame::default(),
+                    s("game1") => StagedGame {
+                        files: hashset! { s("/a/file2.txt"), s("/a/file1.txt") },
+                        registry_file: None,
+                    },
+                },
+            }
+            .serialize()
+            .trim(),
+        );
+    }
+
+    #[test]
+    fn can_be_loaded() {
+        assert_eq!(
+            Ok(StagedRestoration {
+                games: hashmap! {
+                    s("game1") => StagedGame {
+                        files: hashset! { s("/a/file1.txt") },
+                        registry_file: Some(StrictPath::new(s("/b/registry.yaml"))),
+                    },
+                },
+            }),
+            StagedRestoration::load_from_string(
+                r#"
+                games:
+                  game1:
+                    files:
+                      - /a/file1.txt
+                    registryFile: /b/registry.yaml
+                "#
+            ),
+        );
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::BTreeSet;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    tty::IsTty,
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{
+    cli::{run_cli, Subcommand},
+    config::Config,
+    lang::Translator,
+    layout::{restoration_source, BackupLayout},
+    manifest::Manifest,
+    prelude::{set_read_only, Error},
+    sftp::SftpTarget,
+};
+
+/// How far Page Up and Page Down move through the game list.
+const PAGE_SIZE: usize = 10;
+
+fn terminal_failed<T>(_: T) -> Error {
+    Error::CliUnableToRequestConfirmation
+}
+
+/// While this exists, the terminal is in full-screen mode.
+/// Dropping it gives the terminal back, even when unwinding from a panic.
+struct FullScreen;
+
+impl FullScreen {
+    fn enter() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(std::io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen, cursor::Show);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// The games that can be chosen for one kind of operation.
+#[derive(Debug, Default)]
+struct GameList {
+    candidates: Vec<String>,
+    selected: BTreeSet<String>,
+    /// Index into the games that pass the filter.
+    cursor: usize,
+    /// Why the games couldn't be listed, shown instead of the key bindings.
+    error: Option<String>,
+}
+
+impl GameList {
+    fn set_candidates(&mut self, candidates: Result<Vec<String>, Error>, translator: &Translator) {
+        match candidates {
+            Ok(candidates) => {
+                self.selected.retain(|x| candidates.contains(x));
+                self.candidates = candidates;
+                self.error = None;
+            }
+            Err(e) => {
+                self.candidates.clear();
+                self.selected.clear();
+                self.error = Some(translator.handle_error(&e));
+            }
+        }
+    }
+}
+
+/// What the main loop should do after a key press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    Continue,
+    Run { preview: bool },
+    Quit,
+}
+
+struct App {
+    translator: Translator,
+    restoring: bool,
+    backup: GameList,
+    restore: GameList,
+    filter: String,
+    filtering: bool,
+    confirming_backup: Option<String>,
+}
+
+impl App {
+    fn new(translator: Translator) -> Self {
+        Self {
+            translator,
+            restoring: false,
+            backup: Default::default(),
+            restore: Default::default(),
+            filter: String::new(),
+            filtering: false,
+            confirming_backup: None,
+        }
+    }
+
+    fn list(&self) -> &GameList {
+        if self.restoring {
+            &self.restore
+        } else {
+            &self.backup
+        }
+    }
+
+    fn list_mut(&mut self) -> &mut GameList {
+        if self.restoring {
+            &mut self.restore
+        } else {
+            &mut self.backup
+        }
+    }
+
+    fn shown(&self) -> Vec<&String> {
+        filter_games(&self.list().candidates, &self.filter)
+    }
+
+    /// List the games again, since the config or the backups may have changed.
+    fn reload(&mut self) {
+        match Config::load() {
+            Ok(config) => {
+                set_read_only(config.mode.is_read_only());
+                self.backup
+                    .set_candidates(backup_candidates(config.clone()), &self.translator);
+                self.restore
+                    .set_candidates(restore_candidates(&config), &self.translator);
+            }
+            Err(e) => {
+                self.backup.set_candidates(Err(e.clone()), &self.translator);
+                self.restore.set_candidates(Err(e), &self.translator);
+            }
+        }
+        self.clamp_cursor();
+    }
+
+    fn clamp_cursor(&mut self) {
+        let last = self.shown().len().saturating_sub(1);
+        let list = self.list_mut();
+        list.cursor = list.cursor.min(last);
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        let list = self.list_mut();
+        list.cursor = list.cursor.saturating_add_signed(offset);
+        self.clamp_cursor();
+    }
+
+    fn toggle_current(&mut self) {
+        let game = match self.shown().get(self.list().cursor) {
+            Some(game) => game.to_string(),
+            None => return,
+        };
+        let list = self.list_mut();
+        if !list.selected.remove(&game) {
+            list.selected.insert(game);
+        }
+    }
+
+    fn select_shown(&mut self) {
+        let shown: Vec<_> = self.shown().into_iter().cloned().collect();
+        self.list_mut().selected.extend(shown);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Step {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Step::Quit;
+        }
+
+        if self.confirming_backup.is_some() {
+            self.confirming_backup = None;
+            return match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Step::Run { preview: false },
+                _ => Step::Continue,
+            };
+        }
+
+        if self.filtering {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.filtering = false,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => {}
+            }
+            self.clamp_cursor();
+            return Step::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Step::Quit,
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => self.move_cursor(PAGE_SIZE as isize),
+            KeyCode::Home => self.list_mut().cursor = 0,
+            KeyCode::End => self.move_cursor(isize::MAX),
+            KeyCode::Char(' ') => self.toggle_current(),
+            KeyCode::Char('a') => self.select_shown(),
+            KeyCode::Char('c') => self.list_mut().selected.clear(),
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.restoring = !self.restoring;
+                self.clamp_cursor();
+            }
+            KeyCode::Char('p') => return Step::Run { preview: true },
+            KeyCode::Enter if self.restoring => {
+                // The CLI asks for confirmation itself when restoring.
+                return Step::Run { preview: false };
+            }
+            KeyCode::Enter => match Config::load() {
+                Ok(config) => {
+                    let target = &config.backup.path;
+                    self.confirming_backup = Some(self.translator.modal_confirm_backup(
+                        target,
+                        target.exists(),
+                        config.backup.merge,
+                    ));
+                }
+                Err(e) => self.list_mut().error = Some(self.translator.handle_error(&e)),
+            },
+            _ => {}
+        }
+
+        Step::Continue
+    }
+
+    /// Back up or restore the selected games (or all enabled games if none are selected).
+    fn run(&self, preview: bool) -> Result<(), Error> {
+        let games: Vec<_> = self.list().selected.iter().cloned().collect();
+        if self.restoring {
+            restore(games, preview)
+        } else {
+            back_up(games, preview)
+        }
+    }
+
+    fn draw<B: Backend>(&self, frame: &mut Frame<B>) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(4)])
+            .split(frame.size());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[0]);
+
+        let list = self.list();
+        let shown = self.shown();
+        let items: Vec<_> = shown
+            .iter()
+            .map(|game| {
+                let mark = if list.selected.contains(*game) { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{} {}", mark, game))
+            })
+            .collect();
+        let mut title = self.translator.tui_games(self.restoring);
+        if self.filtering || !self.filter.is_empty() {
+            title = format!("{} · {}", title, self.translator.tui_filter(&self.filter));
+            if self.filtering {
+                title.push('_');
+            }
+        }
+        let mut state = ListState::default();
+        if !shown.is_empty() {
+            state.select(Some(list.cursor));
+        }
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            columns[0],
+            &mut state,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.translator.tui_selected_games(&list.selected))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(self.translator.tui_selection()),
+                )
+                .wrap(Wrap { trim: false }),
+            columns[1],
+        );
+
+        let footer = match &list.error {
+            Some(error) => error.clone(),
+            None => self.translator.tui_keys(self.restoring, self.filtering),
+        };
+        frame.render_widget(
+            Paragraph::new(footer)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(self.translator.window_title()),
+                )
+                .wrap(Wrap { trim: true }),
+            rows[1],
+        );
+
+        if let Some(prompt) = &self.confirming_backup {
+            let area = centered(frame.size(), 60, 7);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(self.translator.tui_confirm(prompt))
+                    .block(Block::default().borders(Borders::ALL))
+                    .wrap(Wrap { trim: true }),
+                area,
+            );
+        }
+    }
+}
+
+/// A box of at most the given size in the middle of the area.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// A full-screen alternative to the CLI, with a list of games to back up or restore
+/// and a pane showing which of them are selected. Each operation is delegated to the
+/// same code that handles the equivalent CLI command, which briefly takes over
+/// the normal terminal screen to show its output and ask any questions.
+pub fn run_tui() -> Result<(), Error> {
+    let translator = Translator::default();
+    if !std::io::stdout().is_tty() {
+        return Err(Error::CliUnableToRequestConfirmation);
+    }
+
+    let mut app = App::new(translator);
+    app.reload();
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout())).map_err(terminal_failed)?;
+    let mut full_screen = FullScreen::enter().map_err(terminal_failed)?;
+    terminal.clear().map_err(terminal_failed)?;
+
+    loop {
+        terminal.draw(|frame| app.draw(frame)).map_err(terminal_failed)?;
+
+        let key = match event::read().map_err(terminal_failed)? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match app.handle_key(key) {
+            Step::Continue => {}
+            Step::Quit => break,
+            Step::Run { preview } => {
+                drop(full_screen);
+                if let Err(e) = app.run(preview) {
+                    eprintln!("\n{}", translator.handle_error(&e));
+                }
+                println!("\n{}", translator.tui_press_any_key());
+                wait_for_key().map_err(terminal_failed)?;
+
+                full_screen = FullScreen::enter().map_err(terminal_failed)?;
+                terminal.clear().map_err(terminal_failed)?;
+                app.reload();
+            }
+        }
+    }
+
+    drop(full_screen);
+    Ok(())
+}
+
+fn wait_for_key() -> std::io::Result<()> {
+    enable_raw_mode()?;
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => break Ok(()),
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode()?;
+    result
+}
+
+fn back_up(games: Vec<String>, preview: bool) -> Result<(), Error> {
+    run_cli(Subcommand::Backup {
+        preview,
+        path: None,
+        // The user has already confirmed in the TUI.
+        force: true,
+        merge: false,
+        no_merge: false,
+        update: false,
+        try_update: false,
+        by_steam_id: false,
+        wine_prefix: None,
+        api: false,
+        sort: None,
+        games,
+    })
+}
+
+fn restore(games: Vec<String>, preview: bool) -> Result<(), Error> {
+    // The CLI asks for confirmation itself when restoring.
+    run_cli(Subcommand::Restore {
+        preview,
+        path: None,
+        force: false,
+        by_steam_id: false,
+        api: false,
+        sort: None,
+        games,
+    })
+}
+
+fn backup_candidates(mut config: Config) -> Result<Vec<String>, Error> {
+    let manifest = Manifest::load(&mut config, false)?;
+    let mut games: BTreeSet<_> = manifest.0.into_keys().collect();
+    games.extend(config.custom_games.into_iter().filter(|x| !x.ignore).map(|x| x.name));
+    Ok(games.into_iter().collect())
+}
+
+/// Find the restore source the same way as the CLI's `restore` command,
+/// so that SFTP and archived backups are listed too.
+fn restore_candidates(config: &Config) -> Result<Vec<String>, Error> {
+    let source = match SftpTarget::parse(&config.restore.path.raw()) {
+        Some(remote) => {
+            remote.download()?;
+            remote.mirror()
+        }
+        None => restoration_source(&config.restore.path)?,
+    };
+    let layout = BackupLayout::new(source, config.backup.retention.clone());
+    let mut games = layout.restorable_games();
+    games.sort();
+    Ok(games)
+}
+
+/// Games whose names contain the filter, ignoring case.
+fn filter_games<'a>(candidates: &'a [String], filter: &str) -> Vec<&'a String> {
+    let filter = filter.trim().to_lowercase();
+    candidates
+        .iter()
+        .filter(|x| x.to_lowercase().contains(&filter))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn app() -> App {
+        let mut app = App::new(Translator::default());
+        app.backup.set_candidates(
+            Ok(vec![s("Celeste"), s("Hollow Knight"), s("Knights of Pen and Paper")]),
+            &Translator::default(),
+        );
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Step {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn can_filter_games_ignoring_case() {
+        let candidates = vec![s("Celeste"), s("Hollow Knight"), s("Knights of Pen and Paper")];
+        assert_eq!(
+            vec![&candidates[1], &candidates[2]],
+            filter_games(&candidates, " knight ")
+        );
+        assert_eq!(candidates.iter().collect::<Vec<_>>(), filter_games(&candidates, ""));
+    }
+
+    #[test]
+    fn can_select_games_from_the_list() {
+        let mut app = app();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(BTreeSet::from([s("Hollow Knight")]), app.backup.selected);
+
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(
+            BTreeSet::from([s("Hollow Knight"), s("Knights of Pen and Paper")]),
+            app.backup.selected
+        );
+
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(BTreeSet::new(), app.backup.selected);
+    }
+
+    #[test]
+    fn can_select_only_the_filtered_games() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('/'));
+        for c in "CEL".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(Step::Continue, press(&mut app, KeyCode::Char('a')));
+        assert_eq!(BTreeSet::from([s("Celeste")]), app.backup.selected);
+    }
+
+    #[test]
+    fn previews_without_confirmation() {
+        let mut app = app();
+        assert_eq!(Step::Run { preview: true }, press(&mut app, KeyCode::Char('p')));
+        assert_eq!(Step::Quit, press(&mut app, KeyCode::Char('q')));
+    }
+}